@@ -8,7 +8,10 @@ use crate::Packet;
 /// PAR2 statistics structure
 #[derive(Debug, Clone)]
 pub struct Par2Stats {
+    /// Files protected by the recovery data
     pub file_count: usize,
+    /// Files in the non-recovery set, checksummed but not protected
+    pub other_file_count: usize,
     pub block_size: u32,
     pub total_blocks: usize,
     pub total_size: u64,
@@ -16,33 +19,58 @@ pub struct Par2Stats {
 }
 
 /// Calculate comprehensive statistics for a PAR2 set
+///
+/// Files in the non-recovery set are counted as other files and left out of
+/// the block and size totals.
 pub fn calculate_par2_stats(packets: &[Packet], recovery_blocks: usize) -> Par2Stats {
-    let unique_files = crate::packets::processing::extract_filenames(packets);
-    let (block_size, total_blocks) = crate::packets::processing::extract_main_stats(packets);
-    let total_size = crate::packets::processing::extract_file_descriptions(packets)
-        .into_iter()
-        .map(|fd| fd.file_length)
-        .sum();
+    let non_recovery_ids = crate::packets::processing::extract_non_recovery_file_ids(packets);
+    let (block_size, _) = crate::packets::processing::extract_main_stats(packets);
+    let (other_files, recovery_files): (Vec<_>, Vec<_>) =
+        crate::packets::processing::extract_file_descriptions(packets)
+            .into_iter()
+            .partition(|fd| non_recovery_ids.contains(&fd.file_id));
+    let total_blocks = if block_size > 0 {
+        recovery_files
+            .iter()
+            .map(|fd| fd.file_length.div_ceil(block_size) as usize)
+            .sum()
+    } else {
+        0
+    };
 
     Par2Stats {
-        file_count: unique_files.len(),
+        file_count: recovery_files.len(),
+        other_file_count: other_files.len(),
         block_size: block_size as u32,
         total_blocks,
-        total_size,
+        total_size: recovery_files.iter().map(|fd| fd.file_length).sum(),
         recovery_blocks,
     }
 }
 
+/// Print the comments attached to the PAR2 set, one per line
+pub fn print_comments(comments: &[String]) {
+    for comment in comments {
+        println!("Comment: {}", comment);
+    }
+}
+
+/// Summary statistics about the PAR2 set, in par2cmdline format
+pub fn format_summary_stats(stats: &Par2Stats) -> String {
+    format!(
+        "There are {} recoverable files and {} other files.\n\
+         The block size used was {} bytes.\n\
+         There are a total of {} data blocks.\n\
+         The total size of the data files is {} bytes.",
+        stats.file_count,
+        stats.other_file_count,
+        stats.block_size,
+        stats.total_blocks,
+        stats.total_size
+    )
+}
+
 /// Print summary statistics about the PAR2 set
 pub fn print_summary_stats(stats: &Par2Stats) {
-    println!(
-        "\nThere are {} recoverable files and 0 other files.",
-        stats.file_count
-    );
-    println!("The block size used was {} bytes.", stats.block_size);
-    println!("There are a total of {} data blocks.", stats.total_blocks);
-    println!(
-        "The total size of the data files is {} bytes.",
-        stats.total_size
-    );
+    println!("\n{}", format_summary_stats(stats));
 }
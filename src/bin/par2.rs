@@ -9,7 +9,7 @@ use par2rs::cli::compat::{
     parse_positive_usize, reject_invalid_create_short_clusters, reject_short_value_forms,
 };
 use par2rs::create::cli::{
-    parse_redundancy_option, resolve_create_inputs, validate_recovery_file_count,
+    parse_byte_size, parse_redundancy_option, resolve_create_inputs, validate_recovery_file_count,
     warn_for_high_redundancy, RedundancyOption,
 };
 use par2rs::reporters::VerificationReporter;
@@ -90,7 +90,7 @@ fn main() -> Result<()> {
                     Arg::new("file_threads")
                         .short('T')
                         .long("file-threads")
-                        .help("Number of files hashed in parallel")
+                        .help("Number of threads reading source files ahead of processing")
                         .value_name("N"),
                 )
                 // Create-specific options (match par2cmdline exactly)
@@ -113,6 +113,19 @@ fn main() -> Result<()> {
                         .help("Set the Block-Size (don't use both -b and -s)")
                         .value_name("N"),
                 )
+                .arg(
+                    Arg::new("subslice_size")
+                        .long("subslice-size")
+                        .help("Create a packed set with this Subslice-Size (must divide the Block-Size)")
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::new("optimize_block_size")
+                        .long("optimize-block-size")
+                        .help("Pick the Block-Size with the least padding and recovery data")
+                        .conflicts_with_all(["block_count", "block_size", "subslice_size"])
+                        .action(ArgAction::SetTrue),
+                )
                 .arg(
                     Arg::new("redundancy")
                         .short('r')
@@ -152,11 +165,236 @@ fn main() -> Result<()> {
                         .help("Number of recovery files (max 31) (don't use both -n and -l)")
                         .value_name("N"),
                 )
+                .arg(
+                    Arg::new("max_volume_size")
+                        .long("max-volume-size")
+                        .help("Split recovery data so no recovery file exceeds SIZE (k/m/g suffix)")
+                        .conflicts_with_all([
+                            "uniform",
+                            "limit_size",
+                            "recovery_file_count",
+                            "embed",
+                            "external_recovery",
+                        ])
+                        .value_name("SIZE"),
+                )
+                .arg(
+                    Arg::new("critical_copies")
+                        .long("critical-copies")
+                        .help("Copies of the critical packets in each recovery file (default: scaled by size)")
+                        .conflicts_with("external_recovery")
+                        .value_name("N"),
+                )
                 .arg(
                     Arg::new("recurse")
                         .short('R')
                         .help("Recurse into subdirectories")
                         .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("comment")
+                        .long("comment")
+                        .help("Add a comment to the recovery set (repeatable)")
+                        .value_name("TEXT")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("unicode_filenames")
+                        .long("unicode-filenames")
+                        .help("Write Unicode Filename packets for non-ASCII file names")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("embed")
+                        .long("embed")
+                        .help("Embed the source data in the PAR2 files (FileSlic packets)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("checksum_only")
+                        .long("checksum-only")
+                        .help("Checksum a file without protecting it with recovery data (repeatable)")
+                        .value_name("FILE")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("external_recovery")
+                        .long("external-recovery")
+                        .help("Write recovery data to a headerless .rfsc file (RFSC packet)")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("dry_run")
+                        .long("dry-run")
+                        .help("Print the block layout and output files without creating anything")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("hash_cache")
+                        .long("hash-cache")
+                        .help("Reuse and record file hashes in PATH (default: .par2rs-hashcache)")
+                        .value_name("PATH")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value(""),
+                )
+                .arg(
+                    Arg::new("checkpoint")
+                        .long("checkpoint")
+                        .help("Record progress in PATH so an interrupted run can be resumed (default: <output>.par2rs-checkpoint)")
+                        .value_name("PATH")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value(""),
+                )
+                .arg(
+                    Arg::new("resume")
+                        .long("resume")
+                        .help("Continue an interrupted run from its checkpoint")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("include")
+                        .long("include")
+                        .help("Only protect files matching the glob PATTERN (repeatable)")
+                        .value_name("PATTERN")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("exclude")
+                        .long("exclude")
+                        .help("Skip files and directories matching the glob PATTERN (repeatable)")
+                        .value_name("PATTERN")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("skip_hidden")
+                        .long("skip-hidden")
+                        .help("Skip files and directories whose name starts with a dot")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("symlinks")
+                        .long("symlinks")
+                        .help("Follow, skip or refuse symbolic links among the source files")
+                        .value_name("POLICY")
+                        .value_parser(["follow", "skip", "error"])
+                        .default_value("follow"),
+                )
+                .arg(
+                    Arg::new("files_from")
+                        .long("files-from")
+                        .help("Also protect the files listed in LIST, one per line or NUL separated ('-' for standard input)")
+                        .value_name("LIST"),
+                )
+                .arg(
+                    Arg::new("stdin_name")
+                        .long("stdin-name")
+                        .help("Protect standard input, given as the source '-', as a file called NAME")
+                        .value_name("NAME"),
+                )
+                .arg(
+                    Arg::new("stdin_tee")
+                        .long("stdin-tee")
+                        .help("Copy the data read from standard input to PATH")
+                        .requires("stdin_name")
+                        .value_name("PATH"),
+                ),
+        )
+        .subcommand(
+            Command::new("extend")
+                .about("Add recovery volumes to an existing PAR2 set")
+                .arg(
+                    Arg::new("par2_file")
+                        .help("PAR2 file of the set to extend")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("verbose")
+                        .short('v')
+                        .long("verbose")
+                        .help("Be more verbose")
+                        .action(ArgAction::Count),
+                )
+                .arg(
+                    Arg::new("quiet")
+                        .short('q')
+                        .long("quiet")
+                        .help("Be more quiet (-q -q gives silence)")
+                        .action(ArgAction::Count),
+                )
+                .arg(
+                    Arg::new("memory")
+                        .short('m')
+                        .long("memory")
+                        .help("Memory (in MB) to use")
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::new("threads")
+                        .short('t')
+                        .long("threads")
+                        .help("Number of threads used for main processing")
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::new("file_threads")
+                        .short('T')
+                        .long("file-threads")
+                        .help("Number of threads reading source files ahead of processing")
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::new("recovery_block_count")
+                        .short('c')
+                        .help("Number of recovery blocks to add")
+                        .required(true)
+                        .value_name("N"),
+                )
+                .arg(
+                    Arg::new("uniform")
+                        .short('u')
+                        .help("Uniform recovery file sizes")
+                        .conflicts_with("limit_size")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("limit_size")
+                        .short('l')
+                        .help("Limit size of recovery files (don't use both -u and -l)")
+                        .conflicts_with("recovery_file_count")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("recovery_file_count")
+                        .short('n')
+                        .help("Number of new recovery files (max 31) (don't use both -n and -l)")
+                        .value_name("N"),
+                ),
+        )
+        .subcommand(
+            Command::new("update")
+                .about("Fold in-place changes of the source files into an existing PAR2 set")
+                .arg(
+                    Arg::new("par2_file")
+                        .help("PAR2 file of the set to update")
+                        .required(true)
+                        .index(1),
+                )
+                .arg(
+                    Arg::new("verbose")
+                        .short('v')
+                        .long("verbose")
+                        .help("Be more verbose")
+                        .action(ArgAction::Count),
+                )
+                .arg(
+                    Arg::new("quiet")
+                        .short('q')
+                        .long("quiet")
+                        .help("Be more quiet (-q -q gives silence)")
+                        .action(ArgAction::Count),
                 ),
         )
         .subcommand(
@@ -215,6 +453,25 @@ fn main() -> Result<()> {
                         .help("Rename-only mode")
                         .action(ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("discover_volumes")
+                        .long("discover-volumes")
+                        .help("Find renamed PAR2 volumes by scanning the directory and extra files")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("all_sets")
+                        .long("all-sets")
+                        .help("Process every recovery set found in the directory separately")
+                        .conflicts_with_all(["set_id", "purge"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("set_id")
+                        .long("set-id")
+                        .help("Only process the recovery set with this ID (32 hex digits)")
+                        .value_name("ID"),
+                )
                 .arg(
                     Arg::new("threads")
                         .short('t')
@@ -254,6 +511,15 @@ fn main() -> Result<()> {
                         .short('S')
                         .help("Skip leeway (distance +/- from expected block position)")
                         .value_name("N"),
+                )
+                .arg(
+                    Arg::new("hash_cache")
+                        .long("hash-cache")
+                        .help("Skip full-file MD5s cached by create in PATH (default: .par2rs-hashcache)")
+                        .value_name("PATH")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value(""),
                 ),
         )
         .subcommand(
@@ -312,6 +578,25 @@ fn main() -> Result<()> {
                         .help("Rename-only mode")
                         .action(ArgAction::SetTrue),
                 )
+                .arg(
+                    Arg::new("discover_volumes")
+                        .long("discover-volumes")
+                        .help("Find renamed PAR2 volumes by scanning the directory and extra files")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("all_sets")
+                        .long("all-sets")
+                        .help("Process every recovery set found in the directory separately")
+                        .conflicts_with_all(["set_id", "purge"])
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("set_id")
+                        .long("set-id")
+                        .help("Only process the recovery set with this ID (32 hex digits)")
+                        .value_name("ID"),
+                )
                 .arg(
                     Arg::new("threads")
                         .short('t')
@@ -351,6 +636,15 @@ fn main() -> Result<()> {
                         .short('S')
                         .help("Skip leeway (distance +/- from expected block position)")
                         .value_name("N"),
+                )
+                .arg(
+                    Arg::new("hash_cache")
+                        .long("hash-cache")
+                        .help("Skip full-file MD5s cached by create in PATH (default: .par2rs-hashcache)")
+                        .value_name("PATH")
+                        .num_args(0..=1)
+                        .require_equals(true)
+                        .default_missing_value(""),
                 ),
         )
         .get_matches_from(args);
@@ -358,6 +652,8 @@ fn main() -> Result<()> {
     // Handle subcommands
     match matches.subcommand() {
         Some(("create", sub_matches)) => handle_create(sub_matches),
+        Some(("extend", sub_matches)) => handle_extend(sub_matches),
+        Some(("update", sub_matches)) => handle_update(sub_matches),
         Some(("verify", sub_matches)) => handle_verify(sub_matches),
         Some(("repair", sub_matches)) => handle_repair(sub_matches),
         Some((cmd, _)) => {
@@ -384,6 +680,10 @@ fn reject_detached_short_values_for_subcommand() {
             &["-b", "-s", "-r", "-n", "-T", "-t", "-m"][..],
             &["-B", "-b", "-s", "-r", "-c", "-f", "-n", "-T", "-t", "-m"][..],
         ),
+        "extend" => (
+            &["-n", "-T", "-t", "-m"][..],
+            &["-c", "-n", "-T", "-t", "-m"][..],
+        ),
         "verify" | "v" | "repair" | "r" => {
             (&["-a", "-S", "-T", "-m"][..], &["-B", "-S", "-T", "-m"][..])
         }
@@ -412,10 +712,27 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
         .get_one::<String>("par2_file")
         .expect("par2_file is required");
 
-    let source_inputs: Vec<PathBuf> = matches
+    let mut source_inputs: Vec<PathBuf> = matches
         .get_many::<String>("files")
         .map(|files| files.map(PathBuf::from).collect())
         .unwrap_or_default();
+    if let Some(list) = matches.get_one::<String>("files_from") {
+        source_inputs.extend(read_source_list(list)?);
+    }
+    let symlinks = match matches.get_one::<String>("symlinks").map(String::as_str) {
+        Some("skip") => par2rs::create::SymlinkPolicy::Skip,
+        Some("error") => par2rs::create::SymlinkPolicy::Error,
+        _ => par2rs::create::SymlinkPolicy::Follow,
+    };
+    let mut source_filter = par2rs::create::SourceFilter::default()
+        .skip_hidden(matches.get_flag("skip_hidden"))
+        .symlinks(symlinks);
+    for pattern in matches.get_many::<String>("include").into_iter().flatten() {
+        source_filter = source_filter.include(pattern);
+    }
+    for pattern in matches.get_many::<String>("exclude").into_iter().flatten() {
+        source_filter = source_filter.exclude(pattern);
+    }
 
     // Handle verbosity/quiet flags (par2cmdline style)
     let noise_level = parse_noise_level(matches.get_count("verbose"), matches.get_count("quiet"))
@@ -437,6 +754,12 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
         .transpose()
         .context("Invalid block size")?;
 
+    let subslice_size: Option<u64> = matches
+        .get_one::<String>("subslice_size")
+        .map(|s| s.parse())
+        .transpose()
+        .context("Invalid subslice size")?;
+
     let block_count: Option<u32> = matches
         .get_one::<String>("block_count")
         .map(|s| s.parse())
@@ -461,6 +784,18 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
         .transpose()
         .context("Invalid first recovery block number")?;
 
+    let critical_copies: Option<u32> = matches
+        .get_one::<String>("critical_copies")
+        .map(|s| s.parse())
+        .transpose()
+        .context("Invalid critical packet copy count")?;
+
+    let max_volume_size: Option<u64> = matches
+        .get_one::<String>("max_volume_size")
+        .map(|s| parse_byte_size(s.as_str()))
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+
     let memory_limit = parse_memory_mb(matches.get_one::<String>("memory").map(String::as_str))
         .map_err(anyhow::Error::msg)?;
     let file_threads = parse_positive_usize(
@@ -482,6 +817,19 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
     let uniform = matches.get_flag("uniform");
     let limit_size = matches.get_flag("limit_size");
     let recurse = matches.get_flag("recurse");
+    let unicode_filenames = matches.get_flag("unicode_filenames");
+    let embed = matches.get_flag("embed");
+    let external_recovery = matches.get_flag("external_recovery");
+    let dry_run = matches.get_flag("dry_run");
+    let optimize_block_size = matches.get_flag("optimize_block_size");
+    let checksum_only: Vec<PathBuf> = matches
+        .get_many::<String>("checksum_only")
+        .map(|files| files.map(PathBuf::from).collect())
+        .unwrap_or_default();
+    let comments: Vec<String> = matches
+        .get_many::<String>("comment")
+        .map(|values| values.cloned().collect())
+        .unwrap_or_default();
 
     if let Some(count) = recovery_file_count {
         validate_recovery_file_count(count).map_err(anyhow::Error::msg)?;
@@ -496,14 +844,37 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
             .map(String::as_str),
         source_inputs,
         recurse,
+        &source_filter,
     )
     .map_err(anyhow::Error::msg)?;
     reject_par1_create_target(&output_name)?;
+    let source_count = source_files.len();
+    let stdin_name = matches.get_one::<String>("stdin_name");
+    let reads_stdin = source_files.iter().any(|path| path.as_os_str() == "-");
+    let source_files = match (reads_stdin, stdin_name) {
+        (true, Some(_)) if source_count == 1 => Vec::new(),
+        (true, Some(_)) => anyhow::bail!("Standard input ('-') must be the only source"),
+        (true, None) => anyhow::bail!("Reading standard input ('-') needs --stdin-name"),
+        (false, Some(_)) => anyhow::bail!("--stdin-name needs '-' as the source"),
+        (false, None) => source_files,
+    };
+    let hash_cache = matches
+        .get_one::<String>("hash_cache")
+        .map(|value| par2rs::hash_cache::path_from_option(value, Path::new(&output_name)));
+    let resume = matches.get_flag("resume");
+    let checkpoint = match matches.get_one::<String>("checkpoint") {
+        Some(path) if !path.is_empty() => Some(PathBuf::from(path)),
+        Some(_) => Some(par2rs::create::checkpoint::default_path(Path::new(
+            &output_name,
+        ))),
+        None => resume.then(|| par2rs::create::checkpoint::default_path(Path::new(&output_name))),
+    };
 
     if !quiet_mode {
         println!(
-            "Creating PAR2 files for {} source files...",
-            source_files.len()
+            "{} PAR2 files for {} source files...",
+            if dry_run { "Planning" } else { "Creating" },
+            source_count
         );
         println!("Output: {output_name}");
         if let Some(RedundancyOption::Percent(redundancy)) = redundancy {
@@ -535,6 +906,12 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
         // This is used to calculate block_size if block_size is not specified
         context = context.source_block_count(count);
     }
+    if let Some(size) = subslice_size {
+        context = context.subslice_size(size);
+    }
+    if optimize_block_size {
+        context = context.optimize_block_size(true);
+    }
     if let Some(count) = recovery_block_count {
         context = context.recovery_block_count(count);
     }
@@ -559,12 +936,46 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
     if limit_size {
         context = context.recovery_file_scheme(par2rs::create::RecoveryFileScheme::Limited);
     }
+    if let Some(copies) = critical_copies {
+        context = context.critical_copies(copies);
+    }
+    if let Some(size) = max_volume_size {
+        context =
+            context.recovery_file_scheme(par2rs::create::RecoveryFileScheme::MaxFileSize(size));
+    }
     if let Some(thread_count) = threads {
         context = context.thread_count(thread_count);
     }
     if let Some(file_threads) = file_threads {
         context = context.file_thread_count(file_threads);
     }
+    if unicode_filenames {
+        context = context.unicode_filenames(true);
+    }
+    if embed {
+        context = context.embed_source_data(true);
+    }
+    if external_recovery {
+        context = context.external_recovery_data(true);
+    }
+    if let Some(path) = hash_cache {
+        context = context.hash_cache(path);
+    }
+    if let Some(path) = checkpoint {
+        context = context.checkpoint(path).resume(resume);
+    }
+    if !checksum_only.is_empty() {
+        context = context.non_recovery_files(checksum_only);
+    }
+    if let Some(name) = stdin_name {
+        context = context.stream_source(name, std::io::stdin());
+    }
+    if let Some(path) = matches.get_one::<String>("stdin_tee") {
+        context = context.stream_tee(path);
+    }
+    for comment in comments {
+        context = context.comment(comment);
+    }
 
     // Initialize SIMD policy from CLI flag (disable SIMD if requested)
     let force_scalar = matches.get_flag("force_scalar");
@@ -574,6 +985,15 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
         .build()
         .context("Failed to initialize PAR2 creation context")?;
 
+    if dry_run {
+        let plan = create_context
+            .plan()
+            .context("Failed to plan PAR2 creation")?;
+        println!();
+        plan.print_report();
+        return Ok(());
+    }
+
     if let Err(error) = create_context.create() {
         if let Some(exit_code) = create_error_exit_code(&error) {
             eprintln!("Error: Failed to create PAR2 files\n\nCaused by:\n    0: {error}");
@@ -593,6 +1013,160 @@ fn handle_create(matches: &clap::ArgMatches) -> Result<()> {
     Ok(())
 }
 
+fn handle_extend(matches: &clap::ArgMatches) -> Result<()> {
+    let par2_file = matches
+        .get_one::<String>("par2_file")
+        .expect("par2_file is required");
+
+    let noise_level = parse_noise_level(matches.get_count("verbose"), matches.get_count("quiet"))
+        .map_err(anyhow::Error::msg)?;
+    init_env_logger(noise_level);
+    let quiet_mode = matches.get_count("quiet") > 0;
+
+    let recovery_block_count: u32 = matches
+        .get_one::<String>("recovery_block_count")
+        .expect("recovery_block_count is required")
+        .parse()
+        .context("Invalid recovery block count")?;
+    let recovery_file_count: Option<u32> = matches
+        .get_one::<String>("recovery_file_count")
+        .map(|s| s.parse())
+        .transpose()
+        .context("Invalid recovery file count")?;
+    if let Some(count) = recovery_file_count {
+        validate_recovery_file_count(count).map_err(anyhow::Error::msg)?;
+    }
+    let memory_limit = parse_memory_mb(matches.get_one::<String>("memory").map(String::as_str))
+        .map_err(anyhow::Error::msg)?;
+    let file_thread_count = parse_positive_usize(
+        matches
+            .get_one::<String>("file_threads")
+            .map(String::as_str),
+        "-T",
+    )
+    .map_err(anyhow::Error::msg)?;
+    let thread_count: u32 = matches
+        .get_one::<String>("threads")
+        .map(|s| s.parse())
+        .transpose()
+        .context("Invalid thread count")?
+        .unwrap_or(0);
+
+    // Same scheme selection as create: -n alone means uniform volumes
+    let recovery_file_scheme = if matches.get_flag("limit_size") {
+        par2rs::create::RecoveryFileScheme::Limited
+    } else if matches.get_flag("uniform") || recovery_file_count.is_some() {
+        par2rs::create::RecoveryFileScheme::Uniform
+    } else {
+        par2rs::create::RecoveryFileScheme::Variable
+    };
+
+    let config = par2rs::create::ExtendConfig {
+        recovery_block_count,
+        recovery_file_count,
+        recovery_file_scheme,
+        memory_limit,
+        thread_count,
+        file_thread_count,
+    };
+
+    if !quiet_mode {
+        println!("Extending {par2_file} with {recovery_block_count} recovery blocks...");
+    }
+
+    let reporter = par2rs::create::ConsoleCreateReporter::new(quiet_mode);
+    let output_files =
+        match par2rs::create::extend_recovery_set(Path::new(par2_file), &config, &reporter) {
+            Ok(output_files) => output_files,
+            Err(error) => {
+                if let Some(exit_code) = create_error_exit_code(&error) {
+                    eprintln!("Error: Failed to extend PAR2 set\n\nCaused by:\n    0: {error}");
+                    std::process::exit(exit_code);
+                }
+                return Err(error).context("Failed to extend PAR2 set");
+            }
+        };
+
+    if !quiet_mode {
+        println!("\nCreated PAR2 files:");
+        for file in &output_files {
+            println!("  {}", file);
+        }
+        println!("\nDone.");
+    }
+
+    Ok(())
+}
+
+fn handle_update(matches: &clap::ArgMatches) -> Result<()> {
+    let par2_file = matches
+        .get_one::<String>("par2_file")
+        .expect("par2_file is required");
+
+    let noise_level = parse_noise_level(matches.get_count("verbose"), matches.get_count("quiet"))
+        .map_err(anyhow::Error::msg)?;
+    init_env_logger(noise_level);
+    let quiet_mode = matches.get_count("quiet") > 0;
+
+    if !quiet_mode {
+        println!("Updating {par2_file}...");
+    }
+
+    let reporter = par2rs::create::ConsoleCreateReporter::new(quiet_mode);
+    let summary = match par2rs::create::update_recovery_set(Path::new(par2_file), &reporter) {
+        Ok(summary) => summary,
+        Err(error) => {
+            if let Some(exit_code) = create_error_exit_code(&error) {
+                eprintln!("Error: Failed to update PAR2 set\n\nCaused by:\n    0: {error}");
+                std::process::exit(exit_code);
+            }
+            return Err(error).context("Failed to update PAR2 set");
+        }
+    };
+
+    if !quiet_mode {
+        if summary.updated_files.is_empty() {
+            println!("\nNo source blocks changed, nothing to update.");
+        } else {
+            if summary.changed_blocks > 0 {
+                println!(
+                    "\nFolded {} changed blocks ({} rebuilt from recovery data) into {} recovery packets.",
+                    summary.changed_blocks, summary.recovered_blocks, summary.updated_recovery_packets
+                );
+            }
+            if summary.changed_non_recovery_files > 0 {
+                println!(
+                    "\nRehashed {} changed non-recovery files.",
+                    summary.changed_non_recovery_files
+                );
+            }
+            println!("\nUpdated PAR2 files:");
+            for file in &summary.updated_files {
+                println!("  {}", file);
+            }
+        }
+        println!("\nDone.");
+    }
+
+    Ok(())
+}
+
+/// Source files listed in `list`, or on standard input for `-`
+fn read_source_list(list: &str) -> Result<Vec<PathBuf>> {
+    use std::io::Read;
+
+    if list == "-" {
+        let mut data = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut data)
+            .context("Failed to read the file list from standard input")?;
+        Ok(par2rs::create::source_filter::parse_file_list(&data))
+    } else {
+        par2rs::create::source_filter::read_file_list(Path::new(list))
+            .context("Failed to read the file list")
+    }
+}
+
 fn create_error_exit_code(error: &par2rs::create::CreateError) -> Option<i32> {
     match error {
         par2rs::create::CreateError::FileCreateError { source, .. }
@@ -690,12 +1264,38 @@ fn handle_verify(matches: &clap::ArgMatches) -> Result<()> {
         .and_then(|n| n.to_str())
         .map(Path::new)
         .unwrap_or(&file_path);
-    let par2_files = par2rs::par2_files::collect_par2_files(file_name);
+    if matches.get_flag("all_sets") {
+        return verify_all_sets(
+            file_name,
+            &extra_files,
+            &verify_config,
+            base_path_override,
+            quiet,
+        );
+    }
+
+    let par2_files = match verify_config.set_id {
+        Some(_) => par2rs::par2_files::collect_all_par2_files(
+            file_name,
+            &extra_files,
+            verify_config.discover_volumes,
+        ),
+        None => par2rs::par2_files::discover_par2_files(
+            file_name,
+            &extra_files,
+            verify_config.discover_volumes,
+        ),
+    };
 
     // Parse packets excluding recovery slices but validate and count them
     // Recovery slice data is NOT loaded into memory (saves gigabytes for large PAR2 sets)
     // but they are validated and counted for repair possibility checking
-    let packet_set = par2rs::par2_files::load_par2_packets(&par2_files, false, !quiet);
+    let packet_set = match &verify_config.set_id {
+        Some(set_id) => {
+            par2rs::par2_files::load_par2_packets(&par2_files, false, false).select_set(set_id)?
+        }
+        None => par2rs::par2_files::load_par2_packets(&par2_files, false, !quiet),
+    };
 
     if !quiet {
         println!(); // Blank line after loading
@@ -706,6 +1306,7 @@ fn handle_verify(matches: &clap::ArgMatches) -> Result<()> {
             packet_set.recovery_block_count,
         );
         par2rs::analysis::print_summary_stats(&stats);
+        par2rs::analysis::print_comments(&packet_set.comments());
 
         println!("\nVerifying source files:\n");
     }
@@ -744,7 +1345,19 @@ fn handle_verify(matches: &clap::ArgMatches) -> Result<()> {
         );
     }
 
-    if results.missing_block_count == 0 {
+    let damaged_non_recovery: Vec<_> = results
+        .damaged_non_recovery_files()
+        .map(|file| file.file_name.as_str())
+        .collect();
+    if !damaged_non_recovery.is_empty() && !quiet {
+        eprintln!(
+            "\n{} non-recovery file(s) are damaged or missing and cannot be repaired: {}",
+            damaged_non_recovery.len(),
+            damaged_non_recovery.join(", ")
+        );
+    }
+
+    if results.missing_block_count == 0 && damaged_non_recovery.is_empty() {
         if purge {
             let packet_set = par2rs::par2_files::load_par2_packets(&par2_files, false, false);
             let context = par2rs::repair::RepairContextBuilder::new()
@@ -756,8 +1369,8 @@ fn handle_verify(matches: &clap::ArgMatches) -> Result<()> {
             context.purge_files(&file_name.to_string_lossy())?;
         }
         Ok(())
-    } else if results.repair_possible {
-        if !quiet {
+    } else if results.missing_block_count == 0 || results.repair_possible {
+        if !quiet && results.missing_block_count > 0 {
             eprintln!("\nRepair is required.");
         }
         std::process::exit(1);
@@ -769,6 +1382,191 @@ fn handle_verify(matches: &clap::ArgMatches) -> Result<()> {
     }
 }
 
+/// Verify every recovery set found next to `file_name`, each on its own
+fn verify_all_sets(
+    file_name: &Path,
+    extra_files: &[PathBuf],
+    verify_config: &par2rs::verify::VerificationConfig,
+    base_path_override: Option<PathBuf>,
+    quiet: bool,
+) -> Result<()> {
+    let par2_files = par2rs::par2_files::collect_all_par2_files(
+        file_name,
+        extra_files,
+        verify_config.discover_volumes,
+    );
+    let sets = par2rs::par2_files::load_par2_packets(&par2_files, false, false).group_by_set_id();
+    anyhow::ensure!(!sets.is_empty(), "No recovery sets found");
+
+    let reporter = par2rs::reporters::ConsoleVerificationReporter::new();
+    let mut summary = Vec::with_capacity(sets.len());
+    for (set_id, packet_set) in sets {
+        if !quiet {
+            println!("\nRecovery set {set_id}:\n");
+        }
+        if par2rs::packets::processing::extract_slice_layout(&packet_set.packets).is_none() {
+            summary.push((set_id, SetStatus::Incomplete));
+            if !quiet {
+                println!("Main packet not found, the set cannot be verified.");
+            }
+            continue;
+        }
+        if !quiet {
+            let stats = par2rs::analysis::calculate_par2_stats(
+                &packet_set.packets,
+                packet_set.recovery_block_count,
+            );
+            par2rs::analysis::print_summary_stats(&stats);
+            println!("\nVerifying source files:\n");
+        }
+
+        let base_dir = base_path_override
+            .clone()
+            .unwrap_or_else(|| packet_set.base_dir.clone());
+        let results = if quiet {
+            par2rs::verify::comprehensive_verify_files_with_extra_files(
+                packet_set,
+                verify_config,
+                &par2rs::reporters::SilentVerificationReporter,
+                &base_dir,
+                extra_files,
+            )
+        } else {
+            par2rs::verify::comprehensive_verify_files_with_extra_files(
+                packet_set,
+                verify_config,
+                &reporter,
+                &base_dir,
+                extra_files,
+            )
+        };
+        if !quiet {
+            reporter.report_verification_results(&results);
+        }
+        summary.push((set_id, SetStatus::from_results(&results)));
+    }
+
+    if !quiet {
+        println!("\nSummary of {} recovery sets:", summary.len());
+        for (set_id, status) in &summary {
+            println!("  {set_id}: {}", status.description());
+        }
+    }
+
+    match summary.iter().map(|(_, status)| status.exit_code()).max() {
+        Some(0) | None => Ok(()),
+        Some(code) => std::process::exit(code),
+    }
+}
+
+/// Outcome of verifying one recovery set in `--all-sets` mode
+#[derive(Debug, Clone, Copy)]
+enum SetStatus {
+    Correct,
+    RepairRequired,
+    RepairImpossible,
+    Incomplete,
+}
+
+impl SetStatus {
+    fn from_results(results: &par2rs::verify::VerificationResults) -> Self {
+        match (results.missing_block_count, results.repair_possible) {
+            (0, _)
+                if results.renamed_file_count == 0
+                    && results.damaged_non_recovery_files().count() == 0 =>
+            {
+                SetStatus::Correct
+            }
+            (0, _) | (_, true) => SetStatus::RepairRequired,
+            (_, false) => SetStatus::RepairImpossible,
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            SetStatus::Correct => "all files are correct",
+            SetStatus::RepairRequired => "repair is required",
+            SetStatus::RepairImpossible => "repair is not possible",
+            SetStatus::Incomplete => "main packet missing, cannot verify",
+        }
+    }
+
+    fn exit_code(self) -> i32 {
+        match self {
+            SetStatus::Correct => 0,
+            SetStatus::RepairRequired => 1,
+            SetStatus::RepairImpossible | SetStatus::Incomplete => 2,
+        }
+    }
+}
+
+/// Repair every recovery set found next to `par2_file`, each on its own
+fn repair_all_sets(
+    par2_file: &str,
+    extra_files: &[PathBuf],
+    verify_config: &par2rs::verify::VerificationConfig,
+    base_path_override: Option<&Path>,
+    quiet: bool,
+) -> Result<()> {
+    let par2_files = par2rs::par2_files::collect_all_par2_files(
+        Path::new(par2_file),
+        extra_files,
+        verify_config.discover_volumes,
+    );
+    let set_ids: Vec<_> = par2rs::par2_files::load_par2_packets(&par2_files, false, false)
+        .group_by_set_id()
+        .into_iter()
+        .map(|(set_id, _)| set_id)
+        .collect();
+    anyhow::ensure!(!set_ids.is_empty(), "No recovery sets found");
+
+    let mut summary = Vec::with_capacity(set_ids.len());
+    for set_id in set_ids {
+        if !quiet {
+            println!("\nRecovery set {set_id}:\n");
+        }
+        let config = par2rs::verify::VerificationConfig {
+            set_id: Some(set_id),
+            ..verify_config.clone()
+        };
+        let outcome = par2rs::repair::repair_files_with_base_path_and_extra_files(
+            par2_file,
+            Box::new(par2rs::repair::ConsoleReporter::new(quiet)),
+            &config,
+            base_path_override,
+            extra_files,
+        );
+        let success = match outcome {
+            Ok((context, result)) => {
+                if !quiet {
+                    context.recovery_set.print_statistics();
+                    result.print_result();
+                }
+                result.is_success()
+            }
+            Err(err) => {
+                eprintln!("Failed to repair recovery set {set_id}: {err}");
+                false
+            }
+        };
+        summary.push((set_id, success));
+    }
+
+    if !quiet {
+        println!("\nSummary of {} recovery sets:", summary.len());
+        for (set_id, success) in &summary {
+            let status = if *success { "ok" } else { "repair failed" };
+            println!("  {set_id}: {status}");
+        }
+    }
+
+    if summary.iter().all(|(_, success)| *success) {
+        Ok(())
+    } else {
+        std::process::exit(2);
+    }
+}
+
 fn handle_repair(matches: &clap::ArgMatches) -> Result<()> {
     let noise_level = parse_noise_level(matches.get_count("verbose"), matches.get_count("quiet"))
         .map_err(anyhow::Error::msg)?;
@@ -824,6 +1622,16 @@ fn handle_repair(matches: &clap::ArgMatches) -> Result<()> {
             .with_context(|| format!("Failed to locate PAR2 file for {}", par2_file))?;
     let resolved_par2_file = resolved_par2_file.to_string_lossy().into_owned();
 
+    if matches.get_flag("all_sets") {
+        return repair_all_sets(
+            &resolved_par2_file,
+            &extra_files,
+            &verify_config,
+            base_path_override.as_deref(),
+            quiet,
+        );
+    }
+
     let (context, result) = par2rs::repair::repair_files_with_base_path_and_extra_files(
         &resolved_par2_file,
         Box::new(par2rs::repair::ConsoleReporter::new(quiet)),
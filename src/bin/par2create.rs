@@ -82,7 +82,7 @@ fn main() -> Result<()> {
             Arg::new("file_threads")
                 .short('T')
                 .long("file-threads")
-                .help("Number of files hashed in parallel")
+                .help("Number of threads reading source files ahead of processing")
                 .value_name("N"),
         )
         .arg(
@@ -210,6 +210,7 @@ fn main() -> Result<()> {
             .map(String::as_str),
         source_inputs,
         matches.get_flag("recurse"),
+        &par2rs::create::SourceFilter::default(),
     )
     .map_err(anyhow::Error::msg)?;
     reject_par1_create_target(&output_name)?;
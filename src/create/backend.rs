@@ -133,6 +133,29 @@ impl CreateRecoveryBackend {
             .for_each(|chunk| chunk[..chunk_len].fill(0));
     }
 
+    /// Start a chunk on top of partial recovery data instead of zeros
+    ///
+    /// `load` fills each recovery block's output chunk; inputs added afterwards
+    /// accumulate onto it, so recovery data can be built up over several passes.
+    pub fn begin_chunk_with<E>(
+        &mut self,
+        chunk_len: usize,
+        mut load: impl FnMut(usize, &mut [u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.chunk_len = chunk_len;
+        self.batch_len = 0;
+
+        debug_assert!(self
+            .output_chunks
+            .iter()
+            .all(|buffer| chunk_len <= buffer.len()));
+
+        self.output_chunks
+            .iter_mut()
+            .enumerate()
+            .try_for_each(|(recovery_idx, chunk)| load(recovery_idx, &mut chunk[..chunk_len]))
+    }
+
     #[inline]
     pub fn prepare_transfer_buffer(&mut self, ring_index: usize) -> &mut [u8] {
         let idx = ring_index % self.transfer_buffers.len();
@@ -189,6 +212,24 @@ impl CreateRecoveryBackend {
             });
     }
 
+    /// Flush pending inputs and hand each recovery output chunk to `write`
+    ///
+    /// `write` receives the recovery block index and the chunk's bytes; the
+    /// first error stops the remaining writes.
+    pub fn finish_chunk_with<E>(
+        &mut self,
+        mut write: impl FnMut(usize, &[u8]) -> Result<(), E>,
+    ) -> Result<(), E> {
+        self.flush_batch();
+
+        self.output_chunks
+            .iter()
+            .enumerate()
+            .try_for_each(|(recovery_idx, output_chunk)| {
+                write(recovery_idx, &output_chunk[..self.chunk_len])
+            })
+    }
+
     #[inline]
     pub fn recovery_blocks(&self, block_size: usize) -> Vec<(u16, Vec<u8>)> {
         self.recovery_exponents
@@ -374,6 +415,49 @@ mod tests {
             });
     }
 
+    #[test]
+    fn backend_accumulates_onto_partial_recovery_data() {
+        let block_size = 32;
+        let source_count = 4;
+        let encoder = RecoveryBlockEncoder::new(block_size, source_count);
+        let inputs = (0..source_count)
+            .map(|src| {
+                (0..block_size)
+                    .map(|byte| (src * 31 + byte) as u8)
+                    .collect()
+            })
+            .collect::<Vec<Vec<u8>>>();
+
+        // Two passes of two sources each, carrying the output over in between
+        let mut backend = CreateRecoveryBackend::new(encoder.base_values(), 0, 2, block_size);
+        let mut partial = vec![vec![0u8; block_size]; 2];
+        for (pass_idx, pass_inputs) in inputs.chunks(2).enumerate() {
+            backend
+                .begin_chunk_with(block_size, |idx, out| {
+                    out.copy_from_slice(&partial[idx]);
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+            for (offset, input) in pass_inputs.iter().enumerate() {
+                backend.add_input(pass_idx * 2 + offset, input);
+            }
+            backend
+                .finish_chunk_with(|idx, data| {
+                    partial[idx].copy_from_slice(data);
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+        }
+
+        let refs = inputs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        for (exponent, recovery_data) in partial.iter().enumerate() {
+            let expected = encoder
+                .encode_recovery_block(exponent as u16, &refs)
+                .unwrap();
+            assert_eq!(recovery_data, &expected);
+        }
+    }
+
     #[test]
     fn backend_reuses_fixed_transfer_buffers() {
         let encoder = RecoveryBlockEncoder::new(64, 2);
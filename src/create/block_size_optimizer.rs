@@ -0,0 +1,191 @@
+//! Block size search that minimizes padding and recovery data
+//!
+//! The default block size aims for a fixed number of blocks, which wastes a
+//! lot of space when many small files each round up to whole blocks. The
+//! optimizer instead considers every block count the set can be split into
+//! and, for each, the smallest block size giving that count: a larger block
+//! with the same count only adds padding and recovery data. The caller then
+//! scores the candidates with its recovery block rules.
+
+use std::cmp::Ordering;
+
+/// Sizes that follow from using one block size for the source files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockSizeCost {
+    pub block_size: u64,
+    pub source_block_count: u32,
+    pub recovery_block_count: u32,
+    /// Zero bytes added to fill the last block of every file
+    pub padding: u64,
+    /// Recovery packets plus the block checksums repeated in every output file
+    pub recovery_size: u64,
+}
+
+impl BlockSizeCost {
+    /// What the optimizer minimizes: padding plus recovery output
+    pub fn total(&self) -> u64 {
+        self.padding + self.recovery_size
+    }
+}
+
+/// Outcome of the block size search, with the default choice for comparison
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockSizeChoice {
+    pub chosen: BlockSizeCost,
+    /// What the default target-block-count heuristic would have used, if it applies
+    pub default: Option<BlockSizeCost>,
+    /// Number of block sizes scored
+    pub candidates: usize,
+}
+
+impl BlockSizeChoice {
+    /// Lines explaining the trade-off, for the create report
+    pub fn describe(&self) -> Vec<String> {
+        let describe_cost = |cost: &BlockSizeCost| {
+            format!(
+                "{} blocks, {} bytes padding, {} bytes of recovery data ({} recovery blocks)",
+                cost.source_block_count,
+                cost.padding,
+                cost.recovery_size,
+                cost.recovery_block_count
+            )
+        };
+
+        let mut lines = vec![
+            format!(
+                "Optimized block size: {} bytes, best of {} candidates",
+                self.chosen.block_size, self.candidates
+            ),
+            format!("  {}", describe_cost(&self.chosen)),
+        ];
+        if let Some(default) = &self.default {
+            lines.push(format!(
+                "  default block size {} would use {}",
+                default.block_size,
+                describe_cost(default)
+            ));
+            let saving = default.total().saturating_sub(self.chosen.total());
+            lines.push(format!("  saving {} bytes", saving));
+        }
+        lines
+    }
+}
+
+/// Candidate block sizes, each with its source block count
+///
+/// Every candidate is the smallest multiple of 4 that splits the files into
+/// its block count, for all counts up to `max_blocks`. Returned from the
+/// smallest block size up; empty when even one block per file exceeds
+/// `max_blocks`.
+pub(super) fn block_size_candidates(file_sizes: &[u64], max_blocks: u64) -> Vec<(u64, u64)> {
+    // Work in 4-byte units: ceil(ceil(size / 4) / units) == ceil(size / (4 * units))
+    let sizes: Vec<u64> = file_sizes
+        .iter()
+        .map(|size| size.div_ceil(4))
+        .filter(|&units| units > 0)
+        .collect();
+    let Some(&largest) = sizes.iter().max() else {
+        return vec![(4, 0)];
+    };
+    if sizes.len() as u64 > max_blocks {
+        return Vec::new();
+    }
+
+    let blocks_at = |units: u64| -> u64 { sizes.iter().map(|size| size.div_ceil(units)).sum() };
+    let (mut low, mut high) = (1, largest);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if blocks_at(mid) <= max_blocks {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    let min_units = low;
+
+    // A file of `size` units needs more than k blocks below ceil(size / k) units,
+    // so the block count at `units` is the file count plus the thresholds above it
+    let mut thresholds: Vec<u64> = Vec::new();
+    for &size in &sizes {
+        for k in 1.. {
+            let threshold = size.div_ceil(k);
+            if threshold <= min_units {
+                break;
+            }
+            thresholds.push(threshold);
+        }
+    }
+    thresholds.sort_unstable();
+
+    let mut candidates = Vec::with_capacity(thresholds.len() + 1);
+    let mut units_list = vec![min_units];
+    units_list.extend(thresholds.iter().copied());
+    units_list.dedup();
+    for units in units_list {
+        let above = thresholds.len() - thresholds.partition_point(|&t| t <= units);
+        candidates.push((units * 4, (sizes.len() + above) as u64));
+    }
+    candidates
+}
+
+/// Pick the cheapest candidate; ties go to the larger block size, which is faster
+pub(super) fn cheapest(costs: impl IntoIterator<Item = BlockSizeCost>) -> Option<BlockSizeCost> {
+    costs
+        .into_iter()
+        .min_by(|a, b| match a.total().cmp(&b.total()) {
+            Ordering::Equal => b.block_size.cmp(&a.block_size),
+            other => other,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blocks(file_sizes: &[u64], block_size: u64) -> u64 {
+        file_sizes
+            .iter()
+            .map(|size| size.div_ceil(block_size))
+            .sum()
+    }
+
+    #[test]
+    fn candidates_are_the_smallest_block_size_for_each_count() {
+        let file_sizes = [1, 13, 100, 1000, 4097, 0, 37];
+        let candidates = block_size_candidates(&file_sizes, 400);
+
+        // Brute force: the first block size (multiple of 4) reaching each new count
+        let mut expected = Vec::new();
+        let mut previous = u64::MAX;
+        for block_size in (4..=4100).step_by(4) {
+            let count = blocks(&file_sizes, block_size);
+            if count <= 400 && count < previous {
+                expected.push((block_size, count));
+                previous = count;
+            }
+        }
+        assert_eq!(candidates, expected);
+    }
+
+    #[test]
+    fn candidates_respect_the_block_limit() {
+        let candidates = block_size_candidates(&[10_000, 20_000], 10);
+        assert_eq!(candidates.first(), Some(&(3336, 9)));
+        assert!(candidates.iter().all(|&(_, count)| count <= 10));
+        assert!(block_size_candidates(&[1, 2, 3], 2).is_empty());
+        assert_eq!(block_size_candidates(&[0, 0], 2), vec![(4, 0)]);
+    }
+
+    #[test]
+    fn cheapest_prefers_larger_blocks_on_ties() {
+        let cost = |block_size, padding| BlockSizeCost {
+            block_size,
+            source_block_count: 1,
+            recovery_block_count: 1,
+            padding,
+            recovery_size: 100,
+        };
+        let chosen = cheapest([cost(4, 10), cost(8, 5), cost(16, 5)]).unwrap();
+        assert_eq!(chosen.block_size, 16);
+    }
+}
@@ -3,8 +3,10 @@
 use super::context::CreateContext;
 use super::error::CreateResult;
 use super::progress::{ConsoleCreateReporter, CreateReporter};
-use super::types::{CreateConfig, RecoveryFileScheme};
+use super::source_filter::{SourceFilter, SymlinkPolicy};
+use super::types::{CreateConfig, CustomPacket, RecoveryFileScheme};
 use crate::domain::SourceBlockCount;
+use std::io::Read;
 use std::path::PathBuf;
 
 /// Builder for CreateContext
@@ -27,6 +29,9 @@ use std::path::PathBuf;
 pub struct CreateContextBuilder {
     config: CreateConfig,
     reporter: Option<Box<dyn CreateReporter>>,
+    stream: Option<Box<dyn Read + Send>>,
+    source_filter: SourceFilter,
+    files_from: Option<PathBuf>,
 }
 
 impl CreateContextBuilder {
@@ -35,6 +40,9 @@ impl CreateContextBuilder {
         CreateContextBuilder {
             config: CreateConfig::default(),
             reporter: None,
+            stream: None,
+            source_filter: SourceFilter::default(),
+            files_from: None,
         }
     }
 
@@ -64,6 +72,15 @@ impl CreateContextBuilder {
         self
     }
 
+    /// Set the files of the non-recovery set
+    ///
+    /// These files are checksummed so verify can detect damage, but they are
+    /// left out of the Reed-Solomon input and cannot be repaired.
+    pub fn non_recovery_files(mut self, files: Vec<PathBuf>) -> Self {
+        self.config.non_recovery_files = files;
+        self
+    }
+
     /// Set base path used to derive source packet names.
     pub fn base_path(mut self, path: impl Into<PathBuf>) -> Self {
         self.config.base_path = Some(path.into());
@@ -88,6 +105,25 @@ impl CreateContextBuilder {
         self
     }
 
+    /// Set subslice size in bytes, producing a packed set
+    ///
+    /// Files are padded only to a multiple of the subslice size, so many small
+    /// files can share one block. The block size must be a multiple of it.
+    pub fn subslice_size(mut self, size: u64) -> Self {
+        self.config.subslice_size = Some(size);
+        self
+    }
+
+    /// Choose the block size that minimizes padding plus recovery data
+    ///
+    /// Every block size that changes the block count is scored for the
+    /// requested protection level. Cannot be combined with an explicit block
+    /// size, block count or subslice size.
+    pub fn optimize_block_size(mut self, enabled: bool) -> Self {
+        self.config.optimize_block_size = enabled;
+        self
+    }
+
     /// Set redundancy percentage.
     ///
     /// Typical values: 5-10%
@@ -150,7 +186,7 @@ impl CreateContextBuilder {
         self
     }
 
-    /// Set number of file-level worker threads.
+    /// Set number of threads reading source data ahead of the encoder.
     pub fn file_thread_count(mut self, count: usize) -> Self {
         self.config.file_thread_count = Some(count);
         self
@@ -165,6 +201,150 @@ impl CreateContextBuilder {
         self
     }
 
+    /// Emit Unicode Filename packets for non-ASCII packet names
+    ///
+    /// The FileDescription packet keeps the UTF-8 name; the UniFileN packet
+    /// carries the UTF-16 form that par2cmdline clients use to restore it.
+    pub fn unicode_filenames(mut self, enabled: bool) -> Self {
+        self.config.unicode_filenames = enabled;
+        self
+    }
+
+    /// Add a comment to the recovery set
+    ///
+    /// Each comment is written to every output file as an ASCII comment packet
+    /// plus a Unicode comment packet that refers back to it.
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.config.comments.push(comment.into());
+        self
+    }
+
+    /// Embed the source data in Input File Slice (FileSlic) packets
+    ///
+    /// Each IFSC block of every file is copied into the recovery volumes, so a
+    /// lost file can be restored by straight copy without Reed-Solomon work.
+    pub fn embed_source_data(mut self, enabled: bool) -> Self {
+        self.config.embed_source_data = enabled;
+        self
+    }
+
+    /// Add an application-specific packet to every output file
+    ///
+    /// The packet is written after the critical packets with the recovery set ID
+    /// of the new set, and is preserved as `Packet::Unknown` when read back.
+    pub fn custom_packet(mut self, packet_type: [u8; 16], body: impl Into<Vec<u8>>) -> Self {
+        self.config.custom_packets.push(CustomPacket {
+            packet_type,
+            body: body.into(),
+        });
+        self
+    }
+
+    /// Store the recovery data in an external headerless file
+    ///
+    /// The recovery slices are written end to end to `<base>.rfsc` and described
+    /// by a Recovery File Slice Checksum (RFSC) packet in the index file, so the
+    /// parity can be kept as a flat blob apart from the small PAR2 index.
+    pub fn external_recovery_data(mut self, enabled: bool) -> Self {
+        self.config.external_recovery_data = enabled;
+        self
+    }
+
+    /// Write `copies` copies of the critical packets in every recovery volume
+    ///
+    /// The copies are spread evenly from the start of each volume to its end.
+    /// By default the count grows with the number of blocks in the volume.
+    pub fn critical_copies(mut self, copies: u32) -> Self {
+        self.config.critical_copies = Some(copies);
+        self
+    }
+
+    /// Reuse and record file hashes in the cache file at `path`
+    ///
+    /// Source files whose size, modification time and inode match the cached
+    /// entry are still read for the recovery data, but not hashed again.
+    pub fn hash_cache(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.hash_cache = Some(path.into());
+        self
+    }
+
+    /// Record encoding progress in the checkpoint file at `path`
+    ///
+    /// Outputs stay under their temporary names until the set is complete, and
+    /// are kept along with the checkpoint if the run fails, so that it can be
+    /// resumed.
+    pub fn checkpoint(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.checkpoint = Some(path.into());
+        self
+    }
+
+    /// Continue from the checkpoint instead of starting over
+    ///
+    /// Source files must be unchanged since the checkpoint was written. With
+    /// no checkpoint yet, the run starts from the beginning.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.config.resume = resume;
+        self
+    }
+
+    /// Also protect the files listed in the file at `path`
+    ///
+    /// One path per line, or NUL separated when the list holds a NUL.
+    pub fn files_from(mut self, path: impl Into<PathBuf>) -> Self {
+        self.files_from = Some(path.into());
+        self
+    }
+
+    /// Only protect source files whose PAR2 name matches the glob `pattern`
+    ///
+    /// See `SourceFilter` for the pattern syntax. Repeatable; a file matching
+    /// any include pattern is kept.
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.source_filter = self.source_filter.include(pattern);
+        self
+    }
+
+    /// Leave out source files whose PAR2 name matches the glob `pattern`
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.source_filter = self.source_filter.exclude(pattern);
+        self
+    }
+
+    /// Leave out source files with a hidden component in their PAR2 name
+    pub fn skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.source_filter = self.source_filter.skip_hidden(skip_hidden);
+        self
+    }
+
+    /// Follow, skip or refuse symbolic links among the source files
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.source_filter = self.source_filter.symlinks(policy);
+        self
+    }
+
+    /// Protect the data read from `reader`, such as standard input, as a
+    /// file called `name`
+    ///
+    /// The stream replaces the source files and is read only once. Up to half
+    /// the memory limit is read when the context is built; a longer stream
+    /// needs an explicit block size and recovery block count, since its length
+    /// is known only once it ends.
+    pub fn stream_source(
+        mut self,
+        name: impl Into<String>,
+        reader: impl Read + Send + 'static,
+    ) -> Self {
+        self.config.stream_name = Some(name.into());
+        self.stream = Some(Box::new(reader));
+        self
+    }
+
+    /// Copy the stream data to the file at `path` as it is read
+    pub fn stream_tee(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.stream_tee = Some(path.into());
+        self
+    }
+
     /// Set custom progress reporter
     pub fn reporter(mut self, reporter: Box<dyn CreateReporter>) -> Self {
         self.reporter = Some(reporter);
@@ -180,7 +360,9 @@ impl CreateContextBuilder {
     /// Build the CreateContext
     ///
     /// Validates configuration and initializes the context
-    pub fn build(self) -> CreateResult<CreateContext> {
+    pub fn build(mut self) -> CreateResult<CreateContext> {
+        self.select_source_files()?;
+
         // Validate configuration
         self.config.validate()?;
 
@@ -189,7 +371,50 @@ impl CreateContextBuilder {
             .reporter
             .unwrap_or_else(|| Box::new(ConsoleCreateReporter::new(false)));
 
-        CreateContext::new(self.config, reporter)
+        CreateContext::new(self.config, reporter, self.stream)
+    }
+}
+
+impl CreateContextBuilder {
+    /// Add the listed files and apply the source filter to the source files
+    ///
+    /// Files are matched by the name they get in the PAR2 set, after symbolic
+    /// links are skipped or refused as the policy says.
+    fn select_source_files(&mut self) -> CreateResult<()> {
+        use super::context::{default_output_base_path, packet_name_below};
+        use super::source_filter::read_file_list;
+        use std::path::Path;
+
+        if let Some(list) = &self.files_from {
+            self.config.source_files.extend(read_file_list(list)?);
+        }
+        if self.source_filter.symlink_policy() != SymlinkPolicy::Follow {
+            use super::source_filter::{classify_source, SourceKind};
+
+            let policy = self.source_filter.symlink_policy();
+            let mut kept = Vec::with_capacity(self.config.source_files.len());
+            for path in std::mem::take(&mut self.config.source_files) {
+                // Missing files are left for the scan to report
+                if std::fs::symlink_metadata(&path).is_err()
+                    || !matches!(classify_source(&path, policy)?, SourceKind::Skipped)
+                {
+                    kept.push(path);
+                }
+            }
+            self.config.source_files = kept;
+        }
+        if !self.source_filter.is_empty() {
+            let base_path = self
+                .config
+                .base_path
+                .clone()
+                .unwrap_or_else(|| default_output_base_path(&self.config.output_name));
+            let filter = &self.source_filter;
+            self.config
+                .source_files
+                .retain(|path| filter.allows(Path::new(&packet_name_below(&base_path, path))));
+        }
+        Ok(())
     }
 }
 
@@ -228,8 +453,20 @@ mod tests {
             .first_recovery_block(5)
             .memory_limit(1024 * 1024)
             .overwrite_existing(true)
+            .unicode_filenames(true)
+            .embed_source_data(true)
+            .external_recovery_data(true)
+            .critical_copies(4)
+            .hash_cache("/tmp/base/.par2rs-hashcache")
+            .checkpoint("/tmp/base/mydata.par2.par2rs-checkpoint")
+            .resume(true)
+            .non_recovery_files(vec![PathBuf::from("huge.iso")])
+            .custom_packet(*b"ACME\0\0\0\0IngestID", b"job-1234".to_vec())
+            .comment("ingest batch 2026-10")
+            .comment("source tape 14")
             .base_path(PathBuf::from("/tmp/base"))
             .source_block_count(1000)
+            .subslice_size(512)
             .recovery_block_count(50)
             .recovery_file_count(4)
             .recovery_file_scheme(RecoveryFileScheme::Uniform);
@@ -242,7 +479,36 @@ mod tests {
         assert_eq!(builder.config.first_recovery_block, 5);
         assert_eq!(builder.config.memory_limit, Some(1024 * 1024));
         assert!(builder.config.overwrite_existing);
+        assert!(builder.config.unicode_filenames);
+        assert!(builder.config.embed_source_data);
+        assert_eq!(
+            builder.config.hash_cache,
+            Some(PathBuf::from("/tmp/base/.par2rs-hashcache"))
+        );
+        assert!(builder.config.external_recovery_data);
+        assert_eq!(builder.config.critical_copies, Some(4));
+        assert_eq!(
+            builder.config.checkpoint,
+            Some(PathBuf::from("/tmp/base/mydata.par2.par2rs-checkpoint"))
+        );
+        assert!(builder.config.resume);
+        assert_eq!(
+            builder.config.non_recovery_files,
+            vec![PathBuf::from("huge.iso")]
+        );
+        assert_eq!(
+            builder.config.custom_packets,
+            vec![CustomPacket {
+                packet_type: *b"ACME\0\0\0\0IngestID",
+                body: b"job-1234".to_vec(),
+            }]
+        );
+        assert_eq!(
+            builder.config.comments,
+            vec!["ingest batch 2026-10", "source tape 14"]
+        );
         assert_eq!(builder.config.base_path, Some(PathBuf::from("/tmp/base")));
+        assert_eq!(builder.config.subslice_size, Some(512));
         assert_eq!(builder.config.recovery_block_count, Some(50));
         assert_eq!(builder.config.recovery_file_count, Some(4));
         assert_eq!(
@@ -0,0 +1,601 @@
+//! Checkpoints for resuming an interrupted create
+//!
+//! Recovery data is accumulated in the (staged) output files a group of source
+//! blocks at a time, so after each group the files hold the partial recovery
+//! data of every block read so far. A checkpoint records how far encoding got,
+//! the running file and subslice hashes at that point, and the CRC32 of every
+//! recovery chunk on disk. A later run with the same settings and unchanged
+//! source files picks up from the last checkpoint instead of starting over.
+//!
+//! A crash while a group is being written can leave some recovery chunks with
+//! that group added and others without it. As recovery data is accumulated by
+//! XOR, the first group after a resume is encoded on its own and reconciled
+//! chunk by chunk: a chunk still matching its checkpoint CRC gets the group
+//! added, one that matches once the group is taken out already has it.
+//!
+//! The checkpoint is a text file: a header line, then one line per item with
+//! paths and binary state hex-encoded.
+
+use super::error::{CreateError, CreateResult};
+use super::error_helpers::open_for_reading;
+use super::recovery_output::RecoveryOutput;
+use super::source_file::SourceFileInfo;
+use super::source_reader::SourceHashes;
+use crate::hash_cache::FileStamp;
+use md5::digest::common::hazmat::SerializableState;
+use md5::Md5;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Suffix appended to the index file name for the default checkpoint location
+pub const DEFAULT_SUFFIX: &str = ".par2rs-checkpoint";
+
+const HEADER: &str = "par2rs-create-checkpoint 1";
+
+/// Source data encoded between checkpoints, at the least
+const MIN_CHECKPOINT_BYTES: u64 = 1 << 30;
+
+/// Default checkpoint location for the set with this index file
+pub fn default_path(par2_file: &Path) -> PathBuf {
+    let mut name = par2_file.as_os_str().to_owned();
+    name.push(DEFAULT_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Source blocks to encode between checkpoints when a block fits in one chunk
+///
+/// Every checkpoint writes out and reads back all the recovery data, so
+/// groups cover at least 16 times the recovery data (and at least 1 GiB),
+/// keeping that extra I/O small next to reading the source files.
+pub(super) fn checkpoint_group_blocks(
+    block_size: u64,
+    recovery_count: usize,
+    source_count: usize,
+) -> usize {
+    let bytes = (16 * recovery_count as u64 * block_size).max(MIN_CHECKPOINT_BYTES);
+    (bytes.div_ceil(block_size.max(1)) as usize).clamp(1, source_count.max(1))
+}
+
+/// Settings a checkpoint must match to be resumed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct CheckpointParams {
+    pub block_size: u64,
+    pub subslice_size: u64,
+    pub first_recovery_block: u32,
+    pub recovery_block_count: u32,
+    /// Final paths of the output files, in creation order
+    pub outputs: Vec<PathBuf>,
+    /// Source files with their stamps when encoding started
+    pub sources: Vec<(PathBuf, FileStamp)>,
+}
+
+/// Encoding progress recorded by a checkpoint
+struct Progress {
+    /// Source blocks whose data is in the recovery output
+    blocks_done: usize,
+    bytes_hashed: u64,
+    /// Files whose hashes came from the hash cache
+    known_files: Vec<bool>,
+    /// Serialized MD5 state of every source file
+    file_states: Vec<Vec<u8>>,
+    /// Serialized MD5 state and CRC32 of every completed subslice
+    subslice_states: Vec<(Vec<u8>, u32)>,
+    /// CRC32 of every recovery chunk on disk, chunk by chunk within each block
+    chunk_crcs: Vec<u32>,
+}
+
+/// Checkpoint file of one create run
+pub(super) struct Checkpoint {
+    path: PathBuf,
+    params: CheckpointParams,
+    chunk_size: usize,
+    /// Source blocks per group, fixed by the first run so groups line up
+    group_blocks: Option<usize>,
+    saved: Option<Progress>,
+    /// CRC32 of every recovery chunk written since the last save
+    pending_crcs: Vec<u32>,
+}
+
+impl Checkpoint {
+    /// Start a checkpoint at `path` for a run from the beginning
+    pub fn new(path: PathBuf, params: CheckpointParams, chunk_size: usize) -> Self {
+        Checkpoint {
+            path,
+            params,
+            chunk_size,
+            group_blocks: None,
+            saved: None,
+            pending_crcs: Vec::new(),
+        }
+    }
+
+    /// Load the checkpoint at `path`, or `None` if there is none
+    ///
+    /// Fails if the checkpoint was made with other settings or source files
+    /// have changed since, as the recovery data on disk then no longer fits.
+    pub fn load(path: PathBuf, params: CheckpointParams) -> CreateResult<Option<Self>> {
+        let contents = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(CreateError::FileReadError {
+                    file: path.to_string_lossy().to_string(),
+                    source: e,
+                })
+            }
+        };
+        let Some((saved_params, (chunk_size, group_blocks), progress)) =
+            parse_checkpoint(&contents)
+        else {
+            return Err(CreateError::Other(format!(
+                "Checkpoint {} is malformed",
+                path.display()
+            )));
+        };
+
+        let mismatch = |what: &str| {
+            CreateError::Other(format!(
+                "Checkpoint {} does not match this create: {what}; remove it to start over",
+                path.display()
+            ))
+        };
+        if saved_params.outputs != params.outputs {
+            return Err(mismatch("the output files differ"));
+        }
+        if (
+            saved_params.block_size,
+            saved_params.subslice_size,
+            saved_params.first_recovery_block,
+            saved_params.recovery_block_count,
+        ) != (
+            params.block_size,
+            params.subslice_size,
+            params.first_recovery_block,
+            params.recovery_block_count,
+        ) {
+            return Err(mismatch("the block layout or recovery block count differs"));
+        }
+        if saved_params.sources.len() != params.sources.len() {
+            return Err(mismatch("the source files differ"));
+        }
+        for ((saved_path, saved_stamp), (current_path, current_stamp)) in
+            saved_params.sources.iter().zip(&params.sources)
+        {
+            if saved_path != current_path {
+                return Err(mismatch("the source files differ"));
+            }
+            if saved_stamp != current_stamp {
+                return Err(mismatch(&format!(
+                    "{} has changed since",
+                    current_path.display()
+                )));
+            }
+        }
+
+        let recovery_count = params.recovery_block_count as usize;
+        let chunks = (params.block_size as usize).div_ceil(chunk_size.max(1));
+        if progress.chunk_crcs.len() != recovery_count * chunks {
+            return Err(CreateError::Other(format!(
+                "Checkpoint {} is malformed",
+                path.display()
+            )));
+        }
+
+        Ok(Some(Checkpoint {
+            path,
+            params,
+            chunk_size,
+            group_blocks: Some(group_blocks),
+            saved: Some(progress),
+            pending_crcs: Vec::new(),
+        }))
+    }
+
+    /// Where the checkpoint is saved
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Chunk size the recovery data is being encoded with
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Source blocks per group: those of the checkpointed run, or `default`
+    pub fn group_blocks_or(&mut self, default: usize) -> usize {
+        *self.group_blocks.get_or_insert(default)
+    }
+
+    /// Whether this continues an earlier run
+    pub fn is_resumed(&self) -> bool {
+        self.saved.is_some()
+    }
+
+    /// Source blocks already encoded into the recovery output
+    pub fn blocks_done(&self) -> usize {
+        self.saved
+            .as_ref()
+            .map_or(0, |progress| progress.blocks_done)
+    }
+
+    /// Which files took their hashes from the hash cache, when resuming
+    pub fn known_files(&self) -> Option<&[bool]> {
+        self.saved
+            .as_ref()
+            .map(|progress| progress.known_files.as_slice())
+    }
+
+    fn chunks_per_block(&self) -> usize {
+        (self.params.block_size as usize).div_ceil(self.chunk_size)
+    }
+
+    fn chunk_index(&self, recovery_idx: usize, block_offset: u64) -> usize {
+        recovery_idx * self.chunks_per_block() + (block_offset as usize / self.chunk_size)
+    }
+
+    /// Restore the running hashes saved by the checkpoint
+    ///
+    /// The first 16k of every file already started is read again, which is
+    /// cheaper than storing it.
+    pub fn restore_hashes(
+        &self,
+        hashes: &mut SourceHashes,
+        source_files: &[SourceFileInfo],
+        file_block_meta: &[(u32, u32)],
+    ) -> CreateResult<()> {
+        let Some(progress) = &self.saved else {
+            return Ok(());
+        };
+        let malformed = || {
+            CreateError::Other(format!(
+                "Checkpoint {} holds invalid hash state",
+                self.path.display()
+            ))
+        };
+        let md5_state = |bytes: &[u8]| -> CreateResult<Md5> {
+            let state = bytes.try_into().map_err(|_| malformed())?;
+            Md5::deserialize(&state).map_err(|_| malformed())
+        };
+
+        if progress.file_states.len() != hashes.file_md5_states.len()
+            || progress.subslice_states.len() > hashes.block_md5_states.len()
+        {
+            return Err(malformed());
+        }
+        for (state, saved) in hashes.file_md5_states.iter_mut().zip(&progress.file_states) {
+            *state = md5_state(saved)?;
+        }
+        for (subslice, (saved, crc32)) in progress.subslice_states.iter().enumerate() {
+            hashes.block_md5_states[subslice] = md5_state(saved)?;
+            hashes.block_crc32_states[subslice] = crc32fast::Hasher::new_with_initial(*crc32);
+        }
+        hashes.bytes_hashed = progress.bytes_hashed;
+
+        let subslices_done = progress.subslice_states.len() as u64;
+        for (file_idx, file) in source_files.iter().enumerate() {
+            let (_, first_subslice) = file_block_meta[file_idx];
+            if hashes.known_files[file_idx] || subslices_done <= first_subslice as u64 {
+                continue;
+            }
+            let read = (subslices_done - first_subslice as u64) * self.params.subslice_size;
+            let prefix = &mut hashes.file_16k_buffers[file_idx];
+            let len = (read as usize).min(prefix.len());
+            open_for_reading(&file.path)?
+                .read_exact(&mut prefix[..len])
+                .map_err(|e| CreateError::FileReadError {
+                    file: file.path.to_string_lossy().to_string(),
+                    source: e,
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Note the data of a recovery chunk just written
+    pub fn record_chunk(&mut self, recovery_idx: usize, block_offset: u64, data: &[u8]) {
+        if self.pending_crcs.is_empty() {
+            let chunks = self.params.recovery_block_count as usize * self.chunks_per_block();
+            self.pending_crcs = vec![0; chunks];
+        }
+        let index = self.chunk_index(recovery_idx, block_offset);
+        self.pending_crcs[index] = crc32fast::hash(data);
+    }
+
+    /// Add the first group after a resume onto a recovery chunk
+    ///
+    /// `contribution` is that group's recovery data on its own. The chunk on
+    /// disk either still holds the checkpointed data, or already has the group
+    /// added by the interrupted run; anything else means the output was
+    /// modified and cannot be resumed.
+    pub fn resume_chunk(
+        &mut self,
+        recovery_output: &mut RecoveryOutput,
+        recovery_idx: usize,
+        block_offset: u64,
+        contribution: &[u8],
+    ) -> CreateResult<()> {
+        let saved_crc = self
+            .saved
+            .as_ref()
+            .map(|progress| progress.chunk_crcs[self.chunk_index(recovery_idx, block_offset)]);
+        let mut on_disk = vec![0u8; contribution.len()];
+        recovery_output.read_chunk(recovery_idx, block_offset, &mut on_disk)?;
+
+        let on_disk_crc = crc32fast::hash(&on_disk);
+        for (byte, added) in on_disk.iter_mut().zip(contribution) {
+            *byte ^= added;
+        }
+        if saved_crc == Some(on_disk_crc) {
+            recovery_output.write_chunk(recovery_idx, block_offset, &on_disk)?;
+            self.record_chunk(recovery_idx, block_offset, &on_disk);
+        } else if saved_crc == Some(crc32fast::hash(&on_disk)) {
+            // Already added before the interruption; the data on disk is final
+            for (byte, added) in on_disk.iter_mut().zip(contribution) {
+                *byte ^= added;
+            }
+            self.record_chunk(recovery_idx, block_offset, &on_disk);
+        } else {
+            return Err(CreateError::Other(format!(
+                "Recovery data of block {recovery_idx} no longer matches checkpoint {}",
+                self.path.display()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Record that the first `blocks_done` source blocks are encoded
+    ///
+    /// The recovery output is synced first, so the checkpoint never describes
+    /// data that is not on disk. The file is replaced in one step.
+    pub fn save(
+        &mut self,
+        blocks_done: usize,
+        subslices_done: usize,
+        hashes: &SourceHashes,
+        recovery_output: &mut RecoveryOutput,
+    ) -> CreateResult<()> {
+        recovery_output.sync()?;
+
+        let progress = Progress {
+            blocks_done,
+            bytes_hashed: hashes.bytes_hashed,
+            known_files: hashes.known_files.clone(),
+            file_states: hashes
+                .file_md5_states
+                .iter()
+                .map(|state| state.serialize().to_vec())
+                .collect(),
+            subslice_states: hashes.block_md5_states[..subslices_done]
+                .iter()
+                .zip(&hashes.block_crc32_states)
+                .map(|(md5, crc32)| (md5.serialize().to_vec(), crc32.clone().finalize()))
+                .collect(),
+            chunk_crcs: std::mem::take(&mut self.pending_crcs),
+        };
+
+        let group_blocks = self.group_blocks.unwrap_or(blocks_done);
+        let contents = format_checkpoint(&self.params, (self.chunk_size, group_blocks), &progress);
+        let write_error = |source| CreateError::FileCreateError {
+            file: self.path.to_string_lossy().to_string(),
+            source,
+        };
+        let mut temp_name = self.path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+        std::fs::File::create(&temp_path)
+            .and_then(|mut file| {
+                file.write_all(contents.as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| std::fs::rename(&temp_path, &self.path))
+            .map_err(write_error)?;
+
+        self.saved = Some(progress);
+        Ok(())
+    }
+
+    /// Delete the checkpoint once the set is complete
+    pub fn remove(self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+fn hex_path(path: &Path) -> String {
+    hex::encode(path.to_string_lossy().as_bytes())
+}
+
+/// Format a checkpoint; `grouping` is the chunk size and source blocks per group
+fn format_checkpoint(
+    params: &CheckpointParams,
+    grouping: (usize, usize),
+    progress: &Progress,
+) -> String {
+    let (chunk_size, group_blocks) = grouping;
+    let mut lines = vec![
+        HEADER.to_string(),
+        format!(
+            "params {} {} {} {} {} {}",
+            params.block_size,
+            params.subslice_size,
+            chunk_size,
+            group_blocks,
+            params.first_recovery_block,
+            params.recovery_block_count
+        ),
+    ];
+    lines.extend(
+        params
+            .outputs
+            .iter()
+            .map(|path| format!("output {}", hex_path(path))),
+    );
+    lines.extend(params.sources.iter().map(|(path, stamp)| {
+        format!(
+            "source {} {} {} {}",
+            hex_path(path),
+            stamp.size,
+            stamp.modified_nanos,
+            stamp.inode
+        )
+    }));
+    lines.push(format!(
+        "progress {} {}",
+        progress.blocks_done, progress.bytes_hashed
+    ));
+    lines.extend(
+        progress
+            .known_files
+            .iter()
+            .zip(&progress.file_states)
+            .map(|(&known, state)| format!("file {} {}", u8::from(known), hex::encode(state))),
+    );
+    lines.extend(
+        progress
+            .subslice_states
+            .iter()
+            .map(|(state, crc32)| format!("subslice {} {:08x}", hex::encode(state), crc32)),
+    );
+    let crcs: Vec<u8> = progress
+        .chunk_crcs
+        .iter()
+        .flat_map(|crc32| crc32.to_le_bytes())
+        .collect();
+    lines.push(format!("chunks {}", hex::encode(crcs)));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn parse_checkpoint(contents: &str) -> Option<(CheckpointParams, (usize, usize), Progress)> {
+    let mut lines = contents.lines();
+    if lines.next()? != HEADER {
+        return None;
+    }
+
+    let mut params = CheckpointParams {
+        block_size: 0,
+        subslice_size: 0,
+        first_recovery_block: 0,
+        recovery_block_count: 0,
+        outputs: Vec::new(),
+        sources: Vec::new(),
+    };
+    let (mut chunk_size, mut group_blocks) = (0, 0);
+    let mut progress = Progress {
+        blocks_done: 0,
+        bytes_hashed: 0,
+        known_files: Vec::new(),
+        file_states: Vec::new(),
+        subslice_states: Vec::new(),
+        chunk_crcs: Vec::new(),
+    };
+    let path = |field: &str| -> Option<PathBuf> {
+        Some(PathBuf::from(
+            String::from_utf8(hex::decode(field).ok()?).ok()?,
+        ))
+    };
+
+    for line in lines.filter(|line| !line.is_empty()) {
+        let fields: Vec<&str> = line.split(' ').collect();
+        match fields[..] {
+            ["params", block_size, subslice_size, chunk, group, first, count] => {
+                params.block_size = block_size.parse().ok()?;
+                params.subslice_size = subslice_size.parse().ok()?;
+                chunk_size = chunk.parse().ok()?;
+                group_blocks = group.parse().ok()?;
+                params.first_recovery_block = first.parse().ok()?;
+                params.recovery_block_count = count.parse().ok()?;
+            }
+            ["output", output] => params.outputs.push(path(output)?),
+            ["source", source, size, modified_nanos, inode] => params.sources.push((
+                path(source)?,
+                FileStamp {
+                    size: size.parse().ok()?,
+                    modified_nanos: modified_nanos.parse().ok()?,
+                    inode: inode.parse().ok()?,
+                },
+            )),
+            ["progress", blocks_done, bytes_hashed] => {
+                progress.blocks_done = blocks_done.parse().ok()?;
+                progress.bytes_hashed = bytes_hashed.parse().ok()?;
+            }
+            ["file", known, state] => {
+                progress.known_files.push(known == "1");
+                progress.file_states.push(hex::decode(state).ok()?);
+            }
+            ["subslice", state, crc32] => progress.subslice_states.push((
+                hex::decode(state).ok()?,
+                u32::from_str_radix(crc32, 16).ok()?,
+            )),
+            ["chunks", crcs] => {
+                let bytes = hex::decode(crcs).ok()?;
+                let (crc32s, remainder) = bytes.as_chunks::<4>();
+                if !remainder.is_empty() {
+                    return None;
+                }
+                progress.chunk_crcs = crc32s.iter().copied().map(u32::from_le_bytes).collect();
+            }
+            _ => return None,
+        }
+    }
+
+    (chunk_size > 0 && group_blocks > 0 && params.block_size > 0).then_some((
+        params,
+        (chunk_size, group_blocks),
+        progress,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> CheckpointParams {
+        CheckpointParams {
+            block_size: 1024,
+            subslice_size: 512,
+            first_recovery_block: 2,
+            recovery_block_count: 3,
+            outputs: vec![PathBuf::from("out/data.par2")],
+            sources: vec![(
+                PathBuf::from("a b.bin"),
+                FileStamp {
+                    size: 5000,
+                    modified_nanos: 1_700_000_000_000_000_000,
+                    inode: 42,
+                },
+            )],
+        }
+    }
+
+    #[test]
+    fn checkpoint_round_trips() {
+        use md5::Digest;
+        let mut md5 = Md5::new();
+        md5.update(b"partial data");
+        let progress = Progress {
+            blocks_done: 2,
+            bytes_hashed: 2048,
+            known_files: vec![false],
+            file_states: vec![md5.serialize().to_vec()],
+            subslice_states: vec![(md5.serialize().to_vec(), 0xdeadbeef)],
+            chunk_crcs: vec![1, 2, 3],
+        };
+
+        let contents = format_checkpoint(&params(), (1024, 8), &progress);
+        let (parsed_params, grouping, parsed) = parse_checkpoint(&contents).unwrap();
+        assert_eq!(parsed_params, params());
+        assert_eq!(grouping, (1024, 8));
+        assert_eq!(parsed.blocks_done, 2);
+        assert_eq!(parsed.bytes_hashed, 2048);
+        assert_eq!(parsed.file_states, progress.file_states);
+        assert_eq!(parsed.subslice_states, progress.subslice_states);
+        assert_eq!(parsed.chunk_crcs, vec![1, 2, 3]);
+        assert!(parse_checkpoint("par2rs-create-checkpoint 0\n").is_none());
+    }
+
+    #[test]
+    fn checkpoint_groups_cover_many_times_the_recovery_data() {
+        assert_eq!(checkpoint_group_blocks(1 << 20, 100, 100_000), 1600);
+        assert_eq!(checkpoint_group_blocks(1 << 20, 1, 100_000), 1024);
+        assert_eq!(checkpoint_group_blocks(1 << 20, 100, 500), 500);
+    }
+}
@@ -1,5 +1,8 @@
 //! Shared helpers for create-compatible command-line frontends.
 
+use super::error::{CreateError, CreateResult};
+use super::source_filter::{classify_source, SourceFilter, SourceKind};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
 const SOURCE_LIST_REQUIRED: &str = "You must specify a list of files when creating.";
@@ -58,16 +61,68 @@ fn parse_redundancy_size(unit: char, digits: &str) -> Result<RedundancyOption, S
     Ok(RedundancyOption::TargetSize(bytes))
 }
 
-pub fn expand_source_files(inputs: Vec<PathBuf>, recurse: bool) -> std::io::Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
+/// Parse a byte size with an optional `k`, `m` or `g` suffix, e.g. `700M`.
+pub fn parse_byte_size(size_str: &str) -> Result<u64, String> {
+    let invalid = || format!("Invalid size: {size_str}");
+    let lower = size_str.to_ascii_lowercase();
+    let (digits, multiplier) = match lower.as_bytes().last() {
+        Some(b'k') => (&lower[..lower.len() - 1], 1024),
+        Some(b'm') => (&lower[..lower.len() - 1], 1024 * 1024),
+        Some(b'g') => (&lower[..lower.len() - 1], 1024 * 1024 * 1024),
+        _ => (lower.as_str(), 1),
+    };
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_ascii_digit()) {
+        return Err(invalid());
+    }
+
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|value| value.checked_mul(multiplier))
+        .filter(|&bytes| bytes > 0)
+        .ok_or_else(invalid)
+}
+
+/// Expand the source arguments into the list of files to protect
+///
+/// With `recurse`, directories are walked in sorted order. `filter` applies to
+/// named files by their file name and to walked files by their path below the
+/// directory named; hidden and excluded directories are not walked at all.
+/// Symbolic links are handled by the filter's policy; a file reached twice,
+/// through links or as a named argument, is protected once, and a directory
+/// that links back to one being walked is an error. Special files are refused.
+pub fn expand_source_files(
+    inputs: Vec<PathBuf>,
+    recurse: bool,
+    filter: &SourceFilter,
+) -> CreateResult<Vec<PathBuf>> {
+    let mut walk = DirectoryWalk {
+        filter,
+        files: Vec::new(),
+        seen: HashSet::new(),
+        ancestors: Vec::new(),
+    };
     for input in inputs {
-        if input.is_dir() && recurse {
-            collect_directory_files(&input, &mut files)?;
-        } else {
-            files.push(input);
+        // `-` stands for standard input and is never filtered out, and a
+        // missing file is left for the create to report
+        if input.as_os_str() == "-" || std::fs::symlink_metadata(&input).is_err() {
+            walk.files.push(input);
+            continue;
+        }
+        match classify_source(&input, filter.symlink_policy())? {
+            SourceKind::Dir if recurse => walk.collect(&input, &input)?,
+            SourceKind::Skipped => {}
+            _ => {
+                if input
+                    .file_name()
+                    .is_none_or(|name| filter.allows(Path::new(name)))
+                {
+                    walk.add_file(input);
+                }
+            }
         }
     }
-    Ok(files)
+    Ok(walk.files)
 }
 
 pub fn resolve_create_inputs(
@@ -75,13 +130,14 @@ pub fn resolve_create_inputs(
     archive_name: Option<&str>,
     source_inputs: Vec<PathBuf>,
     recurse: bool,
+    filter: &SourceFilter,
 ) -> Result<(String, Vec<PathBuf>), String> {
     if source_inputs.is_empty() {
         return resolve_implicit_source(par2_file, archive_name);
     }
 
     let output_name = archive_name.unwrap_or(par2_file).to_string();
-    let source_files = expand_source_files(source_inputs, recurse)
+    let source_files = expand_source_files(source_inputs, recurse, filter)
         .map_err(|err| format!("Failed to expand source file list: {err}"))?;
     Ok((output_name, source_files))
 }
@@ -124,20 +180,60 @@ pub fn warn_for_high_redundancy(redundancy: Option<RedundancyOption>) {
     }
 }
 
-fn collect_directory_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
-    let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
-    entries.sort_by_key(|entry| entry.path());
+/// State of the walk over the source arguments
+struct DirectoryWalk<'a> {
+    filter: &'a SourceFilter,
+    files: Vec<PathBuf>,
+    /// Canonical paths of the files found so far
+    seen: HashSet<PathBuf>,
+    /// Canonical paths of the directories being walked
+    ancestors: Vec<PathBuf>,
+}
 
-    for entry in entries {
-        let path = entry.path();
-        if path.is_dir() {
-            collect_directory_files(&path, files)?;
-        } else if path.is_file() {
-            files.push(path);
+impl DirectoryWalk<'_> {
+    fn add_file(&mut self, path: PathBuf) {
+        let identity = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if self.seen.insert(identity) {
+            self.files.push(path);
         }
     }
 
-    Ok(())
+    fn collect(&mut self, root: &Path, dir: &Path) -> CreateResult<()> {
+        let read_error = |e| CreateError::FileReadError {
+            file: dir.to_string_lossy().to_string(),
+            source: e,
+        };
+        let canonical = std::fs::canonicalize(dir).map_err(read_error)?;
+        if self.ancestors.contains(&canonical) {
+            return Err(CreateError::SymlinkLoop(dir.to_string_lossy().to_string()));
+        }
+        let mut entries = std::fs::read_dir(dir)
+            .and_then(|entries| entries.collect::<Result<Vec<_>, _>>())
+            .map_err(read_error)?;
+        entries.sort_by_key(|entry| entry.path());
+
+        self.ancestors.push(canonical);
+        for entry in entries {
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            match classify_source(&path, self.filter.symlink_policy())? {
+                SourceKind::Dir => {
+                    if self.filter.descends_into(relative) {
+                        self.collect(root, &path)?;
+                    }
+                }
+                SourceKind::File => {
+                    if self.filter.allows(relative) {
+                        self.add_file(path);
+                    }
+                }
+                SourceKind::Skipped => {}
+            }
+        }
+        self.ancestors.pop();
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -175,6 +271,18 @@ mod tests {
         assert!(parse_redundancy_option("").is_err());
     }
 
+    #[test]
+    fn parse_byte_size_accepts_suffixes() {
+        assert_eq!(parse_byte_size("4096").unwrap(), 4096);
+        assert_eq!(parse_byte_size("64k").unwrap(), 64 * 1024);
+        assert_eq!(parse_byte_size("700M").unwrap(), 700 * 1024 * 1024);
+        assert_eq!(parse_byte_size("2g").unwrap(), 2 * 1024 * 1024 * 1024);
+        assert!(parse_byte_size("").is_err());
+        assert!(parse_byte_size("M").is_err());
+        assert!(parse_byte_size("0").is_err());
+        assert!(parse_byte_size("1.5G").is_err());
+    }
+
     #[test]
     fn expand_source_files_recurses_in_stable_order() {
         let temp = tempfile::tempdir().unwrap();
@@ -184,13 +292,85 @@ mod tests {
         std::fs::write(nested.join("b.txt"), b"b").unwrap();
         std::fs::write(root.join("a.txt"), b"a").unwrap();
 
-        let files = expand_source_files(vec![root.clone()], true).unwrap();
+        let all = SourceFilter::default();
+        let files = expand_source_files(vec![root.clone()], true, &all).unwrap();
         assert_eq!(files, vec![root.join("a.txt"), nested.join("b.txt")]);
 
-        let files = expand_source_files(vec![root.clone()], false).unwrap();
+        let files = expand_source_files(vec![root.clone()], false, &all).unwrap();
         assert_eq!(files, vec![root]);
     }
 
+    #[test]
+    fn expand_source_files_applies_the_filter_below_each_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("root");
+        for dir in ["keep", ".git", "tmp"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in [
+            "keep/a.txt",
+            "keep/Thumbs.db",
+            "keep/b.tmp",
+            ".git/config",
+            "tmp/c.txt",
+            ".env",
+        ] {
+            std::fs::write(root.join(file), b"x").unwrap();
+        }
+
+        let filter = SourceFilter::default()
+            .exclude("Thumbs.db")
+            .exclude("*.tmp")
+            .skip_hidden(true);
+        let files = expand_source_files(vec![root.clone()], true, &filter).unwrap();
+        assert_eq!(files, vec![root.join("keep/a.txt"), root.join("tmp/c.txt")]);
+
+        let filter = SourceFilter::default().include("*.txt").exclude("tmp");
+        let inputs = vec![root.clone(), root.join("keep/b.tmp"), PathBuf::from("-")];
+        let files = expand_source_files(inputs, true, &filter).unwrap();
+        assert_eq!(files, vec![root.join("keep/a.txt"), PathBuf::from("-")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn expand_source_files_applies_the_symlink_policy() {
+        use super::super::source_filter::SymlinkPolicy;
+        use std::os::unix::fs::symlink;
+
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("root");
+        std::fs::create_dir_all(root.join("data")).unwrap();
+        std::fs::write(root.join("data/a.bin"), b"a").unwrap();
+        symlink(root.join("data/a.bin"), root.join("b.bin")).unwrap();
+        symlink(root.join("data"), root.join("linked")).unwrap();
+
+        // Following protects a.bin once, however many links reach it
+        let follow = SourceFilter::default();
+        let files = expand_source_files(vec![root.clone()], true, &follow).unwrap();
+        assert_eq!(files, vec![root.join("b.bin")]);
+
+        let skip = SourceFilter::default().symlinks(SymlinkPolicy::Skip);
+        let files = expand_source_files(vec![root.clone()], true, &skip).unwrap();
+        assert_eq!(files, vec![root.join("data/a.bin")]);
+
+        let refuse = SourceFilter::default().symlinks(SymlinkPolicy::Error);
+        let error = expand_source_files(vec![root.clone()], true, &refuse).unwrap_err();
+        assert!(matches!(error, CreateError::SymlinkRefused(_)), "{error}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn expand_source_files_detects_symlink_loops() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("root");
+        std::fs::create_dir_all(root.join("nested")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("nested/up")).unwrap();
+
+        let error =
+            expand_source_files(vec![root.clone()], true, &SourceFilter::default()).unwrap_err();
+        assert!(matches!(error, CreateError::SymlinkLoop(_)), "{error}");
+    }
+
     #[test]
     fn validate_recovery_file_count_matches_turbo_limit() {
         assert_eq!(validate_recovery_file_count(1).unwrap(), 1);
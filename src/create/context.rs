@@ -2,60 +2,103 @@
 //!
 //! Reference: par2cmdline-turbo/src/par2creator.h Par2Creator class
 
+use super::block_size_optimizer::{BlockSizeChoice, BlockSizeCost};
+use super::checkpoint::{Checkpoint, CheckpointParams};
 use super::error::{CreateError, CreateResult};
 use super::error_helpers::{
-    create_file, create_new_output_file, get_metadata, open_for_reading, packet_write_error,
+    ensure_outputs_absent, get_metadata, open_for_reading, packet_write_error,
 };
-use super::packet_generator::generate_recovery_set_id;
+use super::packet_generator::{generate_packed_recovery_set_id, generate_recovery_set_id};
+use super::plan::CreatePlan;
 use super::progress::CreateReporter;
+use super::recovery_output::{OutputFile, RecoveryOutput, Volume};
 use super::source_file::{normalize_packet_path, packet_name_from_path, SourceFileInfo};
+use super::source_reader::{with_prefetch, ReadOrder, SourceHashes, SourceLayout};
+use super::staged_output::{remove_stale_temporaries, temp_path, StagedOutputs};
+use super::stream_source::{
+    encode_and_hash_stream, read_ahead_limit, StreamSource, MAX_STREAM_BLOCKS,
+};
 use super::types::CreateConfig;
 use crate::create::backend::CreateRecoveryBackend;
-use crate::domain::{BlockSize, ChunkSize, RecoverySetId, SourceBlockCount};
+use crate::domain::{BlockSize, ChunkSize, Crc32Value, RecoverySetId, SourceBlockCount};
+use crate::hash_cache::{CachedHashes, FileStamp, HashCache};
 use std::borrow::Cow;
+use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-const DEFAULT_MEMORY_LIMIT: usize = 1024 * 1024 * 1024; // 1 GiB
+pub(super) const DEFAULT_MEMORY_LIMIT: usize = 1024 * 1024 * 1024; // 1 GiB
 const MAX_CREATE_CHUNK_SIZE: usize = 32 * 1024 * 1024;
-const RECOVERY_PACKET_TYPE: &[u8; 16] = b"PAR 2.0\0RecvSlic";
+pub(super) const RECOVERY_PACKET_TYPE: &[u8; 16] = b"PAR 2.0\0RecvSlic";
+pub(super) const PACKED_RECOVERY_PACKET_TYPE: &[u8; 16] = b"PAR 2.0\0PkdRecvS";
+const THROUGHPUT_REPORT_INTERVAL: Duration = Duration::from_secs(1);
 
-fn default_output_base_path(output_name: &str) -> PathBuf {
+pub(super) fn default_output_base_path(output_name: &str) -> PathBuf {
     Path::new(output_name)
         .parent()
         .unwrap_or_else(|| Path::new("."))
         .to_path_buf()
 }
 
-/// Serialize and write a single PAR2 recovery slice packet
-///
-/// Computes the packet MD5, builds the packet, and writes it to `writer`.
+fn special_file_error(path: &Path, metadata: &std::fs::Metadata) -> CreateError {
+    CreateError::SpecialFile {
+        file: path.to_string_lossy().to_string(),
+        kind: super::source_filter::special_file_kind(&metadata.file_type()),
+    }
+}
+
+/// PAR2 name of `path`: its path below `base_path`, or else its file name
+pub(super) fn packet_name_below(base_path: &Path, path: &Path) -> String {
+    if !base_path.as_os_str().is_empty() {
+        if let Ok(relative) = path.strip_prefix(base_path) {
+            return normalize_packet_path(relative);
+        }
+
+        if let (Ok(canonical_base), Ok(canonical_path)) = (
+            std::fs::canonicalize(base_path),
+            std::fs::canonicalize(path),
+        ) {
+            if let Ok(relative) = canonical_path.strip_prefix(&canonical_base) {
+                return normalize_packet_path(relative);
+            }
+        }
+    }
+
+    packet_name_from_path(path)
+}
+
+/// Write one Input File Slice packet per IFSC block of a source file
 ///
-/// Reference: par2cmdline-turbo/src/par2creator.cpp WriteRecoveryPackets()
-fn write_recovery_slice_packet<W: std::io::Write>(
+/// Each packet carries `block_size` bytes of the file (less for the last block),
+/// so verify and repair can match embedded data against the IFSC checksums.
+fn write_embedded_slice_packets<W: std::io::Write>(
     writer: &mut W,
-    exponent: u32,
-    recovery_data: &[u8],
+    file: &SourceFileInfo,
+    block_size: u64,
     recovery_set_id: RecoverySetId,
-) -> std::io::Result<()> {
-    use md5::{Digest, Md5};
-
-    let packet_length = 8 + 8 + 16 + 16 + 16 + 4 + recovery_data.len() as u64;
-
-    // Compute MD5 over: set_id || type || exponent || data
-    let mut hasher = Md5::new();
-    hasher.update(recovery_set_id.as_bytes());
-    hasher.update(RECOVERY_PACKET_TYPE);
-    hasher.update(exponent.to_le_bytes());
-    hasher.update(recovery_data);
-    let computed_md5 = hasher.finalize();
-
-    writer.write_all(crate::packets::MAGIC_BYTES)?;
-    writer.write_all(&packet_length.to_le_bytes())?;
-    writer.write_all(&computed_md5)?;
-    writer.write_all(recovery_set_id.as_bytes())?;
-    writer.write_all(RECOVERY_PACKET_TYPE)?;
-    writer.write_all(&exponent.to_le_bytes())?;
-    writer.write_all(recovery_data)
+) -> CreateResult<()> {
+    use super::packet_generator::{
+        generate_input_file_slice_packet, write_input_file_slice_packet,
+    };
+    use std::io::Read;
+
+    let mut reader = std::io::BufReader::new(open_for_reading(&file.path)?);
+    let mut buffer = vec![0u8; block_size as usize];
+    let mut offset = 0u64;
+    while offset < file.size {
+        let len = block_size.min(file.size - offset) as usize;
+        reader
+            .read_exact(&mut buffer[..len])
+            .map_err(|e| CreateError::FileReadError {
+                file: file.path.to_string_lossy().to_string(),
+                source: e,
+            })?;
+        let packet =
+            generate_input_file_slice_packet(recovery_set_id, file.file_id, offset, &buffer[..len]);
+        write_input_file_slice_packet(writer, &packet)?;
+        offset += len as u64;
+    }
+    Ok(())
 }
 
 /// Compute the chunk size for chunked processing.
@@ -71,162 +114,454 @@ fn calculate_chunk_size_impl(
     recovery_block_count: usize,
     memory_limit: usize,
 ) -> usize {
-    let block_overhead = 2 + (source_block_count + 1).min(24);
-    let full_block_memory = block_size * (recovery_block_count + block_overhead);
+    let buffer_count = chunk_buffer_count(source_block_count, recovery_block_count);
+    let full_block_memory = block_size * buffer_count;
     if full_block_memory <= memory_limit {
         return block_size.min(MAX_CREATE_CHUNK_SIZE);
     }
-    let chunk_size = memory_limit / (recovery_block_count + block_overhead);
+    let chunk_size = memory_limit / buffer_count;
     let aligned = chunk_size & !3;
     aligned.clamp(4, block_size.min(MAX_CREATE_CHUNK_SIZE))
 }
 
+/// Chunk-sized buffers held while encoding: one per recovery block plus input staging
+fn chunk_buffer_count(source_block_count: usize, recovery_block_count: usize) -> usize {
+    recovery_block_count + 2 + (source_block_count + 1).min(24)
+}
+
+/// Size in bytes of the Input File Slice packets embedding a file of `file_size` bytes
+fn embedded_slice_packets_size(file_size: u64, slice_size: u64) -> u64 {
+    let full_slices = file_size / slice_size;
+    let tail = file_size % slice_size;
+    let packet_overhead = 64 + 16 + 8;
+    full_slices * (packet_overhead + slice_size)
+        + if tail > 0 {
+            packet_overhead + tail.div_ceil(4) * 4
+        } else {
+            0
+        }
+}
+
+/// Chunk size for `encode_and_hash_files`
+///
+/// When a block spans several chunks, source blocks are buffered a group at
+/// a time (see `calculate_source_group_blocks`), so the recovery chunks only
+/// get a quarter of the memory limit.
+pub(super) fn calculate_grouped_chunk_size(
+    block_size: usize,
+    source_block_count: usize,
+    recovery_block_count: usize,
+    memory_limit: usize,
+) -> usize {
+    let chunk_size = |memory_limit| {
+        calculate_chunk_size_impl(
+            block_size,
+            source_block_count,
+            recovery_block_count,
+            memory_limit,
+        )
+    };
+    let full = chunk_size(memory_limit);
+    if full < block_size {
+        chunk_size(memory_limit / 4)
+    } else {
+        full
+    }
+}
+
+/// Output files planned for one create run
+struct OutputPlan {
+    index_path: PathBuf,
+    /// Recovery volumes and their paths; empty with external recovery data
+    volumes: Vec<(super::file_naming::RecoveryFilePlan, PathBuf)>,
+    external_path: Option<PathBuf>,
+}
+
+impl OutputPlan {
+    fn paths(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.index_path.as_path())
+            .chain(self.volumes.iter().map(|(_, path)| path.as_path()))
+            .chain(self.external_path.as_deref())
+    }
+}
+
+/// Number of source blocks to buffer per pass when a block spans several chunks
+///
+/// Each pass adds its blocks onto the partial recovery data on disk, so fewer,
+/// larger passes mean less recovery I/O. The buffer gets three quarters of the
+/// memory limit and always holds at least one block.
+pub(super) fn calculate_source_group_blocks(
+    block_size: usize,
+    source_block_count: usize,
+    memory_limit: usize,
+) -> usize {
+    ((memory_limit - memory_limit / 4) / block_size.max(1)).clamp(1, source_block_count.max(1))
+}
+
+/// How `encode_and_hash_files` walks the source data, with the I/O it costs
+///
+/// The source is read once, in order, and hashed in the same pass. When a
+/// block spans several chunks, each group of buffered blocks is added onto
+/// the partial recovery data on disk, so every pass after the first reads and
+/// rewrites all recovery data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct EncodeLayout {
+    /// Bytes of each block encoded per pass
+    pub chunk_size: usize,
+    /// Source blocks per pass
+    pub group_blocks: usize,
+    /// Estimated bytes of source data read
+    pub source_io: u64,
+    /// Estimated bytes of recovery data read and written
+    pub recovery_io: u64,
+}
+
+impl EncodeLayout {
+    /// Read the source once in order, buffering groups of blocks
+    pub fn new(
+        block_size: usize,
+        source_block_count: usize,
+        recovery_block_count: usize,
+        memory_limit: usize,
+    ) -> Self {
+        let chunk_size = calculate_grouped_chunk_size(
+            block_size,
+            source_block_count,
+            recovery_block_count,
+            memory_limit,
+        );
+        let group_blocks = if chunk_size < block_size {
+            calculate_source_group_blocks(block_size, source_block_count, memory_limit)
+        } else {
+            source_block_count.max(1)
+        };
+        let passes = source_block_count.div_ceil(group_blocks).max(1) as u64;
+        let recovery_bytes = recovery_block_count as u64 * block_size as u64;
+        EncodeLayout {
+            chunk_size,
+            group_blocks,
+            source_io: source_block_count as u64 * block_size as u64,
+            recovery_io: recovery_bytes * (2 * passes - 1),
+        }
+    }
+
+    /// Estimated bytes read and written in total
+    pub fn io_bytes(&self) -> u64 {
+        self.source_io + self.recovery_io
+    }
+
+    /// Short description for progress output
+    pub fn describe(&self) -> String {
+        format!(
+            "chunk size: {} bytes, {} source blocks per pass, estimated I/O: {} MB",
+            self.chunk_size,
+            self.group_blocks,
+            self.io_bytes().div_ceil(1024 * 1024)
+        )
+    }
+}
+
 /// Per-file hash data computed during `encode_and_hash_files`.
-struct FileHashState {
-    hash_16k: crate::domain::Md5Hash,
-    full_md5: crate::domain::Md5Hash,
-    file_id: crate::domain::FileId,
-    block_count: u32,
+pub(super) struct FileHashState {
+    pub hash_16k: crate::domain::Md5Hash,
+    pub full_md5: crate::domain::Md5Hash,
+    pub file_id: crate::domain::FileId,
+    pub block_count: u32,
+    pub global_block_offset: u32,
+    pub block_checksums: Vec<super::source_file::BlockChecksum>,
+}
+
+/// Block checksums of a cached file, numbered from `global_block_offset`
+fn cached_block_checksums(
+    hashes: &CachedHashes,
     global_block_offset: u32,
-    block_checksums: Vec<super::source_file::BlockChecksum>,
+) -> Vec<super::source_file::BlockChecksum> {
+    hashes
+        .block_checksums
+        .iter()
+        .zip(global_block_offset..)
+        .map(
+            |(&(hash, crc32), global_index)| super::source_file::BlockChecksum {
+                crc32: crc32.as_u32(),
+                hash,
+                global_index,
+            },
+        )
+        .collect()
+}
+
+/// Cached hashes of `files` for `block_size`, one entry per file
+fn lookup_cached_hashes<'a>(
+    cache: Option<&'a HashCache>,
+    files: &[SourceFileInfo],
+    block_size: u64,
+) -> Vec<Option<&'a CachedHashes>> {
+    files
+        .iter()
+        .map(|file| cache.and_then(|cache| cache.get(&file.path, block_size)))
+        .collect()
 }
 
-/// Recovery blocks paired with their exponents, as returned by `encode_and_hash_files`.
-type RecoveryBlockVec = Vec<(u16, Vec<u8>)>;
+/// Record the hashes of files that were hashed rather than taken from the cache
+///
+/// `stamps` holds the stamp of each file taken before it was read, or `None`
+/// for files that need no new entry.
+fn record_hashes(
+    cache: &mut HashCache,
+    files: &[SourceFileInfo],
+    stamps: &[Option<FileStamp>],
+    block_size: u64,
+) {
+    for (file, stamp) in files.iter().zip(stamps) {
+        let Some(stamp) = *stamp else {
+            continue;
+        };
+        cache.insert(
+            &file.path,
+            stamp,
+            CachedHashes {
+                hash: file.hash,
+                hash_16k: file.hash_16k,
+                block_size,
+                block_checksums: file
+                    .block_checksums
+                    .iter()
+                    .map(|checksum| (checksum.hash, Crc32Value::new(checksum.crc32)))
+                    .collect(),
+            },
+        );
+    }
+}
 
 /// Encode all source files into recovery blocks while simultaneously computing
 /// file/block hashes in a single pass.
 ///
-/// Returns `(recovery_blocks, per_file_hash_states)`.
+/// Every source byte is read exactly once, in order. When a block fits in one
+/// chunk, source blocks are streamed straight into the encoder. Otherwise they
+/// are read `encode.group_blocks` at a time and each group is added, chunk by
+/// chunk, onto the partial recovery data kept in `recovery_output`, so only
+/// one chunk per recovery block is ever held in memory.
+///
+/// Ordinary sets pass `subslice_size == block_size`, giving one block per
+/// file-local block. Checksums are computed per subslice.
+///
+/// Files with an entry in `cached` (indexed like `source_files`, and possibly
+/// shorter) are still read for the encoder, but take their hashes from the
+/// cache instead of hashing the data again.
+///
+/// With a `checkpoint`, progress is saved after every group, and a loaded
+/// checkpoint's blocks are skipped, carrying on from its saved hashes.
+///
+/// Returns the per-file hash states.
 ///
 /// Reference: par2cmdline-turbo/src/par2creator.cpp ProcessData()
 #[allow(clippy::too_many_arguments)] // All params are logically distinct; a param struct would add noise
-fn encode_and_hash_files(
+pub(super) fn encode_and_hash_files(
     source_files: &[SourceFileInfo],
+    cached: &[Option<&CachedHashes>],
     block_size: u64,
-    chunk_size: usize,
+    subslice_size: u64,
+    encode: EncodeLayout,
     source_block_count: u32,
     base_values: &[u16],
     first_recovery_block: u32,
     recovery_count: usize,
     thread_count: usize,
+    reader_count: usize,
+    recovery_output: &mut RecoveryOutput,
+    mut checkpoint: Option<&mut Checkpoint>,
     reporter: &dyn CreateReporter,
-) -> CreateResult<(RecoveryBlockVec, Vec<FileHashState>)> {
+) -> CreateResult<Vec<FileHashState>> {
+    use super::checkpoint::checkpoint_group_blocks;
     use crate::checksum::compute_file_id;
     use crate::create::source_file::BlockChecksum;
-    use crc32fast::Hasher as Crc32Hasher;
     use md5::{Digest, Md5};
-    use std::fs::File;
-    use std::io::{Read, Seek};
 
     let pool = rayon::ThreadPoolBuilder::new()
         .num_threads(thread_count)
         .build()
         .map_err(|err| CreateError::Other(format!("failed to create thread pool: {err}")))?;
 
-    let mut file_handles: Vec<File> = Vec::with_capacity(source_files.len());
-    let mut file_md5_states: Vec<Md5> = Vec::with_capacity(source_files.len());
-    let mut file_16k_buffers: Vec<Vec<u8>> = Vec::with_capacity(source_files.len());
-
-    let mut block_md5_states: Vec<Md5> = Vec::with_capacity(source_block_count as usize);
-    let mut block_crc32_states: Vec<Crc32Hasher> = Vec::with_capacity(source_block_count as usize);
-
-    // Per-file metadata: (block_count, global_block_offset)
-    let mut file_block_meta: Vec<(u32, u32)> = Vec::with_capacity(source_files.len());
-    let mut global_block_offset = 0u32;
-
-    for file in source_files {
-        file_handles.push(open_for_reading(&file.path)?);
-        file_md5_states.push(Md5::new());
-        file_16k_buffers.push(vec![0u8; (file.size as usize).min(16 * 1024)]);
-
-        let block_count = file.calculate_block_count(block_size);
-        file_block_meta.push((block_count, global_block_offset));
-        for _ in 0..block_count {
-            block_md5_states.push(Md5::new());
-            block_crc32_states.push(Crc32Hasher::new());
+    let layout = SourceLayout::new(source_files, block_size, subslice_size);
+    let mut cached: Vec<Option<&CachedHashes>> = (0..source_files.len())
+        .map(|file_idx| {
+            let (block_count, _) = layout.file_block_meta[file_idx];
+            cached.get(file_idx).copied().flatten().filter(|hashes| {
+                hashes.block_size == subslice_size
+                    && hashes.block_checksums.len() == block_count as usize
+            })
+        })
+        .collect();
+    // A resumed run must hash exactly the files the checkpointed run did
+    if let Some(known_files) = checkpoint.as_deref().and_then(Checkpoint::known_files) {
+        for ((file, hashes), &known) in source_files.iter().zip(&mut cached).zip(known_files) {
+            if !known {
+                *hashes = None;
+            } else if hashes.is_none() {
+                return Err(CreateError::Other(format!(
+                    "The hash cache no longer holds {}, which the checkpoint relies on",
+                    file.path.display()
+                )));
+            }
         }
-        global_block_offset += block_count;
     }
-
-    let recovery_blocks = pool.install(|| {
+    let mut hashes = SourceHashes::new(&layout, cached.iter().map(Option::is_some).collect());
+    if let Some(checkpoint) = checkpoint.as_deref() {
+        checkpoint.restore_hashes(&mut hashes, source_files, &layout.file_block_meta)?;
+    }
+    let source_count = source_block_count as usize;
+    debug_assert_eq!(layout.block_count(), source_count);
+    let first_block = checkpoint.as_deref().map_or(0, Checkpoint::blocks_done);
+    let subslices_per_block = (block_size / subslice_size) as usize;
+    let chunk_size = encode.chunk_size;
+
+    let chunk_ranges: Vec<(u64, usize)> = (0..block_size)
+        .step_by(chunk_size)
+        .map(|offset| (offset, ((block_size - offset) as usize).min(chunk_size)))
+        .collect();
+    // A group of several blocks has to be buffered so each block is read once
+    // while every chunk of it is encoded; a single block can be streamed.
+    // Checkpointed runs split streamed data into groups as well, as a
+    // checkpoint can only be taken between groups.
+    let group_blocks = if chunk_ranges.len() > 1 {
+        encode.group_blocks.clamp(1, source_count.max(1))
+    } else if checkpoint.is_some() {
+        checkpoint_group_blocks(block_size, recovery_count, source_count)
+    } else {
+        source_count.max(1)
+    };
+    let group_blocks = match checkpoint.as_deref_mut() {
+        Some(checkpoint) => checkpoint.group_blocks_or(group_blocks),
+        None => group_blocks,
+    };
+    let order = ReadOrder {
+        source_count,
+        first_block,
+        group_blocks,
+        block_size,
+        chunk_size,
+        buffered: chunk_ranges.len() > 1 && group_blocks > 1,
+    };
+    let block_len = block_size as usize;
+    let mut group_buffer = if order.buffered {
+        vec![0u8; group_blocks * block_len]
+    } else {
+        Vec::new()
+    };
+    let total_bytes = (source_count as u64 * block_size).max(1);
+    let total_file_bytes: u64 = source_files.iter().map(|file| file.size).sum();
+    let started = Instant::now();
+    let mut last_throughput_report = started;
+
+    pool.install(|| {
         let mut backend = CreateRecoveryBackend::new(
             base_values,
             first_recovery_block,
             recovery_count,
             chunk_size,
         );
-        let mut recovery_blocks = backend.recovery_blocks(block_size as usize);
-
-        // Main chunk loop
-        let mut block_offset = 0u64;
-        while block_offset < block_size {
-            let chunk_len = ((block_size - block_offset) as usize).min(chunk_size);
-            backend.begin_chunk(chunk_len);
-
-            let mut file_block_idx = 0usize;
-
-            for (file_idx, file) in source_files.iter().enumerate() {
-                let (block_count, _) = file_block_meta[file_idx];
-                for block_idx in 0..block_count {
-                    let is_last = block_idx == block_count - 1;
-                    let block_actual = if is_last && file.size % block_size != 0 {
-                        (file.size % block_size) as usize
-                    } else {
-                        block_size as usize
-                    };
-                    let bytes_available = if block_offset >= block_actual as u64 {
-                        0
-                    } else {
-                        block_actual - block_offset as usize
-                    };
-                    let bytes_to_read = bytes_available.min(chunk_len);
-                    {
-                        let chunk = backend.prepare_transfer_buffer(file_block_idx);
-                        if bytes_to_read > 0 {
-                            let file_pos = block_idx as u64 * block_size + block_offset;
-                            file_handles[file_idx]
-                                .seek(std::io::SeekFrom::Start(file_pos))
-                                .map_err(|e| CreateError::FileReadError {
-                                    file: file.path.to_string_lossy().to_string(),
-                                    source: e,
-                                })?;
-                            file_handles[file_idx]
-                                .read_exact(&mut chunk[..bytes_to_read])
-                                .map_err(|e| CreateError::FileReadError {
-                                    file: file.path.to_string_lossy().to_string(),
-                                    source: e,
-                                })?;
-                            if file_pos < 16 * 1024 {
-                                let capture_start = file_pos as usize;
-                                let capture_end = (capture_start + bytes_to_read).min(16 * 1024);
-                                let capture_len = capture_end - capture_start;
-                                file_16k_buffers[file_idx][capture_start..capture_end]
-                                    .copy_from_slice(&chunk[..capture_len]);
-                            }
-                            file_md5_states[file_idx].update(&chunk[..bytes_to_read]);
+
+        with_prefetch(&layout, order, reader_count, |prefetch| {
+            for group_start in (first_block..source_count).step_by(group_blocks) {
+                let group = group_start..(group_start + group_blocks).min(source_count);
+                // The first group after a resume is encoded on its own, then
+                // reconciled with what the interrupted run left on disk
+                let resumed = group_start > 0 && group_start == first_block;
+                if order.buffered {
+                    for slot in 0..group.len() {
+                        for _ in &chunk_ranges {
+                            let (request, data) = prefetch.next()?;
+                            hashes.update(&layout, request, data);
+                            let start = slot * block_len + request.block_offset as usize;
+                            group_buffer[start..start + data.len()].copy_from_slice(data);
                         }
-                        block_md5_states[file_block_idx].update(&chunk[..chunk_len]);
-                        block_crc32_states[file_block_idx].update(&chunk[..chunk_len]);
                     }
-                    backend.add_transfer_input(file_block_idx, file_block_idx);
-                    file_block_idx += 1;
                 }
-            }
 
-            backend.finish_chunk(&mut recovery_blocks, block_size as usize);
+                for &(block_offset, chunk_len) in &chunk_ranges {
+                    if group_start == 0 || resumed {
+                        backend.begin_chunk(chunk_len);
+                    } else {
+                        // Carry on from the recovery data left by earlier groups
+                        backend.begin_chunk_with(chunk_len, |recovery_idx, data| {
+                            recovery_output.read_chunk(recovery_idx, block_offset, data)
+                        })?;
+                    }
 
-            block_offset += chunk_len as u64;
-            let progress =
-                ((block_offset as f64 / block_size as f64) * recovery_count as f64) as u32;
-            reporter.report_recovery_generation(
-                progress.min(recovery_count as u32),
-                recovery_count as u32,
-            );
-        }
+                    for (slot, source_idx) in group.clone().enumerate() {
+                        if order.buffered {
+                            let start = slot * block_len + block_offset as usize;
+                            backend.add_input(source_idx, &group_buffer[start..start + chunk_len]);
+                        } else {
+                            let (request, data) = prefetch.next()?;
+                            debug_assert_eq!(
+                                (request.source_idx, request.block_offset),
+                                (source_idx, block_offset)
+                            );
+                            hashes.update(&layout, request, data);
+                            backend.add_input(source_idx, data);
+                        }
+                    }
+
+                    backend.finish_chunk_with(|recovery_idx, data| {
+                        match checkpoint.as_deref_mut() {
+                            Some(checkpoint) if resumed => checkpoint.resume_chunk(
+                                recovery_output,
+                                recovery_idx,
+                                block_offset,
+                                data,
+                            ),
+                            Some(checkpoint) => {
+                                recovery_output.write_chunk(recovery_idx, block_offset, data)?;
+                                checkpoint.record_chunk(recovery_idx, block_offset, data);
+                                Ok(())
+                            }
+                            None => recovery_output.write_chunk(recovery_idx, block_offset, data),
+                        }
+                    })?;
+
+                    let bytes_done = group_start as u64 * block_size
+                        + group.len() as u64 * (block_offset + chunk_len as u64);
+                    let progress =
+                        ((bytes_done as f64 / total_bytes as f64) * recovery_count as f64) as u32;
+                    reporter.report_recovery_generation(
+                        progress.min(recovery_count as u32),
+                        recovery_count as u32,
+                    );
+
+                    let now = Instant::now();
+                    // The final rate is reported once reading is done
+                    if now.duration_since(last_throughput_report) >= THROUGHPUT_REPORT_INTERVAL
+                        && hashes.bytes_hashed < total_file_bytes
+                    {
+                        last_throughput_report = now;
+                        reporter.report_read_throughput(
+                            hashes.bytes_hashed,
+                            total_file_bytes,
+                            now.duration_since(started),
+                        );
+                    }
+                }
 
-        Ok::<_, CreateError>(recovery_blocks)
+                if let Some(checkpoint) = checkpoint.as_deref_mut() {
+                    let subslices_done =
+                        (group.end * subslices_per_block).min(hashes.block_md5_states.len());
+                    checkpoint.save(group.end, subslices_done, &hashes, recovery_output)?;
+                }
+            }
+            Ok(())
+        })
     })?;
+    reporter.report_read_throughput(hashes.bytes_hashed, total_file_bytes, started.elapsed());
+
+    let SourceHashes {
+        file_md5_states,
+        file_16k_buffers,
+        block_md5_states,
+        block_crc32_states,
+        ..
+    } = hashes;
+    let file_block_meta = layout.file_block_meta;
 
     // Finalize file MD5s and block checksums
     let finalized_file_md5s: Vec<[u8; 16]> = file_md5_states
@@ -249,6 +584,20 @@ fn encode_and_hash_files(
 
     for (file_idx, file) in source_files.iter().enumerate() {
         let (block_count, g_offset) = file_block_meta[file_idx];
+        if let Some(known) = cached[file_idx] {
+            global_block_idx += block_count as usize;
+            reporter.report_file_hashing(file.packet_name(), file.size, file.size);
+            hash_states.push(FileHashState {
+                hash_16k: known.hash_16k,
+                full_md5: known.hash,
+                file_id: compute_file_id(&known.hash_16k, file.size, file.packet_name().as_bytes()),
+                block_count,
+                global_block_offset: g_offset,
+                block_checksums: cached_block_checksums(known, g_offset),
+            });
+            continue;
+        }
+
         let full_md5 = crate::domain::Md5Hash::new(finalized_file_md5s[file_idx]);
         let hash_16k = file_16k_hashes[file_idx];
         let filename = file.packet_name().as_bytes();
@@ -289,7 +638,91 @@ fn encode_and_hash_files(
         });
     }
 
-    Ok((recovery_blocks, hash_states))
+    Ok(hash_states)
+}
+
+/// Hash the files of the non-recovery set without feeding them to the encoder.
+///
+/// Produces the same file and per-block checksums as `encode_and_hash_files`,
+/// so their FileDesc and IFSC packets are indistinguishable from those of
+/// recovery files. Files are hashed in parallel, each with one sequential read,
+/// except those with an entry in `cached`.
+fn hash_non_recovery_files(
+    files: &[SourceFileInfo],
+    cached: &[Option<&CachedHashes>],
+    block_size: u64,
+    reporter: &dyn CreateReporter,
+) -> CreateResult<Vec<FileHashState>> {
+    use crate::checksum::{compute_block_checksums, compute_file_id};
+    use crate::create::source_file::BlockChecksum;
+    use md5::{Digest, Md5};
+    use rayon::prelude::*;
+    use std::io::Read;
+
+    files
+        .par_iter()
+        .enumerate()
+        .map(|(file_idx, file)| {
+            let block_count = file.calculate_block_count(block_size);
+            if let Some(known) = cached.get(file_idx).copied().flatten().filter(|hashes| {
+                hashes.block_size == block_size
+                    && hashes.block_checksums.len() == block_count as usize
+            }) {
+                reporter.report_file_hashing(file.packet_name(), file.size, file.size);
+                return Ok(FileHashState {
+                    hash_16k: known.hash_16k,
+                    full_md5: known.hash,
+                    file_id: compute_file_id(
+                        &known.hash_16k,
+                        file.size,
+                        file.packet_name().as_bytes(),
+                    ),
+                    block_count,
+                    global_block_offset: 0,
+                    block_checksums: cached_block_checksums(known, 0),
+                });
+            }
+
+            let read_error = |e| CreateError::FileReadError {
+                file: file.path.to_string_lossy().to_string(),
+                source: e,
+            };
+            let mut reader = std::io::BufReader::new(open_for_reading(&file.path)?);
+            let mut file_md5 = Md5::new();
+            let mut first_16k = Vec::with_capacity((file.size as usize).min(16 * 1024));
+            let mut block = vec![0u8; block_size as usize];
+
+            let mut block_checksums = Vec::with_capacity(block_count as usize);
+            for block_idx in 0..block_count {
+                let len = (file.size - block_idx as u64 * block_size).min(block_size) as usize;
+                reader.read_exact(&mut block[..len]).map_err(read_error)?;
+                block[len..].fill(0);
+
+                file_md5.update(&block[..len]);
+                let capture = len.min(16 * 1024 - first_16k.len());
+                first_16k.extend_from_slice(&block[..capture]);
+
+                let (hash, crc32) = compute_block_checksums(&block);
+                block_checksums.push(BlockChecksum {
+                    crc32: crc32.as_u32(),
+                    hash,
+                    global_index: block_idx,
+                });
+            }
+
+            let hash_16k = crate::domain::Md5Hash::new(Md5::digest(&first_16k).into());
+            reporter.report_file_hashing(file.packet_name(), file.size, file.size);
+
+            Ok(FileHashState {
+                hash_16k,
+                full_md5: crate::domain::Md5Hash::new(file_md5.finalize().into()),
+                file_id: compute_file_id(&hash_16k, file.size, file.packet_name().as_bytes()),
+                block_count,
+                global_block_offset: 0,
+                block_checksums,
+            })
+        })
+        .collect()
 }
 
 /// Populate `source_files` from the hash data computed during `encode_and_hash_files`.
@@ -331,6 +764,9 @@ pub struct CreateContext {
     /// Source file information
     source_files: Vec<SourceFileInfo>,
 
+    /// Non-recovery set files: described and checksummed, never encoded
+    non_recovery_files: Vec<SourceFileInfo>,
+
     /// Calculated block size (bytes)
     block_size: BlockSize,
 
@@ -340,11 +776,17 @@ pub struct CreateContext {
     /// Number of recovery blocks to generate
     recovery_block_count: u32,
 
-    /// Generated recovery blocks (exponent, data)
-    recovery_blocks: Vec<(u16, Vec<u8>)>,
+    /// Result of the block size search, when it was requested
+    block_size_choice: Option<BlockSizeChoice>,
+
+    /// Recovery file count that keeps every file under the maximum file size
+    capped_recovery_file_count: Option<u32>,
 
     /// Output PAR2 files created
     output_files: Vec<String>,
+
+    /// Stream read in place of source files, until `create` consumes it
+    stream: Option<StreamSource>,
 }
 
 impl CreateContext {
@@ -355,23 +797,36 @@ impl CreateContext {
     pub(super) fn new(
         config: CreateConfig,
         reporter: Box<dyn CreateReporter>,
+        stream: Option<Box<dyn Read + Send>>,
     ) -> CreateResult<Self> {
+        let stream = match (stream, &config.stream_name) {
+            (Some(reader), Some(name)) => Some(StreamSource::read_ahead(
+                name.clone(),
+                reader,
+                read_ahead_limit(config.memory_limit.unwrap_or(DEFAULT_MEMORY_LIMIT)),
+            )?),
+            _ => None,
+        };
         let mut context = CreateContext {
             config,
             reporter,
             recovery_set_id: None,
             source_files: Vec::new(),
+            non_recovery_files: Vec::new(),
             block_size: BlockSize::new(0),
             source_block_count: 0,
             recovery_block_count: 0,
-            recovery_blocks: Vec::new(),
+            block_size_choice: None,
+            capped_recovery_file_count: None,
             output_files: Vec::new(),
+            stream,
         };
 
         // Perform initial setup
         context.scan_source_files()?;
         context.calculate_block_size()?;
         context.calculate_recovery_blocks()?;
+        context.calculate_capped_recovery_file_count()?;
 
         Ok(context)
     }
@@ -383,16 +838,58 @@ impl CreateContext {
     ///
     /// Reference: par2cmdline-turbo/src/par2creator.cpp Par2Creator::Process()
     pub fn create(&mut self) -> CreateResult<()> {
-        // Step 1: Generate recovery blocks AND compute file hashes in single pass
+        let mut staged = if self.config.checkpoint.is_some() {
+            StagedOutputs::resumable()
+        } else {
+            StagedOutputs::default()
+        };
+        // A stream is encoded before anything else: its length, and with it
+        // the layout of the output files, is known only once it ends. Its
+        // recovery data goes to a temporary of the set, so those left by an
+        // interrupted run are swept up first.
+        let stream_recovery = match self.stream.take() {
+            Some(stream) => {
+                remove_stale_temporaries(&self.index_path())?;
+                Some(self.encode_stream(stream, &mut staged)?)
+            }
+            None => None,
+        };
+
+        // Step 1: Create the output files under temporary names and reserve
+        // space for recovery data; any error from here on removes them again,
+        // unless a checkpoint is kept to resume them
+        let outputs = self.plan_output_files();
+        let mut checkpoint = self.open_checkpoint(&outputs)?;
+        let resuming = checkpoint.as_ref().is_some_and(Checkpoint::is_resumed);
+        if !resuming && stream_recovery.is_none() {
+            remove_stale_temporaries(&outputs.index_path)?;
+        }
+        let mut recovery_output = self.open_recovery_output(&outputs, &mut staged, resuming)?;
+
+        // Step 2: Generate recovery blocks AND compute file hashes in single pass
         // This is the performance-critical optimization that eliminates dual file reads
-        // Hashes and block checksums are computed during recovery generation
-        self.generate_recovery_blocks()?;
+        // Hashes and block checksums are computed during recovery generation, and
+        // recovery data goes straight to disk
+        match stream_recovery {
+            Some(mut scratch) => {
+                scratch.copy_into(&mut recovery_output)?;
+                drop(scratch);
+                staged.discard(&self.stream_scratch_path());
+            }
+            None => self.generate_recovery_blocks(&mut recovery_output, checkpoint.as_mut())?,
+        }
 
-        // Step 2: Generate recovery set ID (needs file IDs from hashes computed in step 1)
+        // Step 3: Generate recovery set ID (needs file IDs from hashes computed in step 2)
         self.generate_recovery_set_id()?;
 
-        // Step 3: Write PAR2 files
-        self.write_par2_files()?;
+        // Step 4: Write critical packets and finish the recovery packets
+        self.write_par2_files(&outputs, recovery_output, &mut staged)?;
+
+        // Step 5: Move the complete set into place
+        staged.commit(self.config.overwrite_existing)?;
+        if let Some(checkpoint) = checkpoint {
+            checkpoint.remove();
+        }
 
         // Report completion
         self.reporter.report_complete(&self.output_files);
@@ -404,6 +901,9 @@ impl CreateContext {
     ///
     /// Reference: par2cmdline-turbo/src/par2creator.cpp OpenSourceFiles()
     fn scan_source_files(&mut self) -> CreateResult<()> {
+        if self.stream.is_some() {
+            return self.scan_stream();
+        }
         let total_files = self.config.source_files.len();
 
         for (index, path) in self.config.source_files.iter().enumerate() {
@@ -420,8 +920,11 @@ impl CreateContext {
                 ));
             }
 
-            // Get file metadata
+            // Get file metadata; reading a FIFO or device could block forever
             let metadata = get_metadata(path)?;
+            if !metadata.is_file() {
+                return Err(special_file_error(path, &metadata));
+            }
 
             let size = metadata.len();
             if size == 0 {
@@ -435,6 +938,33 @@ impl CreateContext {
             self.source_files.push(source_info);
         }
 
+        // Non-recovery files are numbered after the recovery set
+        for (offset, path) in self.config.non_recovery_files.iter().enumerate() {
+            if !path.exists() {
+                return Err(CreateError::FileNotFound(
+                    path.to_string_lossy().to_string(),
+                ));
+            }
+
+            let metadata = get_metadata(path)?;
+            if !metadata.is_file() {
+                return Err(special_file_error(path, &metadata));
+            }
+            let size = metadata.len();
+            if size == 0 {
+                continue;
+            }
+
+            let packet_name = self.packet_name_for_path(path)?;
+            self.non_recovery_files
+                .push(SourceFileInfo::new_with_packet_name(
+                    path.clone(),
+                    packet_name,
+                    size,
+                    total_files + offset,
+                ));
+        }
+
         // Validate we have at least some data
         let total_size: u64 = self.source_files.iter().map(|f| f.size).sum();
         if total_size == 0 {
@@ -444,6 +974,121 @@ impl CreateContext {
         Ok(())
     }
 
+    /// Describe a stream source by what was read of it so far
+    ///
+    /// A stream that did not end within the read-ahead is sized by what was
+    /// read until it is encoded, which only works out when nothing has to be
+    /// derived from its length.
+    fn scan_stream(&mut self) -> CreateResult<()> {
+        let Some(stream) = &self.stream else {
+            return Ok(());
+        };
+        let name = stream.name().to_string();
+        let known_size = stream.known_size();
+        let head_len = stream.head_len();
+        self.reporter.report_scanning_files(1, 1, &name);
+        if known_size.is_none()
+            && (self.config.block_size.is_none() || self.config.recovery_block_count.is_none())
+        {
+            return Err(CreateError::Other(format!(
+                "{name} is longer than the memory limit; streaming it needs an explicit block size and recovery block count"
+            )));
+        }
+        if known_size == Some(0) {
+            return Err(CreateError::EmptySourceFiles);
+        }
+
+        self.source_files.push(SourceFileInfo::new_with_packet_name(
+            PathBuf::from(&name),
+            normalize_packet_path(Path::new(&name)),
+            head_len,
+            0,
+        ));
+        Ok(())
+    }
+
+    /// Hash and encode the stream, then lay out the set for its final length
+    ///
+    /// Returns the recovery block data.
+    fn encode_stream(
+        &mut self,
+        stream: StreamSource,
+        staged: &mut StagedOutputs,
+    ) -> CreateResult<RecoveryOutput> {
+        let tee = match &self.config.stream_tee {
+            Some(path) => {
+                if !self.config.overwrite_existing {
+                    ensure_outputs_absent([path.as_path()])?;
+                }
+                Some((path.as_path(), staged.create(path)?))
+            }
+            None => None,
+        };
+        let first = self.config.first_recovery_block;
+        let exponents: Vec<u16> = (first..first + self.recovery_block_count)
+            .map(|exponent| exponent as u16)
+            .collect();
+        let scratch_path = self.stream_scratch_path();
+        let mut scratch = RecoveryOutput::raw(
+            OutputFile {
+                path: temp_path(&scratch_path),
+                file: staged.create(&scratch_path)?,
+            },
+            &exponents,
+            self.block_size.as_u64(),
+        )?;
+
+        // The read-ahead is held until encoding catches up with it, so the
+        // group and chunk buffers share what it leaves of the memory limit
+        let memory_limit = self.config.memory_limit.unwrap_or(DEFAULT_MEMORY_LIMIT);
+        let encode_memory = memory_limit - read_ahead_limit(memory_limit);
+        let block_size = self.block_size.as_usize();
+        let recovery_count = self.recovery_block_count as usize;
+        let encoded = encode_and_hash_stream(
+            stream,
+            tee,
+            &mut scratch,
+            block_size,
+            calculate_chunk_size_impl(
+                block_size,
+                MAX_STREAM_BLOCKS,
+                recovery_count,
+                encode_memory / 4,
+            ),
+            calculate_source_group_blocks(block_size, MAX_STREAM_BLOCKS, encode_memory),
+            first,
+            recovery_count,
+            self.config.effective_threads(),
+            self.reporter.as_ref(),
+        )?;
+
+        self.source_files[0].size = encoded.size;
+        self.source_block_count = encoded.hash_state.block_count;
+        finalize_file_hashes(vec![encoded.hash_state], &mut self.source_files)?;
+        self.calculate_capped_recovery_file_count()?;
+        Ok(scratch)
+    }
+
+    /// Index file of the set being created
+    fn index_path(&self) -> PathBuf {
+        let output_path = Path::new(&self.config.output_name);
+        let base_name = output_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+        output_path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(format!("{}.par2", base_name))
+    }
+
+    /// Scratch file holding a stream's recovery data until the outputs are laid out
+    ///
+    /// Staged like the outputs but never committed.
+    fn stream_scratch_path(&self) -> PathBuf {
+        self.index_path().with_extension("stream")
+    }
+
     fn packet_base_path(&self) -> Cow<'_, Path> {
         match &self.config.base_path {
             Some(base_path) => Cow::Borrowed(base_path.as_path()),
@@ -452,25 +1097,7 @@ impl CreateContext {
     }
 
     fn packet_name_for_path(&self, path: &Path) -> CreateResult<String> {
-        let base_path = self.packet_base_path();
-        let base_path = base_path.as_ref();
-
-        if !base_path.as_os_str().is_empty() {
-            if let Ok(relative) = path.strip_prefix(base_path) {
-                return Ok(normalize_packet_path(relative));
-            }
-
-            if let (Ok(canonical_base), Ok(canonical_path)) = (
-                std::fs::canonicalize(base_path),
-                std::fs::canonicalize(path),
-            ) {
-                if let Ok(relative) = canonical_path.strip_prefix(&canonical_base) {
-                    return Ok(normalize_packet_path(relative));
-                }
-            }
-        }
-
-        Ok(packet_name_from_path(path))
+        Ok(packet_name_below(self.packet_base_path().as_ref(), path))
     }
 
     /// Calculate optimal block size based on source_block_count or total file size
@@ -489,119 +1116,309 @@ impl CreateContext {
                 SourceBlockCount::new(0) // Won't be used
             };
 
-        if let Some(block_size) = self.config.block_size {
+        if let Some(subslice_size) = self.config.subslice_size {
+            return self.calculate_packed_block_size(subslice_size, target_block_count);
+        }
+
+        let block_size = if let Some(block_size) = self.config.block_size {
             // User specified block size explicitly (-s option)
             // Reference: par2cmdline-turbo/src/par2creator.cpp:108
-            self.block_size = BlockSize::new(block_size);
+            block_size
+        } else if self.config.optimize_block_size {
+            self.optimize_block_size()?
         } else {
-            // Calculate block_size from target_block_count
-            // Reference: par2cmdline-turbo/src/commandline.cpp:1147-1239
-            let block_count = target_block_count.as_u64();
-            let file_count = self.source_files.len() as u64;
+            self.heuristic_block_size(target_block_count)?
+        };
+        self.block_size = BlockSize::new(block_size);
 
-            if block_count < file_count {
-                return Err(CreateError::Other(format!(
-                    "Block count ({}) cannot be smaller than the number of files ({})",
-                    block_count, file_count
-                )));
-            }
+        // Calculate total source block count with the determined block_size
+        self.source_block_count = self
+            .source_files
+            .iter()
+            .map(|f| f.calculate_block_count(self.block_size.as_u64()))
+            .sum();
 
-            if block_count == file_count {
-                // If block count equals file count, use size of largest file
-                // Reference: par2cmdline-turbo/src/commandline.cpp:1158-1173
-                let largest_filesize = self.source_files.iter().map(|f| f.size).max().unwrap_or(0);
-                let block_size = (largest_filesize + 3) & !3; // Round up to multiple of 4
-                self.block_size = BlockSize::new(block_size);
-            } else {
-                // Use binary search to find block size that results in target block count
-                // Reference: par2cmdline-turbo/src/commandline.cpp:1175-1237
+        Ok(())
+    }
+
+    /// Block size giving about `target_block_count` blocks across the source files
+    ///
+    /// Reference: par2cmdline-turbo/src/commandline.cpp:1147-1239
+    fn heuristic_block_size(&self, target_block_count: SourceBlockCount) -> CreateResult<u64> {
+        let block_count = target_block_count.as_u64();
+        let file_count = self.source_files.len() as u64;
+
+        if block_count < file_count {
+            return Err(CreateError::Other(format!(
+                "Block count ({}) cannot be smaller than the number of files ({})",
+                block_count, file_count
+            )));
+        }
 
-                // Calculate total size in 4-byte units (par2 uses 4-byte alignment)
-                let total_size: u64 = self.source_files.iter().map(|f| f.size.div_ceil(4)).sum();
+        if block_count == file_count {
+            // If block count equals file count, use size of largest file
+            // Reference: par2cmdline-turbo/src/commandline.cpp:1158-1173
+            let largest_filesize = self.source_files.iter().map(|f| f.size).max().unwrap_or(0);
+            Ok((largest_filesize + 3) & !3) // Round up to multiple of 4
+        } else {
+            // Use binary search to find block size that results in target block count
+            // Reference: par2cmdline-turbo/src/commandline.cpp:1175-1237
 
-                if block_count > total_size {
-                    // Too many blocks requested, use minimum size
-                    self.block_size = BlockSize::new(4);
-                } else {
-                    // Binary search for block size
-                    // Lower/upper bounds are in 4-byte units
-                    let mut lower_bound = total_size / block_count;
-                    let mut upper_bound =
-                        (total_size + block_count - file_count - 1) / (block_count - file_count);
-
-                    let mut size = 0u64;
-                    let mut count = 0u64;
-
-                    while lower_bound < upper_bound {
-                        size = (lower_bound + upper_bound) / 2;
-
-                        // Calculate how many blocks result from this size
-                        count = 0;
-                        for file in &self.source_files {
-                            count += file.size.div_ceil(4).div_ceil(size);
-                        }
+            // Calculate total size in 4-byte units (par2 uses 4-byte alignment)
+            let total_size: u64 = self.source_files.iter().map(|f| f.size.div_ceil(4)).sum();
 
-                        if count > block_count {
-                            lower_bound = size + 1;
-                            if lower_bound >= upper_bound {
-                                size = lower_bound;
-                                // Recalculate count with final size
-                                count = 0;
-                                for file in &self.source_files {
-                                    count += file.size.div_ceil(4).div_ceil(size);
-                                }
-                            }
-                        } else {
-                            upper_bound = size;
-                        }
+            if block_count > total_size {
+                // Too many blocks requested, use minimum size
+                Ok(4)
+            } else {
+                // Binary search for block size
+                // Lower/upper bounds are in 4-byte units
+                let mut lower_bound = total_size / block_count;
+                let mut upper_bound =
+                    (total_size + block_count - file_count - 1) / (block_count - file_count);
+
+                let mut size = 0u64;
+                let mut count = 0u64;
+
+                while lower_bound < upper_bound {
+                    size = (lower_bound + upper_bound) / 2;
+
+                    // Calculate how many blocks result from this size
+                    count = 0;
+                    for file in &self.source_files {
+                        count += file.size.div_ceil(4).div_ceil(size);
                     }
 
-                    if count > 32768 {
-                        return Err(CreateError::Other(format!(
-                            "Error calculating block size. Block count cannot be higher than 32768 (got {})",
-                            count
-                        )));
-                    } else if count == 0 {
-                        return Err(CreateError::Other(
-                            "Error calculating block size. Block count cannot be 0".to_string(),
-                        ));
+                    if count > block_count {
+                        lower_bound = size + 1;
+                        if lower_bound >= upper_bound {
+                            size = lower_bound;
+                            // Recalculate count with final size
+                            count = 0;
+                            for file in &self.source_files {
+                                count += file.size.div_ceil(4).div_ceil(size);
+                            }
+                        }
+                    } else {
+                        upper_bound = size;
                     }
+                }
 
-                    // Convert from 4-byte units to bytes
-                    self.block_size = BlockSize::new(size * 4);
+                if count > 32768 {
+                    return Err(CreateError::Other(format!(
+                        "Error calculating block size. Block count cannot be higher than 32768 (got {})",
+                        count
+                    )));
+                } else if count == 0 {
+                    return Err(CreateError::Other(
+                        "Error calculating block size. Block count cannot be 0".to_string(),
+                    ));
                 }
+
+                // Convert from 4-byte units to bytes
+                Ok(size * 4)
             }
         }
-
-        // Calculate total source block count with the determined block_size
-        self.source_block_count = self
-            .source_files
-            .iter()
-            .map(|f| f.calculate_block_count(self.block_size.as_u64()))
-            .sum();
-
-        Ok(())
     }
 
-    /// Calculate number of recovery blocks to generate
+    /// Pick the block size with the least padding plus recovery data
+    ///
+    /// Every block count the files can be split into is scored with the
+    /// configured recovery block rules. The default 2000-block choice is
+    /// scored as well, so the report can show what the search saved.
+    fn optimize_block_size(&mut self) -> CreateResult<u64> {
+        use super::block_size_optimizer::{block_size_candidates, cheapest};
+
+        let file_sizes: Vec<u64> = self.source_files.iter().map(|f| f.size).collect();
+        let candidates = block_size_candidates(&file_sizes, 32768);
+        let chosen = cheapest(
+            candidates
+                .iter()
+                .filter_map(|&(block_size, count)| self.block_size_cost(block_size, count)),
+        )
+        .ok_or_else(|| {
+            CreateError::InvalidBlockSize(format!(
+                "No block size keeps {} files within 32768 blocks",
+                file_sizes.len()
+            ))
+        })?;
+
+        let default = self
+            .heuristic_block_size(SourceBlockCount::new(2000))
+            .ok()
+            .and_then(|block_size| {
+                let count = self
+                    .source_files
+                    .iter()
+                    .map(|f| f.calculate_block_count(block_size) as u64)
+                    .sum();
+                self.block_size_cost(block_size, count)
+            });
+
+        let choice = BlockSizeChoice {
+            chosen,
+            default,
+            candidates: candidates.len(),
+        };
+        self.reporter.report_block_size_choice(&choice);
+        self.block_size_choice = Some(choice);
+        Ok(chosen.block_size)
+    }
+
+    /// Padding and estimated recovery output for one unpacked block size
+    ///
+    /// `None` when the recovery block rules reject the resulting block count.
+    fn block_size_cost(&self, block_size: u64, source_block_count: u64) -> Option<BlockSizeCost> {
+        use super::file_naming::default_recovery_file_count_for_scheme;
+        use super::recovery_output::RECOVERY_HEADER_SIZE;
+
+        let recovery_block_count = self
+            .recovery_blocks_for(block_size, source_block_count)
+            .and_then(|count| self.checked_recovery_block_count(count))
+            .ok()?;
+
+        // Block checksums are repeated in the index file and in every copy of the
+        // critical packets in the recovery files; external recovery data keeps
+        // one copy plus an RFSC entry per block
+        let (checksum_copies, per_recovery_block) = if self.config.external_recovery_data {
+            (1, block_size + 24)
+        } else {
+            let largest_file_size = self.source_files.iter().map(|f| f.size).max().unwrap_or(0);
+            let file_count = self.config.recovery_file_count.unwrap_or_else(|| {
+                default_recovery_file_count_for_scheme(
+                    self.config.recovery_file_scheme,
+                    recovery_block_count,
+                    largest_file_size,
+                    block_size,
+                )
+            });
+            let copies =
+                self.volume_critical_copies(recovery_block_count.div_ceil(file_count.max(1)));
+            (
+                1 + file_count as u64 * copies as u64,
+                block_size + RECOVERY_HEADER_SIZE,
+            )
+        };
+
+        let total_size: u64 = self.source_files.iter().map(|f| f.size).sum();
+        Some(BlockSizeCost {
+            block_size,
+            source_block_count: source_block_count as u32,
+            recovery_block_count,
+            padding: source_block_count * block_size - total_size,
+            recovery_size: recovery_block_count as u64 * per_recovery_block
+                + checksum_copies * source_block_count * 20,
+        })
+    }
+
+    /// Calculate block size and source block count for a packed set
+    ///
+    /// Files are only padded to whole subslices, so the block count depends on
+    /// the total packed size rather than on the number of files.
+    fn calculate_packed_block_size(
+        &mut self,
+        subslice_size: u64,
+        target_block_count: SourceBlockCount,
+    ) -> CreateResult<()> {
+        let subslice_count: u64 = self
+            .source_files
+            .iter()
+            .map(|f| f.calculate_block_count(subslice_size) as u64)
+            .sum();
+
+        let block_size = match self.config.block_size {
+            Some(block_size) => block_size,
+            None => subslice_count.div_ceil(target_block_count.as_u64()).max(1) * subslice_size,
+        };
+
+        let count = subslice_count.div_ceil(block_size / subslice_size);
+        if count > 32768 {
+            return Err(CreateError::Other(format!(
+                "Error calculating block size. Block count cannot be higher than 32768 (got {})",
+                count
+            )));
+        }
+
+        self.block_size = BlockSize::new(block_size);
+        self.source_block_count = count as u32;
+
+        Ok(())
+    }
+
+    /// Subslice size used to lay out the packed stream
+    ///
+    /// Equal to the block size for ordinary (unpacked) sets.
+    fn subslice_size(&self) -> u64 {
+        self.config
+            .subslice_size
+            .unwrap_or_else(|| self.block_size.as_u64())
+    }
+
+    /// Copies of the critical packets in a volume holding `block_count` recovery blocks
+    fn volume_critical_copies(&self, block_count: u32) -> u32 {
+        use super::file_naming::default_critical_copies;
+
+        self.config
+            .critical_copies
+            .unwrap_or_else(|| default_critical_copies(block_count))
+    }
+
+    /// Calculate number of recovery blocks to generate
     fn calculate_recovery_blocks(&mut self) -> CreateResult<()> {
-        let recovery_blocks = if let Some(count) = self.config.recovery_block_count {
+        let recovery_blocks =
+            self.recovery_blocks_for(self.block_size.as_u64(), self.source_block_count as u64)?;
+        self.recovery_block_count = self.checked_recovery_block_count(recovery_blocks)?;
+        Ok(())
+    }
+
+    /// Recovery blocks the configuration asks for at a given block layout
+    fn recovery_blocks_for(&self, block_size: u64, source_block_count: u64) -> CreateResult<u64> {
+        if let Some(count) = self.config.recovery_block_count {
             // Explicit count specified
-            count as u64
+            Ok(count as u64)
         } else if let Some(target_size) = self.config.recovery_target_size {
-            self.calculate_recovery_blocks_for_target_size(target_size)?
+            self.calculate_recovery_blocks_for_target_size(target_size, block_size)
         } else if let Some(percent) = self.config.redundancy_percentage {
             // Reference: par2cmdline-turbo/src/commandline.cpp ComputeRecoveryBlockCount()
-            let count = (self.source_block_count as u64 * percent as u64 + 50) / 100;
-            count.max(1)
+            let count = (source_block_count * percent as u64 + 50) / 100;
+            Ok(count.max(1))
         } else {
-            return Err(CreateError::Other(
+            Err(CreateError::Other(
                 "Must specify recovery block count, redundancy percentage, or target recovery size"
                     .to_string(),
-            ));
-        };
+            ))
+        }
+    }
 
-        self.recovery_block_count = self.checked_recovery_block_count(recovery_blocks)?;
+    /// Work out the file count for the maximum file size scheme
+    ///
+    /// Fails when the critical packets and one recovery block alone exceed the
+    /// limit, so the problem shows up before anything is read or written.
+    fn calculate_capped_recovery_file_count(&mut self) -> CreateResult<()> {
+        use super::file_naming::recovery_file_count_for_max_size;
+        use super::types::RecoveryFileScheme;
+
+        let RecoveryFileScheme::MaxFileSize(max_file_size) = self.config.recovery_file_scheme
+        else {
+            return Ok(());
+        };
+        let critical_size = self.critical_packet_size()?;
+        let count = recovery_file_count_for_max_size(
+            self.recovery_block_count,
+            max_file_size,
+            self.block_size.as_u64(),
+            critical_size,
+            self.config.critical_copies,
+        )
+        .ok_or_else(|| {
+            CreateError::Other(format!(
+                "Maximum recovery file size of {} bytes cannot hold the critical packets ({} bytes) and one {}-byte recovery block",
+                max_file_size,
+                critical_size,
+                self.block_size.as_u64()
+            ))
+        })?;
+        self.capped_recovery_file_count = Some(count);
         Ok(())
     }
 
@@ -621,11 +1438,22 @@ impl CreateContext {
         Ok(recovery_blocks as u32)
     }
 
-    fn calculate_recovery_blocks_for_target_size(&self, target_size: u64) -> CreateResult<u64> {
+    fn calculate_recovery_blocks_for_target_size(
+        &self,
+        target_size: u64,
+        block_size: u64,
+    ) -> CreateResult<u64> {
         use super::file_naming::default_recovery_file_count_for_scheme;
 
-        let overhead_per_recovery_file = self.source_block_count as u64 * 21;
-        let recovery_packet_size = self.block_size.as_u64() + 70;
+        // Every recovery file repeats the IFSC entries, one per subslice
+        let subslice_size = self.config.subslice_size.unwrap_or(block_size);
+        let checksum_entries: u64 = self
+            .source_files
+            .iter()
+            .map(|f| f.calculate_block_count(subslice_size) as u64)
+            .sum();
+        let overhead_per_recovery_file = checksum_entries * 21;
+        let recovery_packet_size = block_size + 70;
         let largest_file_size = self.source_files.iter().map(|f| f.size).max().unwrap_or(0);
 
         let recovery_file_count = if let Some(count) = self.config.recovery_file_count {
@@ -644,7 +1472,7 @@ impl CreateContext {
                 self.config.recovery_file_scheme,
                 estimated_recovery_blocks,
                 largest_file_size,
-                self.block_size.as_u64(),
+                block_size,
             )
         };
 
@@ -667,7 +1495,19 @@ impl CreateContext {
     /// Generate recovery set ID
     fn generate_recovery_set_id(&mut self) -> CreateResult<()> {
         // Generate recovery set ID
-        let set_id = generate_recovery_set_id(self.block_size.as_u64(), &self.source_files)?;
+        let set_id = match self.config.subslice_size {
+            Some(subslice_size) => generate_packed_recovery_set_id(
+                subslice_size,
+                self.block_size.as_u64(),
+                &self.source_files,
+                &self.non_recovery_files,
+            )?,
+            None => generate_recovery_set_id(
+                self.block_size.as_u64(),
+                &self.source_files,
+                &self.non_recovery_files,
+            )?,
+        };
         self.recovery_set_id = Some(set_id);
         Ok(())
     }
@@ -677,59 +1517,141 @@ impl CreateContext {
     /// Delegates to module-level helpers for each sub-step.
     ///
     /// Reference: par2cmdline-turbo/src/par2creator.cpp ProcessData()
-    fn generate_recovery_blocks(&mut self) -> CreateResult<()> {
+    fn generate_recovery_blocks(
+        &mut self,
+        recovery_output: &mut RecoveryOutput,
+        checkpoint: Option<&mut Checkpoint>,
+    ) -> CreateResult<()> {
         use crate::reed_solomon::RecoveryBlockEncoder;
 
+        let encoder =
+            RecoveryBlockEncoder::new(self.block_size.as_usize(), self.source_block_count as usize);
+
+        // Files found in the hash cache are still read for the encoder, but
+        // not hashed again
+        let mut hash_cache = self.config.hash_cache.as_deref().map(HashCache::load);
+        let source_known = lookup_cached_hashes(
+            hash_cache.as_ref(),
+            &self.source_files,
+            self.subslice_size(),
+        );
+        let non_recovery_known = lookup_cached_hashes(
+            hash_cache.as_ref(),
+            &self.non_recovery_files,
+            self.subslice_size(),
+        );
+
+        // A resumed run keeps the chunk size its recovery data was laid out with
+        let encode = match &checkpoint {
+            Some(checkpoint) => EncodeLayout {
+                chunk_size: checkpoint.chunk_size(),
+                ..self.encode_layout()
+            },
+            None => self.encode_layout(),
+        };
+
+        // Files are still read with no recovery blocks: the file and block hashes
+        // are needed for the critical packets (and for embedded FileSlic data)
         if self.recovery_block_count == 0 {
             self.reporter.report_scanning_files(
                 0,
                 0,
                 "No recovery blocks to generate (redundancy = 0%)",
             );
-            return Ok(());
+        } else {
+            self.reporter.report_scanning_files(
+                0,
+                self.recovery_block_count as usize,
+                &format!("Processing files ({})...", encode.describe()),
+            );
         }
 
-        let encoder =
-            RecoveryBlockEncoder::new(self.block_size.as_usize(), self.source_block_count as usize);
-        let chunk_size = self.calculate_chunk_size();
-
-        self.reporter.report_scanning_files(
-            0,
-            self.recovery_block_count as usize,
-            &format!(
-                "Processing files (chunk size: {} bytes)...",
-                chunk_size.as_usize()
-            ),
-        );
+        let stamps = |files: &[SourceFileInfo], known: &[Option<&CachedHashes>]| {
+            files
+                .iter()
+                .zip(known)
+                .map(|(file, known)| match known {
+                    Some(_) => None,
+                    None => FileStamp::of(&file.path),
+                })
+                .collect::<Vec<_>>()
+        };
+        let source_stamps = stamps(&self.source_files, &source_known);
+        let non_recovery_stamps = stamps(&self.non_recovery_files, &non_recovery_known);
 
-        let (recovery_blocks, hash_states) = encode_and_hash_files(
+        let hash_states = encode_and_hash_files(
             &self.source_files,
+            &source_known,
             self.block_size.as_u64(),
-            chunk_size.as_usize(),
+            self.subslice_size(),
+            encode,
             self.source_block_count,
             encoder.base_values(),
             self.config.first_recovery_block,
             self.recovery_block_count as usize,
             self.config.effective_threads(),
+            self.config.file_thread_count.unwrap_or(1),
+            recovery_output,
+            checkpoint,
             self.reporter.as_ref(),
         )?;
 
         finalize_file_hashes(hash_states, &mut self.source_files)?;
 
+        let non_recovery_states = hash_non_recovery_files(
+            &self.non_recovery_files,
+            &non_recovery_known,
+            self.subslice_size(),
+            self.reporter.as_ref(),
+        )?;
+        finalize_file_hashes(non_recovery_states, &mut self.non_recovery_files)?;
+
+        if let Some(cache) = &mut hash_cache {
+            record_hashes(
+                cache,
+                &self.source_files,
+                &source_stamps,
+                self.subslice_size(),
+            );
+            record_hashes(
+                cache,
+                &self.non_recovery_files,
+                &non_recovery_stamps,
+                self.subslice_size(),
+            );
+            // The cache only saves work; failing to write it must not fail create
+            if let Err(err) = cache.save() {
+                log::warn!(
+                    "Failed to write hash cache {}: {}",
+                    cache.path().display(),
+                    err
+                );
+            }
+        }
+
         self.reporter.report_scanning_files(
             self.recovery_block_count as usize,
             self.recovery_block_count as usize,
             "Processing complete (hashes + recovery blocks)",
         );
 
-        self.recovery_blocks = recovery_blocks;
         Ok(())
     }
 
+    /// Encode layout for this set within the memory limit
+    fn encode_layout(&self) -> EncodeLayout {
+        EncodeLayout::new(
+            self.block_size.as_usize(),
+            self.source_block_count as usize,
+            self.recovery_block_count as usize,
+            self.config.memory_limit.unwrap_or(DEFAULT_MEMORY_LIMIT),
+        )
+    }
+
     /// Calculate optimal chunk size for processing.
     /// Reference: par2cmdline-turbo/src/par2creator.cpp:329-360 CalculateProcessBlockSize()
     fn calculate_chunk_size(&self) -> ChunkSize {
-        ChunkSize::new(calculate_chunk_size_impl(
+        ChunkSize::new(calculate_grouped_chunk_size(
             self.block_size.as_usize(),
             self.source_block_count as usize,
             self.recovery_block_count as usize,
@@ -739,47 +1661,214 @@ impl CreateContext {
 
     /// Write PAR2 files: index file (critical packets only) + volume files (critical + recovery)
     ///
+    /// The recovery data is already on disk in `recovery_output`; this fills in
+    /// the recovery packet headers now that the set ID is known, then appends
+    /// the critical packets to every file.
+    ///
+    /// With `embed_source_data`, each source file is also copied into one volume
+    /// as FileSlic packets (round-robin by file), or into the index file when
+    /// there are no volumes.
+    ///
     /// Reference: par2cmdline-turbo/src/par2creator.cpp WriteCriticalPackets() and
     /// WriteRecoveryPacketHeaders() / InitialiseOutputFiles()
-    fn write_par2_files(&mut self) -> CreateResult<()> {
-        use super::file_naming::plan_recovery_files;
+    fn write_par2_files(
+        &mut self,
+        outputs: &OutputPlan,
+        mut recovery_output: RecoveryOutput,
+        staged: &mut StagedOutputs,
+    ) -> CreateResult<()> {
         use super::packet_generator::{
-            generate_creator_packet, generate_file_description_packet,
-            generate_file_verification_packet, generate_main_packet, write_creator_packet,
-            write_file_description_packet, write_file_verification_packet, write_main_packet,
+            generate_recovery_file_slice_checksum_packet, write_recovery_file_slice_checksum_packet,
         };
         use std::io::Write;
 
         let recovery_set_id = self
             .recovery_set_id
             .ok_or_else(|| CreateError::Other("Recovery set ID not generated".to_string()))?;
-
-        // Generate all critical packets
-        // Reference: par2cmdline-turbo/src/par2creator.cpp CreateMainPacket(), CreateCreatorPacket()
-        let main_packet = generate_main_packet(
+        let critical_bytes = self.critical_packet_bytes(
             recovery_set_id,
-            self.block_size.as_u64(),
             &self.source_files,
+            &self.non_recovery_files,
         )?;
+
+        recovery_output.backfill_packet_headers(recovery_set_id)?;
+        recovery_output.write_critical_packets(&critical_bytes)?;
+
+        // Write index file: critical packets only, no recovery data
+        // Reference: par2cmdline-turbo creates base.par2 with no recovery slices
+        let index_path = &outputs.index_path;
+        let mut index_file = staged.create(index_path)?;
+        index_file
+            .write_all(&critical_bytes)
+            .map_err(|e| CreateError::FileCreateError {
+                file: index_path.to_string_lossy().to_string(),
+                source: e,
+            })?;
+        if let Some(external_path) = &outputs.external_path {
+            let rfsc_packet = generate_recovery_file_slice_checksum_packet(
+                recovery_set_id,
+                recovery_output.slice_checksums()?,
+            );
+            write_recovery_file_slice_checksum_packet(&mut index_file, &rfsc_packet)?;
+            self.output_files
+                .push(external_path.to_string_lossy().to_string());
+        }
+        if self.config.embed_source_data && outputs.volumes.is_empty() {
+            let mut writer = std::io::BufWriter::new(&mut index_file);
+            for file in &self.source_files {
+                write_embedded_slice_packets(
+                    &mut writer,
+                    file,
+                    self.subslice_size(),
+                    recovery_set_id,
+                )?;
+            }
+            writer.flush().map_err(|e| CreateError::FileCreateError {
+                file: index_path.to_string_lossy().to_string(),
+                source: e,
+            })?;
+        }
+        index_file
+            .flush()
+            .and_then(|_| index_file.sync_all())
+            .map_err(|e| CreateError::FileCreateError {
+                file: index_path.to_string_lossy().to_string(),
+                source: e,
+            })?;
+        self.output_files
+            .push(index_path.to_string_lossy().to_string());
+
+        // Finish each volume file: its recovery and critical packets are already
+        // in place, followed by any embedded source data
+        // Reference: par2cmdline-turbo/src/par2creator.cpp WriteCriticalPackets()
+        let volume_count = outputs.volumes.len();
+        let mut output_files = recovery_output.into_files()?;
+        if outputs.external_path.is_some() {
+            for output in &output_files {
+                output
+                    .file
+                    .sync_all()
+                    .map_err(|e| CreateError::FileCreateError {
+                        file: output.path.to_string_lossy().to_string(),
+                        source: e,
+                    })?;
+            }
+            output_files.clear();
+        }
+        for (vol_idx, ((_, final_path), output)) in
+            outputs.volumes.iter().zip(output_files).enumerate()
+        {
+            let OutputFile {
+                path: vol_path,
+                file: mut vol_file,
+            } = output;
+
+            if self.config.embed_source_data {
+                let mut writer = std::io::BufWriter::new(&mut vol_file);
+                for file in self.source_files.iter().skip(vol_idx).step_by(volume_count) {
+                    write_embedded_slice_packets(
+                        &mut writer,
+                        file,
+                        self.subslice_size(),
+                        recovery_set_id,
+                    )?;
+                }
+                writer.flush().map_err(|e| CreateError::FileCreateError {
+                    file: vol_path.to_string_lossy().to_string(),
+                    source: e,
+                })?;
+            }
+
+            vol_file
+                .flush()
+                .and_then(|_| vol_file.sync_all())
+                .map_err(|e| CreateError::FileCreateError {
+                    file: vol_path.to_string_lossy().to_string(),
+                    source: e,
+                })?;
+            self.output_files
+                .push(final_path.to_string_lossy().to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Serialize the critical packets written to every output file
+    ///
+    /// Reference: par2cmdline-turbo/src/par2creator.cpp WriteCriticalPackets()
+    fn critical_packet_bytes(
+        &self,
+        recovery_set_id: RecoverySetId,
+        source_files: &[SourceFileInfo],
+        non_recovery_files: &[SourceFileInfo],
+    ) -> CreateResult<Vec<u8>> {
+        use super::packet_generator::{
+            generate_comment_packets, generate_creator_packet, generate_file_description_packet,
+            generate_file_verification_packet, generate_main_packet, generate_packed_main_packet,
+            generate_unicode_filename_packet, write_ascii_comment_packet, write_creator_packet,
+            write_file_description_packet, write_file_verification_packet, write_main_packet,
+            write_packed_main_packet, write_unicode_comment_packet, write_unicode_filename_packet,
+        };
+
+        // Generate all critical packets
+        // Reference: par2cmdline-turbo/src/par2creator.cpp CreateMainPacket(), CreateCreatorPacket()
         let creator_packet = generate_creator_packet(recovery_set_id)?;
 
-        let file_desc_packets: Vec<_> = self
-            .source_files
-            .iter()
+        // Non-recovery files get the same FileDesc and IFSC packets as recovery files
+        let described_files = || source_files.iter().chain(non_recovery_files);
+
+        let file_desc_packets: Vec<_> = described_files()
             .map(|f| generate_file_description_packet(recovery_set_id, f))
             .collect::<CreateResult<_>>()?;
 
-        let file_verif_packets: Vec<_> = self
-            .source_files
-            .iter()
+        let file_verif_packets: Vec<_> = described_files()
             .map(|f| generate_file_verification_packet(recovery_set_id, f))
             .collect::<CreateResult<_>>()?;
 
+        // UniFileN packets are only needed when the 8-bit name cannot carry the name as-is
+        let unicode_name_packets: Vec<_> = if self.config.unicode_filenames {
+            described_files()
+                .filter(|f| !f.packet_name().is_ascii())
+                .map(|f| generate_unicode_filename_packet(recovery_set_id, f))
+                .collect::<CreateResult<_>>()?
+        } else {
+            Vec::new()
+        };
+
+        let comment_packets: Vec<_> = self
+            .config
+            .comments
+            .iter()
+            .map(|comment| generate_comment_packets(recovery_set_id, comment))
+            .collect::<CreateResult<_>>()?;
+
         // Serialize critical packets to a byte buffer once, reuse for every output file
         // Reference: par2cmdline-turbo/src/par2creator.cpp WriteCriticalPackets()
+        // Packed sets replace the Main packet with a PkdMain packet
         let mut critical_bytes: Vec<u8> = Vec::new();
-        write_main_packet(&mut critical_bytes, &main_packet)
-            .map_err(|e| packet_write_error("main packet", e))?;
+        match self.config.subslice_size {
+            Some(subslice_size) => {
+                let packed_main_packet = generate_packed_main_packet(
+                    recovery_set_id,
+                    subslice_size,
+                    self.block_size.as_u64(),
+                    source_files,
+                    non_recovery_files,
+                )?;
+                write_packed_main_packet(&mut critical_bytes, &packed_main_packet)
+                    .map_err(|e| packet_write_error("packed main packet", e))?;
+            }
+            None => {
+                let main_packet = generate_main_packet(
+                    recovery_set_id,
+                    self.block_size.as_u64(),
+                    source_files,
+                    non_recovery_files,
+                )?;
+                write_main_packet(&mut critical_bytes, &main_packet)
+                    .map_err(|e| packet_write_error("main packet", e))?;
+            }
+        }
         write_creator_packet(&mut critical_bytes, &creator_packet)
             .map_err(|e| packet_write_error("creator packet", e))?;
         for packet in &file_desc_packets {
@@ -790,8 +1879,35 @@ impl CreateContext {
             write_file_verification_packet(&mut critical_bytes, packet)
                 .map_err(|e| packet_write_error("file verification packet", e))?;
         }
+        for packet in &unicode_name_packets {
+            write_unicode_filename_packet(&mut critical_bytes, packet)
+                .map_err(|e| packet_write_error("unicode filename packet", e))?;
+        }
+        for (ascii_packet, unicode_packet) in &comment_packets {
+            write_ascii_comment_packet(&mut critical_bytes, ascii_packet)
+                .map_err(|e| packet_write_error("ascii comment packet", e))?;
+            write_unicode_comment_packet(&mut critical_bytes, unicode_packet)
+                .map_err(|e| packet_write_error("unicode comment packet", e))?;
+        }
+        for packet in &self.config.custom_packets {
+            crate::packets::unknown_packet::write(
+                &mut critical_bytes,
+                &packet.packet_type,
+                &recovery_set_id,
+                &packet.body,
+            )
+            .map_err(|e| packet_write_error("custom packet", e))?;
+        }
+
+        Ok(critical_bytes)
+    }
+
+    /// Decide the names of the index, volume and external recovery files
+    ///
+    /// External recovery data replaces the recovery volumes with one flat file.
+    fn plan_output_files(&self) -> OutputPlan {
+        use super::file_naming::{default_recovery_file_count_for_scheme, plan_recovery_files};
 
-        // Determine output directory and base name
         let output_path = Path::new(&self.config.output_name);
         let output_dir = output_path
             .parent()
@@ -803,112 +1919,176 @@ impl CreateContext {
             .unwrap_or("output")
             .to_string();
 
-        let index_path = output_dir.join(format!("{}.par2", base_name));
         let largest_file_size = self.source_files.iter().map(|f| f.size).max().unwrap_or(0);
-        let file_count = {
-            use super::file_naming::default_recovery_file_count_for_scheme;
-            let default_count = default_recovery_file_count_for_scheme(
-                self.config.recovery_file_scheme,
+        let file_count = self
+            .config
+            .recovery_file_count
+            .or(self.capped_recovery_file_count)
+            .unwrap_or_else(|| {
+                default_recovery_file_count_for_scheme(
+                    self.config.recovery_file_scheme,
+                    self.recovery_block_count,
+                    largest_file_size,
+                    self.block_size.as_u64(),
+                )
+            });
+        let external_path = self
+            .config
+            .external_recovery_data
+            .then(|| output_dir.join(format!("{}.rfsc", base_name)));
+        let volumes = if external_path.is_some() {
+            Vec::new()
+        } else {
+            plan_recovery_files(
+                &base_name,
+                file_count,
                 self.recovery_block_count,
+                self.config.first_recovery_block,
+                self.config.recovery_file_scheme,
                 largest_file_size,
                 self.block_size.as_u64(),
-            );
-            self.config.recovery_file_count.unwrap_or(default_count)
+            )
+            .into_iter()
+            .map(|entry| {
+                let path = output_dir.join(&entry.filename);
+                (entry, path)
+            })
+            .collect()
         };
-        let plan = plan_recovery_files(
-            &base_name,
-            file_count,
-            self.recovery_block_count,
-            self.config.first_recovery_block,
-            self.config.recovery_file_scheme,
-            largest_file_size,
-            self.block_size.as_u64(),
-        );
 
-        let volume_paths: Vec<PathBuf> = plan
-            .iter()
-            .map(|entry| output_dir.join(&entry.filename))
-            .collect();
-        let output_paths: Vec<&Path> = std::iter::once(index_path.as_path())
-            .chain(volume_paths.iter().map(PathBuf::as_path))
-            .collect();
+        OutputPlan {
+            index_path: self.index_path(),
+            volumes,
+            external_path,
+        }
+    }
 
+    /// Create the files that receive recovery data and reserve space for it
+    ///
+    /// Every planned output path is checked up front, so an existing file is
+    /// reported before any source data is read. The files are staged under
+    /// temporary names until the whole set has been written.
+    ///
+    /// Reference: par2cmdline-turbo/src/par2creator.cpp InitialiseOutputFiles()
+    fn open_recovery_output(
+        &self,
+        outputs: &OutputPlan,
+        staged: &mut StagedOutputs,
+        resuming: bool,
+    ) -> CreateResult<RecoveryOutput> {
         if !self.config.overwrite_existing {
-            for path in &output_paths {
-                if path.exists() {
-                    return Err(CreateError::FileCreateError {
-                        file: path.to_string_lossy().to_string(),
-                        source: std::io::Error::new(
-                            std::io::ErrorKind::AlreadyExists,
-                            "output file already exists",
-                        ),
-                    });
-                }
-            }
+            ensure_outputs_absent(outputs.paths())?;
         }
 
-        let open_output = |path: &Path, overwrite_existing: bool| {
-            if overwrite_existing {
-                create_file(path)
-            } else {
-                create_new_output_file(path)
-            }
+        let mut open = |path: &Path| -> CreateResult<OutputFile> {
+            Ok(OutputFile {
+                path: temp_path(path),
+                file: if resuming {
+                    staged.reopen(path)?
+                } else {
+                    staged.create(path)?
+                },
+            })
         };
+        let block_size = self.block_size.as_u64();
+
+        if let Some(external_path) = &outputs.external_path {
+            let first = self.config.first_recovery_block;
+            let exponents: Vec<u16> = (first..first + self.recovery_block_count)
+                .map(|exponent| exponent as u16)
+                .collect();
+            return RecoveryOutput::raw(open(external_path)?, &exponents, block_size);
+        }
 
-        // Write index file: critical packets only, no recovery data
-        // Reference: par2cmdline-turbo creates base.par2 with no recovery slices
-        let mut index_file = open_output(&index_path, self.config.overwrite_existing)?;
-        index_file
-            .write_all(&critical_bytes)
-            .map_err(|e| CreateError::FileCreateError {
-                file: index_path.to_string_lossy().to_string(),
-                source: e,
-            })?;
-        index_file
-            .flush()
-            .map_err(|e| CreateError::FileCreateError {
-                file: index_path.to_string_lossy().to_string(),
-                source: e,
-            })?;
-        self.output_files
-            .push(index_path.to_string_lossy().to_string());
+        let packet_type = if self.config.subslice_size.is_some() {
+            PACKED_RECOVERY_PACKET_TYPE
+        } else {
+            RECOVERY_PACKET_TYPE
+        };
+        let volumes = outputs
+            .volumes
+            .iter()
+            .map(|(entry, path)| {
+                Ok(Volume {
+                    output: open(path)?,
+                    exponents: (entry.first_exponent..entry.first_exponent + entry.block_count)
+                        .map(|exponent| exponent as u16)
+                        .collect(),
+                    critical_copies: self.volume_critical_copies(entry.block_count),
+                })
+            })
+            .collect::<CreateResult<Vec<_>>>()?;
+        RecoveryOutput::packets(
+            volumes,
+            block_size,
+            packet_type,
+            self.critical_packet_size()?,
+        )
+    }
 
-        // Write each volume file: critical packets + its slice of recovery blocks
-        // Reference: par2cmdline-turbo/src/par2creator.cpp WriteRecoveryPackets()
-        for (entry, vol_path) in plan.iter().zip(volume_paths) {
-            let mut vol_file = open_output(&vol_path, self.config.overwrite_existing)?;
+    /// Open the checkpoint of a checkpointed run
+    ///
+    /// With `resume` set, an existing checkpoint is loaded and must match this
+    /// run; otherwise, or when there is none yet, encoding starts from the
+    /// beginning and any old checkpoint is discarded.
+    fn open_checkpoint(&self, outputs: &OutputPlan) -> CreateResult<Option<Checkpoint>> {
+        let Some(path) = self.config.checkpoint.clone() else {
+            return Ok(None);
+        };
 
-            vol_file
-                .write_all(&critical_bytes)
-                .map_err(|e| CreateError::FileCreateError {
-                    file: vol_path.to_string_lossy().to_string(),
-                    source: e,
+        let sources = self
+            .source_files
+            .iter()
+            .map(|file| {
+                let stamp = FileStamp::of(&file.path).ok_or_else(|| {
+                    CreateError::Other(format!(
+                        "Cannot checkpoint {}: its modification time is unavailable",
+                        file.path.display()
+                    ))
                 })?;
+                Ok((file.path.clone(), stamp))
+            })
+            .collect::<CreateResult<Vec<_>>>()?;
+        let params = CheckpointParams {
+            block_size: self.block_size.as_u64(),
+            subslice_size: self.subslice_size(),
+            first_recovery_block: self.config.first_recovery_block,
+            recovery_block_count: self.recovery_block_count,
+            outputs: outputs.paths().map(Path::to_path_buf).collect(),
+            sources,
+        };
 
-            // Write recovery slice packets for this volume
-            for i in 0..entry.block_count {
-                let packet_exponent = entry.first_exponent + i;
-                let local_idx = (packet_exponent - self.config.first_recovery_block) as usize;
-                let (recovery_exponent, recovery_data) = &self.recovery_blocks[local_idx];
-
-                write_recovery_slice_packet(
-                    &mut vol_file,
-                    *recovery_exponent as u32,
-                    recovery_data,
-                    recovery_set_id,
-                )
-                .map_err(|e| packet_write_error("recovery packet", e))?;
+        if self.config.resume {
+            if let Some(checkpoint) = Checkpoint::load(path.clone(), params.clone())? {
+                self.reporter.report_scanning_files(
+                    checkpoint.blocks_done(),
+                    self.source_block_count as usize,
+                    &format!(
+                        "Resuming from {}: {} of {} source blocks done",
+                        checkpoint.path().display(),
+                        checkpoint.blocks_done(),
+                        self.source_block_count
+                    ),
+                );
+                return Ok(Some(checkpoint));
             }
-
-            vol_file.flush().map_err(|e| CreateError::FileCreateError {
-                file: vol_path.to_string_lossy().to_string(),
-                source: e,
-            })?;
-            self.output_files
-                .push(vol_path.to_string_lossy().to_string());
         }
 
-        Ok(())
+        match std::fs::remove_file(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(CreateError::FileCreateError {
+                    file: path.to_string_lossy().to_string(),
+                    source: e,
+                })
+            }
+        }
+        Ok(Some(Checkpoint::new(
+            path,
+            params,
+            self.calculate_chunk_size().as_usize(),
+        )))
     }
 
     /// Get the list of created output files
@@ -930,6 +2110,147 @@ impl CreateContext {
     pub fn source_block_count(&self) -> u32 {
         self.source_block_count
     }
+
+    /// Get the outcome of the block size search, if it was requested
+    pub fn block_size_choice(&self) -> Option<&BlockSizeChoice> {
+        self.block_size_choice.as_ref()
+    }
+
+    /// Size of the critical packets repeated in every output file
+    ///
+    /// Worked out before hashing: placeholder checksums serialize to the same
+    /// size as the real ones.
+    fn critical_packet_size(&self) -> CreateResult<u64> {
+        use super::source_file::BlockChecksum;
+
+        let subslice_size = self.subslice_size();
+        let with_placeholder_checksums = |files: &[SourceFileInfo]| -> Vec<SourceFileInfo> {
+            files
+                .iter()
+                .map(|file| {
+                    let mut file = file.clone();
+                    let placeholder = BlockChecksum {
+                        crc32: 0,
+                        hash: crate::domain::Md5Hash::new([0; 16]),
+                        global_index: 0,
+                    };
+                    file.block_checksums =
+                        vec![placeholder; file.calculate_block_count(subslice_size) as usize];
+                    file
+                })
+                .collect()
+        };
+        Ok(self
+            .critical_packet_bytes(
+                RecoverySetId::new([0; 16]),
+                &with_placeholder_checksums(&self.source_files),
+                &with_placeholder_checksums(&self.non_recovery_files),
+            )?
+            .len() as u64)
+    }
+
+    /// Work out what `create` would write, without reading any source data
+    ///
+    /// Output sizes are exact: the critical packets are serialized with
+    /// placeholder hashes, which have the same size as the real ones.
+    pub fn plan(&self) -> CreateResult<CreatePlan> {
+        use super::plan::{PlannedOutputFile, PlannedSourceFile};
+        use super::recovery_output::RECOVERY_HEADER_SIZE;
+
+        if let Some(stream) = self.stream.as_ref().filter(|s| s.known_size().is_none()) {
+            return Err(CreateError::Other(format!(
+                "{} is longer than the memory limit, so its layout is known only once it has been read",
+                stream.name()
+            )));
+        }
+
+        let block_size = self.block_size.as_u64();
+        let subslice_size = self.subslice_size();
+        let critical_size = self.critical_packet_size()?;
+        let embedded_size = |files: &mut dyn Iterator<Item = &SourceFileInfo>| -> u64 {
+            if !self.config.embed_source_data {
+                return 0;
+            }
+            files
+                .map(|file| embedded_slice_packets_size(file.size, subslice_size))
+                .sum()
+        };
+
+        let outputs = self.plan_output_files();
+        let first = self.config.first_recovery_block;
+        let mut index_size = critical_size;
+        if outputs.external_path.is_some() {
+            // RFSC packet: 24 bytes per recovery slice
+            index_size += 64 + 24 * self.recovery_block_count as u64;
+        }
+        if outputs.volumes.is_empty() {
+            index_size += embedded_size(&mut self.source_files.iter());
+        }
+
+        let mut output_files = vec![PlannedOutputFile {
+            path: outputs.index_path.clone(),
+            exponents: first..first,
+            size: index_size,
+        }];
+        let volume_count = outputs.volumes.len();
+        for (vol_idx, (entry, path)) in outputs.volumes.iter().enumerate() {
+            let embedded_files = &mut self.source_files.iter().skip(vol_idx).step_by(volume_count);
+            output_files.push(PlannedOutputFile {
+                path: path.clone(),
+                exponents: entry.first_exponent..entry.first_exponent + entry.block_count,
+                size: entry.block_count as u64 * (RECOVERY_HEADER_SIZE + block_size)
+                    + self.volume_critical_copies(entry.block_count) as u64 * critical_size
+                    + embedded_size(embedded_files),
+            });
+        }
+        if let Some(external_path) = &outputs.external_path {
+            output_files.push(PlannedOutputFile {
+                path: external_path.clone(),
+                exponents: first..first + self.recovery_block_count,
+                size: self.recovery_block_count as u64 * block_size,
+            });
+        }
+
+        let source_files = self
+            .source_files
+            .iter()
+            .map(|file| {
+                let block_count = file.calculate_block_count(subslice_size);
+                PlannedSourceFile {
+                    path: file.path.clone(),
+                    size: file.size,
+                    block_count,
+                    padding: block_count as u64 * subslice_size - file.size,
+                }
+            })
+            .collect();
+
+        let source_count = self.source_block_count as usize;
+        let encode = self.encode_layout();
+        let chunk_size = encode.chunk_size;
+        // Blocks spanning several chunks are buffered a group at a time
+        let group_buffer = if chunk_size < self.block_size.as_usize() && encode.group_blocks > 1 {
+            encode.group_blocks * self.block_size.as_usize()
+        } else {
+            0
+        };
+
+        Ok(CreatePlan {
+            block_size,
+            subslice_size: self.config.subslice_size,
+            source_block_count: self.source_block_count,
+            recovery_block_count: self.recovery_block_count,
+            block_size_choice: self.block_size_choice.clone(),
+            source_files,
+            output_files,
+            chunk_size,
+            source_group_blocks: encode.group_blocks,
+            estimated_io: encode.io_bytes(),
+            estimated_memory: chunk_size
+                * chunk_buffer_count(source_count, self.recovery_block_count as usize)
+                + group_buffer,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -993,23 +2314,27 @@ mod tests {
     }
 
     #[test]
-    fn recovery_slice_packet_writer_streams_borrowed_data() {
-        let set_id = RecoverySetId::new([0xAB; 16]);
-        let recovery_data = vec![1u8, 2, 3, 4, 5, 6, 7, 8];
-        let mut bytes = Vec::new();
-
-        write_recovery_slice_packet(&mut bytes, 7, &recovery_data, set_id).unwrap();
-
-        assert_eq!(&bytes[0..8], crate::packets::MAGIC_BYTES);
-        let length = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
-        assert_eq!(length as usize, bytes.len());
-        assert_eq!(&bytes[32..48], set_id.as_bytes());
-        assert_eq!(&bytes[48..64], RECOVERY_PACKET_TYPE);
-        assert_eq!(u32::from_le_bytes(bytes[64..68].try_into().unwrap()), 7);
-        assert_eq!(&bytes[68..], recovery_data.as_slice());
-
-        let expected_md5 = crate::checksum::compute_md5_bytes(&bytes[32..]);
-        assert_eq!(&bytes[16..32], expected_md5.as_slice());
+    fn source_group_uses_most_of_memory_limit_and_at_least_one_block() {
+        assert_eq!(calculate_source_group_blocks(1024, 100, 16 * 1024), 12);
+        assert_eq!(calculate_source_group_blocks(1024, 5, 16 * 1024), 5);
+        assert_eq!(calculate_source_group_blocks(1024 * 1024, 100, 64), 1);
+    }
+
+    #[test]
+    fn encode_layout_counts_recovery_rewrites_between_passes() {
+        let kib = 1024u64;
+        // Three passes rewrite the recovery data twice more
+        let grouped = EncodeLayout::new(1024, 9, 5, 4096);
+        assert_eq!(grouped.group_blocks, 3);
+        assert_eq!(
+            (grouped.source_io, grouped.recovery_io),
+            (9 * kib, 25 * kib)
+        );
+
+        // Whole blocks fit in memory: one pass
+        let single = EncodeLayout::new(1024, 9, 5, 1 << 20);
+        assert_eq!(single.chunk_size, 1024);
+        assert_eq!(single.recovery_io, 5 * kib);
     }
 
     #[test]
@@ -1056,6 +2381,35 @@ mod tests {
         assert_eq!(recovery_packet.recovery_data, expected);
     }
 
+    #[test]
+    fn packed_block_size_counts_blocks_over_the_packed_stream() {
+        let tmp = tempfile::tempdir().unwrap();
+        let sizes = [100usize, 300, 50];
+        let paths: Vec<_> = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| {
+                let path = tmp.path().join(format!("f{i}.dat"));
+                std::fs::write(&path, vec![i as u8 + 1; size]).unwrap();
+                path
+            })
+            .collect();
+
+        // 2 + 5 + 1 subslices of 64 bytes, packed into 2 blocks of 4 subslices
+        let ctx = crate::create::CreateContextBuilder::new()
+            .output_name(tmp.path().join("out.par2").to_str().unwrap())
+            .source_files(paths)
+            .subslice_size(64)
+            .source_block_count(2)
+            .recovery_block_count(1)
+            .quiet(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(ctx.block_size(), 256);
+        assert_eq!(ctx.source_block_count(), 2);
+    }
+
     // --- calculate_chunk_size (method) via CreateContextBuilder ---
 
     #[test]
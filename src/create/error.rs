@@ -54,6 +54,18 @@ pub enum CreateError {
     #[error("No source files specified")]
     NoSourceFiles,
 
+    /// Source is a directory, FIFO, socket or device rather than a file
+    #[error("Not a regular file: {file} ({kind})")]
+    SpecialFile { file: String, kind: &'static str },
+
+    /// Symbolic link found while symbolic links are refused
+    #[error("Symbolic link in the source files: {0}")]
+    SymlinkRefused(String),
+
+    /// Directory reached again through a symbolic link
+    #[error("Symbolic link loop: {0}")]
+    SymlinkLoop(String),
+
     /// Total source file size is zero
     #[error("Total source file size is zero (empty files only)")]
     EmptySourceFiles,
@@ -90,6 +102,12 @@ mod tests {
             CreateError::InvalidFirstRecoveryBlock("32769".to_string()),
             CreateError::InvalidRedundancy(0),
             CreateError::NoSourceFiles,
+            CreateError::SpecialFile {
+                file: "pipe".to_string(),
+                kind: "FIFO",
+            },
+            CreateError::SymlinkRefused("link.bin".to_string()),
+            CreateError::SymlinkLoop("dir/up".to_string()),
             CreateError::EmptySourceFiles,
             CreateError::ReedSolomonError("matrix failed".to_string()),
             CreateError::PacketGenerationError("packet failed".to_string()),
@@ -120,6 +138,13 @@ mod tests {
         assert!(messages
             .iter()
             .any(|message| message.contains("No source files specified")));
+        assert!(messages
+            .iter()
+            .any(|message| message.contains("pipe (FIFO)")));
+        assert!(messages.iter().any(|message| message.contains("link.bin")));
+        assert!(messages
+            .iter()
+            .any(|message| message.contains("loop: dir/up")));
         assert!(messages
             .iter()
             .any(|message| message.contains("empty files only")));
@@ -74,6 +74,44 @@ pub fn create_new_output_file(path: impl AsRef<Path>) -> CreateResult<File> {
         })
 }
 
+/// Create an output file that can also be read back, truncating an existing
+/// file only when `overwrite_existing` is set
+///
+/// Recovery data is written to disk as it is computed and read back later to
+/// checksum it, so volume files need both read and write access.
+pub fn create_output_file(path: impl AsRef<Path>, overwrite_existing: bool) -> CreateResult<File> {
+    let path = path.as_ref();
+    let mut options = OpenOptions::new();
+    options.read(true).write(true);
+    if overwrite_existing {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    options
+        .open(path)
+        .map_err(|e| CreateError::FileCreateError {
+            file: path.to_string_lossy().to_string(),
+            source: e,
+        })
+}
+
+/// Fail if any of `paths` already exists
+///
+/// Lets a run report an existing output file before it reads any source data.
+pub fn ensure_outputs_absent<'a>(paths: impl IntoIterator<Item = &'a Path>) -> CreateResult<()> {
+    match paths.into_iter().find(|path| path.exists()) {
+        Some(path) => Err(CreateError::FileCreateError {
+            file: path.to_string_lossy().to_string(),
+            source: std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "output file already exists",
+            ),
+        }),
+        None => Ok(()),
+    }
+}
+
 /// Helper to wrap packet write errors with descriptive context
 ///
 /// # Example
@@ -175,6 +213,23 @@ mod tests {
         assert_eq!(std::fs::read(&path).unwrap(), b"keep me");
     }
 
+    #[test]
+    fn test_create_output_file_is_readable_and_respects_overwrite() {
+        use std::io::{Read, Seek, Write};
+
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("vol.par2");
+        std::fs::write(&path, b"old").unwrap();
+        assert!(create_output_file(&path, false).is_err());
+
+        let mut file = create_output_file(&path, true).unwrap();
+        file.write_all(b"new data").unwrap();
+        file.rewind().unwrap();
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "new data");
+    }
+
     #[test]
     fn test_packet_write_error_formatting() {
         let io_err = std::io::Error::other("disk full");
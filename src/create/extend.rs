@@ -0,0 +1,424 @@
+//! Extending an existing PAR2 set with additional recovery volumes
+//!
+//! New recovery blocks use exponents above the highest one already present, so
+//! they are independent of the existing blocks and old and new volumes combine
+//! during repair. The source files are re-read to encode the new blocks and
+//! must still match their FileDesc hashes. Every critical packet of the set,
+//! including packets this library does not interpret, is copied unchanged into
+//! the new volumes, which therefore keep the original recovery set ID.
+//!
+//! Reference: par2cmdline-turbo/src/par2creator.cpp ProcessData() (the encoder
+//! is the same one used by create, started at a higher exponent)
+
+use super::context::{
+    encode_and_hash_files, EncodeLayout, DEFAULT_MEMORY_LIMIT, PACKED_RECOVERY_PACKET_TYPE,
+    RECOVERY_PACKET_TYPE,
+};
+use super::error::{CreateError, CreateResult};
+use super::error_helpers::{
+    create_output_file, ensure_outputs_absent, get_metadata, packet_write_error,
+};
+use super::file_naming::{
+    default_critical_copies, default_recovery_file_count_for_scheme, plan_recovery_files,
+};
+use super::progress::CreateReporter;
+use super::recovery_output::{OutputFile, RecoveryOutput, Volume};
+use super::source_file::SourceFileInfo;
+use super::source_reader::SourceLayout;
+use super::types::RecoveryFileScheme;
+use crate::packets::processing::{
+    extract_non_recovery_file_ids, extract_ordered_file_descriptions, extract_slice_layout,
+    extract_unicode_filenames,
+};
+use crate::packets::{FileDescriptionPacket, Packet};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Highest recovery exponent a PAR2 set can use
+const MAX_EXPONENT: u32 = 65535;
+
+/// Options for [`extend_recovery_set`]
+#[derive(Debug, Clone)]
+pub struct ExtendConfig {
+    /// Number of recovery blocks to add
+    pub recovery_block_count: u32,
+
+    /// Number of new volume files (derived from the scheme if not specified)
+    pub recovery_file_count: Option<u32>,
+
+    /// How the new blocks are spread over the new volumes
+    pub recovery_file_scheme: RecoveryFileScheme,
+
+    /// Memory limit in bytes
+    pub memory_limit: Option<usize>,
+
+    /// Number of threads for computation (0 = auto)
+    pub thread_count: u32,
+
+    /// Number of threads reading source files ahead of processing
+    pub file_thread_count: Option<usize>,
+}
+
+impl Default for ExtendConfig {
+    fn default() -> Self {
+        ExtendConfig {
+            recovery_block_count: 1,
+            recovery_file_count: None,
+            recovery_file_scheme: RecoveryFileScheme::default(),
+            memory_limit: None,
+            thread_count: 0,
+            file_thread_count: None,
+        }
+    }
+}
+
+impl ExtendConfig {
+    fn validate(&self) -> CreateResult<()> {
+        if !(1..=32768).contains(&self.recovery_block_count) {
+            return Err(CreateError::InvalidRecoveryBlockCount(
+                "Recovery block count must be between 1 and 32768".to_string(),
+            ));
+        }
+        if let Some(count) = self.recovery_file_count {
+            if !(1..=31).contains(&count) {
+                return Err(CreateError::InvalidRecoveryFileCount(
+                    "Recovery file count must be between 1 and 31".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn effective_threads(&self) -> usize {
+        if self.thread_count == 0 {
+            std::thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1)
+        } else {
+            self.thread_count as usize
+        }
+    }
+}
+
+/// Add recovery volumes to the PAR2 set of `par2_file`
+///
+/// Returns the paths of the new volume files. Existing files are never
+/// modified; if extending fails, the new volumes are removed again.
+pub fn extend_recovery_set(
+    par2_file: &Path,
+    config: &ExtendConfig,
+    reporter: &dyn CreateReporter,
+) -> CreateResult<Vec<String>> {
+    config.validate()?;
+
+    let par2_files = crate::par2_files::collect_par2_files(par2_file);
+    let packet_set = crate::par2_files::load_par2_packets(&par2_files, false, false);
+    let layout = extract_slice_layout(&packet_set.packets).ok_or_else(|| {
+        CreateError::Other(format!(
+            "{}: no main packet found, cannot extend",
+            par2_file.display()
+        ))
+    })?;
+    let set_id = layout.set_id;
+    let packets: Vec<Packet> = packet_set
+        .packets
+        .into_iter()
+        .filter(|packet| packet.set_id() == set_id)
+        .collect();
+
+    let source_files = recovery_set_files(&packets, layout.file_ids.len(), &packet_set.base_dir)?;
+    let first_exponent = next_recovery_exponent(&par2_files, &packets, set_id);
+    if first_exponent + config.recovery_block_count > MAX_EXPONENT + 1 {
+        return Err(CreateError::InvalidRecoveryBlockCount(format!(
+            "the set already uses exponents below {first_exponent}; at most {} more blocks fit",
+            (MAX_EXPONENT + 1).saturating_sub(first_exponent)
+        )));
+    }
+
+    let block_size = layout.slice_size;
+    let subslice_size = layout.subslice_size.unwrap_or(block_size);
+    let source_block_count =
+        SourceLayout::new(&source_files, block_size, subslice_size).block_count();
+    let recovery_count = config.recovery_block_count;
+
+    let base_name = crate::par2_files::par2_base_stem(par2_file);
+    let largest_file_size = source_files.iter().map(|f| f.size).max().unwrap_or(0);
+    let file_count = config.recovery_file_count.unwrap_or_else(|| {
+        default_recovery_file_count_for_scheme(
+            config.recovery_file_scheme,
+            recovery_count,
+            largest_file_size,
+            block_size,
+        )
+    });
+    let volumes: Vec<_> = plan_recovery_files(
+        &base_name,
+        file_count,
+        recovery_count,
+        first_exponent,
+        config.recovery_file_scheme,
+        largest_file_size,
+        block_size,
+    )
+    .into_iter()
+    .map(|entry| {
+        let path = par2_file.with_file_name(&entry.filename);
+        (entry, path)
+    })
+    .collect();
+    ensure_outputs_absent(volumes.iter().map(|(_, path)| path.as_path()))?;
+
+    // Embedded source data is left out: it belongs to the existing volumes
+    let mut critical_bytes = std::io::Cursor::new(Vec::new());
+    for packet in packets
+        .iter()
+        .filter(|packet| !matches!(packet, Packet::InputFileSlice(_)))
+    {
+        packet
+            .write_to(&mut critical_bytes)
+            .map_err(|e| packet_write_error("critical packet", e))?;
+    }
+    let critical_bytes = critical_bytes.into_inner();
+
+    let write_volumes = || -> CreateResult<Vec<String>> {
+        let outputs = volumes
+            .iter()
+            .map(|(entry, path)| {
+                let file = create_output_file(path, false)?;
+                Ok(Volume {
+                    output: OutputFile {
+                        path: path.clone(),
+                        file,
+                    },
+                    exponents: (entry.first_exponent..entry.first_exponent + entry.block_count)
+                        .map(|exponent| exponent as u16)
+                        .collect(),
+                    critical_copies: default_critical_copies(entry.block_count),
+                })
+            })
+            .collect::<CreateResult<Vec<_>>>()?;
+        let packet_type = if layout.subslice_size.is_some() {
+            PACKED_RECOVERY_PACKET_TYPE
+        } else {
+            RECOVERY_PACKET_TYPE
+        };
+        let mut recovery_output = RecoveryOutput::packets(
+            outputs,
+            block_size,
+            packet_type,
+            critical_bytes.len() as u64,
+        )?;
+
+        let memory_limit = config.memory_limit.unwrap_or(DEFAULT_MEMORY_LIMIT);
+        let encode = EncodeLayout::new(
+            block_size as usize,
+            source_block_count,
+            recovery_count as usize,
+            memory_limit,
+        );
+        reporter.report_scanning_files(
+            0,
+            recovery_count as usize,
+            &format!("Processing files ({})...", encode.describe()),
+        );
+        let encoder =
+            crate::reed_solomon::RecoveryBlockEncoder::new(block_size as usize, source_block_count);
+        let hash_states = encode_and_hash_files(
+            &source_files,
+            &[],
+            block_size,
+            subslice_size,
+            encode,
+            source_block_count as u32,
+            encoder.base_values(),
+            first_exponent,
+            recovery_count as usize,
+            config.effective_threads(),
+            config.file_thread_count.unwrap_or(1),
+            &mut recovery_output,
+            None,
+            reporter,
+        )?;
+
+        let descriptions = extract_ordered_file_descriptions(&packets);
+        for ((file, state), description) in source_files.iter().zip(&hash_states).zip(&descriptions)
+        {
+            if state.full_md5 != description.md5_hash {
+                return Err(CreateError::Other(format!(
+                    "{}: file does not match the recovery set; repair it before extending",
+                    file.path.display()
+                )));
+            }
+        }
+        recovery_output.backfill_packet_headers(set_id)?;
+        recovery_output.write_critical_packets(&critical_bytes)?;
+
+        recovery_output
+            .into_files()?
+            .into_iter()
+            .map(|OutputFile { path, mut file }| {
+                file.flush().map_err(|e| CreateError::FileCreateError {
+                    file: path.to_string_lossy().to_string(),
+                    source: e,
+                })?;
+                Ok(path.to_string_lossy().to_string())
+            })
+            .collect()
+    };
+
+    match write_volumes() {
+        Ok(output_files) => {
+            reporter.report_complete(&output_files);
+            Ok(output_files)
+        }
+        Err(err) => {
+            for (_, path) in &volumes {
+                let _ = std::fs::remove_file(path);
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Source files of the recovery set, in main packet order
+///
+/// Each file must still exist with the size recorded in its FileDesc packet.
+pub(super) fn recovery_set_files(
+    packets: &[Packet],
+    file_count: usize,
+    base_dir: &Path,
+) -> CreateResult<Vec<SourceFileInfo>> {
+    let descriptions = extract_ordered_file_descriptions(packets);
+    if descriptions.len() != file_count {
+        return Err(CreateError::Other(format!(
+            "{} of {} file description packets are missing",
+            file_count - descriptions.len(),
+            file_count
+        )));
+    }
+
+    described_files(packets, &descriptions, base_dir)
+}
+
+/// Files of the non-recovery set, in main packet order
+///
+/// Each file must still exist with the size recorded in its FileDesc packet.
+pub(super) fn non_recovery_set_files(
+    packets: &[Packet],
+    base_dir: &Path,
+) -> CreateResult<Vec<SourceFileInfo>> {
+    let descriptions = extract_non_recovery_file_ids(packets)
+        .into_iter()
+        .map(|file_id| {
+            packets
+                .iter()
+                .find_map(|packet| match packet {
+                    Packet::FileDescription(fd) if fd.file_id == file_id => Some(fd.clone()),
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    CreateError::Other(format!(
+                        "the file description packet of non-recovery file {} is missing",
+                        hex::encode(file_id.as_bytes())
+                    ))
+                })
+        })
+        .collect::<CreateResult<Vec<_>>>()?;
+    described_files(packets, &descriptions, base_dir)
+}
+
+/// The files `descriptions` describe, checked against their recorded sizes
+fn described_files(
+    packets: &[Packet],
+    descriptions: &[FileDescriptionPacket],
+    base_dir: &Path,
+) -> CreateResult<Vec<SourceFileInfo>> {
+    let unicode_names = extract_unicode_filenames(packets);
+    descriptions
+        .iter()
+        .enumerate()
+        .map(|(index, description)| {
+            let name = crate::verify::resolve_file_name(description, &unicode_names);
+            let path: PathBuf = base_dir.join(&name);
+            let size = get_metadata(&path)?.len();
+            if size != description.file_length {
+                return Err(CreateError::Other(format!(
+                    "{}: file is {size} bytes, the recovery set expects {}",
+                    path.display(),
+                    description.file_length
+                )));
+            }
+            let mut file = SourceFileInfo::new_with_packet_name(path, name, size, index);
+            file.file_id = description.file_id;
+            Ok(file)
+        })
+        .collect()
+}
+
+/// First exponent above every recovery block of the set
+///
+/// Intact recovery packets, external slices listed in an RFSC packet and the
+/// exponent ranges in volume file names (`base.volA+B.par2`) all count, so a
+/// volume whose packets are all damaged is still skipped. A missing volume
+/// leaves no trace, so a gap in the exponents is reported: the missing volume
+/// may also have been the last one, whose exponents would then be reused.
+fn next_recovery_exponent(
+    par2_files: &[PathBuf],
+    packets: &[Packet],
+    set_id: crate::domain::RecoverySetId,
+) -> u32 {
+    let packet_exponents = crate::par2_files::parse_recovery_slice_metadata(par2_files, false)
+        .into_iter()
+        .filter(|metadata| metadata.set_id == set_id)
+        .map(|metadata| metadata.exponent);
+    let external_exponents = packets
+        .iter()
+        .filter_map(|packet| match packet {
+            Packet::RecoveryFileSliceChecksum(rfsc) => Some(&rfsc.slice_checksums),
+            _ => None,
+        })
+        .flatten()
+        .map(|checksum| checksum.exponent);
+    let named_exponents = par2_files
+        .iter()
+        .filter_map(|path| volume_exponents(path))
+        .filter(|range| range.end <= MAX_EXPONENT + 1)
+        .flatten();
+    let exponents: std::collections::BTreeSet<u32> = packet_exponents
+        .chain(external_exponents)
+        .chain(named_exponents)
+        .collect();
+
+    let (Some(&first), Some(&last)) = (exponents.first(), exponents.last()) else {
+        return 0;
+    };
+    let missing = (last - first + 1) as usize - exponents.len();
+    if missing > 0 {
+        log::warn!(
+            "{missing} recovery blocks between exponents {first} and {last} were not found; \
+             a volume may be missing, and if the set's last volume is missing too, \
+             the new blocks will repeat its exponents"
+        );
+    }
+    last + 1
+}
+
+/// Exponents named by a volume file name such as `base.vol04+3.par2`
+fn volume_exponents(path: &Path) -> Option<std::ops::Range<u32>> {
+    let name = path.file_name()?.to_str()?;
+    let stem = name.get(..name.len().checked_sub(5)?)?;
+    if !name[stem.len()..].eq_ignore_ascii_case(".par2") {
+        return None;
+    }
+    let volume = &stem[stem.rfind('.')? + 1..];
+    let (first, count) = volume
+        .get(..3)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("vol"))
+        .and_then(|_| volume[3..].split_once('+'))?;
+    let digits = |n: &str| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit());
+    if !digits(first) || !digits(count) {
+        return None;
+    }
+    let first: u32 = first.parse().ok()?;
+    Some(first..first.checked_add(count.parse().ok()?)?)
+}
@@ -3,6 +3,7 @@
 // Reference: par2cmdline-turbo/src/par2creator.cpp InitialiseOutputFiles() lines 484-630
 // Reference: par2cmdline-turbo/src/libpar2.h Scheme enum lines 91-96
 
+use super::recovery_output::RECOVERY_HEADER_SIZE;
 use super::types::RecoveryFileScheme;
 use std::path::PathBuf;
 
@@ -60,7 +61,8 @@ fn allocate_recovery_blocks(
 
     match scheme {
         // Reference: par2cmdline-turbo/src/par2creator.cpp lines 503-514
-        RecoveryFileScheme::Uniform => {
+        // MaxFileSize picks a file count that keeps uniform files under the cap
+        RecoveryFileScheme::Uniform | RecoveryFileScheme::MaxFileSize(_) => {
             // Files will have roughly the same number of recovery blocks each.
             let base = recovery_block_count / recovery_file_count;
             let remainder = recovery_block_count % recovery_file_count;
@@ -275,6 +277,17 @@ pub fn default_recovery_file_count_for_scheme(
         RecoveryFileScheme::Variable | RecoveryFileScheme::Uniform => {
             default_recovery_file_count(recovery_block_count)
         }
+        RecoveryFileScheme::MaxFileSize(max_file_size) => {
+            // Estimate without the critical packets; create counts them exactly
+            recovery_file_count_for_max_size(
+                recovery_block_count,
+                max_file_size,
+                block_size,
+                0,
+                None,
+            )
+            .unwrap_or(recovery_block_count)
+        }
         RecoveryFileScheme::Limited => {
             let largest = largest_file_size.div_ceil(block_size) as u32;
             let whole = recovery_block_count / largest;
@@ -285,6 +298,43 @@ pub fn default_recovery_file_count_for_scheme(
     }
 }
 
+/// Number of uniform recovery files that keeps each within `max_file_size` bytes
+///
+/// Every file also holds `critical_copies` copies (the default scaling when
+/// `None`) of the `critical_size` bytes of critical packets. `None` when not
+/// even one recovery block fits.
+pub fn recovery_file_count_for_max_size(
+    recovery_block_count: u32,
+    max_file_size: u64,
+    block_size: u64,
+    critical_size: u64,
+    critical_copies: Option<u32>,
+) -> Option<u32> {
+    let packet_size = block_size + RECOVERY_HEADER_SIZE;
+    let file_size = |blocks: u64| {
+        let copies = critical_copies.unwrap_or_else(|| default_critical_copies(blocks as u32));
+        blocks * packet_size + copies as u64 * critical_size
+    };
+
+    // Copies grow with the block count, so step down until the file fits
+    let mut blocks_per_file = max_file_size.saturating_sub(critical_size) / packet_size;
+    while blocks_per_file > 0 && file_size(blocks_per_file) > max_file_size {
+        blocks_per_file -= 1;
+    }
+    if blocks_per_file == 0 {
+        return None;
+    }
+    Some((recovery_block_count as u64).div_ceil(blocks_per_file) as u32)
+}
+
+/// Default number of critical packet copies in a volume with this many recovery blocks
+///
+/// One copy per bit of the block count, like par2cmdline, so larger volumes
+/// carry more copies.
+pub fn default_critical_copies(volume_block_count: u32) -> u32 {
+    default_recovery_file_count(volume_block_count).max(1)
+}
+
 /// Count number of decimal digits needed to represent a number
 /// Reference: par2cmdline-turbo/src/par2creator.cpp lines 604-608, 611-615
 fn count_digits(n: u32) -> usize {
@@ -492,6 +542,15 @@ mod tests {
         assert_eq!(default_recovery_file_count(17), 5);
     }
 
+    #[test]
+    fn default_critical_copies_grow_with_volume_size() {
+        assert_eq!(default_critical_copies(0), 1);
+        assert_eq!(default_critical_copies(1), 1);
+        assert_eq!(default_critical_copies(3), 2);
+        assert_eq!(default_critical_copies(4), 3);
+        assert_eq!(default_critical_copies(1000), 10);
+    }
+
     #[test]
     fn default_limited_recovery_file_count_matches_turbo_shape() {
         assert_eq!(
@@ -505,6 +564,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn max_file_size_recovery_file_count_accounts_for_fixed_size() {
+        // 1024-byte blocks take 1092 bytes as recovery packets
+        assert_eq!(
+            recovery_file_count_for_max_size(10, 4 * 1092, 1024, 0, None),
+            Some(3)
+        );
+        assert_eq!(
+            recovery_file_count_for_max_size(10, 4 * 1092 + 500, 1024, 500, Some(1)),
+            Some(3)
+        );
+        assert_eq!(
+            recovery_file_count_for_max_size(10, 4 * 1092 + 499, 1024, 500, Some(1)),
+            Some(4)
+        );
+        assert_eq!(
+            recovery_file_count_for_max_size(10, 1591, 1024, 500, Some(1)),
+            None
+        );
+        // Four blocks would need three copies; three blocks need only two
+        assert_eq!(
+            recovery_file_count_for_max_size(10, 4 * 1092 + 1000, 1024, 500, None),
+            Some(4)
+        );
+
+        let plan = plan_recovery_files(
+            "test",
+            4,
+            10,
+            0,
+            RecoveryFileScheme::MaxFileSize(4 * 1092),
+            0,
+            1024,
+        );
+        assert!(plan.iter().all(|file| file.block_count <= 3));
+        assert_eq!(plan.iter().map(|file| file.block_count).sum::<u32>(), 10);
+    }
+
     /// Test variable scheme low_block_count calculation
     /// Reference: par2cmdline-turbo/src/par2creator.cpp lines 520-525
     #[test]
@@ -29,23 +29,38 @@
 //! ```
 
 pub mod backend;
+pub mod block_size_optimizer;
 pub mod builder;
+pub mod checkpoint;
 pub mod cli;
 pub mod context;
 pub mod error;
 pub mod error_helpers;
+pub mod extend;
 pub mod file_naming;
 pub mod packet_generator;
+pub mod plan;
 pub mod progress;
+mod recovery_output;
 pub mod source_file;
+pub mod source_filter;
+mod source_reader;
+pub mod staged_output;
+mod stream_source;
 pub mod types;
+pub mod update;
 
+pub use block_size_optimizer::{BlockSizeChoice, BlockSizeCost};
 pub use builder::CreateContextBuilder;
 pub use context::CreateContext;
 pub use error::{CreateError, CreateResult};
+pub use extend::{extend_recovery_set, ExtendConfig};
 pub use file_naming::RecoveryFilePlan;
+pub use plan::{CreatePlan, PlannedOutputFile, PlannedSourceFile};
 pub use progress::{ConsoleCreateReporter, CreateReporter, SilentCreateReporter};
-pub use types::{CreateConfig, RecoveryFileScheme};
+pub use source_filter::{SourceFilter, SymlinkPolicy};
+pub use types::{CreateConfig, CustomPacket, RecoveryFileScheme};
+pub use update::{update_recovery_set, UpdateSummary};
 
 // Re-export from reed_solomon for convenience
 pub use crate::reed_solomon::RecoveryBlockEncoder;
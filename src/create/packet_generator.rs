@@ -2,9 +2,14 @@
 //!
 //! This module handles generation of all PAR2 packets needed for file creation:
 //! - Main packet (recovery set metadata)
+//! - Packed Main packet (recovery set metadata for packed sets)
 //! - Creator packet (client identification)
 //! - FileDescription packets (file metadata)
 //! - FileVerification packets (file checksums)
+//! - Unicode Filename packets (UTF-16 names for non-ASCII files)
+//! - Comment packets (ASCII and Unicode)
+//! - Input File Slice packets (embedded source data)
+//! - Recovery File Slice Checksum packets (external recovery data)
 //! - Recovery packets (Reed-Solomon data)
 //!
 //! Reference: par2cmdline-turbo/src/par2creator.cpp CreateMainPacket(),
@@ -12,10 +17,16 @@
 
 use crate::domain::{Crc32Value, FileId, Md5Hash, RecoverySetId};
 use crate::packets::{
+    ascii_comment_packet::{self, AsciiCommentPacket},
     creator_packet::CreatorPacket,
     file_description_packet::{self, FileDescriptionPacket},
     input_file_slice_checksum_packet::InputFileSliceChecksumPacket,
+    input_file_slice_packet::InputFileSlicePacket,
     main_packet::MainPacket,
+    packed_main_packet::PackedMainPacket,
+    recovery_file_slice_checksum_packet::{RecoveryFileSliceChecksumPacket, RecoverySliceChecksum},
+    unicode_comment_packet::UnicodeCommentPacket,
+    unicode_filename_packet::UnicodeFilenamePacket,
 };
 use binrw::BinWrite;
 
@@ -35,12 +46,14 @@ pub fn generate_main_packet(
     recovery_set_id: RecoverySetId,
     block_size: u64,
     source_files: &[SourceFileInfo],
+    non_recovery_files: &[SourceFileInfo],
 ) -> CreateResult<MainPacket> {
     let file_count = source_files.len() as u32;
     let file_ids: Vec<FileId> = source_files.iter().map(|f| f.file_id).collect();
+    let non_recovery_file_ids: Vec<FileId> = non_recovery_files.iter().map(|f| f.file_id).collect();
 
     // Calculate packet length: header (64) + slice_size (8) + file_count (4) + file_ids
-    let packet_length = 64 + 8 + 4 + (file_ids.len() * 16) as u64;
+    let packet_length = 64 + 8 + 4 + ((file_ids.len() + non_recovery_file_ids.len()) * 16) as u64;
 
     // For now, compute MD5 as zeros - will be computed when writing
     let md5 = Md5Hash::new([0u8; 16]);
@@ -52,7 +65,38 @@ pub fn generate_main_packet(
         slice_size: block_size,
         file_count,
         file_ids,
-        non_recovery_file_ids: Vec::new(), // Empty for standard PAR2 sets
+        non_recovery_file_ids,
+    })
+}
+
+/// Generate a Packed Main packet for a packed PAR2 set
+///
+/// Same role as the Main packet, with the subslice size stored ahead of the
+/// slice size. File IDs are listed in the order files appear in the packed stream.
+pub fn generate_packed_main_packet(
+    recovery_set_id: RecoverySetId,
+    subslice_size: u64,
+    block_size: u64,
+    source_files: &[SourceFileInfo],
+    non_recovery_files: &[SourceFileInfo],
+) -> CreateResult<PackedMainPacket> {
+    let file_count = source_files.len() as u32;
+    let recovery_set_ids: Vec<FileId> = source_files.iter().map(|f| f.file_id).collect();
+    let non_recovery_set_ids: Vec<FileId> = non_recovery_files.iter().map(|f| f.file_id).collect();
+
+    // Calculate packet length: header (64) + subslice_size (8) + slice_size (8) + file_count (4) + file_ids
+    let packet_length =
+        64 + 8 + 8 + 4 + ((recovery_set_ids.len() + non_recovery_set_ids.len()) * 16) as u64;
+
+    Ok(PackedMainPacket {
+        length: packet_length,
+        md5: Md5Hash::new([0u8; 16]),
+        set_id: recovery_set_id,
+        subslice_size,
+        slice_size: block_size,
+        file_count,
+        recovery_set_ids,
+        non_recovery_set_ids,
     })
 }
 
@@ -150,6 +194,129 @@ pub fn generate_file_verification_packet(
     })
 }
 
+/// Generate a Unicode Filename packet for a source file
+///
+/// The name is stored as UTF-16LE with no terminator, padded with a zero code
+/// unit when needed so the packet length stays a multiple of 4.
+///
+/// Reference: par2cmdline-turbo/src/par2fileformat.h UNICODEFILENAMEPACKET
+pub fn generate_unicode_filename_packet(
+    recovery_set_id: RecoverySetId,
+    source_file: &SourceFileInfo,
+) -> CreateResult<UnicodeFilenamePacket> {
+    let mut unicode_name: Vec<u16> = source_file.packet_name().encode_utf16().collect();
+
+    if unicode_name.is_empty() {
+        return Err(CreateError::Other("Empty filename".to_string()));
+    }
+
+    if unicode_name.len() % 2 != 0 {
+        unicode_name.push(0);
+    }
+
+    // Calculate packet length: header (64) + file_id (16) + name
+    let packet_length = 64 + 16 + (unicode_name.len() * 2) as u64;
+
+    Ok(UnicodeFilenamePacket {
+        length: packet_length,
+        md5: Md5Hash::new([0u8; 16]),
+        set_id: recovery_set_id,
+        file_id: source_file.file_id,
+        unicode_name,
+    })
+}
+
+/// Generate the ASCII and Unicode comment packets for one comment
+///
+/// The ASCII packet carries the comment with non-ASCII characters replaced by
+/// `?`. Its MD5 is computed here so the Unicode packet can reference it as the
+/// translation it supersedes.
+pub fn generate_comment_packets(
+    recovery_set_id: RecoverySetId,
+    comment: &str,
+) -> CreateResult<(AsciiCommentPacket, UnicodeCommentPacket)> {
+    if comment.is_empty() {
+        return Err(CreateError::Other("Empty comment".to_string()));
+    }
+
+    let mut ascii_text: Vec<u8> = comment
+        .chars()
+        .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+        .collect();
+    while ascii_text.len() % 4 != 0 {
+        ascii_text.push(0);
+    }
+
+    let mut ascii_body = Vec::with_capacity(32 + ascii_text.len());
+    ascii_body.extend_from_slice(recovery_set_id.as_bytes());
+    ascii_body.extend_from_slice(ascii_comment_packet::TYPE_OF_PACKET);
+    ascii_body.extend_from_slice(&ascii_text);
+    let ascii_md5 = Md5Hash::new(crate::checksum::compute_md5_bytes(&ascii_body));
+
+    let ascii_packet = AsciiCommentPacket {
+        length: 64 + ascii_text.len() as u64,
+        md5: ascii_md5,
+        set_id: recovery_set_id,
+        comment: ascii_text,
+    };
+
+    let mut unicode_text: Vec<u16> = comment.encode_utf16().collect();
+    if unicode_text.len() % 2 != 0 {
+        unicode_text.push(0);
+    }
+
+    // Calculate packet length: header (64) + ASCII packet MD5 (16) + text
+    let unicode_packet = UnicodeCommentPacket {
+        length: 64 + 16 + (unicode_text.len() * 2) as u64,
+        md5: Md5Hash::new([0u8; 16]),
+        set_id: recovery_set_id,
+        ascii_comment_md5: ascii_md5,
+        comment: unicode_text,
+    };
+
+    Ok((ascii_packet, unicode_packet))
+}
+
+/// Generate an Input File Slice packet carrying a copy of source data
+///
+/// `data` holds the file bytes starting at `offset`; it is zero padded to a
+/// multiple of 4 so the packet length stays aligned.
+pub fn generate_input_file_slice_packet(
+    recovery_set_id: RecoverySetId,
+    file_id: FileId,
+    offset: u64,
+    data: &[u8],
+) -> InputFileSlicePacket {
+    let mut slice_data = data.to_vec();
+    slice_data.resize(data.len().div_ceil(4) * 4, 0);
+
+    // Calculate packet length: header (64) + file_id (16) + offset (8) + data
+    InputFileSlicePacket {
+        length: 64 + 16 + 8 + slice_data.len() as u64,
+        md5: Md5Hash::new([0u8; 16]),
+        set_id: recovery_set_id,
+        file_id,
+        offset,
+        slice_data,
+    }
+}
+
+/// Generate a Recovery File Slice Checksum packet for external recovery data
+///
+/// Entries must follow the order the slices are laid out in the external file.
+pub fn generate_recovery_file_slice_checksum_packet(
+    recovery_set_id: RecoverySetId,
+    slice_checksums: Vec<RecoverySliceChecksum>,
+) -> RecoveryFileSliceChecksumPacket {
+    // Calculate packet length: header (64) + 24 bytes per slice
+    RecoveryFileSliceChecksumPacket {
+        length: 64 + 24 * slice_checksums.len() as u64,
+        md5: Md5Hash::new([0u8; 16]),
+        set_id: recovery_set_id,
+        slice_checksums,
+    }
+}
+
 /// Generate recovery set ID from main packet data
 ///
 /// The recovery set ID is the MD5 hash of the main packet body (excluding header)
@@ -158,11 +325,28 @@ pub fn generate_file_verification_packet(
 pub fn generate_recovery_set_id(
     block_size: u64,
     source_files: &[SourceFileInfo],
+    non_recovery_files: &[SourceFileInfo],
 ) -> CreateResult<RecoverySetId> {
+    let file_ids: Vec<FileId> = source_files.iter().map(|f| f.file_id).collect();
+    let non_recovery_file_ids: Vec<FileId> = non_recovery_files.iter().map(|f| f.file_id).collect();
+    Ok(recovery_set_id_from_file_ids(
+        block_size,
+        &file_ids,
+        &non_recovery_file_ids,
+    ))
+}
+
+/// Recovery set ID of a Main packet listing the given file IDs
+///
+/// Used directly when a set is rewritten and only the IDs are known.
+pub fn recovery_set_id_from_file_ids(
+    block_size: u64,
+    file_ids: &[FileId],
+    non_recovery_file_ids: &[FileId],
+) -> RecoverySetId {
     use crate::packets::main_packet::TYPE_OF_PACKET;
 
-    let file_count = source_files.len() as u32;
-    let file_ids: Vec<FileId> = source_files.iter().map(|f| f.file_id).collect();
+    let file_count = file_ids.len() as u32;
 
     // Build the packet body that will be hashed
     let mut body = Vec::new();
@@ -177,15 +361,40 @@ pub fn generate_recovery_set_id(
     body.extend_from_slice(&block_size.to_le_bytes());
     body.extend_from_slice(&file_count.to_le_bytes());
 
-    // Add all file IDs
-    for file_id in &file_ids {
+    // Add all file IDs, then the non-recovery file IDs
+    for file_id in file_ids.iter().chain(non_recovery_file_ids) {
         body.extend_from_slice(file_id.as_bytes());
     }
 
     // Compute MD5 of the body
-    let set_id_bytes = crate::checksum::compute_md5_bytes(&body);
+    RecoverySetId::new(crate::checksum::compute_md5_bytes(&body))
+}
 
-    Ok(RecoverySetId::new(set_id_bytes))
+/// Generate recovery set ID from packed main packet data
+///
+/// Mirrors [`generate_recovery_set_id`] over the Packed Main packet body, so a
+/// packed set never shares an ID with an ordinary set over the same files.
+pub fn generate_packed_recovery_set_id(
+    subslice_size: u64,
+    block_size: u64,
+    source_files: &[SourceFileInfo],
+    non_recovery_files: &[SourceFileInfo],
+) -> CreateResult<RecoverySetId> {
+    use crate::packets::packed_main_packet::TYPE_OF_PACKET;
+
+    let mut body = Vec::new();
+    body.extend_from_slice(&[0u8; 16]);
+    body.extend_from_slice(TYPE_OF_PACKET);
+    body.extend_from_slice(&subslice_size.to_le_bytes());
+    body.extend_from_slice(&block_size.to_le_bytes());
+    body.extend_from_slice(&(source_files.len() as u32).to_le_bytes());
+    for file in source_files.iter().chain(non_recovery_files) {
+        body.extend_from_slice(file.file_id.as_bytes());
+    }
+
+    Ok(RecoverySetId::new(crate::checksum::compute_md5_bytes(
+        &body,
+    )))
 }
 
 /// Helper to serialize a packet, compute MD5, and update the MD5 field in the bytes
@@ -233,6 +442,26 @@ pub fn write_main_packet<W: std::io::Write>(
     Ok(())
 }
 
+/// Write a PackedMainPacket to a writer with computed MD5
+pub fn write_packed_main_packet<W: std::io::Write>(
+    writer: &mut W,
+    packet: &PackedMainPacket,
+) -> CreateResult<()> {
+    use std::io::Cursor;
+
+    // Serialize with placeholder MD5
+    let mut buffer = Cursor::new(Vec::new());
+    packet.write_le(&mut buffer).map_err(|e| {
+        CreateError::PacketGenerationError(format!("Failed to serialize PackedMainPacket: {}", e))
+    })?;
+
+    // Finalize and write
+    let bytes = finalize_packet_bytes(buffer.into_inner())?;
+    writer.write_all(&bytes).map_err(CreateError::IoError)?;
+
+    Ok(())
+}
+
 /// Write a CreatorPacket to a writer with computed MD5
 pub fn write_creator_packet<W: std::io::Write>(
     writer: &mut W,
@@ -323,6 +552,118 @@ pub fn write_file_verification_packet<W: std::io::Write>(
     Ok(())
 }
 
+/// Write a UnicodeFilenamePacket to a writer with computed MD5
+pub fn write_unicode_filename_packet<W: std::io::Write>(
+    writer: &mut W,
+    packet: &UnicodeFilenamePacket,
+) -> CreateResult<()> {
+    use std::io::Cursor;
+
+    // Serialize with placeholder MD5
+    let mut buffer = Cursor::new(Vec::new());
+    packet.write_le(&mut buffer).map_err(|e| {
+        CreateError::PacketGenerationError(format!(
+            "Failed to serialize UnicodeFilenamePacket: {}",
+            e
+        ))
+    })?;
+
+    // Finalize and write
+    let bytes = finalize_packet_bytes(buffer.into_inner())?;
+    writer.write_all(&bytes).map_err(CreateError::IoError)?;
+
+    Ok(())
+}
+
+/// Write an AsciiCommentPacket to a writer with computed MD5
+pub fn write_ascii_comment_packet<W: std::io::Write>(
+    writer: &mut W,
+    packet: &AsciiCommentPacket,
+) -> CreateResult<()> {
+    use std::io::Cursor;
+
+    // Serialize with placeholder MD5
+    let mut buffer = Cursor::new(Vec::new());
+    packet.write_le(&mut buffer).map_err(|e| {
+        CreateError::PacketGenerationError(format!("Failed to serialize AsciiCommentPacket: {}", e))
+    })?;
+
+    // Finalize and write
+    let bytes = finalize_packet_bytes(buffer.into_inner())?;
+    writer.write_all(&bytes).map_err(CreateError::IoError)?;
+
+    Ok(())
+}
+
+/// Write a UnicodeCommentPacket to a writer with computed MD5
+pub fn write_unicode_comment_packet<W: std::io::Write>(
+    writer: &mut W,
+    packet: &UnicodeCommentPacket,
+) -> CreateResult<()> {
+    use std::io::Cursor;
+
+    // Serialize with placeholder MD5
+    let mut buffer = Cursor::new(Vec::new());
+    packet.write_le(&mut buffer).map_err(|e| {
+        CreateError::PacketGenerationError(format!(
+            "Failed to serialize UnicodeCommentPacket: {}",
+            e
+        ))
+    })?;
+
+    // Finalize and write
+    let bytes = finalize_packet_bytes(buffer.into_inner())?;
+    writer.write_all(&bytes).map_err(CreateError::IoError)?;
+
+    Ok(())
+}
+
+/// Write an InputFileSlicePacket to a writer with computed MD5
+pub fn write_input_file_slice_packet<W: std::io::Write>(
+    writer: &mut W,
+    packet: &InputFileSlicePacket,
+) -> CreateResult<()> {
+    use std::io::Cursor;
+
+    // Serialize with placeholder MD5
+    let mut buffer = Cursor::new(Vec::new());
+    packet.write_le(&mut buffer).map_err(|e| {
+        CreateError::PacketGenerationError(format!(
+            "Failed to serialize InputFileSlicePacket: {}",
+            e
+        ))
+    })?;
+
+    // Finalize and write
+    let bytes = finalize_packet_bytes(buffer.into_inner())?;
+    writer.write_all(&bytes).map_err(CreateError::IoError)?;
+
+    Ok(())
+}
+
+/// Write a RecoveryFileSliceChecksumPacket to a writer with computed MD5
+pub fn write_recovery_file_slice_checksum_packet<W: std::io::Write>(
+    writer: &mut W,
+    packet: &RecoveryFileSliceChecksumPacket,
+) -> CreateResult<()> {
+    use std::io::Cursor;
+
+    // Serialize with placeholder MD5
+    let mut buffer = Cursor::new(Vec::new());
+    packet.write_le(&mut buffer).map_err(|e| {
+        CreateError::PacketGenerationError(format!(
+            "Failed to serialize RecoveryFileSliceChecksumPacket: {}",
+            e
+        ))
+    })?;
+
+    // Finalize and write
+    let bytes = finalize_packet_bytes(buffer.into_inner())?;
+    writer.write_all(&bytes).map_err(CreateError::IoError)?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -358,7 +699,7 @@ mod tests {
             },
         ];
 
-        let set_id = generate_recovery_set_id(512, &source_files).unwrap();
+        let set_id = generate_recovery_set_id(512, &source_files, &[]).unwrap();
 
         // Should generate a valid non-zero ID
         assert_ne!(set_id.as_bytes(), &[0u8; 16]);
@@ -380,7 +721,7 @@ mod tests {
             block_count: 2,
         }];
 
-        let packet = generate_main_packet(set_id, 512, &source_files).unwrap();
+        let packet = generate_main_packet(set_id, 512, &source_files, &[]).unwrap();
 
         assert_eq!(packet.slice_size, 512);
         assert_eq!(packet.file_count, 1);
@@ -389,6 +730,45 @@ mod tests {
         assert_eq!(packet.length, 64 + 8 + 4 + 16); // header + slice_size + file_count + 1 file_id
     }
 
+    #[test]
+    fn test_generate_main_packet_with_non_recovery_files() {
+        use binrw::BinReaderExt;
+
+        let file = |id: u8, index: usize| SourceFileInfo {
+            file_id: FileId::new([id; 16]),
+            path: PathBuf::from(format!("{id}.dat")),
+            packet_name: format!("{id}.dat"),
+            size: 1024,
+            hash: Md5Hash::new([0u8; 16]),
+            hash_16k: Md5Hash::new([0u8; 16]),
+            index,
+            block_checksums: Vec::new(),
+            global_block_offset: 0,
+            block_count: 2,
+        };
+        let source_files = vec![file(1, 0)];
+        let non_recovery_files = vec![file(2, 1), file(3, 2)];
+
+        let set_id = generate_recovery_set_id(512, &source_files, &non_recovery_files).unwrap();
+        assert_ne!(
+            set_id,
+            generate_recovery_set_id(512, &source_files, &[]).unwrap()
+        );
+
+        let packet = generate_main_packet(set_id, 512, &source_files, &non_recovery_files).unwrap();
+        let mut buffer = Vec::new();
+        write_main_packet(&mut buffer, &packet).unwrap();
+
+        let read_packet: MainPacket = std::io::Cursor::new(&buffer).read_le().unwrap();
+        assert!(read_packet.verify());
+        assert_eq!(read_packet.set_id, set_id);
+        assert_eq!(read_packet.file_ids, vec![FileId::new([1; 16])]);
+        assert_eq!(
+            read_packet.non_recovery_file_ids,
+            vec![FileId::new([2; 16]), FileId::new([3; 16])]
+        );
+    }
+
     #[test]
     fn test_generate_creator_packet() {
         let set_id = RecoverySetId::new([0xAA; 16]);
@@ -486,7 +866,7 @@ mod tests {
             global_block_offset: 0,
             block_count: 1,
         };
-        let packet = generate_main_packet(set_id, 512, &[source_file]).unwrap();
+        let packet = generate_main_packet(set_id, 512, &[source_file], &[]).unwrap();
 
         // Write it with MD5
         let mut buffer = Vec::new();
@@ -506,6 +886,41 @@ mod tests {
         assert_eq!(read_packet.md5.as_bytes(), &expected_md5);
     }
 
+    #[test]
+    fn test_write_packed_main_packet_round_trip() {
+        use binrw::BinReaderExt;
+
+        let source_file = SourceFileInfo {
+            file_id: FileId::new([0xBB; 16]),
+            path: PathBuf::from("test.dat"),
+            packet_name: "test.dat".to_string(),
+            size: 700,
+            hash: Md5Hash::new([0xCC; 16]),
+            hash_16k: Md5Hash::new([0u8; 16]),
+            index: 0,
+            block_checksums: Vec::new(),
+            global_block_offset: 0,
+            block_count: 3,
+        };
+        let sources = [source_file];
+        let set_id = generate_packed_recovery_set_id(256, 1024, &sources, &[]).unwrap();
+        assert_ne!(
+            set_id,
+            generate_recovery_set_id(1024, &sources, &[]).unwrap()
+        );
+
+        let packet = generate_packed_main_packet(set_id, 256, 1024, &sources, &[]).unwrap();
+        let mut buffer = Vec::new();
+        write_packed_main_packet(&mut buffer, &packet).unwrap();
+        assert_eq!(buffer.len() as u64, packet.length);
+
+        let read_packet: PackedMainPacket = std::io::Cursor::new(&buffer).read_le().unwrap();
+        assert_eq!(read_packet.subslice_size, 256);
+        assert_eq!(read_packet.slice_size, 1024);
+        assert_eq!(read_packet.recovery_set_ids, vec![FileId::new([0xBB; 16])]);
+        assert!(read_packet.verify());
+    }
+
     #[test]
     fn test_write_file_description_packet_with_md5() {
         use binrw::BinReaderExt;
@@ -540,4 +955,139 @@ mod tests {
         let expected_md5 = crate::checksum::compute_md5_bytes(body);
         assert_eq!(read_packet.md5.as_bytes(), &expected_md5);
     }
+
+    #[test]
+    fn test_write_unicode_filename_packet_round_trip() {
+        use binrw::BinReaderExt;
+
+        let set_id = RecoverySetId::new([0xAA; 16]);
+        let source_file = SourceFileInfo {
+            file_id: FileId::new([0xBB; 16]),
+            path: PathBuf::from("/tmp/写真.jpg"),
+            packet_name: "写真.jpg".to_string(),
+            size: 12345,
+            hash: Md5Hash::new([0xCC; 16]),
+            hash_16k: Md5Hash::new([0u8; 16]),
+            index: 0,
+            block_checksums: Vec::new(),
+            global_block_offset: 0,
+            block_count: 25,
+        };
+
+        let packet = generate_unicode_filename_packet(set_id, &source_file).unwrap();
+        // 6 UTF-16 code units, already even
+        assert_eq!(packet.length, 64 + 16 + 12);
+
+        let mut buffer = Vec::new();
+        write_unicode_filename_packet(&mut buffer, &packet).unwrap();
+        assert_eq!(buffer.len() as u64, packet.length);
+
+        let mut cursor = std::io::Cursor::new(&buffer);
+        let read_packet: UnicodeFilenamePacket = cursor.read_le().unwrap();
+        assert!(read_packet.verify());
+        assert_eq!(read_packet.file_id, source_file.file_id);
+        assert_eq!(read_packet.file_name(), "写真.jpg");
+    }
+
+    #[test]
+    fn test_generate_unicode_filename_packet_pads_odd_length() {
+        let source_file = SourceFileInfo {
+            file_id: FileId::new([0xBB; 16]),
+            path: PathBuf::from("/tmp/naïve"),
+            packet_name: "naïve".to_string(),
+            size: 1,
+            hash: Md5Hash::new([0u8; 16]),
+            hash_16k: Md5Hash::new([0u8; 16]),
+            index: 0,
+            block_checksums: Vec::new(),
+            global_block_offset: 0,
+            block_count: 1,
+        };
+
+        let packet =
+            generate_unicode_filename_packet(RecoverySetId::new([0xAA; 16]), &source_file).unwrap();
+        // 5 code units plus one zero unit of padding
+        assert_eq!(packet.unicode_name.len(), 6);
+        assert_eq!(packet.length % 4, 0);
+        assert_eq!(packet.file_name(), "naïve");
+    }
+
+    #[test]
+    fn test_generate_comment_packets_links_unicode_to_ascii() {
+        use binrw::BinReaderExt;
+
+        let set_id = RecoverySetId::new([0xAA; 16]);
+        let (ascii, unicode) = generate_comment_packets(set_id, "tape № 14").unwrap();
+
+        assert_eq!(ascii.comment_text(), "tape ? 14");
+        assert_eq!(ascii.length % 4, 0);
+        assert!(ascii.verify());
+        assert_eq!(unicode.ascii_comment_md5, ascii.md5);
+
+        let mut buffer = Vec::new();
+        write_ascii_comment_packet(&mut buffer, &ascii).unwrap();
+        write_unicode_comment_packet(&mut buffer, &unicode).unwrap();
+
+        let mut cursor = std::io::Cursor::new(&buffer);
+        let read_ascii: AsciiCommentPacket = cursor.read_le().unwrap();
+        let read_unicode: UnicodeCommentPacket = cursor.read_le().unwrap();
+        assert_eq!(read_ascii.md5, ascii.md5);
+        assert!(read_unicode.verify());
+        assert_eq!(read_unicode.comment_text(), "tape № 14");
+    }
+
+    #[test]
+    fn test_write_input_file_slice_packet_pads_data() {
+        use binrw::BinReaderExt;
+
+        let packet = generate_input_file_slice_packet(
+            RecoverySetId::new([0xAA; 16]),
+            FileId::new([0xBB; 16]),
+            1024,
+            b"hello",
+        );
+        assert_eq!(packet.slice_data, b"hello\0\0\0");
+        assert_eq!(packet.length, 64 + 16 + 8 + 8);
+
+        let mut buffer = Vec::new();
+        write_input_file_slice_packet(&mut buffer, &packet).unwrap();
+        assert_eq!(buffer.len() as u64, packet.length);
+
+        let read_packet: InputFileSlicePacket = std::io::Cursor::new(&buffer).read_le().unwrap();
+        assert!(read_packet.verify());
+        assert_eq!(read_packet.offset, 1024);
+    }
+
+    #[test]
+    fn test_write_recovery_file_slice_checksum_packet() {
+        use binrw::BinReaderExt;
+
+        let slice_checksums = [(3u32, [1u8; 64]), (9u32, [2u8; 64])]
+            .iter()
+            .map(|(exponent, data)| {
+                let (md5, crc32) = crate::checksum::compute_block_checksums(data);
+                RecoverySliceChecksum {
+                    md5,
+                    crc32,
+                    exponent: *exponent,
+                }
+            })
+            .collect();
+        let packet = generate_recovery_file_slice_checksum_packet(
+            RecoverySetId::new([0xCC; 16]),
+            slice_checksums,
+        );
+        assert_eq!(packet.length, 64 + 48);
+
+        let mut buffer = Vec::new();
+        write_recovery_file_slice_checksum_packet(&mut buffer, &packet).unwrap();
+        let read_packet: RecoveryFileSliceChecksumPacket =
+            std::io::Cursor::new(&buffer).read_le().unwrap();
+        assert!(read_packet.verify());
+        assert_eq!(read_packet.slice_checksums[1].exponent, 9);
+        assert_eq!(
+            read_packet.slice_checksums[0].md5,
+            crate::checksum::compute_md5(&[1u8; 64])
+        );
+    }
 }
@@ -0,0 +1,119 @@
+//! Create plan - what a create run would produce, without reading any data
+//!
+//! Everything here follows from the file sizes and the configuration: block
+//! layout, output files with their recovery exponents and exact sizes, and the
+//! memory the encoder is expected to need.
+
+use super::block_size_optimizer::BlockSizeChoice;
+use std::ops::Range;
+use std::path::PathBuf;
+
+/// Layout and output of a create run, worked out before any hashing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatePlan {
+    /// Block (slice) size in bytes
+    pub block_size: u64,
+    /// Subslice size for packed sets
+    pub subslice_size: Option<u64>,
+    pub source_block_count: u32,
+    pub recovery_block_count: u32,
+    /// How the block size was chosen, when the block size search ran
+    ///
+    /// Already shown by the create reporter, so not repeated by `print_report`.
+    pub block_size_choice: Option<BlockSizeChoice>,
+    /// Files covered by recovery data, in recovery set order
+    pub source_files: Vec<PlannedSourceFile>,
+    /// Index file first, then the recovery volumes or external recovery file
+    pub output_files: Vec<PlannedOutputFile>,
+    /// Bytes of each block encoded per pass
+    pub chunk_size: usize,
+    /// Source blocks buffered per pass when a block spans several chunks
+    pub source_group_blocks: usize,
+    /// Expected bytes of source and recovery data read and written
+    pub estimated_io: u64,
+    /// Expected encoder memory in bytes
+    pub estimated_memory: usize,
+}
+
+/// One source file in a create plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedSourceFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub block_count: u32,
+    /// Zero bytes added to fill the last block (or subslice)
+    pub padding: u64,
+}
+
+/// One output file in a create plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedOutputFile {
+    pub path: PathBuf,
+    /// Exponents of the recovery blocks stored in the file; empty for the index file
+    pub exponents: Range<u32>,
+    /// Exact size in bytes
+    pub size: u64,
+}
+
+impl CreatePlan {
+    /// Total zero padding across all source files
+    pub fn total_padding(&self) -> u64 {
+        self.source_files.iter().map(|file| file.padding).sum()
+    }
+
+    /// Total size of all output files
+    pub fn total_output_size(&self) -> u64 {
+        self.output_files.iter().map(|file| file.size).sum()
+    }
+
+    /// Print the plan in the style of the create progress output
+    pub fn print_report(&self) {
+        println!("Block size: {}", self.block_size);
+        if let Some(subslice_size) = self.subslice_size {
+            println!("Subslice size: {}", subslice_size);
+        }
+        println!("Source block count: {}", self.source_block_count);
+        println!("Recovery block count: {}", self.recovery_block_count);
+
+        println!("\nSource files:");
+        for file in &self.source_files {
+            println!(
+                "  {}: {} bytes, {} blocks, {} bytes padding",
+                file.path.display(),
+                file.size,
+                file.block_count,
+                file.padding
+            );
+        }
+        println!("Total padding: {} bytes", self.total_padding());
+
+        println!("\nOutput files:");
+        for file in &self.output_files {
+            if file.exponents.is_empty() {
+                println!("  {}: {} bytes", file.path.display(), file.size);
+            } else {
+                println!(
+                    "  {}: {} bytes, exponents {}-{}",
+                    file.path.display(),
+                    file.size,
+                    file.exponents.start,
+                    file.exponents.end - 1
+                );
+            }
+        }
+        println!("Total output size: {} bytes", self.total_output_size());
+
+        println!("\nChunk size: {} bytes", self.chunk_size);
+        if self.chunk_size < self.block_size as usize {
+            println!("Source blocks per pass: {}", self.source_group_blocks);
+        }
+        println!(
+            "Estimated I/O: {} MB",
+            self.estimated_io.div_ceil(1024 * 1024)
+        );
+        println!(
+            "Estimated memory: {} MB",
+            self.estimated_memory.div_ceil(1024 * 1024)
+        );
+    }
+}
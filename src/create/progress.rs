@@ -1,5 +1,8 @@
 //! Progress reporting for PAR2 creation
 
+use super::block_size_optimizer::BlockSizeChoice;
+use std::time::Duration;
+
 /// Trait for reporting creation progress
 ///
 /// Similar to repair::RepairReporter but for creation operations
@@ -7,6 +10,9 @@ pub trait CreateReporter: Send + Sync {
     /// Report scanning of source files
     fn report_scanning_files(&self, current: usize, total: usize, filename: &str);
 
+    /// Report the block size picked by the block size search
+    fn report_block_size_choice(&self, choice: &BlockSizeChoice);
+
     /// Report file hash computation progress
     fn report_file_hashing(&self, filename: &str, bytes_processed: u64, total_bytes: u64);
 
@@ -16,6 +22,13 @@ pub trait CreateReporter: Send + Sync {
     /// Report recovery block generation
     fn report_recovery_generation(&self, blocks_generated: u32, total_blocks: u32);
 
+    /// Report source read throughput: file bytes read so far out of the total,
+    /// and the time spent since reading started
+    ///
+    /// Called about once a second while encoding and once more when all
+    /// source data has been read.
+    fn report_read_throughput(&self, bytes_read: u64, total_bytes: u64, elapsed: Duration);
+
     /// Report PAR2 file writing
     fn report_writing_file(&self, filename: &str);
 
@@ -39,6 +52,15 @@ fn percent_complete(completed: u64, total: u64) -> u32 {
     }
 }
 
+fn mib_per_second(bytes: u64, elapsed: Duration) -> f64 {
+    let seconds = elapsed.as_secs_f64();
+    if seconds > 0.0 {
+        bytes as f64 / (1024.0 * 1024.0) / seconds
+    } else {
+        0.0
+    }
+}
+
 impl ConsoleCreateReporter {
     pub fn new(quiet: bool) -> Self {
         ConsoleCreateReporter { quiet }
@@ -52,6 +74,14 @@ impl CreateReporter for ConsoleCreateReporter {
         }
     }
 
+    fn report_block_size_choice(&self, choice: &BlockSizeChoice) {
+        if !self.quiet {
+            for line in choice.describe() {
+                println!("{}", line);
+            }
+        }
+    }
+
     fn report_file_hashing(&self, filename: &str, bytes_processed: u64, total_bytes: u64) {
         if !self.quiet {
             let percent = percent_complete(bytes_processed, total_bytes);
@@ -82,6 +112,18 @@ impl CreateReporter for ConsoleCreateReporter {
         }
     }
 
+    fn report_read_throughput(&self, bytes_read: u64, total_bytes: u64, elapsed: Duration) {
+        // Only the final rate is printed; periodic updates would clobber the
+        // recovery progress line
+        if !self.quiet && bytes_read == total_bytes {
+            println!(
+                "\nRead {} bytes of source data at {:.1} MiB/s",
+                bytes_read,
+                mib_per_second(bytes_read, elapsed)
+            );
+        }
+    }
+
     fn report_writing_file(&self, filename: &str) {
         if !self.quiet {
             println!("Writing: {}", filename);
@@ -107,9 +149,11 @@ pub struct SilentCreateReporter;
 
 impl CreateReporter for SilentCreateReporter {
     fn report_scanning_files(&self, _current: usize, _total: usize, _filename: &str) {}
+    fn report_block_size_choice(&self, _choice: &BlockSizeChoice) {}
     fn report_file_hashing(&self, _filename: &str, _bytes_processed: u64, _total_bytes: u64) {}
     fn report_block_checksums(&self, _blocks_processed: u32, _total_blocks: u32) {}
     fn report_recovery_generation(&self, _blocks_generated: u32, _total_blocks: u32) {}
+    fn report_read_throughput(&self, _bytes_read: u64, _total_bytes: u64, _elapsed: Duration) {}
     fn report_writing_file(&self, _filename: &str) {}
     fn report_complete(&self, _output_files: &[String]) {}
     fn report_error(&self, _error: &str) {}
@@ -117,7 +161,14 @@ impl CreateReporter for SilentCreateReporter {
 
 #[cfg(test)]
 mod tests {
-    use super::percent_complete;
+    use super::{mib_per_second, percent_complete};
+    use std::time::Duration;
+
+    #[test]
+    fn mib_per_second_handles_zero_elapsed_time() {
+        assert_eq!(mib_per_second(4 * 1024 * 1024, Duration::from_secs(2)), 2.0);
+        assert_eq!(mib_per_second(1024, Duration::ZERO), 0.0);
+    }
 
     #[test]
     fn percent_complete_treats_empty_work_as_complete() {
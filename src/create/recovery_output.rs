@@ -0,0 +1,488 @@
+//! Disk-backed recovery block output for PAR2 creation
+//!
+//! Recovery blocks are written straight to their final place in the output
+//! files as each chunk is encoded, so memory use during create depends on the
+//! chunk size rather than on the number or size of recovery blocks.
+//!
+//! Recovery packet MD5s cover the recovery set ID, which is only known once
+//! every source file has been hashed. Packet headers are therefore written up
+//! front with a zero MD5 and set ID and filled in by `backfill_packet_headers`
+//! once encoding is done, reading each block back from disk.
+//!
+//! Reference: par2cmdline-turbo/src/par2creator.cpp InitialiseOutputFiles(),
+//! WriteRecoveryPacketHeaders() and FinishRecoveryPackets()
+
+use super::error::{CreateError, CreateResult};
+use crate::domain::{Crc32Value, RecoverySetId};
+use crate::packets::recovery_file_slice_checksum_packet::RecoverySliceChecksum;
+use md5::Digest;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+/// Recovery packet header: packet header plus the 4-byte exponent
+pub(super) const RECOVERY_HEADER_SIZE: u64 = 64 + 4;
+/// Buffer size used when reading recovery data back for checksumming
+const READ_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// An output file receiving recovery data
+pub(super) struct OutputFile {
+    pub path: PathBuf,
+    pub file: File,
+}
+
+impl OutputFile {
+    fn write_error(&self, source: std::io::Error) -> CreateError {
+        CreateError::FileCreateError {
+            file: self.path.to_string_lossy().to_string(),
+            source,
+        }
+    }
+
+    fn read_error(&self, source: std::io::Error) -> CreateError {
+        CreateError::FileReadError {
+            file: self.path.to_string_lossy().to_string(),
+            source,
+        }
+    }
+
+    fn write_at(&mut self, offset: u64, data: &[u8]) -> CreateResult<()> {
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.write_all(data))
+            .map_err(|e| self.write_error(e))
+    }
+
+    /// Feed `len` bytes starting at `offset` to `consume`, a buffer at a time
+    fn read_range(
+        &mut self,
+        offset: u64,
+        len: u64,
+        buffer: &mut [u8],
+        mut consume: impl FnMut(&[u8]),
+    ) -> CreateResult<()> {
+        self.file
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| self.read_error(e))?;
+        let mut remaining = len;
+        while remaining > 0 {
+            let n = (remaining as usize).min(buffer.len());
+            self.file
+                .read_exact(&mut buffer[..n])
+                .map_err(|e| self.read_error(e))?;
+            consume(&buffer[..n]);
+            remaining -= n as u64;
+        }
+        Ok(())
+    }
+}
+
+/// How recovery blocks are stored in the output files
+enum Layout {
+    /// RecvSlic or PkdRecvS packets of the given type
+    Packets(&'static [u8; 16]),
+    /// Bare slices laid end to end, described by an RFSC packet
+    Raw,
+}
+
+/// Where one recovery block lives on disk
+struct BlockLocation {
+    file: usize,
+    /// Offset of the recovery packet header (unused for raw slices)
+    packet_offset: u64,
+    data_offset: u64,
+    exponent: u16,
+}
+
+/// One recovery volume to lay out
+pub(super) struct Volume {
+    pub output: OutputFile,
+    /// Exponents of the recovery packets it holds
+    pub exponents: Vec<u16>,
+    /// Copies of the critical packets spread among the recovery packets
+    pub critical_copies: u32,
+}
+
+/// Recovery blocks being accumulated in their output files
+pub(super) struct RecoveryOutput {
+    files: Vec<OutputFile>,
+    /// Indexed by recovery block, in increasing exponent order
+    blocks: Vec<BlockLocation>,
+    block_size: u64,
+    layout: Layout,
+    /// File and offset of every slot reserved for the critical packets
+    critical_slots: Vec<(usize, u64)>,
+    critical_size: u64,
+}
+
+/// Number of recovery packets before each copy of the critical packets
+///
+/// Copies are spread evenly from the start of the file to its end, so a
+/// damaged region leaves the other copies intact. A single copy goes last.
+fn critical_copy_positions(copies: u32, packet_count: usize) -> Vec<usize> {
+    let copies = copies as usize;
+    if copies <= 1 {
+        return vec![packet_count; copies];
+    }
+    (0..copies)
+        .map(|copy| copy * packet_count / (copies - 1))
+        .collect()
+}
+
+impl RecoveryOutput {
+    /// Reserve recovery packets and critical packet slots in each volume file
+    ///
+    /// Each volume holds its recovery packets with `critical_copies` slots of
+    /// `critical_size` bytes interleaved among them; the slots are filled by
+    /// `write_critical_packets`. The whole region is allocated immediately.
+    pub fn packets(
+        volumes: Vec<Volume>,
+        block_size: u64,
+        packet_type: &'static [u8; 16],
+        critical_size: u64,
+    ) -> CreateResult<Self> {
+        let packet_length = RECOVERY_HEADER_SIZE + block_size;
+        let mut files = Vec::with_capacity(volumes.len());
+        let mut blocks = Vec::new();
+        let mut critical_slots = Vec::new();
+
+        for (file_idx, volume) in volumes.into_iter().enumerate() {
+            let Volume {
+                mut output,
+                exponents,
+                critical_copies,
+            } = volume;
+            output
+                .file
+                .set_len(
+                    exponents.len() as u64 * packet_length + critical_copies as u64 * critical_size,
+                )
+                .map_err(|e| output.write_error(e))?;
+
+            let positions = critical_copy_positions(critical_copies, exponents.len());
+            let mut offset = 0;
+            for i in 0..=exponents.len() {
+                for _ in positions.iter().filter(|&&position| position == i) {
+                    critical_slots.push((file_idx, offset));
+                    offset += critical_size;
+                }
+                let Some(&exponent) = exponents.get(i) else {
+                    break;
+                };
+
+                let mut header = Vec::with_capacity(RECOVERY_HEADER_SIZE as usize);
+                header.extend_from_slice(crate::packets::MAGIC_BYTES);
+                header.extend_from_slice(&packet_length.to_le_bytes());
+                header.extend_from_slice(&[0u8; 32]); // MD5 and set ID, backfilled
+                header.extend_from_slice(packet_type);
+                header.extend_from_slice(&(exponent as u32).to_le_bytes());
+                output.write_at(offset, &header)?;
+
+                blocks.push(BlockLocation {
+                    file: file_idx,
+                    packet_offset: offset,
+                    data_offset: offset + RECOVERY_HEADER_SIZE,
+                    exponent,
+                });
+                offset += packet_length;
+            }
+            files.push(output);
+        }
+        blocks.sort_by_key(|block| block.exponent);
+
+        Ok(Self {
+            files,
+            blocks,
+            block_size,
+            layout: Layout::Packets(packet_type),
+            critical_slots,
+            critical_size,
+        })
+    }
+
+    /// Reserve bare recovery slices, in exponent order, in a single file
+    pub fn raw(output: OutputFile, exponents: &[u16], block_size: u64) -> CreateResult<Self> {
+        output
+            .file
+            .set_len(exponents.len() as u64 * block_size)
+            .map_err(|e| output.write_error(e))?;
+        let blocks = exponents
+            .iter()
+            .enumerate()
+            .map(|(i, &exponent)| BlockLocation {
+                file: 0,
+                packet_offset: i as u64 * block_size,
+                data_offset: i as u64 * block_size,
+                exponent,
+            })
+            .collect();
+
+        Ok(Self {
+            files: vec![output],
+            blocks,
+            block_size,
+            layout: Layout::Raw,
+            critical_slots: Vec::new(),
+            critical_size: 0,
+        })
+    }
+
+    /// Write `data` at `offset` bytes into recovery block `recovery_idx`
+    pub fn write_chunk(
+        &mut self,
+        recovery_idx: usize,
+        offset: u64,
+        data: &[u8],
+    ) -> CreateResult<()> {
+        let block = &self.blocks[recovery_idx];
+        debug_assert!(offset + data.len() as u64 <= self.block_size);
+        self.files[block.file].write_at(block.data_offset + offset, data)
+    }
+
+    /// Read back `data.len()` bytes at `offset` into recovery block `recovery_idx`
+    pub fn read_chunk(
+        &mut self,
+        recovery_idx: usize,
+        offset: u64,
+        data: &mut [u8],
+    ) -> CreateResult<()> {
+        let block = &self.blocks[recovery_idx];
+        debug_assert!(offset + data.len() as u64 <= self.block_size);
+        let output = &mut self.files[block.file];
+        output
+            .file
+            .seek(SeekFrom::Start(block.data_offset + offset))
+            .and_then(|_| output.file.read_exact(data))
+            .map_err(|e| output.read_error(e))
+    }
+
+    /// Copy every recovery block into the same block of `target`
+    pub fn copy_into(&mut self, target: &mut RecoveryOutput) -> CreateResult<()> {
+        debug_assert_eq!(self.blocks.len(), target.blocks.len());
+        let mut buffer = vec![0u8; READ_BUFFER_SIZE.min(self.block_size as usize)];
+        for recovery_idx in 0..self.blocks.len() {
+            let mut offset = 0;
+            while offset < self.block_size {
+                let len = ((self.block_size - offset) as usize).min(buffer.len());
+                self.read_chunk(recovery_idx, offset, &mut buffer[..len])?;
+                target.write_chunk(recovery_idx, offset, &buffer[..len])?;
+                offset += len as u64;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fill in the set ID and MD5 of every recovery packet
+    ///
+    /// Does nothing for raw slices, which carry no header.
+    pub fn backfill_packet_headers(&mut self, recovery_set_id: RecoverySetId) -> CreateResult<()> {
+        let Layout::Packets(packet_type) = self.layout else {
+            return Ok(());
+        };
+
+        let mut buffer = vec![0u8; READ_BUFFER_SIZE.min(self.block_size as usize)];
+        for block in &self.blocks {
+            let output = &mut self.files[block.file];
+            let mut hasher = crate::checksum::new_md5_hasher();
+            hasher.update(recovery_set_id.as_bytes());
+            hasher.update(packet_type);
+            hasher.update((block.exponent as u32).to_le_bytes());
+            output.read_range(block.data_offset, self.block_size, &mut buffer, |data| {
+                hasher.update(data)
+            })?;
+
+            let mut md5_and_set_id = [0u8; 32];
+            md5_and_set_id[..16].copy_from_slice(crate::checksum::finalize_md5(hasher).as_bytes());
+            md5_and_set_id[16..].copy_from_slice(recovery_set_id.as_bytes());
+            output.write_at(block.packet_offset + 16, &md5_and_set_id)?;
+        }
+        Ok(())
+    }
+
+    /// Fill every slot reserved for the critical packets
+    pub fn write_critical_packets(&mut self, critical_bytes: &[u8]) -> CreateResult<()> {
+        debug_assert!(
+            self.critical_slots.is_empty() || critical_bytes.len() as u64 == self.critical_size
+        );
+        for &(file, offset) in &self.critical_slots {
+            self.files[file].write_at(offset, critical_bytes)?;
+        }
+        Ok(())
+    }
+
+    /// MD5, CRC32 and exponent of every recovery block, in exponent order
+    pub fn slice_checksums(&mut self) -> CreateResult<Vec<RecoverySliceChecksum>> {
+        let mut buffer = vec![0u8; READ_BUFFER_SIZE.min(self.block_size as usize)];
+        self.blocks
+            .iter()
+            .map(|block| {
+                let mut md5 = crate::checksum::new_md5_hasher();
+                let mut crc32 = crc32fast::Hasher::new();
+                self.files[block.file].read_range(
+                    block.data_offset,
+                    self.block_size,
+                    &mut buffer,
+                    |data| {
+                        md5.update(data);
+                        crc32.update(data);
+                    },
+                )?;
+                Ok(RecoverySliceChecksum {
+                    md5: crate::checksum::finalize_md5(md5),
+                    crc32: Crc32Value::new(crc32.finalize()),
+                    exponent: block.exponent as u32,
+                })
+            })
+            .collect()
+    }
+
+    /// Flush the recovery data written so far to disk
+    pub fn sync(&self) -> CreateResult<()> {
+        self.files
+            .iter()
+            .try_for_each(|output| output.file.sync_data().map_err(|e| output.write_error(e)))
+    }
+
+    /// Hand back the output files, each positioned after its recovery data
+    pub fn into_files(self) -> CreateResult<Vec<OutputFile>> {
+        self.files
+            .into_iter()
+            .map(|mut output| {
+                output
+                    .file
+                    .seek(SeekFrom::End(0))
+                    .map_err(|e| output.write_error(e))?;
+                Ok(output)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::BinReaderExt;
+
+    const PACKET_TYPE: &[u8; 16] = b"PAR 2.0\0RecvSlic";
+
+    fn output_file(dir: &std::path::Path, name: &str) -> OutputFile {
+        let path = dir.join(name);
+        let file = super::super::error_helpers::create_output_file(&path, false).unwrap();
+        OutputFile { path, file }
+    }
+
+    #[test]
+    fn chunked_writes_produce_valid_recovery_packets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let set_id = RecoverySetId::new([0x5A; 16]);
+        let block_a: Vec<u8> = (0..16).collect();
+        let block_b: Vec<u8> = (100..116).collect();
+
+        // Exponents are handed out out of order to check the block lookup
+        let volume = |name, exponent| Volume {
+            output: output_file(tmp.path(), name),
+            exponents: vec![exponent],
+            critical_copies: 0,
+        };
+        let mut output = RecoveryOutput::packets(
+            vec![volume("b.par2", 4), volume("a.par2", 3)],
+            16,
+            PACKET_TYPE,
+            0,
+        )
+        .unwrap();
+        for offset in (0..16).step_by(8) {
+            output
+                .write_chunk(0, offset, &block_a[offset as usize..][..8])
+                .unwrap();
+            output
+                .write_chunk(1, offset, &block_b[offset as usize..][..8])
+                .unwrap();
+        }
+        output.backfill_packet_headers(set_id).unwrap();
+        drop(output.into_files().unwrap());
+
+        for (name, exponent, data) in [("a.par2", 3, &block_a), ("b.par2", 4, &block_b)] {
+            let bytes = std::fs::read(tmp.path().join(name)).unwrap();
+            let packet: crate::packets::RecoverySlicePacket =
+                std::io::Cursor::new(&bytes).read_le().unwrap();
+            assert!(packet.verify());
+            assert_eq!(packet.set_id, set_id);
+            assert_eq!(packet.exponent, exponent);
+            assert_eq!(&packet.recovery_data, data);
+        }
+    }
+
+    #[test]
+    fn critical_copies_are_spread_from_start_to_end() {
+        assert_eq!(critical_copy_positions(0, 5), Vec::<usize>::new());
+        assert_eq!(critical_copy_positions(1, 5), vec![5]);
+        assert_eq!(critical_copy_positions(2, 5), vec![0, 5]);
+        assert_eq!(critical_copy_positions(3, 4), vec![0, 2, 4]);
+        assert_eq!(critical_copy_positions(3, 1), vec![0, 0, 1]);
+    }
+
+    #[test]
+    fn critical_slots_are_interleaved_with_recovery_packets() {
+        let tmp = tempfile::tempdir().unwrap();
+        let critical = [0xC5u8; 12];
+        let mut output = RecoveryOutput::packets(
+            vec![Volume {
+                output: output_file(tmp.path(), "vol.par2"),
+                exponents: vec![0, 1, 2, 3],
+                critical_copies: 3,
+            }],
+            8,
+            PACKET_TYPE,
+            critical.len() as u64,
+        )
+        .unwrap();
+        for block in 0..4 {
+            output.write_chunk(block, 0, &[block as u8; 8]).unwrap();
+        }
+        output
+            .backfill_packet_headers(RecoverySetId::new([1; 16]))
+            .unwrap();
+        output.write_critical_packets(&critical).unwrap();
+        drop(output.into_files().unwrap());
+
+        let bytes = std::fs::read(tmp.path().join("vol.par2")).unwrap();
+        let packet_length = (RECOVERY_HEADER_SIZE + 8) as usize;
+        assert_eq!(bytes.len(), 4 * packet_length + 3 * critical.len());
+        let middle = critical.len() + 2 * packet_length;
+        for start in [0, middle, bytes.len() - critical.len()] {
+            assert_eq!(&bytes[start..start + critical.len()], &critical);
+        }
+
+        let mut cursor = std::io::Cursor::new(&bytes);
+        let (packets, recovery_count) =
+            crate::packets::parse_packets_with_options(&mut cursor, true);
+        assert_eq!(recovery_count, 4);
+        assert!(packets.iter().all(|packet| packet.verify()));
+    }
+
+    #[test]
+    fn raw_slices_are_checksummed_in_exponent_order() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut output =
+            RecoveryOutput::raw(output_file(tmp.path(), "data.rfsc"), &[7, 8], 8).unwrap();
+        output.write_chunk(1, 0, &[2u8; 8]).unwrap();
+        output.write_chunk(0, 0, &[1u8; 8]).unwrap();
+
+        let checksums = output.slice_checksums().unwrap();
+        assert_eq!(checksums[0].exponent, 7);
+        assert_eq!(checksums[0].md5, crate::checksum::compute_md5(&[1u8; 8]));
+        assert_eq!(
+            checksums[1].crc32,
+            crate::checksum::compute_crc32(&[2u8; 8])
+        );
+        drop(output.into_files().unwrap());
+
+        let mut expected = vec![1u8; 8];
+        expected.extend_from_slice(&[2u8; 8]);
+        assert_eq!(
+            std::fs::read(tmp.path().join("data.rfsc")).unwrap(),
+            expected
+        );
+    }
+}
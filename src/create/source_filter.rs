@@ -0,0 +1,342 @@
+//! Selection of the source files to protect
+//!
+//! Include and exclude patterns are globs matched component by component:
+//! `*` and `?` stay within one path component, `[a-z]` and `[!a-z]` match a
+//! character class, and a `**` component spans any number of components. A
+//! pattern matches a path when it matches any run of consecutive components,
+//! so `.git` excludes everything under a `.git` directory, `*.tmp` any file
+//! ending in `.tmp`, and `cache/*.bin` the `.bin` files of any `cache`
+//! directory. Paths are matched relative to the directory being walked, or
+//! by their PAR2 name when given to the builder.
+//!
+//! Only regular files can be protected: FIFOs, sockets and devices are
+//! refused rather than read, and symbolic links are followed, skipped or
+//! refused as the `SymlinkPolicy` says. PAR2 describes files only, so empty
+//! directories are not part of a recovery set.
+
+use super::error::{CreateError, CreateResult};
+use std::path::{Component, Path, PathBuf};
+
+/// What to do with a symbolic link among the source files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Protect what the link points to
+    #[default]
+    Follow,
+    /// Leave the link out
+    Skip,
+    /// Fail the create
+    Error,
+}
+
+/// Include/exclude patterns, hidden-file and symlink policy for source files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+    skip_hidden: bool,
+    symlinks: SymlinkPolicy,
+}
+
+impl SourceFilter {
+    /// Keep only files matching one of the include patterns
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.include.push(pattern.into());
+        self
+    }
+
+    /// Leave out files and directories matching the pattern
+    pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
+        self.exclude.push(pattern.into());
+        self
+    }
+
+    /// Leave out files and directories whose name starts with a dot
+    pub fn skip_hidden(mut self, skip_hidden: bool) -> Self {
+        self.skip_hidden = skip_hidden;
+        self
+    }
+
+    /// Follow, skip or refuse symbolic links
+    pub fn symlinks(mut self, policy: SymlinkPolicy) -> Self {
+        self.symlinks = policy;
+        self
+    }
+
+    /// The symbolic link policy
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        self.symlinks
+    }
+
+    /// Whether the filter lets every file through
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+            && self.exclude.is_empty()
+            && !self.skip_hidden
+            && self.symlinks == SymlinkPolicy::Follow
+    }
+
+    /// Whether the file at `relative` is selected
+    pub fn allows(&self, relative: &Path) -> bool {
+        let components = normal_components(relative);
+        self.descends(&components)
+            && (self.include.is_empty()
+                || self
+                    .include
+                    .iter()
+                    .any(|pattern| pattern_matches(pattern, &components)))
+    }
+
+    /// Whether the directory at `relative` may hold selected files
+    ///
+    /// Include patterns only apply to files, so only hidden and excluded
+    /// directories are skipped.
+    pub fn descends_into(&self, relative: &Path) -> bool {
+        self.descends(&normal_components(relative))
+    }
+
+    fn descends(&self, components: &[&[u8]]) -> bool {
+        !(self.skip_hidden && components.iter().any(|name| name.starts_with(b".")))
+            && !self
+                .exclude
+                .iter()
+                .any(|pattern| pattern_matches(pattern, components))
+    }
+}
+
+fn normal_components(path: &Path) -> Vec<&[u8]> {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.as_encoded_bytes()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether `pattern` matches a run of consecutive `components`
+fn pattern_matches(pattern: &str, components: &[&[u8]]) -> bool {
+    let pattern: Vec<&[u8]> = pattern
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .map(str::as_bytes)
+        .collect();
+    !pattern.is_empty()
+        && (0..components.len()).any(|start| run_matches(&pattern, &components[start..]))
+}
+
+/// Whether `pattern` matches the components at the start of `components`
+fn run_matches(pattern: &[&[u8]], components: &[&[u8]]) -> bool {
+    match pattern.split_first() {
+        None => true,
+        Some((&b"**", rest)) => {
+            (0..=components.len()).any(|skip| run_matches(rest, &components[skip..]))
+        }
+        Some((first, rest)) => components
+            .split_first()
+            .is_some_and(|(name, names)| glob_matches(first, name) && run_matches(rest, names)),
+    }
+}
+
+/// Match a single path component against a glob
+fn glob_matches(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|skip| glob_matches(rest, &name[skip..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_matches(rest, &name[1..]),
+        Some((b'[', class)) => match (class_matches(class, name.first()), name.split_first()) {
+            (Some((true, rest)), Some((_, name))) => glob_matches(rest, name),
+            (Some(_), _) => false,
+            // An unterminated class is an ordinary `[`
+            (None, _) => name.first() == Some(&b'[') && glob_matches(class, &name[1..]),
+        },
+        Some((&literal, rest)) => name.first() == Some(&literal) && glob_matches(rest, &name[1..]),
+    }
+}
+
+/// Match `byte` against the character class starting after its `[`
+///
+/// Returns whether it matched and the pattern after the closing `]`, or None
+/// when the class is not terminated.
+fn class_matches<'a>(class: &'a [u8], byte: Option<&u8>) -> Option<(bool, &'a [u8])> {
+    let (negated, class) = match class.first() {
+        Some(b'!' | b'^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    // A `]` right after the opening bracket is part of the class
+    let end = class
+        .iter()
+        .skip(1)
+        .position(|&b| b == b']')
+        .map(|pos| pos + 1)?;
+    let members = &class[..end];
+    let matched = byte.is_some_and(|&byte| {
+        let mut i = 0;
+        let mut found = false;
+        while i < members.len() {
+            if i + 2 < members.len() && members[i + 1] == b'-' {
+                found |= (members[i]..=members[i + 2]).contains(&byte);
+                i += 3;
+            } else {
+                found |= members[i] == byte;
+                i += 1;
+            }
+        }
+        found
+    });
+    Some((matched != negated, &class[end + 1..]))
+}
+
+/// What a source path turned out to be
+pub(super) enum SourceKind {
+    File,
+    Dir,
+    /// A symbolic link left out by the policy
+    Skipped,
+}
+
+/// Classify `path` under the symlink `policy`, refusing special files
+pub(super) fn classify_source(path: &Path, policy: SymlinkPolicy) -> CreateResult<SourceKind> {
+    let read_error = |e| CreateError::FileReadError {
+        file: path.to_string_lossy().to_string(),
+        source: e,
+    };
+    let mut metadata = std::fs::symlink_metadata(path).map_err(read_error)?;
+    if metadata.file_type().is_symlink() {
+        match policy {
+            SymlinkPolicy::Skip => return Ok(SourceKind::Skipped),
+            SymlinkPolicy::Error => {
+                return Err(CreateError::SymlinkRefused(
+                    path.to_string_lossy().to_string(),
+                ))
+            }
+            // A dangling link or one in a cycle of links fails here
+            SymlinkPolicy::Follow => metadata = std::fs::metadata(path).map_err(read_error)?,
+        }
+    }
+
+    if metadata.is_dir() {
+        Ok(SourceKind::Dir)
+    } else if metadata.is_file() {
+        Ok(SourceKind::File)
+    } else {
+        Err(CreateError::SpecialFile {
+            file: path.to_string_lossy().to_string(),
+            kind: special_file_kind(&metadata.file_type()),
+        })
+    }
+}
+
+/// Name of a file type that is neither a file, a directory nor a link
+pub(super) fn special_file_kind(file_type: &std::fs::FileType) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_fifo() {
+            return "FIFO";
+        } else if file_type.is_socket() {
+            return "socket";
+        } else if file_type.is_block_device() {
+            return "block device";
+        } else if file_type.is_char_device() {
+            return "character device";
+        }
+    }
+    if file_type.is_dir() {
+        "directory"
+    } else {
+        "special file"
+    }
+}
+
+/// Paths listed one per line, or NUL separated when the list holds a NUL
+///
+/// Empty entries are skipped, as is the carriage return of a CRLF line.
+pub fn parse_file_list(data: &[u8]) -> Vec<PathBuf> {
+    let separator = if data.contains(&0) { 0 } else { b'\n' };
+    data.split(|&b| b == separator)
+        .map(|entry| match separator {
+            b'\n' => entry.strip_suffix(b"\r").unwrap_or(entry),
+            _ => entry,
+        })
+        .filter(|entry| !entry.is_empty())
+        .map(path_from_bytes)
+        .collect()
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    use std::os::unix::ffi::OsStrExt;
+    PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// Read a list of source files written for `parse_file_list`
+pub fn read_file_list(path: &Path) -> CreateResult<Vec<PathBuf>> {
+    std::fs::read(path)
+        .map(|data| parse_file_list(&data))
+        .map_err(|e| CreateError::FileReadError {
+            file: path.to_string_lossy().to_string(),
+            source: e,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_within_a_component() {
+        assert!(glob_matches(b"*.tmp", b"a.tmp"));
+        assert!(glob_matches(b"*.tmp", b".tmp"));
+        assert!(!glob_matches(b"*.tmp", b"a.tmp.bak"));
+        assert!(glob_matches(b"file?.[ch]", b"file1.c"));
+        assert!(!glob_matches(b"file?.[ch]", b"file1.o"));
+        assert!(glob_matches(b"[!a-c]x", b"dx"));
+        assert!(!glob_matches(b"[!a-c]x", b"bx"));
+        assert!(glob_matches(b"[]]", b"]"));
+        assert!(glob_matches(b"a[b", b"a[b"));
+        assert!(glob_matches(b"Thumbs.db", b"Thumbs.db"));
+    }
+
+    #[test]
+    fn patterns_match_any_run_of_components() {
+        let filter = SourceFilter::default()
+            .exclude(".git")
+            .exclude("cache/*.bin")
+            .exclude("logs/**/*.log");
+        assert!(!filter.allows(Path::new("repo/.git/config")));
+        assert!(!filter.allows(Path::new("a/cache/x.bin")));
+        assert!(filter.allows(Path::new("a/cache/sub/x.bin")));
+        assert!(!filter.allows(Path::new("logs/x.log")));
+        assert!(!filter.allows(Path::new("logs/2024/01/x.log")));
+        assert!(filter.allows(Path::new("src/main.rs")));
+        assert!(!filter.descends_into(Path::new("repo/.git")));
+    }
+
+    #[test]
+    fn includes_select_files_but_not_directories() {
+        let filter = SourceFilter::default().include("*.sql").skip_hidden(true);
+        assert!(filter.allows(Path::new("dumps/a.sql")));
+        assert!(!filter.allows(Path::new("dumps/a.txt")));
+        assert!(!filter.allows(Path::new(".hidden/a.sql")));
+        assert!(filter.descends_into(Path::new("dumps")));
+        assert!(!filter.descends_into(Path::new(".hidden")));
+        assert!(SourceFilter::default().is_empty());
+    }
+
+    #[test]
+    fn file_lists_split_on_newlines_or_nuls() {
+        assert_eq!(
+            parse_file_list(b"a.bin\r\nb c.bin\n\n"),
+            vec![PathBuf::from("a.bin"), PathBuf::from("b c.bin")]
+        );
+        assert_eq!(
+            parse_file_list(b"a\nb\0c\0"),
+            vec![PathBuf::from("a\nb"), PathBuf::from("c")]
+        );
+    }
+}
@@ -0,0 +1,386 @@
+//! Source data reading for PAR2 creation
+//!
+//! Source files are padded to whole subslices and packed back to back; each
+//! source block covers `block_size / subslice_size` consecutive subslices of
+//! that stream. Reads are issued in a fixed order (`ReadOrder`) and served by a
+//! bounded pipeline: reader threads fill recycled buffers ahead of the encoder,
+//! so GF16 work on the current chunk overlaps the I/O for the next ones.
+//! Hashing stays on the consuming thread, which sees every byte in stream
+//! order, so file MD5s and subslice checksums can be updated incrementally.
+//!
+//! Reference: par2cmdline-turbo/src/par2creator.cpp ProcessData()
+
+use super::error::{CreateError, CreateResult};
+use super::error_helpers::open_for_reading;
+use super::source_file::SourceFileInfo;
+use md5::{Digest, Md5};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+
+/// Read buffers shared by all reader threads; each reader gets at least one
+const PREFETCH_BUFFERS: usize = 8;
+
+/// Position of every subslice of the packed source stream
+pub(super) struct SourceLayout<'a> {
+    source_files: &'a [SourceFileInfo],
+    subslice_size: u64,
+    subslices_per_block: usize,
+    /// Per-file (block_count, global_block_offset), counted in subslices
+    pub file_block_meta: Vec<(u32, u32)>,
+    /// Owning file of every subslice in the packed stream
+    subslice_files: Vec<usize>,
+}
+
+/// The part of a read that falls inside one subslice
+struct Segment {
+    subslice: usize,
+    file_idx: usize,
+    /// Byte range within the read buffer
+    window: Range<usize>,
+    file_pos: u64,
+    /// Bytes of file data at the start of `window`; the rest is padding
+    file_bytes: usize,
+}
+
+impl<'a> SourceLayout<'a> {
+    pub fn new(source_files: &'a [SourceFileInfo], block_size: u64, subslice_size: u64) -> Self {
+        let mut file_block_meta = Vec::with_capacity(source_files.len());
+        let mut subslice_files = Vec::new();
+        for (file_idx, file) in source_files.iter().enumerate() {
+            let block_count = file.calculate_block_count(subslice_size);
+            file_block_meta.push((block_count, subslice_files.len() as u32));
+            subslice_files.extend(std::iter::repeat_n(file_idx, block_count as usize));
+        }
+
+        SourceLayout {
+            source_files,
+            subslice_size,
+            subslices_per_block: (block_size / subslice_size) as usize,
+            file_block_meta,
+            subslice_files,
+        }
+    }
+
+    /// Number of source blocks in the packed stream
+    pub fn block_count(&self) -> usize {
+        self.subslice_files.len().div_ceil(self.subslices_per_block)
+    }
+
+    /// Split a read into the subslices it covers
+    fn segments(&self, request: ReadRequest) -> impl Iterator<Item = Segment> + '_ {
+        let chunk_end = request.block_offset + request.len as u64;
+        let first_subslice = request.source_idx * self.subslices_per_block;
+        let last_subslice =
+            (first_subslice + self.subslices_per_block).min(self.subslice_files.len());
+
+        (first_subslice..last_subslice).filter_map(move |subslice| {
+            // Byte range of this subslice within the block, clipped to the read
+            let subslice_start = (subslice - first_subslice) as u64 * self.subslice_size;
+            let range_start = subslice_start.max(request.block_offset);
+            let range_end = (subslice_start + self.subslice_size).min(chunk_end);
+            if range_start >= range_end {
+                return None;
+            }
+
+            let file_idx = self.subslice_files[subslice];
+            let local_idx = (subslice - self.file_block_meta[file_idx].1 as usize) as u64;
+            let file_pos = local_idx * self.subslice_size + (range_start - subslice_start);
+            let window = (range_start - request.block_offset) as usize
+                ..(range_end - request.block_offset) as usize;
+            let file_size = self.source_files[file_idx].size;
+            let file_bytes = (file_size.saturating_sub(file_pos) as usize).min(window.len());
+            Some(Segment {
+                subslice,
+                file_idx,
+                window,
+                file_pos,
+                file_bytes,
+            })
+        })
+    }
+
+    /// Fill `buffer` with the bytes of `request`, zero-padded past each file's end
+    fn read_into(
+        &self,
+        handles: &mut [Option<File>],
+        request: ReadRequest,
+        buffer: &mut [u8],
+    ) -> CreateResult<()> {
+        buffer.fill(0);
+        for segment in self.segments(request) {
+            if segment.file_bytes == 0 {
+                continue;
+            }
+            let file = &self.source_files[segment.file_idx];
+            let handle = match &mut handles[segment.file_idx] {
+                Some(handle) => handle,
+                slot => slot.insert(open_for_reading(&file.path)?),
+            };
+            let target = &mut buffer[segment.window.start..][..segment.file_bytes];
+            handle
+                .seek(SeekFrom::Start(segment.file_pos))
+                .and_then(|_| handle.read_exact(target))
+                .map_err(|e| CreateError::FileReadError {
+                    file: file.path.to_string_lossy().to_string(),
+                    source: e,
+                })?;
+        }
+        Ok(())
+    }
+}
+
+/// Running file and subslice hashes, updated in stream order
+pub(super) struct SourceHashes {
+    pub file_md5_states: Vec<Md5>,
+    pub file_16k_buffers: Vec<Vec<u8>>,
+    pub block_md5_states: Vec<Md5>,
+    pub block_crc32_states: Vec<crc32fast::Hasher>,
+    /// Files whose hashes are already known; their data is counted but not hashed
+    pub known_files: Vec<bool>,
+    /// File bytes hashed so far, excluding padding
+    pub bytes_hashed: u64,
+}
+
+impl SourceHashes {
+    pub fn new(layout: &SourceLayout, known_files: Vec<bool>) -> Self {
+        debug_assert_eq!(known_files.len(), layout.source_files.len());
+        SourceHashes {
+            file_md5_states: layout.source_files.iter().map(|_| Md5::new()).collect(),
+            file_16k_buffers: layout
+                .source_files
+                .iter()
+                .map(|file| vec![0u8; (file.size as usize).min(16 * 1024)])
+                .collect(),
+            block_md5_states: layout.subslice_files.iter().map(|_| Md5::new()).collect(),
+            block_crc32_states: layout
+                .subslice_files
+                .iter()
+                .map(|_| crc32fast::Hasher::new())
+                .collect(),
+            known_files,
+            bytes_hashed: 0,
+        }
+    }
+
+    /// Hash the data of `request`, which must be the next read in stream order
+    pub fn update(&mut self, layout: &SourceLayout, request: ReadRequest, data: &[u8]) {
+        for segment in layout.segments(request) {
+            let window = &data[segment.window];
+            if self.known_files[segment.file_idx] {
+                self.bytes_hashed += segment.file_bytes as u64;
+                continue;
+            }
+            if segment.file_bytes > 0 {
+                if segment.file_pos < 16 * 1024 {
+                    let capture_start = segment.file_pos as usize;
+                    let capture_end = (capture_start + segment.file_bytes).min(16 * 1024);
+                    self.file_16k_buffers[segment.file_idx][capture_start..capture_end]
+                        .copy_from_slice(&window[..capture_end - capture_start]);
+                }
+                self.file_md5_states[segment.file_idx].update(&window[..segment.file_bytes]);
+                self.bytes_hashed += segment.file_bytes as u64;
+            }
+            self.block_md5_states[segment.subslice].update(window);
+            self.block_crc32_states[segment.subslice].update(window);
+        }
+    }
+}
+
+/// One read of part of a source block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ReadRequest {
+    pub source_idx: usize,
+    pub block_offset: u64,
+    pub len: usize,
+}
+
+/// The order in which source data is read during create
+///
+/// Source blocks are taken `group_blocks` at a time, starting at `first_block`.
+/// A buffered group is read block by block, each in chunk-sized pieces; an
+/// unbuffered group is read chunk offset by chunk offset across its blocks,
+/// which is only sequential when the group holds a single block or each block
+/// is a single chunk.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct ReadOrder {
+    pub source_count: usize,
+    /// First source block read; earlier ones are already done
+    pub first_block: usize,
+    pub group_blocks: usize,
+    pub block_size: u64,
+    pub chunk_size: usize,
+    pub buffered: bool,
+}
+
+impl ReadOrder {
+    pub fn chunks_per_block(&self) -> usize {
+        (self.block_size as usize).div_ceil(self.chunk_size)
+    }
+
+    /// Total number of reads
+    pub fn len(&self) -> usize {
+        (self.source_count - self.first_block) * self.chunks_per_block()
+    }
+
+    /// The `index`th read
+    pub fn get(&self, index: usize) -> ReadRequest {
+        let chunks = self.chunks_per_block();
+        let group_offset = index / (self.group_blocks * chunks) * self.group_blocks;
+        let group_start = self.first_block + group_offset;
+        let group_len = self.group_blocks.min(self.source_count - group_start);
+        let in_group = index - group_offset * chunks;
+        let (block, chunk) = if self.buffered {
+            (in_group / chunks, in_group % chunks)
+        } else {
+            (in_group % group_len, in_group / group_len)
+        };
+        let block_offset = (chunk * self.chunk_size) as u64;
+        ReadRequest {
+            source_idx: group_start + block,
+            block_offset,
+            len: ((self.block_size - block_offset) as usize).min(self.chunk_size),
+        }
+    }
+}
+
+/// Consumer end of the read pipeline, handing out reads in `ReadOrder`
+pub(super) struct Prefetch {
+    order: ReadOrder,
+    next: usize,
+    filled: Vec<Receiver<CreateResult<Vec<u8>>>>,
+    free: Vec<SyncSender<Vec<u8>>>,
+    /// Buffer handed out by the last `next`, returned to its reader on the next call
+    current: Option<(usize, Vec<u8>)>,
+}
+
+impl Prefetch {
+    /// Wait for the next read and return it with its data
+    pub fn next(&mut self) -> CreateResult<(ReadRequest, &[u8])> {
+        if let Some((reader, buffer)) = self.current.take() {
+            // A reader that already stopped has no use for the buffer
+            let _ = self.free[reader].send(buffer);
+        }
+
+        let index = self.next;
+        self.next += 1;
+        let reader = index % self.filled.len();
+        let buffer = self.filled[reader].recv().map_err(|_| {
+            CreateError::Other("source reader thread stopped unexpectedly".to_string())
+        })??;
+        let request = self.order.get(index);
+        let (_, buffer) = self.current.insert((reader, buffer));
+        Ok((request, &buffer[..request.len]))
+    }
+}
+
+/// Run `consume` while `reader_count` threads read ahead in `order`
+///
+/// Read `i` is served by reader `i % reader_count`, each reader working
+/// through its share in order with a fixed set of buffers, so at most
+/// `PREFETCH_BUFFERS` reads (or one per reader) are in flight.
+pub(super) fn with_prefetch<T>(
+    layout: &SourceLayout,
+    order: ReadOrder,
+    reader_count: usize,
+    consume: impl FnOnce(&mut Prefetch) -> CreateResult<T>,
+) -> CreateResult<T> {
+    let reader_count = reader_count.clamp(1, order.len().max(1));
+    let depth = (PREFETCH_BUFFERS / reader_count).max(1);
+    let buffer_len = order.chunk_size.min(order.block_size as usize);
+
+    std::thread::scope(|scope| {
+        let mut prefetch = Prefetch {
+            order,
+            next: 0,
+            filled: Vec::with_capacity(reader_count),
+            free: Vec::with_capacity(reader_count),
+            current: None,
+        };
+
+        for reader in 0..reader_count {
+            let (free_tx, free_rx) = sync_channel::<Vec<u8>>(depth);
+            let (filled_tx, filled_rx) = sync_channel(depth);
+            for _ in 0..depth {
+                free_tx
+                    .send(vec![0u8; buffer_len])
+                    .expect("free buffer channel has room for every buffer");
+            }
+            prefetch.free.push(free_tx);
+            prefetch.filled.push(filled_rx);
+
+            scope.spawn(move || {
+                let mut handles: Vec<Option<File>> =
+                    (0..layout.source_files.len()).map(|_| None).collect();
+                for index in (reader..order.len()).step_by(reader_count) {
+                    // The consumer hung up early, typically on an error
+                    let Ok(mut buffer) = free_rx.recv() else {
+                        return;
+                    };
+                    let request = order.get(index);
+                    let result = layout
+                        .read_into(&mut handles, request, &mut buffer[..request.len])
+                        .map(|()| buffer);
+                    let failed = result.is_err();
+                    if filled_tx.send(result).is_err() || failed {
+                        return;
+                    }
+                }
+            });
+        }
+
+        // Dropping `prefetch` on return disconnects the readers so the scope can join
+        consume(&mut prefetch)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(buffered: bool) -> ReadOrder {
+        ReadOrder {
+            source_count: 5,
+            first_block: 0,
+            group_blocks: 2,
+            block_size: 10,
+            chunk_size: 4,
+            buffered,
+        }
+    }
+
+    fn requests(order: ReadOrder) -> Vec<(usize, u64, usize)> {
+        (0..order.len())
+            .map(|i| order.get(i))
+            .map(|r| (r.source_idx, r.block_offset, r.len))
+            .collect()
+    }
+
+    #[test]
+    fn buffered_order_reads_each_block_front_to_back() {
+        let reads = requests(order(true));
+        assert_eq!(reads.len(), 15);
+        assert_eq!(&reads[..4], &[(0, 0, 4), (0, 4, 4), (0, 8, 2), (1, 0, 4)]);
+        assert_eq!(&reads[12..], &[(4, 0, 4), (4, 4, 4), (4, 8, 2)]);
+    }
+
+    #[test]
+    fn streamed_order_walks_chunk_offsets_across_the_group() {
+        let reads = requests(order(false));
+        assert_eq!(&reads[..4], &[(0, 0, 4), (1, 0, 4), (0, 4, 4), (1, 4, 4)]);
+        // The last group holds a single block
+        assert_eq!(&reads[12..], &[(4, 0, 4), (4, 4, 4), (4, 8, 2)]);
+    }
+
+    #[test]
+    fn resumed_order_starts_its_groups_at_the_first_block() {
+        let reads = requests(ReadOrder {
+            first_block: 1,
+            ..order(false)
+        });
+        assert_eq!(reads.len(), 12);
+        assert_eq!(&reads[..4], &[(1, 0, 4), (2, 0, 4), (1, 4, 4), (2, 4, 4)]);
+        assert_eq!(&reads[6..8], &[(3, 0, 4), (4, 0, 4)]);
+    }
+}
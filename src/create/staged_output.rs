@@ -0,0 +1,304 @@
+//! Crash-safe output for PAR2 creation
+//!
+//! Every output file is written under a temporary name next to its final
+//! path. Only once the whole set has been written and synced are the files
+//! renamed into place, so a failed or interrupted run never leaves a partial
+//! set under the real names. Temporaries of a failed run are removed when the
+//! `StagedOutputs` is dropped, and those left behind by a crash are swept up
+//! by the next run for the same set. Checkpointed runs keep their temporaries
+//! instead, so that a later run can resume them.
+
+use super::error::{CreateError, CreateResult};
+use super::error_helpers::{create_output_file, ensure_outputs_absent};
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+
+/// Suffix appended to the final name of a file being written
+pub const TEMP_SUFFIX: &str = ".par2rs-tmp";
+
+/// Suffix an existing output is moved aside to while a set is committed over it
+pub const BACKUP_SUFFIX: &str = ".par2rs-old";
+
+/// Temporary path a file is written to before being renamed to `final_path`
+pub fn temp_path(final_path: &Path) -> PathBuf {
+    let mut name = final_path.as_os_str().to_owned();
+    name.push(TEMP_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Whether `file_name` is a temporary of the set with base name `base_name`
+///
+/// Covers the index file, recovery volumes, external recovery data and the
+/// scratch file of a stream.
+fn is_set_temporary(file_name: &str, base_name: &str) -> bool {
+    let Some(final_name) = file_name.strip_suffix(TEMP_SUFFIX) else {
+        return false;
+    };
+    let Some(rest) = final_name
+        .strip_prefix(base_name)
+        .and_then(|rest| rest.strip_prefix('.'))
+    else {
+        return false;
+    };
+    rest == "par2"
+        || rest == "rfsc"
+        || rest == "stream"
+        || rest
+            .strip_prefix("vol")
+            .and_then(|volume| volume.strip_suffix(".par2"))
+            .is_some_and(|range| {
+                range.split_once('+').is_some_and(|(first, count)| {
+                    [first, count]
+                        .iter()
+                        .all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+                })
+            })
+}
+
+/// Remove temporaries left by an interrupted run for the set with this index file
+///
+/// Returns the paths removed.
+pub fn remove_stale_temporaries(index_path: &Path) -> CreateResult<Vec<PathBuf>> {
+    let output_dir = index_path.parent().unwrap_or_else(|| Path::new(""));
+    let Some(base_name) = index_path.file_stem().and_then(|stem| stem.to_str()) else {
+        return Ok(Vec::new());
+    };
+    let dir = if output_dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        output_dir
+    };
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(CreateError::FileReadError {
+                file: dir.to_string_lossy().to_string(),
+                source: e,
+            })
+        }
+    };
+
+    let mut removed = Vec::new();
+    for entry in entries.flatten() {
+        let is_temporary = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| is_set_temporary(name, base_name));
+        if is_temporary && entry.file_type().is_ok_and(|t| t.is_file()) {
+            let path = output_dir.join(entry.file_name());
+            std::fs::remove_file(&path).map_err(|e| CreateError::FileCreateError {
+                file: path.to_string_lossy().to_string(),
+                source: e,
+            })?;
+            removed.push(path);
+        }
+    }
+    removed.sort();
+    Ok(removed)
+}
+
+/// Output files written under temporary names until the set is committed
+#[derive(Default)]
+pub(super) struct StagedOutputs {
+    /// Temporary and final path of every file created so far
+    files: Vec<(PathBuf, PathBuf)>,
+    committed: bool,
+    /// Keep the temporaries of a failed run, for a checkpointed run to resume
+    keep_on_failure: bool,
+}
+
+impl StagedOutputs {
+    /// Outputs whose temporaries survive a failed run
+    pub fn resumable() -> Self {
+        StagedOutputs {
+            files: Vec::new(),
+            committed: false,
+            keep_on_failure: true,
+        }
+    }
+
+    /// Reopen the temporary file an interrupted run left for `final_path`
+    pub fn reopen(&mut self, final_path: &Path) -> CreateResult<File> {
+        let temp = temp_path(final_path);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&temp)
+            .map_err(|e| CreateError::FileReadError {
+                file: temp.to_string_lossy().to_string(),
+                source: e,
+            })?;
+        self.files.push((temp, final_path.to_path_buf()));
+        Ok(file)
+    }
+
+    /// Create the temporary file for `final_path`, open for reading and writing
+    pub fn create(&mut self, final_path: &Path) -> CreateResult<File> {
+        let temp = temp_path(final_path);
+        let file = create_output_file(&temp, true)?;
+        self.files.push((temp, final_path.to_path_buf()));
+        Ok(file)
+    }
+
+    /// Drop the temporary file for `final_path`, leaving the existing file alone
+    pub fn discard(&mut self, final_path: &Path) {
+        self.files.retain(|(temp, path)| {
+            let keep = path != final_path;
+            if !keep {
+                let _ = std::fs::remove_file(temp);
+            }
+            keep
+        });
+    }
+
+    /// Rename every file to its final name
+    ///
+    /// The files must already be synced. Unless `overwrite_existing` is set,
+    /// fails without renaming anything if a final path has appeared since the
+    /// run started. Files being overwritten are first moved aside and only
+    /// removed once every rename has succeeded. Should a rename fail, the files
+    /// already moved are removed again and the moved-aside files restored, so
+    /// that neither a partial set nor a half-replaced one is left behind.
+    pub fn commit(mut self, overwrite_existing: bool) -> CreateResult<()> {
+        let rename_error = |path: &Path, e| CreateError::FileCreateError {
+            file: path.to_string_lossy().to_string(),
+            source: e,
+        };
+
+        let mut backups = Vec::new();
+        if overwrite_existing {
+            for (_, path) in &self.files {
+                if !path.is_file() {
+                    continue;
+                }
+                let backup = backup_path(path);
+                if let Err(e) = std::fs::rename(path, &backup) {
+                    restore_backups(&backups);
+                    return Err(rename_error(path, e));
+                }
+                backups.push((backup, path.clone()));
+            }
+        } else {
+            ensure_outputs_absent(self.files.iter().map(|(_, path)| path.as_path()))?;
+        }
+
+        for (index, (temp, path)) in self.files.iter().enumerate() {
+            if let Err(e) = std::fs::rename(temp, path) {
+                for (_, renamed) in &self.files[..index] {
+                    let _ = std::fs::remove_file(renamed);
+                }
+                restore_backups(&backups);
+                return Err(rename_error(path, e));
+            }
+        }
+        self.committed = true;
+        for (backup, _) in &backups {
+            let _ = std::fs::remove_file(backup);
+        }
+
+        // Make the renames themselves durable
+        #[cfg(unix)]
+        {
+            let mut dirs: Vec<&Path> = self
+                .files
+                .iter()
+                .filter_map(|(_, path)| path.parent())
+                .map(|dir| {
+                    if dir.as_os_str().is_empty() {
+                        Path::new(".")
+                    } else {
+                        dir
+                    }
+                })
+                .collect();
+            dirs.dedup();
+            for dir in dirs {
+                File::open(dir)
+                    .and_then(|dir| dir.sync_all())
+                    .map_err(|e| CreateError::FileCreateError {
+                        file: dir.to_string_lossy().to_string(),
+                        source: e,
+                    })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Path an existing output is moved to while it is being overwritten
+fn backup_path(final_path: &Path) -> PathBuf {
+    let mut name = final_path.as_os_str().to_owned();
+    name.push(BACKUP_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Move outputs set aside by [`StagedOutputs::commit`] back to their own names
+fn restore_backups(backups: &[(PathBuf, PathBuf)]) {
+    for (backup, path) in backups {
+        let _ = std::fs::rename(backup, path);
+    }
+}
+
+impl Drop for StagedOutputs {
+    fn drop(&mut self) {
+        if !self.committed && !self.keep_on_failure {
+            for (temp, _) in &self.files {
+                let _ = std::fs::remove_file(temp);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::tempdir;
+
+    #[test]
+    fn temporaries_are_recognized_by_set_name() {
+        assert!(is_set_temporary("data.par2.par2rs-tmp", "data"));
+        assert!(is_set_temporary("data.rfsc.par2rs-tmp", "data"));
+        assert!(is_set_temporary("data.stream.par2rs-tmp", "data"));
+        assert!(is_set_temporary("data.vol03+04.par2.par2rs-tmp", "data"));
+        assert!(!is_set_temporary("data.par2", "data"));
+        assert!(!is_set_temporary("database.par2.par2rs-tmp", "data"));
+        assert!(!is_set_temporary("data.vol03.par2.par2rs-tmp", "data"));
+        assert!(!is_set_temporary("data.notes.par2rs-tmp", "data"));
+    }
+
+    #[test]
+    fn commit_renames_and_drop_removes() {
+        let temp = tempdir().unwrap();
+        let kept = temp.path().join("kept.par2");
+        let dropped = temp.path().join("dropped.par2");
+
+        let mut staged = StagedOutputs::default();
+        staged.create(&kept).unwrap().write_all(b"kept").unwrap();
+        assert!(!kept.exists());
+        staged.commit(false).unwrap();
+        assert_eq!(std::fs::read(&kept).unwrap(), b"kept");
+        assert!(!temp_path(&kept).exists());
+
+        let mut staged = StagedOutputs::default();
+        staged.create(&dropped).unwrap();
+        assert!(temp_path(&dropped).exists());
+        drop(staged);
+        assert!(!temp_path(&dropped).exists());
+        assert!(!dropped.exists());
+    }
+
+    #[test]
+    fn commit_refuses_an_output_that_appeared_meanwhile() {
+        let temp = tempdir().unwrap();
+        let path = temp.path().join("data.par2");
+
+        let mut staged = StagedOutputs::default();
+        staged.create(&path).unwrap();
+        std::fs::write(&path, b"other").unwrap();
+        assert!(staged.commit(false).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"other");
+        assert!(!temp_path(&path).exists());
+    }
+}
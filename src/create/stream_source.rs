@@ -0,0 +1,300 @@
+//! Source data from a stream, such as standard input
+//!
+//! A stream can be read only once, front to back, and its length is unknown
+//! until it ends. Up to half the memory limit is read ahead when the context
+//! is built: a stream that ends within it is sized like any file, so the block
+//! size and recovery block count are picked as usual, while a longer one needs
+//! both given up front. Either way the data is then hashed, encoded and
+//! optionally copied to a tee file in a single forward pass, a group of blocks
+//! at a time. The recovery data builds up in a scratch file, since the layout
+//! of the output files is only known once the stream ends.
+
+use super::context::FileHashState;
+use super::error::{CreateError, CreateResult};
+use super::progress::CreateReporter;
+use super::recovery_output::RecoveryOutput;
+use std::fs::File;
+use std::io::{Cursor, Read, Write};
+
+/// Most source blocks a recovery set can hold
+pub(super) const MAX_STREAM_BLOCKS: usize = 32768;
+
+/// Bytes of a stream read ahead when the context is built
+///
+/// The read-ahead is held until encoding catches up with it, so encoding
+/// gets the other half of the memory limit.
+pub(super) fn read_ahead_limit(memory_limit: usize) -> usize {
+    memory_limit / 2
+}
+
+/// A stream source and the data read ahead from it
+pub(super) struct StreamSource {
+    name: String,
+    reader: Box<dyn Read + Send>,
+    /// Data read when the context was built
+    head: Vec<u8>,
+    /// Whether `head` holds the whole stream
+    complete: bool,
+}
+
+impl StreamSource {
+    /// Read up to `limit` bytes of `reader` ahead, to learn whether it fits
+    pub fn read_ahead(
+        name: String,
+        mut reader: Box<dyn Read + Send>,
+        limit: usize,
+    ) -> CreateResult<Self> {
+        let mut head = Vec::new();
+        // One byte past the limit tells a stream of exactly `limit` bytes apart
+        reader
+            .by_ref()
+            .take(limit as u64 + 1)
+            .read_to_end(&mut head)
+            .map_err(|e| CreateError::FileReadError {
+                file: name.clone(),
+                source: e,
+            })?;
+        let complete = head.len() <= limit;
+        Ok(StreamSource {
+            name,
+            reader,
+            head,
+            complete,
+        })
+    }
+
+    /// PAR2 packet name of the stream
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Length of the stream, if it ended within the read-ahead
+    pub fn known_size(&self) -> Option<u64> {
+        self.complete.then_some(self.head.len() as u64)
+    }
+
+    /// Bytes read ahead so far
+    pub fn head_len(&self) -> u64 {
+        self.head.len() as u64
+    }
+}
+
+/// Hashes of a stream read to its end
+pub(super) struct StreamEncoding {
+    pub size: u64,
+    pub hash_state: FileHashState,
+}
+
+/// Read as much of `block` as the stream still holds
+fn read_block(reader: &mut impl Read, block: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < block.len() {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Hash, encode and tee a stream in one forward pass
+///
+/// Blocks are buffered `group_blocks` at a time and each group is added,
+/// `chunk_size` bytes of every block at a time, onto the recovery data the
+/// groups before it left in `recovery`. Only one group and one chunk of each
+/// recovery block are ever held in memory.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn encode_and_hash_stream(
+    stream: StreamSource,
+    mut tee: Option<(&std::path::Path, File)>,
+    recovery: &mut RecoveryOutput,
+    block_size: usize,
+    chunk_size: usize,
+    group_blocks: usize,
+    first_recovery_block: u32,
+    recovery_count: usize,
+    thread_count: usize,
+    reporter: &dyn CreateReporter,
+) -> CreateResult<StreamEncoding> {
+    use super::backend::CreateRecoveryBackend;
+    use super::source_file::BlockChecksum;
+    use crate::checksum::{compute_block_checksums, compute_file_id};
+    use crate::reed_solomon::RecoveryBlockEncoder;
+    use md5::{Digest, Md5};
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(thread_count)
+        .build()
+        .map_err(|err| CreateError::Other(format!("failed to create thread pool: {err}")))?;
+
+    let StreamSource {
+        name, reader, head, ..
+    } = stream;
+    let read_error = |e| CreateError::FileReadError {
+        file: name.clone(),
+        source: e,
+    };
+    let mut reader = Cursor::new(head).chain(reader);
+    let encoder = RecoveryBlockEncoder::new(block_size, MAX_STREAM_BLOCKS);
+    let group_blocks = group_blocks.clamp(1, MAX_STREAM_BLOCKS);
+    let chunk_ranges: Vec<(u64, usize)> = (0..block_size)
+        .step_by(chunk_size.clamp(1, block_size))
+        .map(|offset| (offset as u64, (block_size - offset).min(chunk_size)))
+        .collect();
+
+    let mut size = 0u64;
+    let mut file_md5 = Md5::new();
+    let mut first_16k = Vec::with_capacity(16 * 1024);
+    let mut block_checksums = Vec::new();
+    let mut group_buffer = vec![0u8; group_blocks * block_size];
+
+    let mut ended = false;
+    while !ended {
+        let group_start = block_checksums.len();
+        let mut group_len = 0;
+        while group_len < group_blocks {
+            let block = &mut group_buffer[group_len * block_size..][..block_size];
+            let len = read_block(&mut reader, block).map_err(read_error)?;
+            if len == 0 {
+                ended = true;
+                break;
+            }
+            if group_start + group_len == MAX_STREAM_BLOCKS {
+                return Err(CreateError::Other(format!(
+                    "{name} needs more than {MAX_STREAM_BLOCKS} blocks of {block_size} bytes; use a larger block size"
+                )));
+            }
+            block[len..].fill(0);
+
+            if let Some((path, file)) = &mut tee {
+                file.write_all(&block[..len])
+                    .map_err(|e| CreateError::FileCreateError {
+                        file: path.to_string_lossy().to_string(),
+                        source: e,
+                    })?;
+            }
+            size += len as u64;
+            file_md5.update(&block[..len]);
+            let capture = len.min(16 * 1024 - first_16k.len());
+            first_16k.extend_from_slice(&block[..capture]);
+
+            let (hash, crc32) = compute_block_checksums(block);
+            block_checksums.push(BlockChecksum {
+                crc32: crc32.as_u32(),
+                hash,
+                global_index: (group_start + group_len) as u32,
+            });
+            group_len += 1;
+            if len < block_size {
+                ended = true;
+                break;
+            }
+        }
+        // The read-ahead is no longer needed once the pass has caught up with it
+        let (head, _) = reader.get_mut();
+        if head.position() >= head.get_ref().len() as u64 {
+            *head = Cursor::new(Vec::new());
+        }
+        if group_len == 0 || recovery_count == 0 {
+            continue;
+        }
+
+        // Coefficients are prepared for this group's blocks only
+        let group = group_start..group_start + group_len;
+        pool.install(|| {
+            let mut backend = CreateRecoveryBackend::new(
+                &encoder.base_values()[group],
+                first_recovery_block,
+                recovery_count,
+                chunk_ranges[0].1,
+            );
+            for &(block_offset, chunk_len) in &chunk_ranges {
+                if group_start == 0 {
+                    backend.begin_chunk(chunk_len);
+                } else {
+                    // Carry on from the recovery data left by earlier groups
+                    backend.begin_chunk_with(chunk_len, |recovery_idx, data| {
+                        recovery.read_chunk(recovery_idx, block_offset, data)
+                    })?;
+                }
+                for slot in 0..group_len {
+                    let start = slot * block_size + block_offset as usize;
+                    backend.add_input(slot, &group_buffer[start..start + chunk_len]);
+                }
+                backend.finish_chunk_with(|recovery_idx, data| {
+                    recovery.write_chunk(recovery_idx, block_offset, data)
+                })?;
+            }
+            Ok::<_, CreateError>(())
+        })?;
+    }
+
+    if let Some((path, file)) = &mut tee {
+        file.sync_all().map_err(|e| CreateError::FileCreateError {
+            file: path.to_string_lossy().to_string(),
+            source: e,
+        })?;
+    }
+    if size == 0 {
+        return Err(CreateError::EmptySourceFiles);
+    }
+
+    let hash_16k = crate::domain::Md5Hash::new(Md5::digest(&first_16k).into());
+    reporter.report_file_hashing(&name, size, size);
+    Ok(StreamEncoding {
+        size,
+        hash_state: FileHashState {
+            hash_16k,
+            full_md5: crate::domain::Md5Hash::new(file_md5.finalize().into()),
+            file_id: compute_file_id(&hash_16k, size, name.as_bytes()),
+            block_count: block_checksums.len() as u32,
+            global_block_offset: 0,
+            block_checksums,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reader that hands out at most a few bytes per call
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(3);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn read_ahead_tells_whether_the_stream_fits() {
+        let data = (0..100u8).collect::<Vec<_>>();
+        let fits = StreamSource::read_ahead(
+            "fits".to_string(),
+            Box::new(Trickle(Cursor::new(data.clone()))),
+            100,
+        )
+        .unwrap();
+        assert_eq!(fits.known_size(), Some(100));
+
+        let overflows =
+            StreamSource::read_ahead("overflows".to_string(), Box::new(Cursor::new(data)), 99)
+                .unwrap();
+        assert_eq!(overflows.known_size(), None);
+        assert_eq!(overflows.head_len(), 100);
+    }
+
+    #[test]
+    fn read_block_fills_across_short_reads() {
+        let mut reader = Trickle(Cursor::new((0..10u8).collect()));
+        let mut block = [0u8; 8];
+        assert_eq!(read_block(&mut reader, &mut block).unwrap(), 8);
+        assert_eq!(read_block(&mut reader, &mut block).unwrap(), 2);
+        assert_eq!(&block[..2], &[8, 9]);
+        assert_eq!(read_block(&mut reader, &mut block).unwrap(), 0);
+    }
+}
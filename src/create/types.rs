@@ -21,6 +21,20 @@ pub enum RecoveryFileScheme {
     /// Create limited number of recovery files
     /// Distributes blocks as evenly as possible across specified file count
     Limited,
+
+    /// Create as many uniform recovery files as needed so that none exceeds
+    /// this many bytes, counting the critical packets repeated in each
+    MaxFileSize(u64),
+}
+
+/// Application-specific packet to write alongside the critical packets
+///
+/// The recovery set ID is filled in at creation time. The body must be a
+/// multiple of 4 bytes, and the type must not use the reserved `PAR 2.0\0` prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomPacket {
+    pub packet_type: [u8; 16],
+    pub body: Vec<u8>,
 }
 
 /// Configuration for PAR2 creation
@@ -32,6 +46,10 @@ pub struct CreateConfig {
     /// List of source files to protect
     pub source_files: Vec<PathBuf>,
 
+    /// Files in the non-recovery set: hashed and described (FileDesc + IFSC)
+    /// so verify can check them, but not covered by recovery data
+    pub non_recovery_files: Vec<PathBuf>,
+
     /// Base path used to derive packet names stored in PAR2 metadata
     pub base_path: Option<PathBuf>,
 
@@ -44,6 +62,17 @@ pub struct CreateConfig {
     /// If block_size is set, this is ignored. If neither is set, defaults to 2000.
     pub source_block_count: Option<SourceBlockCount>,
 
+    /// Subslice size in bytes for packed sets (PkdMain + PkdRecvS)
+    ///
+    /// When set, files are padded only to whole subslices and packed back to back,
+    /// so one block can span several small files. The block size must be a
+    /// multiple of the subslice size.
+    pub subslice_size: Option<u64>,
+
+    /// Search for the block size that minimizes padding plus recovery data
+    /// instead of aiming for a fixed block count
+    pub optimize_block_size: bool,
+
     /// Number of recovery blocks to create (if None, calculated from redundancy_percentage)
     pub recovery_block_count: Option<u32>,
 
@@ -67,7 +96,7 @@ pub struct CreateConfig {
     /// Number of threads for computation (0 = auto-detect)
     pub thread_count: u32,
 
-    /// Number of file-level worker threads.
+    /// Number of threads reading source data ahead of the encoder (default 1)
     pub file_thread_count: Option<usize>,
 
     /// Allow existing output PAR2 files to be overwritten.
@@ -78,6 +107,48 @@ pub struct CreateConfig {
     /// First recovery block exponent (typically 0)
     /// Advanced option for compatibility
     pub first_recovery_block: u32,
+
+    /// Emit Unicode Filename (UniFileN) packets for packet names that are not plain ASCII
+    pub unicode_filenames: bool,
+
+    /// Comments written as CommASCI + CommUni packet pairs into every output file
+    pub comments: Vec<String>,
+
+    /// Embed the source data in FileSlic packets so small files can be restored
+    /// by copying them out of the PAR2 files
+    pub embed_source_data: bool,
+
+    /// Write the recovery slices to a headerless `.rfsc` file described by an
+    /// RFSC packet in the index file, instead of into recovery volumes
+    pub external_recovery_data: bool,
+
+    /// Copies of the critical packets spread through each recovery volume
+    ///
+    /// When None, every volume gets one copy per bit of its recovery block
+    /// count, so larger volumes carry more copies.
+    pub critical_copies: Option<u32>,
+
+    /// Application-specific packets written into every output file
+    pub custom_packets: Vec<CustomPacket>,
+
+    /// Hash cache file; unchanged source files reuse the hashes stored there
+    /// instead of being hashed again
+    pub hash_cache: Option<PathBuf>,
+
+    /// Checkpoint file recording encoding progress, so an interrupted run
+    /// can be resumed; its output files are kept under their temporary names
+    pub checkpoint: Option<PathBuf>,
+
+    /// Continue from the checkpoint instead of starting over
+    pub resume: bool,
+
+    /// PAR2 file name of a stream source, read in place of source files
+    ///
+    /// The stream itself is handed to `CreateContextBuilder::stream_source`.
+    pub stream_name: Option<String>,
+
+    /// File that receives a copy of the stream data as it is read
+    pub stream_tee: Option<PathBuf>,
 }
 
 impl Default for CreateConfig {
@@ -85,9 +156,12 @@ impl Default for CreateConfig {
         CreateConfig {
             output_name: String::new(),
             source_files: Vec::new(),
+            non_recovery_files: Vec::new(),
             base_path: None,
             block_size: None,
             source_block_count: None,
+            subslice_size: None,
+            optimize_block_size: false,
             recovery_block_count: None,
             redundancy_percentage: Some(5), // 5% is typical default
             recovery_target_size: None,
@@ -98,6 +172,17 @@ impl Default for CreateConfig {
             file_thread_count: None,
             overwrite_existing: false,
             first_recovery_block: 0,
+            unicode_filenames: false,
+            comments: Vec::new(),
+            embed_source_data: false,
+            external_recovery_data: false,
+            critical_copies: None,
+            custom_packets: Vec::new(),
+            hash_cache: None,
+            checkpoint: None,
+            resume: false,
+            stream_name: None,
+            stream_tee: None,
         }
     }
 }
@@ -111,7 +196,7 @@ impl CreateConfig {
             return Err(CreateError::Other("Output name not specified".to_string()));
         }
 
-        if self.source_files.is_empty() {
+        if self.source_files.is_empty() && self.stream_name.is_none() {
             return Err(CreateError::NoSourceFiles);
         }
 
@@ -131,6 +216,32 @@ impl CreateConfig {
             }
         }
 
+        if let Some(subslice_size) = self.subslice_size {
+            if subslice_size < 1 || subslice_size % 4 != 0 {
+                return Err(CreateError::InvalidBlockSize(
+                    "Subslice size must be a multiple of 4".to_string(),
+                ));
+            }
+            if self
+                .block_size
+                .is_some_and(|block_size| block_size % subslice_size != 0)
+            {
+                return Err(CreateError::InvalidBlockSize(
+                    "Block size must be a multiple of the subslice size".to_string(),
+                ));
+            }
+        }
+
+        if self.optimize_block_size
+            && (self.block_size.is_some()
+                || self.source_block_count.is_some()
+                || self.subslice_size.is_some())
+        {
+            return Err(CreateError::InvalidBlockSize(
+                "Block size optimization cannot be combined with an explicit block size, block count or subslice size".to_string(),
+            ));
+        }
+
         if let Some(count) = self.source_block_count {
             if count.as_u32() == 0 || count.as_u32() > 32768 {
                 return Err(CreateError::InvalidBlockCount(
@@ -147,6 +258,25 @@ impl CreateConfig {
             }
         }
 
+        if let RecoveryFileScheme::MaxFileSize(max_file_size) = self.recovery_file_scheme {
+            if max_file_size == 0 {
+                return Err(CreateError::InvalidRecoveryFileCount(
+                    "Maximum recovery file size must be greater than 0".to_string(),
+                ));
+            }
+            if self.recovery_file_count.is_some() {
+                return Err(CreateError::InvalidRecoveryFileCount(
+                    "Recovery file count cannot be set with a maximum recovery file size"
+                        .to_string(),
+                ));
+            }
+            if self.external_recovery_data || self.embed_source_data {
+                return Err(CreateError::Other(
+                    "A maximum recovery file size cannot be combined with external recovery data or embedded source data".to_string(),
+                ));
+            }
+        }
+
         if let Some(count) = self.recovery_file_count {
             if !(1..=31).contains(&count) {
                 return Err(CreateError::InvalidRecoveryFileCount(
@@ -160,6 +290,61 @@ impl CreateConfig {
             }
         }
 
+        if self.critical_copies == Some(0) {
+            return Err(CreateError::Other(
+                "Critical packet copies must be at least 1".to_string(),
+            ));
+        }
+
+        if self.resume && self.checkpoint.is_none() {
+            return Err(CreateError::Other(
+                "Resuming requires a checkpoint file".to_string(),
+            ));
+        }
+
+        if let Some(name) = &self.stream_name {
+            if name.is_empty() {
+                return Err(CreateError::Other(
+                    "A stream source needs a file name".to_string(),
+                ));
+            }
+            if !self.source_files.is_empty() || !self.non_recovery_files.is_empty() {
+                return Err(CreateError::Other(
+                    "A stream source cannot be combined with other source files".to_string(),
+                ));
+            }
+            if self.subslice_size.is_some()
+                || self.embed_source_data
+                || self.checkpoint.is_some()
+                || self.hash_cache.is_some()
+            {
+                return Err(CreateError::Other(
+                    "A stream source cannot be combined with packed blocks, embedded source data, a checkpoint or a hash cache".to_string(),
+                ));
+            }
+        } else if self.stream_tee.is_some() {
+            return Err(CreateError::Other(
+                "Only a stream source can be copied to a tee file".to_string(),
+            ));
+        }
+
+        if self.comments.iter().any(|comment| comment.is_empty()) {
+            return Err(CreateError::Other("Comments must not be empty".to_string()));
+        }
+
+        for packet in &self.custom_packets {
+            if packet.packet_type.starts_with(b"PAR 2.0\0") {
+                return Err(CreateError::Other(
+                    "Custom packet types must not use the PAR 2.0 prefix".to_string(),
+                ));
+            }
+            if !packet.body.len().is_multiple_of(4) {
+                return Err(CreateError::Other(
+                    "Custom packet body must be a multiple of 4 bytes".to_string(),
+                ));
+            }
+        }
+
         if self.first_recovery_block > 32768 {
             return Err(CreateError::InvalidFirstRecoveryBlock(
                 "First recovery block must be at most 32768".to_string(),
@@ -201,11 +386,55 @@ mod tests {
         let c = CreateConfig::default();
         assert!(c.output_name.is_empty());
         assert!(c.source_files.is_empty());
+        assert!(c.non_recovery_files.is_empty());
         assert_eq!(c.redundancy_percentage, Some(5));
         assert_eq!(c.thread_count, 0);
         assert!(!c.overwrite_existing);
         assert_eq!(c.first_recovery_block, 0);
         assert_eq!(c.recovery_file_scheme, RecoveryFileScheme::Variable);
+        assert!(!c.unicode_filenames);
+        assert!(c.comments.is_empty());
+        assert_eq!(c.subslice_size, None);
+        assert!(!c.optimize_block_size);
+        assert!(!c.embed_source_data);
+        assert!(!c.external_recovery_data);
+        assert_eq!(c.critical_copies, None);
+        assert!(c.custom_packets.is_empty());
+        assert_eq!(c.hash_cache, None);
+        assert_eq!(c.checkpoint, None);
+        assert!(!c.resume);
+        assert_eq!(c.stream_name, None);
+        assert_eq!(c.stream_tee, None);
+    }
+
+    #[test]
+    fn validate_rejects_bad_custom_packets() {
+        let reserved = CreateConfig {
+            custom_packets: vec![CustomPacket {
+                packet_type: *b"PAR 2.0\0Anything",
+                body: Vec::new(),
+            }],
+            ..valid_config()
+        };
+        assert!(reserved.validate().is_err());
+
+        let unaligned = CreateConfig {
+            custom_packets: vec![CustomPacket {
+                packet_type: *b"ACME\0\0\0\0IngestID",
+                body: vec![1, 2, 3],
+            }],
+            ..valid_config()
+        };
+        assert!(unaligned.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_empty_comment() {
+        let c = CreateConfig {
+            comments: vec![String::new()],
+            ..valid_config()
+        };
+        assert!(c.validate().is_err());
     }
 
     // --- validate() ---
@@ -283,6 +512,143 @@ mod tests {
         assert!(c.validate().is_ok());
     }
 
+    #[test]
+    fn validate_rejects_subslice_size_not_multiple_of_4() {
+        let c = CreateConfig {
+            subslice_size: Some(510),
+            ..valid_config()
+        };
+        assert!(c.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_block_size_not_multiple_of_subslice_size() {
+        let c = CreateConfig {
+            block_size: Some(4096),
+            subslice_size: Some(1536),
+            ..valid_config()
+        };
+        assert!(c.validate().is_err());
+    }
+
+    #[test]
+    fn validate_accepts_packed_block_size() {
+        let c = CreateConfig {
+            block_size: Some(4096),
+            subslice_size: Some(512),
+            ..valid_config()
+        };
+        assert!(c.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_optimized_block_size_with_explicit_layout() {
+        let optimized = CreateConfig {
+            optimize_block_size: true,
+            ..valid_config()
+        };
+        assert!(optimized.validate().is_ok());
+
+        for c in [
+            CreateConfig {
+                block_size: Some(4096),
+                ..optimized.clone()
+            },
+            CreateConfig {
+                source_block_count: Some(SourceBlockCount::new(100)),
+                ..optimized.clone()
+            },
+            CreateConfig {
+                subslice_size: Some(512),
+                ..optimized.clone()
+            },
+        ] {
+            assert!(c.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn validate_rejects_conflicting_max_file_size() {
+        let capped = CreateConfig {
+            recovery_file_scheme: RecoveryFileScheme::MaxFileSize(700 * 1024 * 1024),
+            ..valid_config()
+        };
+        assert!(capped.validate().is_ok());
+
+        for c in [
+            CreateConfig {
+                recovery_file_scheme: RecoveryFileScheme::MaxFileSize(0),
+                ..valid_config()
+            },
+            CreateConfig {
+                recovery_file_count: Some(4),
+                ..capped.clone()
+            },
+            CreateConfig {
+                external_recovery_data: true,
+                ..capped.clone()
+            },
+            CreateConfig {
+                embed_source_data: true,
+                ..capped.clone()
+            },
+        ] {
+            assert!(c.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn validate_rejects_zero_critical_copies() {
+        let c = CreateConfig {
+            critical_copies: Some(0),
+            ..valid_config()
+        };
+        assert!(c.validate().is_err());
+    }
+
+    #[test]
+    fn validate_requires_a_checkpoint_to_resume() {
+        let c = CreateConfig {
+            resume: true,
+            ..valid_config()
+        };
+        assert!(c.validate().is_err());
+        let c = CreateConfig {
+            checkpoint: Some(PathBuf::from("out.par2.par2rs-checkpoint")),
+            ..c
+        };
+        assert!(c.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_keeps_a_stream_source_on_its_own() {
+        let stream = CreateConfig {
+            source_files: Vec::new(),
+            stream_name: Some("dump.sql".to_string()),
+            stream_tee: Some(PathBuf::from("dump.sql")),
+            ..valid_config()
+        };
+        assert!(stream.validate().is_ok());
+        assert!(CreateConfig {
+            source_files: vec![PathBuf::from("a.bin")],
+            ..stream.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(CreateConfig {
+            embed_source_data: true,
+            ..stream.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(CreateConfig {
+            stream_name: None,
+            ..stream
+        }
+        .validate()
+        .is_err());
+    }
+
     #[test]
     fn validate_rejects_source_block_count_zero() {
         let c = CreateConfig {
@@ -0,0 +1,806 @@
+//! Updating a PAR2 set in place after its source files changed
+//!
+//! Recovery data is linear in the source blocks, so a changed block can be
+//! folded into every recovery block as `parity += coeff * (new - old)` without
+//! re-encoding the rest of the data. Changed blocks are found by looking every
+//! block of every file up in the global block table built from the IFSC
+//! packets. The old contents of a changed block come from an unchanged block
+//! with the same checksums, from an embedded FileSlic packet or, failing both,
+//! from the recovery data itself, which costs one more pass over the source
+//! files and one intact recovery block per block to recover.
+//!
+//! Every packet keeps its size, so each PAR2 file is patched packet by packet
+//! in a staged copy: FileDesc, IFSC, Main and FileSlic packets get the new
+//! hashes, file IDs and data, and when a file ID changes every packet of the
+//! set gets the new recovery set ID. The copies replace the originals only
+//! once all of them are written, as create does with its outputs. Blocks that
+//! do not match the set are taken to be intended changes, so damaged files
+//! must be repaired rather than updated.
+//!
+//! Files in the non-recovery set are rehashed too. They add nothing to the
+//! recovery data, so only their FileDesc and IFSC packets and their file IDs
+//! are rewritten.
+//!
+//! Packed sets and sets with external recovery data are not supported.
+
+use super::context::RECOVERY_PACKET_TYPE;
+use super::error::{CreateError, CreateResult};
+use super::error_helpers::open_for_reading;
+use super::extend::{non_recovery_set_files, recovery_set_files};
+use super::packet_generator::recovery_set_id_from_file_ids;
+use super::progress::CreateReporter;
+use super::source_file::SourceFileInfo;
+use super::staged_output::{temp_path, StagedOutputs};
+use crate::domain::{Crc32Value, FileId, Md5Hash, RecoverySetId};
+use crate::packets::processing::extract_slice_layout;
+use crate::packets::{self, Packet};
+use crate::reed_solomon::codec::process_slice_multiply_add;
+use crate::reed_solomon::{build_split_mul_table, Galois16, RecoveryBlockEncoder};
+use crate::verify::GlobalBlockTable;
+use md5::Digest;
+use rayon::prelude::*;
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// Bytes covered by the 16k MD5 of a FileDesc packet
+const HASH_16K_SIZE: u64 = 16 * 1024;
+
+/// Outcome of [`update_recovery_set`]
+#[derive(Debug, Clone, Default)]
+pub struct UpdateSummary {
+    /// Source blocks whose contents changed
+    pub changed_blocks: usize,
+    /// Changed blocks whose old contents were rebuilt from recovery data
+    pub recovered_blocks: usize,
+    /// Recovery packets the changes were folded into
+    pub updated_recovery_packets: usize,
+    /// Non-recovery files whose hashes were rewritten
+    pub changed_non_recovery_files: usize,
+    /// PAR2 files that were rewritten
+    pub updated_files: Vec<String>,
+}
+
+/// Global block index and `new XOR old` contents of a changed block
+type BlockDelta = (usize, Vec<u8>);
+
+/// A source block that no longer matches its IFSC checksums
+struct ChangedBlock {
+    file: usize,
+    block_number: u32,
+    global_index: usize,
+    old_checksums: (Md5Hash, Crc32Value),
+    /// Current contents, zero padded to the block size
+    new_data: Vec<u8>,
+}
+
+/// New hashes of a source file, computed while looking for changed blocks
+struct ScannedFile {
+    hash: Md5Hash,
+    hash_16k: Md5Hash,
+    block_checksums: Vec<(Md5Hash, Crc32Value)>,
+    /// Changed blocks, with their new contents when asked to keep them
+    changed: Vec<(u32, Vec<u8>)>,
+}
+
+/// Everything the packets of a changed file need rewritten
+struct FileUpdate<'a> {
+    source: &'a SourceFileInfo,
+    file_id: FileId,
+    hash: Md5Hash,
+    hash_16k: Md5Hash,
+    block_checksums: Vec<(Md5Hash, Crc32Value)>,
+    changed_blocks: Vec<u32>,
+}
+
+/// Fold changes made to the source files of `par2_file`'s set into the set
+///
+/// Files, including those of the non-recovery set, must keep their sizes. Changed blocks are held in memory while the
+/// recovery packets are updated. The rewritten PAR2 files are staged and
+/// committed together, so a failed update leaves the old set untouched.
+pub fn update_recovery_set(
+    par2_file: &Path,
+    reporter: &dyn CreateReporter,
+) -> CreateResult<UpdateSummary> {
+    let par2_files = crate::par2_files::collect_par2_files(par2_file);
+    let packet_set = crate::par2_files::load_par2_packets(&par2_files, false, false);
+    let layout = extract_slice_layout(&packet_set.packets).ok_or_else(|| {
+        CreateError::Other(format!(
+            "{}: no main packet found, cannot update",
+            par2_file.display()
+        ))
+    })?;
+    if layout.subslice_size.is_some() {
+        return Err(CreateError::Other(
+            "packed recovery sets cannot be updated in place".to_string(),
+        ));
+    }
+    let set_id = layout.set_id;
+    let packets: Vec<Packet> = packet_set
+        .packets
+        .into_iter()
+        .filter(|packet| packet.set_id() == set_id)
+        .collect();
+    if packets
+        .iter()
+        .any(|packet| matches!(packet, Packet::RecoveryFileSliceChecksum(_)))
+    {
+        return Err(CreateError::Other(
+            "sets with external recovery data cannot be updated in place".to_string(),
+        ));
+    }
+    let non_recovery_file_ids = packets
+        .iter()
+        .find_map(|packet| match packet {
+            Packet::Main(main) => Some(main.non_recovery_file_ids.clone()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let source_files = recovery_set_files(&packets, layout.file_ids.len(), &packet_set.base_dir)?;
+    let non_recovery_files = non_recovery_set_files(&packets, &packet_set.base_dir)?;
+    let block_size = layout.slice_size;
+    let expected = expected_block_checksums(&packets, &source_files, block_size)?;
+    let table = build_block_table(&source_files, &expected, block_size);
+    let non_recovery_expected =
+        expected_block_checksums(&packets, &non_recovery_files, block_size)?;
+    let non_recovery_table =
+        build_block_table(&non_recovery_files, &non_recovery_expected, block_size);
+
+    let started = std::time::Instant::now();
+    let scanned = source_files
+        .par_iter()
+        .zip(&expected)
+        .map(|(file, expected)| scan_file(file, expected, &table, block_size, true, reporter))
+        .collect::<CreateResult<Vec<_>>>()?;
+    let non_recovery_scanned = non_recovery_files
+        .par_iter()
+        .zip(&non_recovery_expected)
+        .map(|(file, expected)| {
+            scan_file(
+                file,
+                expected,
+                &non_recovery_table,
+                block_size,
+                false,
+                reporter,
+            )
+        })
+        .collect::<CreateResult<Vec<_>>>()?;
+
+    let total_bytes = source_files
+        .iter()
+        .chain(&non_recovery_files)
+        .map(|file| file.size)
+        .sum();
+    reporter.report_read_throughput(total_bytes, total_bytes, started.elapsed());
+
+    let mut global_offsets = Vec::with_capacity(source_files.len());
+    let mut source_block_count = 0usize;
+    for checksums in &expected {
+        global_offsets.push(source_block_count);
+        source_block_count += checksums.len();
+    }
+
+    let mut updates: HashMap<FileId, FileUpdate> = HashMap::default();
+    let mut changed = Vec::new();
+    for (file, scan) in scanned.into_iter().enumerate() {
+        if scan.changed.is_empty() {
+            continue;
+        }
+        let source = &source_files[file];
+        updates.insert(source.file_id, file_update(source, &scan, &packets));
+        for (block_number, new_data) in scan.changed {
+            changed.push(ChangedBlock {
+                file,
+                block_number,
+                global_index: global_offsets[file] + block_number as usize,
+                old_checksums: expected[file][block_number as usize],
+                new_data,
+            });
+        }
+    }
+    let mut changed_non_recovery_files = 0;
+    for (source, scan) in non_recovery_files.iter().zip(non_recovery_scanned) {
+        if !scan.changed.is_empty() {
+            changed_non_recovery_files += 1;
+            updates.insert(source.file_id, file_update(source, &scan, &packets));
+        }
+    }
+    if updates.is_empty() {
+        return Ok(UpdateSummary::default());
+    }
+
+    let encoder = RecoveryBlockEncoder::new(block_size as usize, source_block_count);
+    let base_values = encoder.base_values();
+    let (deltas, recovered_blocks) = old_block_deltas(
+        &par2_files,
+        set_id,
+        &source_files,
+        &table,
+        &changed,
+        &global_offsets,
+        base_values,
+        block_size,
+    )?;
+
+    let new_file_id = |file_id: &FileId| updates.get(file_id).map_or(*file_id, |u| u.file_id);
+    let new_set_id = recovery_set_id_from_file_ids(
+        block_size,
+        &layout.file_ids.iter().map(new_file_id).collect::<Vec<_>>(),
+        &non_recovery_file_ids
+            .iter()
+            .map(new_file_id)
+            .collect::<Vec<_>>(),
+    );
+
+    let mut summary = UpdateSummary {
+        changed_blocks: changed.len(),
+        recovered_blocks,
+        changed_non_recovery_files,
+        ..Default::default()
+    };
+    let mut staged = StagedOutputs::default();
+    for path in &par2_files {
+        let staged_file = stage_copy(&mut staged, path)?;
+        let mut written = false;
+        for_each_set_packet(&temp_path(path), set_id, true, |bytes| {
+            let packet_type = &bytes[48..64];
+            if packet_type == RECOVERY_PACKET_TYPE {
+                let exponent =
+                    u32::from_le_bytes(bytes[64..68].try_into().expect("4 byte exponent")) as u16;
+                for (global_index, delta) in &deltas {
+                    let coefficient = Galois16::new(base_values[*global_index]).pow(exponent);
+                    process_slice_multiply_add(
+                        delta,
+                        &mut bytes[68..],
+                        &build_split_mul_table(coefficient),
+                    );
+                }
+                summary.updated_recovery_packets += 1;
+            } else if packet_type == packets::main_packet::TYPE_OF_PACKET {
+                // Recovery file IDs, then non-recovery file IDs
+                for entry in bytes[76..].as_chunks_mut::<16>().0 {
+                    let file_id = new_file_id(&FileId::new(*entry));
+                    entry.copy_from_slice(file_id.as_bytes());
+                }
+            } else if let Some(update) = file_packet_update(bytes, &updates) {
+                patch_file_packet(bytes, update, block_size)?;
+            } else if new_set_id == set_id {
+                return Ok(false);
+            }
+
+            bytes[32..48].copy_from_slice(new_set_id.as_bytes());
+            let md5 = crate::checksum::compute_md5_bytes(&bytes[32..]);
+            bytes[16..32].copy_from_slice(&md5);
+            written = true;
+            Ok(true)
+        })?;
+        if written {
+            staged_file
+                .sync_all()
+                .map_err(|e| CreateError::FileCreateError {
+                    file: temp_path(path).to_string_lossy().to_string(),
+                    source: e,
+                })?;
+            reporter.report_writing_file(&path.to_string_lossy());
+            summary
+                .updated_files
+                .push(path.to_string_lossy().to_string());
+        } else {
+            staged.discard(path);
+        }
+    }
+    staged.commit(true)?;
+
+    Ok(summary)
+}
+
+/// New hashes and file ID of a file whose scan found changed blocks
+fn file_update<'a>(
+    source: &'a SourceFileInfo,
+    scan: &ScannedFile,
+    packets: &[Packet],
+) -> FileUpdate<'a> {
+    let name = packets
+        .iter()
+        .find_map(|packet| match packet {
+            Packet::FileDescription(fd) if fd.file_id == source.file_id => {
+                Some(file_name_bytes(&fd.file_name))
+            }
+            _ => None,
+        })
+        .expect("every scanned file has a FileDesc packet");
+    FileUpdate {
+        source,
+        file_id: crate::checksum::compute_file_id(&scan.hash_16k, source.size, name),
+        hash: scan.hash,
+        hash_16k: scan.hash_16k,
+        block_checksums: scan.block_checksums.clone(),
+        changed_blocks: scan.changed.iter().map(|(block, _)| *block).collect(),
+    }
+}
+
+/// Copy `path` to a staged temporary, returning the open temporary
+fn stage_copy(staged: &mut StagedOutputs, path: &Path) -> CreateResult<File> {
+    let mut original = open_for_reading(path)?;
+    let mut copy = staged.create(path)?;
+    std::io::copy(&mut original, &mut copy).map_err(|e| CreateError::FileCreateError {
+        file: temp_path(path).to_string_lossy().to_string(),
+        source: e,
+    })?;
+    Ok(copy)
+}
+
+/// IFSC checksums of every source file, in main packet order
+fn expected_block_checksums(
+    packets: &[Packet],
+    source_files: &[SourceFileInfo],
+    block_size: u64,
+) -> CreateResult<Vec<Vec<(Md5Hash, Crc32Value)>>> {
+    source_files
+        .iter()
+        .map(|file| {
+            let checksums = packets
+                .iter()
+                .find_map(|packet| match packet {
+                    Packet::InputFileSliceChecksum(ifsc) if ifsc.file_id == file.file_id => {
+                        Some(&ifsc.slice_checksums)
+                    }
+                    _ => None,
+                })
+                .ok_or_else(|| {
+                    CreateError::Other(format!(
+                        "{}: no IFSC packet found, cannot update",
+                        file.path.display()
+                    ))
+                })?;
+            if checksums.len() != file.calculate_block_count(block_size) as usize {
+                return Err(CreateError::Other(format!(
+                    "{}: IFSC packet lists {} blocks, the file has {}",
+                    file.path.display(),
+                    checksums.len(),
+                    file.calculate_block_count(block_size)
+                )));
+            }
+            Ok(checksums.clone())
+        })
+        .collect()
+}
+
+fn build_block_table(
+    source_files: &[SourceFileInfo],
+    expected: &[Vec<(Md5Hash, Crc32Value)>],
+    block_size: u64,
+) -> GlobalBlockTable {
+    let mut builder = crate::verify::GlobalBlockTableBuilder::new(block_size);
+    for (file, checksums) in source_files.iter().zip(expected) {
+        builder.add_file_blocks(file.file_id, checksums);
+    }
+    builder.build()
+}
+
+/// Positions in the table holding a block with the given checksums
+fn table_positions(
+    table: &GlobalBlockTable,
+    (md5, crc32): (Md5Hash, Crc32Value),
+) -> impl Iterator<Item = (FileId, u32)> + '_ {
+    table
+        .find_exact_match(&md5, crc32)
+        .into_iter()
+        .flat_map(|entry| entry.iter_duplicates())
+        .map(|entry| (entry.position.file_id, entry.position.block_number))
+}
+
+/// Hash `file` and collect the blocks that no longer sit at their position
+///
+/// The new contents of changed blocks are kept only with `keep_data`.
+fn scan_file(
+    file: &SourceFileInfo,
+    expected: &[(Md5Hash, Crc32Value)],
+    table: &GlobalBlockTable,
+    block_size: u64,
+    keep_data: bool,
+    reporter: &dyn CreateReporter,
+) -> CreateResult<ScannedFile> {
+    let mut reader = BufReader::new(open_for_reading(&file.path)?);
+    let mut file_md5 = crate::checksum::new_md5_hasher();
+    let mut md5_16k = crate::checksum::new_md5_hasher();
+    let mut block_checksums = Vec::with_capacity(expected.len());
+    let mut changed = Vec::new();
+    let mut buffer = vec![0u8; block_size as usize];
+    let mut offset = 0u64;
+
+    for block_number in 0..expected.len() as u32 {
+        let len = block_size.min(file.size - offset) as usize;
+        buffer[len..].fill(0);
+        reader
+            .read_exact(&mut buffer[..len])
+            .map_err(|e| CreateError::FileReadError {
+                file: file.path.to_string_lossy().to_string(),
+                source: e,
+            })?;
+        file_md5.update(&buffer[..len]);
+        if offset < HASH_16K_SIZE {
+            md5_16k.update(&buffer[..len.min((HASH_16K_SIZE - offset) as usize)]);
+        }
+
+        let checksums = crate::checksum::compute_block_checksums(&buffer);
+        let unchanged = table_positions(table, checksums)
+            .any(|position| position == (file.file_id, block_number));
+        if !unchanged {
+            let data = if keep_data {
+                buffer.clone()
+            } else {
+                Vec::new()
+            };
+            changed.push((block_number, data));
+        }
+        block_checksums.push(checksums);
+        offset += len as u64;
+        reporter.report_file_hashing(file.packet_name(), offset, file.size);
+    }
+
+    Ok(ScannedFile {
+        hash: crate::checksum::finalize_md5(file_md5),
+        hash_16k: crate::checksum::finalize_md5(md5_16k),
+        block_checksums,
+        changed,
+    })
+}
+
+/// The delta of every changed block, in `changed` order
+///
+/// Also returns how many old blocks had to be rebuilt from recovery data.
+#[allow(clippy::too_many_arguments)] // All params are logically distinct; a param struct would add noise
+fn old_block_deltas(
+    par2_files: &[PathBuf],
+    set_id: RecoverySetId,
+    source_files: &[SourceFileInfo],
+    table: &GlobalBlockTable,
+    changed: &[ChangedBlock],
+    global_offsets: &[usize],
+    base_values: &[u16],
+    block_size: u64,
+) -> CreateResult<(Vec<BlockDelta>, usize)> {
+    let changed_positions: HashSet<(FileId, u32)> = changed
+        .iter()
+        .map(|block| (source_files[block.file].file_id, block.block_number))
+        .collect();
+    let file_index: HashMap<FileId, usize> = source_files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| (file.file_id, index))
+        .collect();
+    let embedded: HashMap<(FileId, u64), packets::InputFileSliceMetadata> =
+        crate::par2_files::parse_input_file_slice_metadata(par2_files)
+            .into_iter()
+            .filter(|slice| slice.set_id == set_id)
+            .map(|slice| ((slice.file_id, slice.offset), slice))
+            .collect();
+
+    let mut deltas: Vec<Option<Vec<u8>>> = Vec::with_capacity(changed.len());
+    for block in changed {
+        // An unchanged block with the old checksums still holds the old data
+        let copy = table_positions(table, block.old_checksums)
+            .find(|position| !changed_positions.contains(position))
+            .map(|(file_id, block_number)| {
+                read_block(
+                    &source_files[file_index[&file_id]],
+                    block_number,
+                    block_size,
+                )
+            })
+            .transpose()?;
+        let old_data = match copy {
+            Some(data) => Some(data),
+            None => {
+                let file_id = source_files[block.file].file_id;
+                let offset = block.block_number as u64 * block_size;
+                embedded
+                    .get(&(file_id, offset))
+                    .map(|slice| read_embedded_block(slice, block_size))
+                    .transpose()?
+                    .filter(|data| {
+                        crate::checksum::compute_block_checksums(data) == block.old_checksums
+                    })
+            }
+        };
+        deltas.push(old_data.map(|mut data| {
+            xor_into(&mut data, &block.new_data);
+            data
+        }));
+    }
+
+    let unknown: Vec<usize> = (0..changed.len())
+        .filter(|&index| deltas[index].is_none())
+        .collect();
+    if !unknown.is_empty() {
+        let recovered = recover_deltas(
+            par2_files,
+            set_id,
+            source_files,
+            changed,
+            &deltas,
+            &unknown,
+            global_offsets,
+            base_values,
+            block_size,
+        )?;
+        for (index, delta) in unknown.iter().zip(recovered) {
+            deltas[*index] = Some(delta);
+        }
+    }
+
+    let deltas = changed
+        .iter()
+        .zip(deltas)
+        .map(|(block, delta)| (block.global_index, delta.expect("every delta is known")))
+        .collect();
+    Ok((deltas, unknown.len()))
+}
+
+/// Rebuild the deltas of the `unknown` changed blocks from recovery data
+///
+/// Encoding the current source data at the exponents of `unknown.len()` intact
+/// recovery blocks and adding the stored blocks leaves `sum(coeff * delta)`
+/// over the changed blocks. Removing the known deltas leaves a square system
+/// in the unknown ones.
+#[allow(clippy::too_many_arguments)] // All params are logically distinct; a param struct would add noise
+fn recover_deltas(
+    par2_files: &[PathBuf],
+    set_id: RecoverySetId,
+    source_files: &[SourceFileInfo],
+    changed: &[ChangedBlock],
+    deltas: &[Option<Vec<u8>>],
+    unknown: &[usize],
+    global_offsets: &[usize],
+    base_values: &[u16],
+    block_size: u64,
+) -> CreateResult<Vec<Vec<u8>>> {
+    let mut syndromes: Vec<(u16, Vec<u8>)> = Vec::with_capacity(unknown.len());
+    for path in par2_files {
+        for_each_set_packet(path, set_id, false, |bytes| {
+            if &bytes[48..64] == RECOVERY_PACKET_TYPE && syndromes.len() < unknown.len() {
+                let exponent =
+                    u32::from_le_bytes(bytes[64..68].try_into().expect("4 byte exponent")) as u16;
+                if syndromes.iter().all(|(e, _)| *e != exponent) {
+                    syndromes.push((exponent, bytes[68..].to_vec()));
+                }
+            }
+            Ok(false)
+        })?;
+    }
+    if syndromes.len() < unknown.len() {
+        return Err(CreateError::Other(format!(
+            "the old contents of {} changed blocks are needed but only {} intact recovery blocks were found",
+            unknown.len(),
+            syndromes.len()
+        )));
+    }
+
+    let coefficient =
+        |global_index: usize, exponent: u16| Galois16::new(base_values[global_index]).pow(exponent);
+    for (file, global_offset) in source_files.iter().zip(global_offsets) {
+        let mut reader = BufReader::new(open_for_reading(&file.path)?);
+        let mut buffer = vec![0u8; block_size as usize];
+        for block_number in 0..file.calculate_block_count(block_size) as usize {
+            let len = block_size.min(file.size - block_number as u64 * block_size) as usize;
+            buffer[len..].fill(0);
+            reader
+                .read_exact(&mut buffer[..len])
+                .map_err(|e| CreateError::FileReadError {
+                    file: file.path.to_string_lossy().to_string(),
+                    source: e,
+                })?;
+            syndromes.par_iter_mut().for_each(|(exponent, syndrome)| {
+                let table =
+                    build_split_mul_table(coefficient(global_offset + block_number, *exponent));
+                process_slice_multiply_add(&buffer, syndrome, &table);
+            });
+        }
+    }
+    for (block, delta) in changed.iter().zip(deltas) {
+        if let Some(delta) = delta {
+            for (exponent, syndrome) in &mut syndromes {
+                let table = build_split_mul_table(coefficient(block.global_index, *exponent));
+                process_slice_multiply_add(delta, syndrome, &table);
+            }
+        }
+    }
+
+    let matrix: Vec<Vec<Galois16>> = syndromes
+        .iter()
+        .map(|(exponent, _)| {
+            unknown
+                .iter()
+                .map(|&index| coefficient(changed[index].global_index, *exponent))
+                .collect()
+        })
+        .collect();
+    // The engine only lends its solver here; it needs no input slices
+    let inverse =
+        crate::reed_solomon::ReconstructionEngine::new(block_size as usize, 0, Vec::new())
+            .invert_gf_matrix(&matrix)
+            .map_err(|e| CreateError::Other(format!("cannot recover old block contents: {e}")))?;
+
+    unknown
+        .iter()
+        .zip(&inverse)
+        .map(|(&index, row)| {
+            let block = &changed[index];
+            let mut delta = vec![0u8; block_size as usize];
+            for (factor, (_, syndrome)) in row.iter().zip(&syndromes) {
+                process_slice_multiply_add(syndrome, &mut delta, &build_split_mul_table(*factor));
+            }
+            let mut old_data = delta.clone();
+            xor_into(&mut old_data, &block.new_data);
+            if crate::checksum::compute_block_checksums(&old_data) != block.old_checksums {
+                return Err(CreateError::Other(format!(
+                    "{}: recovery data does not reproduce the old contents of block {}; the set is damaged",
+                    source_files[block.file].path.display(),
+                    block.block_number
+                )));
+            }
+            Ok(delta)
+        })
+        .collect()
+}
+
+/// The update for a packet that starts with a file ID, if that file changed
+fn file_packet_update<'a, 'b>(
+    bytes: &[u8],
+    updates: &'b HashMap<FileId, FileUpdate<'a>>,
+) -> Option<&'b FileUpdate<'a>> {
+    let packet_type = &bytes[48..64];
+    let has_file_id = [
+        packets::file_description_packet::TYPE_OF_PACKET,
+        packets::input_file_slice_checksum_packet::TYPE_OF_PACKET,
+        packets::input_file_slice_packet::TYPE_OF_PACKET,
+        packets::unicode_filename_packet::TYPE_OF_PACKET,
+    ]
+    .contains(&packet_type);
+    if !has_file_id || bytes.len() < 80 {
+        return None;
+    }
+    let file_id = FileId::new(bytes[64..80].try_into().expect("16 byte file ID"));
+    updates.get(&file_id)
+}
+
+/// Rewrite the file ID and whatever else a file packet records about the file
+fn patch_file_packet(bytes: &mut [u8], update: &FileUpdate, block_size: u64) -> CreateResult<()> {
+    let packet_type = &bytes[48..64];
+    if packet_type == packets::file_description_packet::TYPE_OF_PACKET {
+        bytes[80..96].copy_from_slice(update.hash.as_bytes());
+        bytes[96..112].copy_from_slice(update.hash_16k.as_bytes());
+    } else if packet_type == packets::input_file_slice_checksum_packet::TYPE_OF_PACKET {
+        for (entry, (md5, crc32)) in bytes[80..]
+            .as_chunks_mut::<20>()
+            .0
+            .iter_mut()
+            .zip(&update.block_checksums)
+        {
+            entry[..16].copy_from_slice(md5.as_bytes());
+            entry[16..].copy_from_slice(&crc32.as_u32().to_le_bytes());
+        }
+    } else if packet_type == packets::input_file_slice_packet::TYPE_OF_PACKET {
+        let offset = u64::from_le_bytes(bytes[80..88].try_into().expect("8 byte offset"));
+        let end = offset + (bytes.len() - 88) as u64;
+        let stale = update.changed_blocks.iter().any(|&block| {
+            let start = block as u64 * block_size;
+            start < end && offset < start + block_size
+        });
+        if stale {
+            let data = &mut bytes[88..];
+            data.fill(0);
+            let len = (data.len() as u64).min(update.source.size.saturating_sub(offset)) as usize;
+            read_at(update.source, offset, &mut data[..len])?;
+        }
+    }
+    bytes[64..80].copy_from_slice(update.file_id.as_bytes());
+    Ok(())
+}
+
+/// Call `visit` on every intact packet of `set_id` in `path`
+///
+/// Packets for which `visit` returns true are written back; `writable` must
+/// be set for that. Scanning stops at the first bytes that are not a packet.
+fn for_each_set_packet(
+    path: &Path,
+    set_id: RecoverySetId,
+    writable: bool,
+    mut visit: impl FnMut(&mut [u8]) -> CreateResult<bool>,
+) -> CreateResult<()> {
+    let read_error = |e| CreateError::FileReadError {
+        file: path.to_string_lossy().to_string(),
+        source: e,
+    };
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(writable)
+        .open(path)
+        .map_err(read_error)?;
+    let file_len = file.metadata().map_err(read_error)?.len();
+
+    let mut offset = 0u64;
+    let mut header = [0u8; 64];
+    loop {
+        file.seek(SeekFrom::Start(offset)).map_err(read_error)?;
+        if file.read_exact(&mut header).is_err() || &header[0..8] != packets::MAGIC_BYTES {
+            break;
+        }
+        let length = u64::from_le_bytes(header[8..16].try_into().expect("8 byte length"));
+        if length < 64 || length % 4 != 0 || offset + length > file_len {
+            break;
+        }
+        if header[32..48] == *set_id.as_bytes() {
+            let mut bytes = vec![0u8; length as usize];
+            bytes[..64].copy_from_slice(&header);
+            file.read_exact(&mut bytes[64..]).map_err(read_error)?;
+            let intact = crate::checksum::compute_md5_bytes(&bytes[32..]) == bytes[16..32];
+            if intact && visit(&mut bytes)? {
+                file.seek(SeekFrom::Start(offset))
+                    .and_then(|_| file.write_all(&bytes))
+                    .map_err(|e| CreateError::FileCreateError {
+                        file: path.to_string_lossy().to_string(),
+                        source: e,
+                    })?;
+            }
+        }
+        offset += length;
+    }
+    Ok(())
+}
+
+fn read_at(file: &SourceFileInfo, offset: u64, data: &mut [u8]) -> CreateResult<()> {
+    let mut source: File = open_for_reading(&file.path)?;
+    source
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| source.read_exact(data))
+        .map_err(|e| CreateError::FileReadError {
+            file: file.path.to_string_lossy().to_string(),
+            source: e,
+        })
+}
+
+/// Current contents of a source block, zero padded to the block size
+fn read_block(file: &SourceFileInfo, block_number: u32, block_size: u64) -> CreateResult<Vec<u8>> {
+    let offset = block_number as u64 * block_size;
+    let mut data = vec![0u8; block_size as usize];
+    let len = block_size.min(file.size - offset) as usize;
+    read_at(file, offset, &mut data[..len])?;
+    Ok(data)
+}
+
+/// Data of an embedded FileSlic packet, cut or zero padded to the block size
+fn read_embedded_block(
+    slice: &packets::InputFileSliceMetadata,
+    block_size: u64,
+) -> CreateResult<Vec<u8>> {
+    let mut data = vec![0u8; block_size as usize];
+    let len = slice.data_size.min(block_size as usize);
+    let mut par2 = open_for_reading(&slice.file_path)?;
+    par2.seek(SeekFrom::Start(slice.data_offset))
+        .and_then(|_| par2.read_exact(&mut data[..len]))
+        .map_err(|e| CreateError::FileReadError {
+            file: slice.file_path.to_string_lossy().to_string(),
+            source: e,
+        })?;
+    Ok(data)
+}
+
+/// FileDesc name without its null padding, as hashed into the file ID
+fn file_name_bytes(name: &[u8]) -> &[u8] {
+    let len = name
+        .iter()
+        .rposition(|&b| b != 0)
+        .map_or(0, |last| last + 1);
+    &name[..len]
+}
+
+fn xor_into(target: &mut [u8], other: &[u8]) {
+    for (a, b) in target.iter_mut().zip(other) {
+        *a ^= b;
+    }
+}
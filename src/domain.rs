@@ -146,6 +146,25 @@ impl From<[u8; 16]> for RecoverySetId {
     }
 }
 
+impl std::fmt::Display for RecoverySetId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl std::str::FromStr for RecoverySetId {
+    type Err = String;
+
+    /// Parse a recovery set ID from 32 hex digits
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|e| format!("Invalid recovery set ID {s}: {e}"))?;
+        bytes
+            .try_into()
+            .map(RecoverySetId)
+            .map_err(|_| format!("Recovery set ID must be 32 hex digits: {s}"))
+    }
+}
+
 impl AsRef<[u8; 16]> for RecoverySetId {
     fn as_ref(&self) -> &[u8; 16] {
         &self.0
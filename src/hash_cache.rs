@@ -0,0 +1,371 @@
+//! Persistent cache of file hashes
+//!
+//! Hashing every source file dominates create for data that rarely changes,
+//! and the full-file MD5 is a large part of verify. The cache remembers the
+//! full and 16k MD5 of a file together with its block checksums for a given
+//! block size. Entries are keyed by canonical path and only used while the
+//! file's size, modification time and inode are unchanged.
+//!
+//! The cache is a text file with one line per file and block size:
+//! hex-encoded path, size, modification time in nanoseconds, inode, block
+//! size, file MD5, 16k MD5 and the concatenated block MD5s and CRC32s, all
+//! hex-encoded. A missing or unreadable cache is treated as empty.
+
+use crate::domain::{Crc32Value, Md5Hash};
+use rustc_hash::FxHashMap as HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Name of the cache file when only a directory is given
+pub const DEFAULT_FILE_NAME: &str = ".par2rs-hashcache";
+
+const HEADER: &str = "par2rs-hash-cache 1";
+
+/// Default cache location for PAR2 files written to or read from `dir`
+pub fn default_path(dir: &Path) -> PathBuf {
+    dir.join(DEFAULT_FILE_NAME)
+}
+
+/// Cache location for a `--hash-cache[=PATH]` value
+///
+/// The bare flag (an empty value) selects the default cache next to `par2_file`.
+pub fn path_from_option(value: &str, par2_file: &Path) -> PathBuf {
+    if !value.is_empty() {
+        return PathBuf::from(value);
+    }
+    let dir = par2_file
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    default_path(dir)
+}
+
+/// What identifies an unchanged file: size, modification time and inode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    pub size: u64,
+    pub modified_nanos: u128,
+    pub inode: u64,
+}
+
+impl FileStamp {
+    /// Stamp of the file at `path`, if it is a regular file with a usable mtime
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        if !metadata.is_file() {
+            return None;
+        }
+        let modified = metadata
+            .modified()
+            .ok()?
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()?;
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+        Some(FileStamp {
+            size: metadata.len(),
+            modified_nanos: modified.as_nanos(),
+            inode,
+        })
+    }
+}
+
+/// Hashes of one file, with block checksums for one block size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedHashes {
+    pub hash: Md5Hash,
+    pub hash_16k: Md5Hash,
+    pub block_size: u64,
+    /// MD5 and CRC32 of every block, the last one zero padded
+    pub block_checksums: Vec<(Md5Hash, Crc32Value)>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    stamp: FileStamp,
+    /// One per block size
+    hashes: Vec<CachedHashes>,
+}
+
+/// File hashes loaded from, and saved back to, a cache file
+#[derive(Debug)]
+pub struct HashCache {
+    path: PathBuf,
+    entries: HashMap<String, CacheEntry>,
+    dirty: bool,
+}
+
+impl HashCache {
+    /// Load the cache at `path`
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => parse_entries(&contents).unwrap_or_else(|| {
+                log::warn!("Ignoring malformed hash cache {}", path.display());
+                HashMap::default()
+            }),
+            Err(_) => HashMap::default(),
+        };
+        HashCache {
+            path,
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Location the cache is loaded from and saved to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Cached hashes of `file` for `block_size`, if the file is unchanged
+    pub fn get(&self, file: &Path, block_size: u64) -> Option<&CachedHashes> {
+        self.current_entry(file)?
+            .hashes
+            .iter()
+            .find(|hashes| hashes.block_size == block_size)
+    }
+
+    /// Cached size, MD5 and 16k MD5 of `file` for any block size, if the file is unchanged
+    pub fn file_hashes(&self, file: &Path) -> Option<(u64, Md5Hash, Md5Hash)> {
+        let entry = self.current_entry(file)?;
+        let hashes = entry.hashes.first()?;
+        Some((entry.stamp.size, hashes.hash, hashes.hash_16k))
+    }
+
+    /// Remember the hashes of `file`, computed after `stamp` was taken
+    ///
+    /// Nothing is stored if the file has changed since, as the hashes may
+    /// then describe neither version.
+    pub fn insert(&mut self, file: &Path, stamp: FileStamp, hashes: CachedHashes) {
+        let Some(key) = cache_key(file) else {
+            return;
+        };
+        if FileStamp::of(file) != Some(stamp) {
+            return;
+        }
+
+        let entry = self.entries.entry(key).or_insert_with(|| CacheEntry {
+            stamp,
+            hashes: Vec::new(),
+        });
+        if entry.stamp != stamp {
+            entry.stamp = stamp;
+            entry.hashes.clear();
+        }
+        entry
+            .hashes
+            .retain(|cached| cached.block_size != hashes.block_size);
+        entry.hashes.push(hashes);
+        self.dirty = true;
+    }
+
+    /// Write the cache back if anything was added, dropping files that no longer exist
+    pub fn save(&self) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let mut contents = String::from(HEADER);
+        contents.push('\n');
+        let mut keys: Vec<_> = self
+            .entries
+            .keys()
+            .filter(|key| Path::new(key).is_file())
+            .collect();
+        keys.sort();
+        for key in keys {
+            let entry = &self.entries[key];
+            for hashes in &entry.hashes {
+                contents.push_str(&format_line(key, &entry.stamp, hashes));
+                contents.push('\n');
+            }
+        }
+
+        // Replace the cache in one step so readers never see a partial file
+        let mut temp_name = self.path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, &self.path)
+    }
+
+    fn current_entry(&self, file: &Path) -> Option<&CacheEntry> {
+        let entry = self.entries.get(&cache_key(file)?)?;
+        (FileStamp::of(file)? == entry.stamp).then_some(entry)
+    }
+}
+
+/// Canonical path of `file`; files without a UTF-8 path are not cached
+fn cache_key(file: &Path) -> Option<String> {
+    std::fs::canonicalize(file)
+        .ok()?
+        .to_str()
+        .map(str::to_owned)
+}
+
+fn format_line(key: &str, stamp: &FileStamp, hashes: &CachedHashes) -> String {
+    let mut checksums = Vec::with_capacity(hashes.block_checksums.len() * 20);
+    for (md5, crc32) in &hashes.block_checksums {
+        checksums.extend_from_slice(md5.as_bytes());
+        checksums.extend_from_slice(&crc32.to_le_bytes());
+    }
+    format!(
+        "{} {} {} {} {} {} {} {}",
+        hex::encode(key),
+        stamp.size,
+        stamp.modified_nanos,
+        stamp.inode,
+        hashes.block_size,
+        hex::encode(hashes.hash.as_bytes()),
+        hex::encode(hashes.hash_16k.as_bytes()),
+        if checksums.is_empty() {
+            "-".to_string()
+        } else {
+            hex::encode(checksums)
+        }
+    )
+}
+
+fn parse_entries(contents: &str) -> Option<HashMap<String, CacheEntry>> {
+    let mut lines = contents.lines();
+    if lines.next()? != HEADER {
+        return None;
+    }
+
+    let mut entries: HashMap<String, CacheEntry> = HashMap::default();
+    for line in lines.filter(|line| !line.is_empty()) {
+        let (key, stamp, hashes) = parse_line(line)?;
+        let entry = entries.entry(key).or_insert_with(|| CacheEntry {
+            stamp,
+            hashes: Vec::new(),
+        });
+        if entry.stamp == stamp {
+            entry.hashes.push(hashes);
+        }
+    }
+    Some(entries)
+}
+
+fn parse_line(line: &str) -> Option<(String, FileStamp, CachedHashes)> {
+    let fields: Vec<&str> = line.split(' ').collect();
+    let [key, size, modified_nanos, inode, block_size, hash, hash_16k, checksums] = fields[..]
+    else {
+        return None;
+    };
+
+    let md5 = |field: &str| -> Option<Md5Hash> {
+        Some(Md5Hash::new(hex::decode(field).ok()?.try_into().ok()?))
+    };
+    let checksum_bytes = match checksums {
+        "-" => Vec::new(),
+        _ => hex::decode(checksums).ok()?,
+    };
+    let (entries, remainder) = checksum_bytes.as_chunks::<20>();
+    if !remainder.is_empty() {
+        return None;
+    }
+    let block_checksums = entries
+        .iter()
+        .map(|chunk| {
+            let md5 = Md5Hash::new(chunk[..16].try_into().expect("16 byte MD5"));
+            let crc32 = u32::from_le_bytes(chunk[16..].try_into().expect("4 byte CRC32"));
+            (md5, Crc32Value::new(crc32))
+        })
+        .collect();
+
+    Some((
+        String::from_utf8(hex::decode(key).ok()?).ok()?,
+        FileStamp {
+            size: size.parse().ok()?,
+            modified_nanos: modified_nanos.parse().ok()?,
+            inode: inode.parse().ok()?,
+        },
+        CachedHashes {
+            hash: md5(hash)?,
+            hash_16k: md5(hash_16k)?,
+            block_size: block_size.parse().ok()?,
+            block_checksums,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_hashes(block_size: u64) -> CachedHashes {
+        CachedHashes {
+            hash: Md5Hash::new([1; 16]),
+            hash_16k: Md5Hash::new([2; 16]),
+            block_size,
+            block_checksums: vec![
+                (Md5Hash::new([3; 16]), Crc32Value::new(0xDEADBEEF)),
+                (Md5Hash::new([4; 16]), Crc32Value::new(7)),
+            ],
+        }
+    }
+
+    #[test]
+    fn saved_entries_load_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data with spaces.bin");
+        std::fs::write(&file, b"contents").unwrap();
+        let cache_path = default_path(dir.path());
+
+        let mut cache = HashCache::load(&cache_path);
+        let stamp = FileStamp::of(&file).unwrap();
+        cache.insert(&file, stamp, sample_hashes(1024));
+        cache.insert(&file, stamp, sample_hashes(4096));
+        cache.save().unwrap();
+
+        let loaded = HashCache::load(&cache_path);
+        assert_eq!(loaded.get(&file, 1024), Some(&sample_hashes(1024)));
+        assert_eq!(loaded.get(&file, 4096), Some(&sample_hashes(4096)));
+        assert_eq!(loaded.get(&file, 512), None);
+        assert_eq!(
+            loaded.file_hashes(&file),
+            Some((8, Md5Hash::new([1; 16]), Md5Hash::new([2; 16])))
+        );
+    }
+
+    #[test]
+    fn entries_are_ignored_once_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.bin");
+        std::fs::write(&file, b"contents").unwrap();
+
+        let mut cache = HashCache::load(default_path(dir.path()));
+        cache.insert(&file, FileStamp::of(&file).unwrap(), sample_hashes(1024));
+        assert!(cache.get(&file, 1024).is_some());
+
+        std::fs::write(&file, b"contents!").unwrap();
+        assert_eq!(cache.get(&file, 1024), None);
+        assert_eq!(cache.file_hashes(&file), None);
+    }
+
+    #[test]
+    fn hashes_of_a_file_changed_while_hashing_are_not_stored() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("data.bin");
+        std::fs::write(&file, b"contents").unwrap();
+        let stamp = FileStamp::of(&file).unwrap();
+        std::fs::write(&file, b"other contents").unwrap();
+
+        let mut cache = HashCache::load(default_path(dir.path()));
+        cache.insert(&file, stamp, sample_hashes(1024));
+        assert_eq!(cache.get(&file, 1024), None);
+    }
+
+    #[test]
+    fn malformed_cache_loads_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = default_path(dir.path());
+        std::fs::write(&cache_path, "par2rs-hash-cache 1\nnot a valid line\n").unwrap();
+        assert!(HashCache::load(&cache_path).entries.is_empty());
+    }
+}
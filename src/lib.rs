@@ -26,6 +26,7 @@ pub mod verify;
 // Internal modules (exposed but not typically used directly by binaries)
 pub mod checksum;
 pub mod domain;
+pub mod hash_cache;
 pub mod packets;
 pub mod reporters;
 
@@ -0,0 +1,127 @@
+use crate::domain::{Md5Hash, RecoverySetId};
+use binrw::{BinRead, BinWrite};
+
+pub const TYPE_OF_PACKET: &[u8] = b"PAR 2.0\0CommASCI";
+
+/// Optional ASCII comment packet
+///
+/// Holds free-form ASCII text attached to the recovery set. The text is padded
+/// with NUL bytes to keep the packet length a multiple of 4.
+#[derive(Debug, Clone)]
+pub struct AsciiCommentPacket {
+    pub length: u64,           // Length of the packet
+    pub md5: Md5Hash,          // MD5 hash of the packet
+    pub set_id: RecoverySetId, // Unique identifier for the PAR2 set
+    pub comment: Vec<u8>,      // ASCII comment, including any trailing NUL padding
+}
+
+impl AsciiCommentPacket {
+    /// Decode the comment text, dropping trailing NUL padding
+    pub fn comment_text(&self) -> String {
+        String::from_utf8_lossy(&self.comment)
+            .trim_end_matches('\0')
+            .to_string()
+    }
+
+    /// Verifies the MD5 hash of the packet.
+    /// Computes the MD5 hash of the serialized fields and compares it to the stored MD5 value.
+    pub fn verify(&self) -> bool {
+        if self.length < 64 {
+            println!("Invalid packet length: {}", self.length);
+            return false;
+        }
+        let mut data = Vec::new();
+        data.extend_from_slice(self.set_id.as_bytes());
+        data.extend_from_slice(TYPE_OF_PACKET);
+        data.extend_from_slice(&self.comment);
+        let computed_md5 = crate::checksum::compute_md5_bytes(&data);
+        if computed_md5 != *self.md5.as_bytes() {
+            println!(
+                "MD5 mismatch: expected {:?}, computed {:?}",
+                self.md5.as_bytes(),
+                computed_md5
+            );
+            return false;
+        }
+
+        // Check that BinWrite output matches the packet length
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        if self.write_le(&mut buffer).is_err() {
+            println!("Failed to serialize packet");
+            return false;
+        }
+
+        let serialized_length = buffer.get_ref().len() as u64;
+        if serialized_length != self.length {
+            println!(
+                "Serialized length mismatch: expected {}, got {}",
+                self.length, serialized_length
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+impl BinRead for AsciiCommentPacket {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<Self> {
+        let mut header = [0u8; 64];
+        reader.read_exact(&mut header).map_err(binrw::Error::Io)?;
+
+        if &header[0..8] != b"PAR2\0PKT" {
+            return Err(binrw::Error::AssertFail {
+                pos: 0,
+                message: "Invalid magic".to_string(),
+            });
+        }
+
+        let length =
+            u64::from_le_bytes(header[8..16].try_into().expect("slice is exactly 8 bytes"));
+        if length < 64 {
+            return Err(binrw::Error::AssertFail {
+                pos: 8,
+                message: format!("Invalid CommASCI packet length: {}", length),
+            });
+        }
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&header[16..32]);
+        let mut set_id = [0u8; 16];
+        set_id.copy_from_slice(&header[32..48]);
+
+        let mut comment = vec![0u8; (length - 64) as usize];
+        reader.read_exact(&mut comment).map_err(binrw::Error::Io)?;
+
+        Ok(AsciiCommentPacket {
+            length,
+            md5: Md5Hash::new(md5),
+            set_id: RecoverySetId::new(set_id),
+            comment,
+        })
+    }
+}
+
+impl BinWrite for AsciiCommentPacket {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        writer.write_all(b"PAR2\0PKT")?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        writer.write_all(self.md5.as_bytes())?;
+        writer.write_all(self.set_id.as_bytes())?;
+        writer.write_all(TYPE_OF_PACKET)?;
+        writer.write_all(&self.comment)?;
+        Ok(())
+    }
+}
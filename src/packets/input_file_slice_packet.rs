@@ -0,0 +1,275 @@
+use crate::domain::{FileId, Md5Hash, RecoverySetId};
+use binrw::{BinRead, BinWrite};
+use std::path::PathBuf;
+
+pub const TYPE_OF_PACKET: &[u8] = b"PAR 2.0\0FileSlic";
+
+/// Optional input file slice packet
+///
+/// Carries a copy of protected data: `slice_data` holds the bytes of the file
+/// starting at `offset`. The data is zero padded to a multiple of 4 bytes, so the
+/// last slice of a file may run past the file length.
+#[derive(Debug, Clone)]
+pub struct InputFileSlicePacket {
+    pub length: u64,           // Length of the packet
+    pub md5: Md5Hash,          // MD5 hash of the packet
+    pub set_id: RecoverySetId, // Unique identifier for the PAR2 set
+    pub file_id: FileId,       // File ID of the file the data belongs to
+    pub offset: u64,           // Byte offset of the data within the file
+    pub slice_data: Vec<u8>,   // Copy of the file data, zero padded
+}
+
+impl InputFileSlicePacket {
+    /// Verifies the MD5 hash of the packet.
+    /// Computes the MD5 hash of the serialized fields and compares it to the stored MD5 value.
+    pub fn verify(&self) -> bool {
+        if self.length < 64 + 16 + 8 {
+            println!("Invalid packet length: {}", self.length);
+            return false;
+        }
+        let mut data = Vec::new();
+        data.extend_from_slice(self.set_id.as_bytes());
+        data.extend_from_slice(TYPE_OF_PACKET);
+        data.extend_from_slice(self.file_id.as_bytes());
+        data.extend_from_slice(&self.offset.to_le_bytes());
+        data.extend_from_slice(&self.slice_data);
+        let computed_md5 = crate::checksum::compute_md5_bytes(&data);
+        if computed_md5 != *self.md5.as_bytes() {
+            println!(
+                "MD5 mismatch: expected {:?}, computed {:?}",
+                self.md5.as_bytes(),
+                computed_md5
+            );
+            return false;
+        }
+
+        // Check that BinWrite output matches the packet length
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        if self.write_le(&mut buffer).is_err() {
+            println!("Failed to serialize packet");
+            return false;
+        }
+
+        let serialized_length = buffer.get_ref().len() as u64;
+        if serialized_length != self.length {
+            println!(
+                "Serialized length mismatch: expected {}, got {}",
+                self.length, serialized_length
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+impl BinRead for InputFileSlicePacket {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<Self> {
+        let mut header = [0u8; 64];
+        reader.read_exact(&mut header).map_err(binrw::Error::Io)?;
+
+        if &header[0..8] != b"PAR2\0PKT" {
+            return Err(binrw::Error::AssertFail {
+                pos: 0,
+                message: "Invalid magic".to_string(),
+            });
+        }
+
+        let length =
+            u64::from_le_bytes(header[8..16].try_into().expect("slice is exactly 8 bytes"));
+        if length < 64 + 16 + 8 || length % 4 != 0 {
+            return Err(binrw::Error::AssertFail {
+                pos: 8,
+                message: format!("Invalid FileSlic packet length: {}", length),
+            });
+        }
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&header[16..32]);
+        let mut set_id = [0u8; 16];
+        set_id.copy_from_slice(&header[32..48]);
+
+        let mut file_id = [0u8; 16];
+        reader.read_exact(&mut file_id).map_err(binrw::Error::Io)?;
+        let mut offset = [0u8; 8];
+        reader.read_exact(&mut offset).map_err(binrw::Error::Io)?;
+
+        let mut slice_data = vec![0u8; (length - 64 - 16 - 8) as usize];
+        reader
+            .read_exact(&mut slice_data)
+            .map_err(binrw::Error::Io)?;
+
+        Ok(InputFileSlicePacket {
+            length,
+            md5: Md5Hash::new(md5),
+            set_id: RecoverySetId::new(set_id),
+            file_id: FileId::new(file_id),
+            offset: u64::from_le_bytes(offset),
+            slice_data,
+        })
+    }
+}
+
+impl BinWrite for InputFileSlicePacket {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        writer.write_all(b"PAR2\0PKT")?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        writer.write_all(self.md5.as_bytes())?;
+        writer.write_all(self.set_id.as_bytes())?;
+        writer.write_all(TYPE_OF_PACKET)?;
+        writer.write_all(self.file_id.as_bytes())?;
+        writer.write_all(&self.offset.to_le_bytes())?;
+        writer.write_all(&self.slice_data)?;
+        Ok(())
+    }
+}
+
+/// Location of the data of an input file slice packet inside a PAR2 file
+///
+/// Lets repair read embedded data straight from the volume that carries it,
+/// the same way `RecoverySliceMetadata` does for recovery data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputFileSliceMetadata {
+    pub set_id: RecoverySetId,
+    pub file_id: FileId,
+    /// Byte offset of the data within the protected file
+    pub offset: u64,
+    /// PAR2 file holding the packet
+    pub file_path: PathBuf,
+    /// Byte offset in `file_path` where the slice data starts
+    pub data_offset: u64,
+    /// Length of the slice data, including padding
+    pub data_size: usize,
+}
+
+impl InputFileSliceMetadata {
+    /// Parse input file slice metadata from a reader positioned at a packet
+    ///
+    /// The slice data is read to verify the packet MD5 but is not kept.
+    pub fn parse_from_reader<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        file_path: PathBuf,
+    ) -> std::io::Result<Self> {
+        use md5::{Digest, Md5};
+
+        let invalid = |message: &str| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+
+        let mut header = [0u8; 64];
+        reader.read_exact(&mut header)?;
+        if &header[0..8] != b"PAR2\0PKT" {
+            return Err(invalid("Invalid PAR2 packet magic"));
+        }
+        if &header[48..64] != TYPE_OF_PACKET {
+            return Err(invalid("Not an input file slice packet"));
+        }
+
+        let length =
+            u64::from_le_bytes(header[8..16].try_into().expect("slice is exactly 8 bytes"));
+        let data_size = length
+            .checked_sub(64 + 16 + 8)
+            .ok_or_else(|| invalid("Invalid packet length"))? as usize;
+
+        let mut fixed = [0u8; 24];
+        reader.read_exact(&mut fixed)?;
+        let data_offset = reader.stream_position()?;
+
+        let mut slice_data = vec![0u8; data_size];
+        reader.read_exact(&mut slice_data)?;
+
+        let mut hasher = Md5::new();
+        hasher.update(&header[32..64]);
+        hasher.update(fixed);
+        hasher.update(&slice_data);
+        if hasher.finalize().as_slice() != &header[16..32] {
+            return Err(invalid("Input file slice packet MD5 mismatch"));
+        }
+
+        Ok(Self {
+            set_id: RecoverySetId::new(header[32..48].try_into().expect("slice is 16 bytes")),
+            file_id: FileId::new(fixed[0..16].try_into().expect("slice is 16 bytes")),
+            offset: u64::from_le_bytes(fixed[16..24].try_into().expect("slice is 8 bytes")),
+            file_path,
+            data_offset,
+            data_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::BinReaderExt;
+
+    fn packet_bytes(offset: u64, slice_data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[7u8; 16]);
+        body.extend_from_slice(TYPE_OF_PACKET);
+        body.extend_from_slice(&[9u8; 16]);
+        body.extend_from_slice(&offset.to_le_bytes());
+        body.extend_from_slice(slice_data);
+        let md5 = crate::checksum::compute_md5_bytes(&body);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"PAR2\0PKT");
+        bytes.extend_from_slice(&(32 + body.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&md5);
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn round_trips_and_verifies() {
+        let bytes = packet_bytes(4096, &[0xAB; 64]);
+        let packet: InputFileSlicePacket = std::io::Cursor::new(&bytes).read_le().unwrap();
+        assert_eq!(packet.file_id, FileId::new([9; 16]));
+        assert_eq!(packet.offset, 4096);
+        assert_eq!(packet.slice_data, vec![0xAB; 64]);
+        assert!(packet.verify());
+
+        let mut written = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut written).unwrap();
+        assert_eq!(written.into_inner(), bytes);
+    }
+
+    #[test]
+    fn metadata_points_at_slice_data() {
+        let mut bytes = vec![0u8; 12];
+        bytes.extend(packet_bytes(128, &[0x5A; 32]));
+        let mut cursor = std::io::Cursor::new(&bytes);
+        cursor.set_position(12);
+
+        let metadata =
+            InputFileSliceMetadata::parse_from_reader(&mut cursor, PathBuf::from("x.par2"))
+                .unwrap();
+        assert_eq!(metadata.file_id, FileId::new([9; 16]));
+        assert_eq!(metadata.offset, 128);
+        assert_eq!(metadata.data_size, 32);
+        let start = metadata.data_offset as usize;
+        assert_eq!(&bytes[start..start + 32], &[0x5A; 32]);
+        assert_eq!(cursor.position() as usize, bytes.len());
+    }
+
+    #[test]
+    fn metadata_rejects_corrupt_data() {
+        let mut bytes = packet_bytes(0, &[1u8; 16]);
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        let result = InputFileSliceMetadata::parse_from_reader(
+            &mut std::io::Cursor::new(&bytes),
+            PathBuf::from("x.par2"),
+        );
+        assert!(result.is_err());
+    }
+}
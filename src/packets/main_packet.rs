@@ -29,7 +29,7 @@ pub struct MainPacket {
     pub set_id: RecoverySetId, // Unique identifier for the PAR2 set
     pub slice_size: u64, // Size of each slice
     pub file_count: u32, // Number of files in the recovery set
-    #[br(count = (length - 72) / 16)]
+    #[br(count = file_count)]
     #[br(map = |v: Vec<[u8; 16]>| v.into_iter().map(FileId::new).collect())]
     pub file_ids: Vec<FileId>, // File IDs of all files in the recovery set
     #[br(count = (length - 72 - (file_ids.len() as u64 * 16)) / 16)]
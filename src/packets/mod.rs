@@ -1,23 +1,40 @@
+use crate::domain::{Md5Hash, RecoverySetId};
 use binrw::BinReaderExt;
+use rustc_hash::FxHashMap as HashMap;
 use std::io::{Read, Seek, SeekFrom};
 
+pub mod ascii_comment_packet;
 pub mod creator_packet;
 pub mod error;
 pub mod file_description_packet;
 pub mod input_file_slice_checksum_packet;
+pub mod input_file_slice_packet;
 pub mod main_packet;
 pub mod packed_main_packet;
+pub mod packed_recovery_slice_packet;
 pub mod processing;
+pub mod recovery_file_slice_checksum_packet;
 pub mod recovery_slice_packet;
+pub mod unicode_comment_packet;
+pub mod unicode_filename_packet;
+pub mod unknown_packet;
 
+pub use ascii_comment_packet::AsciiCommentPacket;
 pub use creator_packet::CreatorPacket;
 pub use error::{PacketParseError, PacketParseResult};
 pub use file_description_packet::FileDescriptionPacket;
 pub use input_file_slice_checksum_packet::InputFileSliceChecksumPacket;
+pub use input_file_slice_packet::{InputFileSliceMetadata, InputFileSlicePacket};
 pub use main_packet::MainPacket;
 pub use packed_main_packet::PackedMainPacket;
+pub use packed_recovery_slice_packet::PackedRecoverySlicePacket;
 pub use processing::*;
+pub use recovery_file_slice_checksum_packet::{
+    RecoveryFileSliceChecksumPacket, RecoverySliceChecksum,
+};
 pub use recovery_slice_packet::{RecoverySliceMetadata, RecoverySlicePacket};
+pub use unicode_comment_packet::UnicodeCommentPacket;
+pub use unicode_filename_packet::UnicodeFilenamePacket;
 
 /// PAR2 packet magic bytes signature
 /// Reference: par2cmdline-turbo/src/par2fileformat.h
@@ -119,8 +136,22 @@ pub enum Packet {
     PackedMain(PackedMainPacket),
     FileDescription(FileDescriptionPacket),
     RecoverySlice(RecoverySlicePacket),
+    PackedRecoverySlice(PackedRecoverySlicePacket),
     Creator(CreatorPacket),
     InputFileSliceChecksum(InputFileSliceChecksumPacket),
+    InputFileSlice(InputFileSlicePacket),
+    UnicodeFilename(UnicodeFilenamePacket),
+    AsciiComment(AsciiCommentPacket),
+    UnicodeComment(UnicodeCommentPacket),
+    RecoveryFileSliceChecksum(RecoveryFileSliceChecksumPacket),
+    /// Packet of a type this library does not interpret, such as an
+    /// application-specific packet; kept verbatim so it can be written back
+    Unknown {
+        packet_type: [u8; 16],
+        set_id: RecoverySetId,
+        md5: Md5Hash,
+        body: Vec<u8>,
+    },
 }
 
 impl Packet {
@@ -130,8 +161,80 @@ impl Packet {
             Packet::PackedMain(packet) => packet.verify(),
             Packet::FileDescription(packet) => packet.verify(),
             Packet::RecoverySlice(packet) => packet.verify(),
+            Packet::PackedRecoverySlice(packet) => packet.verify(),
             Packet::Creator(packet) => packet.verify(),
             Packet::InputFileSliceChecksum(packet) => packet.verify(),
+            Packet::InputFileSlice(packet) => packet.verify(),
+            Packet::UnicodeFilename(packet) => packet.verify(),
+            Packet::AsciiComment(packet) => packet.verify(),
+            Packet::UnicodeComment(packet) => packet.verify(),
+            Packet::RecoveryFileSliceChecksum(packet) => packet.verify(),
+            Packet::Unknown {
+                packet_type,
+                set_id,
+                md5,
+                body,
+            } => unknown_packet::compute_packet_md5(set_id, packet_type, body) == *md5,
+        }
+    }
+
+    /// Recovery set ID from the packet header
+    pub fn set_id(&self) -> RecoverySetId {
+        match self {
+            Packet::Main(p) => p.set_id,
+            Packet::PackedMain(p) => p.set_id,
+            Packet::FileDescription(p) => p.set_id,
+            Packet::InputFileSliceChecksum(p) => p.set_id,
+            Packet::InputFileSlice(p) => p.set_id,
+            Packet::RecoverySlice(p) => p.set_id,
+            Packet::PackedRecoverySlice(p) => p.set_id,
+            Packet::Creator(p) => p.set_id,
+            Packet::UnicodeFilename(p) => p.set_id,
+            Packet::AsciiComment(p) => p.set_id,
+            Packet::UnicodeComment(p) => p.set_id,
+            Packet::RecoveryFileSliceChecksum(p) => p.set_id,
+            Packet::Unknown { set_id, .. } => *set_id,
+        }
+    }
+
+    /// Whether this packet carries recovery data (RecvSlic or PkdRecvS)
+    pub fn is_recovery_slice(&self) -> bool {
+        matches!(
+            self,
+            Packet::RecoverySlice(_) | Packet::PackedRecoverySlice(_)
+        )
+    }
+
+    /// Serialize the packet, header included
+    ///
+    /// Unknown packets are written back byte for byte, so packets from other
+    /// applications survive being copied into new PAR2 files.
+    pub fn write_to<W: std::io::Write + Seek>(&self, writer: &mut W) -> binrw::BinResult<()> {
+        use binrw::BinWrite;
+
+        match self {
+            Packet::Main(p) => p.write_le(writer),
+            Packet::PackedMain(p) => p.write_le(writer),
+            // The derived FileDesc writer leaves out the magic
+            Packet::FileDescription(p) => {
+                writer.write_all(MAGIC_BYTES)?;
+                p.write_le(writer)
+            }
+            Packet::RecoverySlice(p) => p.write_le(writer),
+            Packet::PackedRecoverySlice(p) => p.write_le(writer),
+            Packet::Creator(p) => p.write_le(writer),
+            Packet::InputFileSliceChecksum(p) => p.write_le(writer),
+            Packet::InputFileSlice(p) => p.write_le(writer),
+            Packet::UnicodeFilename(p) => p.write_le(writer),
+            Packet::AsciiComment(p) => p.write_le(writer),
+            Packet::UnicodeComment(p) => p.write_le(writer),
+            Packet::RecoveryFileSliceChecksum(p) => p.write_le(writer),
+            Packet::Unknown {
+                packet_type,
+                set_id,
+                body,
+                ..
+            } => unknown_packet::write(writer, packet_type, set_id, body).map_err(binrw::Error::Io),
         }
     }
 
@@ -170,6 +273,10 @@ impl Packet {
                 .read_le::<RecoverySlicePacket>()
                 .ok()
                 .map(Packet::RecoverySlice),
+            packed_recovery_slice_packet::TYPE_OF_PACKET => reader
+                .read_le::<PackedRecoverySlicePacket>()
+                .ok()
+                .map(Packet::PackedRecoverySlice),
             creator_packet::TYPE_OF_PACKET => {
                 reader.read_le::<CreatorPacket>().ok().map(Packet::Creator)
             }
@@ -177,7 +284,35 @@ impl Packet {
                 .read_le::<InputFileSliceChecksumPacket>()
                 .ok()
                 .map(Packet::InputFileSliceChecksum),
-            _ => None,
+            input_file_slice_packet::TYPE_OF_PACKET => reader
+                .read_le::<InputFileSlicePacket>()
+                .ok()
+                .map(Packet::InputFileSlice),
+            unicode_filename_packet::TYPE_OF_PACKET => reader
+                .read_le::<UnicodeFilenamePacket>()
+                .ok()
+                .map(Packet::UnicodeFilename),
+            ascii_comment_packet::TYPE_OF_PACKET => reader
+                .read_le::<AsciiCommentPacket>()
+                .ok()
+                .map(Packet::AsciiComment),
+            unicode_comment_packet::TYPE_OF_PACKET => reader
+                .read_le::<UnicodeCommentPacket>()
+                .ok()
+                .map(Packet::UnicodeComment),
+            recovery_file_slice_checksum_packet::TYPE_OF_PACKET => reader
+                .read_le::<RecoveryFileSliceChecksumPacket>()
+                .ok()
+                .map(Packet::RecoveryFileSliceChecksum),
+            // Keep other packets verbatim if their MD5 checks out
+            _ => {
+                let mut packet_data = Vec::new();
+                reader
+                    .seek(SeekFrom::Start(0))
+                    .and_then(|_| reader.read_to_end(&mut packet_data))
+                    .ok()
+                    .and_then(|_| unknown_packet::parse(&packet_data))
+            }
         };
 
         packet.ok_or_else(|| {
@@ -204,7 +339,7 @@ pub fn parse_packets<R: Read + Seek>(reader: &mut R) -> Vec<Packet> {
 /// When a packet fails validation, we scan forward byte-by-byte to find
 /// the next valid PAR2\0PKT magic sequence, similar to how par2cmdline recovers
 /// from corrupted packets.
-fn scan_for_next_magic<R: Read>(reader: &mut R) -> std::io::Result<Option<[u8; 8]>> {
+pub(crate) fn scan_for_next_magic<R: Read>(reader: &mut R) -> std::io::Result<Option<[u8; 8]>> {
     let mut buffer = [0u8; 8];
 
     // Try to read initial 8 bytes
@@ -251,12 +386,31 @@ fn rewind_after_invalid_header<R: Seek>(reader: &mut R) -> std::io::Result<()> {
 ///
 /// Returns (packets, recovery_block_count) where recovery_block_count includes validated
 /// recovery blocks even when include_recovery_slices=false.
+///
+/// Packets repeated in the stream, such as the critical packet copies spread
+/// through a recovery volume, are returned once, preferring a copy whose MD5
+/// verifies.
 pub fn parse_packets_with_options<R: Read + Seek>(
     reader: &mut R,
     include_recovery_slices: bool,
 ) -> (Vec<Packet>, usize) {
+    let (packets, recovery_blocks_by_set) =
+        parse_packets_with_set_counts(reader, include_recovery_slices);
+    (packets, recovery_blocks_by_set.values().sum())
+}
+
+/// Parse packets like [`parse_packets_with_options`], counting recovery blocks per set
+///
+/// Recovery slices of every set are validated and counted under the recovery
+/// set ID in their header, so files holding several sets can be split later.
+pub fn parse_packets_with_set_counts<R: Read + Seek>(
+    reader: &mut R,
+    include_recovery_slices: bool,
+) -> (Vec<Packet>, HashMap<RecoverySetId, usize>) {
     let mut packets = Vec::new();
-    let mut recovery_block_count = 0;
+    let mut recovery_blocks_by_set: HashMap<RecoverySetId, usize> = HashMap::default();
+    // Index of each distinct packet in `packets`, and whether it verified
+    let mut packet_slots: HashMap<[u8; 16], (usize, bool)> = HashMap::default();
 
     loop {
         // Try to parse packet header
@@ -286,10 +440,15 @@ pub fn parse_packets_with_options<R: Read + Seek>(
         };
 
         // Special handling for recovery slice packets when not loading data
-        if !include_recovery_slices && header.packet_type == recovery_slice_packet::TYPE_OF_PACKET {
+        if !include_recovery_slices && is_recovery_slice_type(&header.packet_type) {
             match validate_recovery_packet(reader, &header) {
                 Ok(()) => {
-                    recovery_block_count += 1;
+                    let set_id = RecoverySetId::new(
+                        header.raw[32..48]
+                            .try_into()
+                            .expect("slice is exactly 16 bytes"),
+                    );
+                    *recovery_blocks_by_set.entry(set_id).or_default() += 1;
                 }
                 Err(_) => {
                     // Validation failed - try to find next valid packet
@@ -324,16 +483,42 @@ pub fn parse_packets_with_options<R: Read + Seek>(
 
         let mut cursor = std::io::Cursor::new(&packet_data);
         if let Ok(packet) = Packet::match_packet_type(&mut cursor, &header.packet_type) {
+            // The packet MD5 covers its whole body, so equal hashes mean a repeat.
+            // A damaged copy is kept only until an intact one turns up.
+            let packet_hash: [u8; 16] = header.raw[16..32]
+                .try_into()
+                .expect("slice is exactly 16 bytes");
+            let verified = packet.is_recovery_slice() || packet.verify();
+            match packet_slots.get_mut(&packet_hash) {
+                Some((_, true)) => continue,
+                Some((index, intact)) => {
+                    if verified {
+                        packets[*index] = packet;
+                        *intact = true;
+                    }
+                    continue;
+                }
+                None => {
+                    packet_slots.insert(packet_hash, (packets.len(), verified));
+                }
+            }
             // Count recovery slices when we're loading them
-            if matches!(packet, Packet::RecoverySlice(_)) {
-                recovery_block_count += 1;
+            if packet.is_recovery_slice() {
+                *recovery_blocks_by_set.entry(packet.set_id()).or_default() += 1;
             }
             packets.push(packet);
         }
-        // Note: We silently skip unknown packet types to maintain forward compatibility
+        // Note: Packets of unknown type are kept as Packet::Unknown when their MD5
+        // verifies, and silently skipped otherwise
     }
 
-    (packets, recovery_block_count)
+    (packets, recovery_blocks_by_set)
+}
+
+/// Whether a packet type identifies recovery data (RecvSlic or PkdRecvS)
+pub(crate) fn is_recovery_slice_type(packet_type: &[u8]) -> bool {
+    packet_type == recovery_slice_packet::TYPE_OF_PACKET
+        || packet_type == packed_recovery_slice_packet::TYPE_OF_PACKET
 }
 
 /// Validate a recovery packet by loading it with binrw and checking its MD5
@@ -347,17 +532,26 @@ fn validate_recovery_packet<R: Read + Seek>(
 
     // Parse the recovery packet to validate structure
     let mut cursor = std::io::Cursor::new(&packet_data);
-    let packet = cursor
-        .read_le::<RecoverySlicePacket>()
-        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    let (verified, exponent) = if header.packet_type == packed_recovery_slice_packet::TYPE_OF_PACKET
+    {
+        let packet = cursor
+            .read_le::<PackedRecoverySlicePacket>()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+        (packet.verify(), packet.exponent)
+    } else {
+        let packet = cursor
+            .read_le::<RecoverySlicePacket>()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+        (packet.verify(), packet.exponent)
+    };
 
     // Verify the MD5 hash
-    if !packet.verify() {
+    if !verified {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
             format!(
                 "Recovery packet MD5 verification failed for exponent {}",
-                packet.exponent
+                exponent
             ),
         ));
     }
@@ -532,6 +726,30 @@ mod tests {
             let result = Packet::parse(&mut cursor);
             assert!(matches!(result, Err(PacketParseError::InvalidMagic(_))));
         }
+
+        #[test]
+        fn write_to_round_trips_known_and_unknown_packets() {
+            let mut data =
+                include_bytes!("../../tests/fixtures/packets/CreatorPacket.par2").to_vec();
+            data.extend_from_slice(include_bytes!(
+                "../../tests/fixtures/packets/FileDescriptionPacket.par2"
+            ));
+            unknown_packet::write(
+                &mut data,
+                b"MyApp\0\0\0Metadata",
+                &RecoverySetId::new([7; 16]),
+                &[1, 2, 3, 4],
+            )
+            .unwrap();
+
+            let packets = parse_packets(&mut Cursor::new(&data));
+            assert_eq!(packets.len(), 3);
+            let mut written = Cursor::new(Vec::new());
+            for packet in &packets {
+                packet.write_to(&mut written).unwrap();
+            }
+            assert_eq!(written.into_inner(), data);
+        }
     }
 
     mod parse_packets_with_options {
@@ -568,6 +786,32 @@ mod tests {
             assert!(matches!(packets[0], Packet::Creator(_)));
         }
 
+        #[test]
+        fn parse_returns_repeated_packets_once() {
+            let creator = include_bytes!("../../tests/fixtures/packets/CreatorPacket.par2");
+            let mut data = creator.to_vec();
+            data.extend_from_slice(creator);
+            data.extend_from_slice(creator);
+
+            let packets = parse_packets(&mut Cursor::new(&data));
+            assert_eq!(packets.len(), 1);
+            assert!(matches!(packets[0], Packet::Creator(_)));
+        }
+
+        #[test]
+        fn parse_prefers_an_intact_copy_of_a_damaged_packet() {
+            let creator = include_bytes!("../../tests/fixtures/packets/CreatorPacket.par2");
+            let mut damaged = creator.to_vec();
+            let last = damaged.len() - 1;
+            damaged[last] ^= 0xFF;
+            let mut data = damaged;
+            data.extend_from_slice(creator);
+
+            let packets = parse_packets(&mut Cursor::new(&data));
+            assert_eq!(packets.len(), 1);
+            assert!(packets[0].verify());
+        }
+
         #[test]
         fn parse_multiple_unknown_packets() {
             let unknown_type = [0xFFu8; 16];
@@ -0,0 +1,128 @@
+use crate::domain::{Md5Hash, RecoverySetId};
+use binrw::{BinRead, BinWrite};
+
+pub const TYPE_OF_PACKET: &[u8] = b"PAR 2.0\0PkdRecvS";
+
+/// Packed recovery slice packet
+///
+/// Same body layout as a RecvSlic packet (exponent followed by one slice of
+/// recovery data), but the data was computed over the packed input stream: every
+/// file padded to a whole number of subslices and concatenated in PkdMain order.
+/// Repair uses `RecoverySliceMetadata` for these as well, so the data is normally
+/// never loaded through this type.
+#[derive(Debug, Clone, BinRead)]
+#[br(magic = b"PAR2\0PKT")]
+pub struct PackedRecoverySlicePacket {
+    pub length: u64, // Length of the packet
+    #[br(map = |x: [u8; 16]| Md5Hash::new(x))]
+    pub md5: Md5Hash, // MD5 hash of the packet
+    #[br(map = |x: [u8; 16]| RecoverySetId::new(x))]
+    pub set_id: RecoverySetId, // Unique identifier for the PAR2 set
+    #[br(assert(&type_of_packet[..] == TYPE_OF_PACKET))]
+    pub type_of_packet: [u8; 16], // Type of packet - should be "PAR 2.0\0PkdRecvS"
+    pub exponent: u32, // Exponent used to generate recovery data
+    #[br(count = length as usize - (8 + 8 + 16 + 16 + 16 + 4))]
+    pub recovery_data: Vec<u8>, // Recovery data over the packed subslice stream
+}
+
+impl PackedRecoverySlicePacket {
+    /// Verifies the MD5 hash of the packet.
+    /// Computes the MD5 hash of the serialized fields and compares it to the stored MD5 value.
+    pub fn verify(&self) -> bool {
+        if self.length < 68 {
+            println!("Invalid packet length: {}", self.length);
+            return false;
+        }
+        let mut data = Vec::new();
+        data.extend_from_slice(self.set_id.as_bytes());
+        data.extend_from_slice(TYPE_OF_PACKET);
+        data.extend_from_slice(&self.exponent.to_le_bytes());
+        data.extend_from_slice(&self.recovery_data);
+        let computed_md5 = crate::checksum::compute_md5_bytes(&data);
+        if computed_md5 != *self.md5.as_bytes() {
+            return false;
+        }
+
+        // Check that BinWrite output matches the packet length
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        if self.write_le(&mut buffer).is_err() {
+            println!("Failed to serialize packet");
+            return false;
+        }
+
+        let serialized_length = buffer.get_ref().len() as u64;
+        if serialized_length != self.length {
+            println!(
+                "Serialized length mismatch: expected {}, got {}",
+                self.length, serialized_length
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+impl BinWrite for PackedRecoverySlicePacket {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        writer.write_all(b"PAR2\0PKT")?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        writer.write_all(self.md5.as_bytes())?;
+        writer.write_all(self.set_id.as_bytes())?;
+        writer.write_all(TYPE_OF_PACKET)?;
+        writer.write_all(&self.exponent.to_le_bytes())?;
+        writer.write_all(&self.recovery_data)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::BinReaderExt;
+
+    fn packet_bytes(exponent: u32, recovery_data: &[u8]) -> Vec<u8> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[7u8; 16]);
+        body.extend_from_slice(TYPE_OF_PACKET);
+        body.extend_from_slice(&exponent.to_le_bytes());
+        body.extend_from_slice(recovery_data);
+        let md5 = crate::checksum::compute_md5_bytes(&body);
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"PAR2\0PKT");
+        bytes.extend_from_slice(&(32 + body.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&md5);
+        bytes.extend_from_slice(&body);
+        bytes
+    }
+
+    #[test]
+    fn round_trips_and_verifies() {
+        let bytes = packet_bytes(3, &[0xAB; 64]);
+        let packet: PackedRecoverySlicePacket = std::io::Cursor::new(&bytes).read_le().unwrap();
+        assert_eq!(packet.exponent, 3);
+        assert_eq!(packet.recovery_data, vec![0xAB; 64]);
+        assert!(packet.verify());
+
+        let mut written = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut written).unwrap();
+        assert_eq!(written.into_inner(), bytes);
+    }
+
+    #[test]
+    fn rejects_recovery_slice_type() {
+        let mut bytes = packet_bytes(0, &[0u8; 8]);
+        bytes[48..64].copy_from_slice(crate::packets::recovery_slice_packet::TYPE_OF_PACKET);
+        let result: binrw::BinResult<PackedRecoverySlicePacket> =
+            std::io::Cursor::new(&bytes).read_le();
+        assert!(result.is_err());
+    }
+}
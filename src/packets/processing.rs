@@ -1,4 +1,4 @@
-use crate::domain::{Crc32Value, FileId, Md5Hash};
+use crate::domain::{Crc32Value, FileId, Md5Hash, RecoverySetId};
 use crate::packets::{FileDescriptionPacket, MainPacket, Packet};
 use rustc_hash::FxHashMap as HashMap;
 
@@ -20,14 +20,102 @@ pub fn extract_main_packet(packets: &[Packet]) -> Option<&MainPacket> {
     })
 }
 
-/// Count recovery blocks available
+/// Slice layout of a recovery set
 ///
-/// Returns the total number of RecoverySlice packets
-pub fn count_recovery_blocks(packets: &[Packet]) -> usize {
+/// Taken from the Main packet, or from the PackedMain packet when the set is packed.
+/// In a packed set every file is padded to a whole number of subslices and the
+/// files are concatenated in `file_ids` order; IFSC checksums then describe
+/// subslices, and each recovery slice covers `slice_size / subslice_size`
+/// consecutive subslices of that stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceLayout {
+    pub set_id: RecoverySetId,
+    pub slice_size: u64,
+    /// Subslice size for packed sets, `None` for ordinary sets
+    pub subslice_size: Option<u64>,
+    /// Recovery set file IDs in global slice order
+    pub file_ids: Vec<FileId>,
+}
+
+impl SliceLayout {
+    /// Size of the blocks described by IFSC checksums (the subslice size when packed)
+    pub fn block_size(&self) -> u64 {
+        self.subslice_size.unwrap_or(self.slice_size)
+    }
+
+    /// Number of subslices making up one recovery slice, for packed sets
+    pub fn subslices_per_slice(&self) -> Option<usize> {
+        self.subslice_size
+            .map(|subslice_size| (self.slice_size / subslice_size) as usize)
+    }
+
+    /// Map a global block index to the recovery slice that contains it
+    pub fn slice_for_block(&self, global_block: usize) -> usize {
+        global_block / self.subslices_per_slice().unwrap_or(1)
+    }
+}
+
+/// Extract the slice layout of the recovery set
+///
+/// Prefers the Main packet. Falls back to a PackedMain packet whose subslice size
+/// is a non-zero multiple of 4 that evenly divides the slice size.
+pub fn extract_slice_layout(packets: &[Packet]) -> Option<SliceLayout> {
+    if let Some(main) = extract_main_packet(packets) {
+        return Some(SliceLayout {
+            set_id: main.set_id,
+            slice_size: main.slice_size,
+            subslice_size: None,
+            file_ids: main.file_ids.clone(),
+        });
+    }
+
+    packets.iter().find_map(|p| match p {
+        Packet::PackedMain(packed)
+            if packed.subslice_size > 0
+                && packed.subslice_size % 4 == 0
+                && packed.slice_size % packed.subslice_size == 0 =>
+        {
+            Some(SliceLayout {
+                set_id: packed.set_id,
+                slice_size: packed.slice_size,
+                subslice_size: Some(packed.subslice_size),
+                file_ids: packed.recovery_set_ids.clone(),
+            })
+        }
+        _ => None,
+    })
+}
+
+/// Extract the file IDs of the non-recovery set
+///
+/// Non-recovery files are described and checksummed like recovery files, but
+/// are not part of the Reed-Solomon input and cannot be repaired. An ID that
+/// also appears in the recovery set is treated as a recovery file.
+pub fn extract_non_recovery_file_ids(packets: &[Packet]) -> Vec<FileId> {
     packets
         .iter()
-        .filter(|p| matches!(p, Packet::RecoverySlice(_)))
-        .count()
+        .find_map(|p| match p {
+            Packet::Main(main) => Some((&main.file_ids, &main.non_recovery_file_ids)),
+            Packet::PackedMain(packed) => {
+                Some((&packed.recovery_set_ids, &packed.non_recovery_set_ids))
+            }
+            _ => None,
+        })
+        .map(|(recovery_ids, non_recovery_ids)| {
+            non_recovery_ids
+                .iter()
+                .filter(|id| !recovery_ids.contains(id))
+                .copied()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Count recovery blocks available
+///
+/// Returns the total number of RecoverySlice and PackedRecoverySlice packets
+pub fn count_recovery_blocks(packets: &[Packet]) -> usize {
+    packets.iter().filter(|p| p.is_recovery_slice()).count()
 }
 
 /// Extract file descriptions (deduplicated by file_id)
@@ -70,8 +158,9 @@ pub fn extract_slice_checksums(packets: &[Packet]) -> HashMap<FileId, Vec<(Md5Ha
 /// Returns (block_size, total_blocks) calculated from MainPacket and FileDescriptions
 /// This is more efficient than calculating separately
 pub fn extract_main_stats(packets: &[Packet]) -> (u64, usize) {
-    let main_packet = extract_main_packet(packets);
-    let block_size = main_packet.map(|m| m.slice_size).unwrap_or(0);
+    let block_size = extract_slice_layout(packets)
+        .map(|layout| layout.block_size())
+        .unwrap_or(0);
 
     let total_blocks = if block_size > 0 {
         extract_file_descriptions(packets)
@@ -85,6 +174,121 @@ pub fn extract_main_stats(packets: &[Packet]) -> (u64, usize) {
     (block_size, total_blocks)
 }
 
+/// Extract Unicode file names indexed by file ID
+///
+/// Returns the decoded name from each UniFileN packet. These take precedence over
+/// the 8-bit names in FileDescription packets when resolving on-disk targets.
+pub fn extract_unicode_filenames(packets: &[Packet]) -> HashMap<FileId, String> {
+    packets
+        .iter()
+        .filter_map(|p| {
+            if let Packet::UnicodeFilename(uni) = p {
+                Some((uni.file_id, uni.file_name()))
+            } else {
+                None
+            }
+        })
+        .filter(|(_, name)| !name.is_empty())
+        .collect()
+}
+
+/// Extract comments from CommASCI and CommUni packets
+///
+/// Returns each distinct comment once, in the order first seen. An ASCII comment
+/// whose packet MD5 is referenced by a Unicode comment is replaced by that Unicode
+/// text, as the PAR2 spec asks readers to prefer the Unicode form.
+pub fn extract_comments(packets: &[Packet]) -> Vec<String> {
+    let translated: rustc_hash::FxHashSet<Md5Hash> = packets
+        .iter()
+        .filter_map(|p| match p {
+            Packet::UnicodeComment(uni) if uni.ascii_comment_md5 != Md5Hash::new([0; 16]) => {
+                Some(uni.ascii_comment_md5)
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut comments: Vec<String> = Vec::new();
+    for packet in packets {
+        let text = match packet {
+            Packet::UnicodeComment(uni) => uni.comment_text(),
+            Packet::AsciiComment(ascii) if !translated.contains(&ascii.md5) => ascii.comment_text(),
+            _ => continue,
+        };
+        if !text.is_empty() && !comments.contains(&text) {
+            comments.push(text);
+        }
+    }
+    comments
+}
+
+/// Map embedded FileSlic data onto the IFSC block it restores
+///
+/// Embedded data can stand in for a block only when it starts on a block boundary
+/// and covers the whole block (the last block of a file may be short).
+/// Returns the block index and the number of file bytes in that block.
+pub fn embedded_block(
+    offset: u64,
+    data_len: u64,
+    file_length: u64,
+    block_size: u64,
+) -> Option<(usize, usize)> {
+    if block_size == 0 || !offset.is_multiple_of(block_size) || offset >= file_length {
+        return None;
+    }
+    let block_len = block_size.min(file_length - offset);
+    (data_len >= block_len).then_some(((offset / block_size) as usize, block_len as usize))
+}
+
+/// Extract the blocks that FileSlic packets can restore, indexed by file ID
+///
+/// Only embedded data matching the block's IFSC checksums is counted.
+pub fn extract_embedded_blocks(
+    packets: &[Packet],
+    block_size: u64,
+) -> HashMap<FileId, rustc_hash::FxHashSet<u32>> {
+    let file_lengths: HashMap<FileId, u64> = extract_file_descriptions(packets)
+        .into_iter()
+        .map(|fd| (fd.file_id, fd.file_length))
+        .collect();
+    let slice_checksums = extract_slice_checksums(packets);
+
+    let mut embedded: HashMap<FileId, rustc_hash::FxHashSet<u32>> = HashMap::default();
+    for packet in packets {
+        let Packet::InputFileSlice(slice) = packet else {
+            continue;
+        };
+        let Some(&file_length) = file_lengths.get(&slice.file_id) else {
+            continue;
+        };
+        let Some((block, block_len)) = embedded_block(
+            slice.offset,
+            slice.slice_data.len() as u64,
+            file_length,
+            block_size,
+        ) else {
+            continue;
+        };
+        let Some(&expected) = slice_checksums
+            .get(&slice.file_id)
+            .and_then(|checksums| checksums.get(block))
+        else {
+            continue;
+        };
+        let computed = crate::checksum::compute_block_checksums_padded(
+            &slice.slice_data[..block_len],
+            block_size as usize,
+        );
+        if computed == expected {
+            embedded
+                .entry(slice.file_id)
+                .or_default()
+                .insert(block as u32);
+        }
+    }
+    embedded
+}
+
 /// Extract file information as a map from filename to (file_id, md5_hash, file_length)
 ///
 /// This is useful for file analysis and verification operations
@@ -103,10 +307,11 @@ pub fn extract_file_info(packets: &[Packet]) -> HashMap<String, (FileId, Md5Hash
 /// Extract file descriptions in main packet order
 ///
 /// Returns FileDescriptions in the same order as specified in MainPacket.file_ids
+/// (or PackedMainPacket.recovery_set_ids for packed sets)
 /// This is critical for correct global slice indexing in repair operations
 pub fn extract_ordered_file_descriptions(packets: &[Packet]) -> Vec<FileDescriptionPacket> {
-    let main_packet = match extract_main_packet(packets) {
-        Some(main) => main,
+    let layout = match extract_slice_layout(packets) {
+        Some(layout) => layout,
         None => return Vec::new(),
     };
 
@@ -123,7 +328,7 @@ pub fn extract_ordered_file_descriptions(packets: &[Packet]) -> Vec<FileDescript
         .collect();
 
     // Return files in main packet order
-    main_packet
+    layout
         .file_ids
         .iter()
         .filter_map(|file_id| fd_map.get(file_id).cloned())
@@ -152,7 +357,7 @@ pub fn separate_packets(
             Packet::Main(main) => main_packet = Some(main),
             Packet::FileDescription(fd) => file_descriptions.push(fd),
             Packet::InputFileSliceChecksum(ifsc) => slice_checksums.push(ifsc),
-            Packet::RecoverySlice(_) => recovery_count += 1,
+            Packet::RecoverySlice(_) | Packet::PackedRecoverySlice(_) => recovery_count += 1,
             _ => {} // Ignore other packet types
         }
     }
@@ -319,4 +524,137 @@ mod tests {
         assert_eq!(ordered_fds[0].file_id, FileId::new([2; 16]));
         assert_eq!(ordered_fds[1].file_id, FileId::new([1; 16]));
     }
+
+    #[test]
+    fn test_extract_comments_prefers_unicode_translation() {
+        use crate::packets::{AsciiCommentPacket, UnicodeCommentPacket};
+
+        let ascii = |md5: u8, text: &str| {
+            Packet::AsciiComment(AsciiCommentPacket {
+                length: 64 + text.len() as u64,
+                md5: Md5Hash::new([md5; 16]),
+                set_id: RecoverySetId::new([1; 16]),
+                comment: text.as_bytes().to_vec(),
+            })
+        };
+        let unicode = |ascii_md5: u8, text: &str| {
+            Packet::UnicodeComment(UnicodeCommentPacket {
+                length: 0,
+                md5: Md5Hash::new([0xEE; 16]),
+                set_id: RecoverySetId::new([1; 16]),
+                ascii_comment_md5: Md5Hash::new([ascii_md5; 16]),
+                comment: text.encode_utf16().collect(),
+            })
+        };
+
+        let packets = vec![
+            ascii(7, "tape ? 14\0\0\0"),
+            unicode(7, "tape № 14"),
+            ascii(8, "plain note"),
+            ascii(8, "plain note"),
+        ];
+
+        assert_eq!(extract_comments(&packets), vec!["tape № 14", "plain note"]);
+    }
+
+    fn create_test_packed_main_packet(subslice_size: u64) -> crate::packets::PackedMainPacket {
+        crate::packets::PackedMainPacket {
+            length: 104,
+            md5: Md5Hash::new([0; 16]),
+            set_id: RecoverySetId::new([1; 16]),
+            subslice_size,
+            slice_size: 1024,
+            file_count: 2,
+            recovery_set_ids: vec![FileId::new([2; 16]), FileId::new([1; 16])],
+            non_recovery_set_ids: vec![],
+        }
+    }
+
+    #[test]
+    fn test_extract_slice_layout_prefers_main_packet() {
+        let packets = vec![
+            Packet::PackedMain(create_test_packed_main_packet(256)),
+            Packet::Main(create_test_main_packet()),
+        ];
+
+        let layout = extract_slice_layout(&packets).unwrap();
+        assert_eq!(layout.subslice_size, None);
+        assert_eq!(layout.block_size(), 1024);
+        assert_eq!(layout.slice_for_block(3), 3);
+    }
+
+    #[test]
+    fn test_extract_slice_layout_from_packed_main() {
+        let packets = vec![Packet::PackedMain(create_test_packed_main_packet(256))];
+
+        let layout = extract_slice_layout(&packets).unwrap();
+        assert_eq!(layout.block_size(), 256);
+        assert_eq!(layout.subslices_per_slice(), Some(4));
+        assert_eq!(layout.slice_for_block(3), 0);
+        assert_eq!(layout.slice_for_block(4), 1);
+        assert_eq!(
+            layout.file_ids,
+            vec![FileId::new([2; 16]), FileId::new([1; 16])]
+        );
+    }
+
+    #[test]
+    fn test_extract_slice_layout_rejects_uneven_subslices() {
+        let packets = vec![Packet::PackedMain(create_test_packed_main_packet(384))];
+        assert!(extract_slice_layout(&packets).is_none());
+    }
+
+    #[test]
+    fn test_embedded_block_requires_aligned_full_block() {
+        assert_eq!(embedded_block(2048, 1024, 3000, 1024), Some((2, 952)));
+        assert_eq!(embedded_block(1024, 1024, 3000, 1024), Some((1, 1024)));
+        assert_eq!(embedded_block(1000, 1024, 3000, 1024), None);
+        assert_eq!(embedded_block(1024, 512, 3000, 1024), None);
+        assert_eq!(embedded_block(3072, 1024, 3000, 1024), None);
+    }
+
+    #[test]
+    fn test_extract_embedded_blocks_checks_ifsc() {
+        use crate::packets::{InputFileSliceChecksumPacket, InputFileSlicePacket};
+
+        let file_id = FileId::new([1; 16]);
+        let data: Vec<u8> = (0..1500u32).map(|i| (i * 7 % 256) as u8).collect();
+        let slice_checksums = data
+            .chunks(1024)
+            .map(|chunk| crate::checksum::compute_block_checksums_padded(chunk, 1024))
+            .collect();
+        let embed = |offset: usize, bytes: Vec<u8>| {
+            Packet::InputFileSlice(InputFileSlicePacket {
+                length: 0,
+                md5: Md5Hash::new([0; 16]),
+                set_id: RecoverySetId::new([1; 16]),
+                file_id,
+                offset: offset as u64,
+                slice_data: bytes,
+            })
+        };
+
+        let mut tampered = data[..1024].to_vec();
+        tampered[10] ^= 0xFF;
+        let mut tail = data[1024..].to_vec();
+        tail.resize(480, 0);
+
+        let packets = vec![
+            Packet::Main(create_test_main_packet()),
+            Packet::FileDescription(create_test_file_description(file_id, "a.bin", 1500)),
+            Packet::InputFileSliceChecksum(InputFileSliceChecksumPacket {
+                length: 0,
+                md5: Md5Hash::new([0; 16]),
+                set_id: RecoverySetId::new([1; 16]),
+                file_id,
+                slice_checksums,
+            }),
+            embed(0, tampered),
+            embed(1024, tail),
+        ];
+
+        let embedded = extract_embedded_blocks(&packets, 1024);
+        let blocks: Vec<u32> = embedded[&file_id].iter().copied().collect();
+        assert_eq!(blocks, vec![1]);
+    }
 }
@@ -0,0 +1,186 @@
+use crate::domain::{Crc32Value, Md5Hash, RecoverySetId};
+use binrw::{BinRead, BinWrite};
+
+pub const TYPE_OF_PACKET: &[u8] = b"PAR 2.0\0RFSC\0\0\0\0";
+
+/// Checksums of one recovery slice stored in an external file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoverySliceChecksum {
+    pub md5: Md5Hash,
+    pub crc32: Crc32Value,
+    pub exponent: u32,
+}
+
+/// Optional recovery file slice checksum packet
+///
+/// Describes recovery data kept outside of any packet, as raw slices laid end to
+/// end in an external file. Each entry holds the MD5, CRC32 and exponent of one
+/// slice, so the data can be located and validated without packet headers.
+#[derive(Debug, Clone)]
+pub struct RecoveryFileSliceChecksumPacket {
+    pub length: u64,                                 // Length of the packet
+    pub md5: Md5Hash,                                // MD5 hash of the packet
+    pub set_id: RecoverySetId,                       // Unique identifier for the PAR2 set
+    pub slice_checksums: Vec<RecoverySliceChecksum>, // One entry per external slice
+}
+
+impl BinRead for RecoveryFileSliceChecksumPacket {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<Self> {
+        let mut header = [0u8; 64];
+        reader.read_exact(&mut header).map_err(binrw::Error::Io)?;
+
+        if &header[0..8] != b"PAR2\0PKT" {
+            return Err(binrw::Error::AssertFail {
+                pos: 0,
+                message: "Invalid magic".to_string(),
+            });
+        }
+
+        let length =
+            u64::from_le_bytes(header[8..16].try_into().expect("slice is exactly 8 bytes"));
+        if length < 64 || (length - 64) % 24 != 0 {
+            return Err(binrw::Error::AssertFail {
+                pos: 8,
+                message: format!("Invalid RFSC packet length: {}", length),
+            });
+        }
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&header[16..32]);
+        let mut set_id = [0u8; 16];
+        set_id.copy_from_slice(&header[32..48]);
+
+        let mut buffer = vec![0u8; (length - 64) as usize];
+        reader.read_exact(&mut buffer).map_err(binrw::Error::Io)?;
+
+        let slice_checksums = buffer
+            .as_chunks::<24>()
+            .0
+            .iter()
+            .map(|chunk| RecoverySliceChecksum {
+                md5: Md5Hash::new(chunk[0..16].try_into().expect("slice is 16 bytes")),
+                crc32: Crc32Value::new(u32::from_le_bytes(
+                    chunk[16..20].try_into().expect("slice is 4 bytes"),
+                )),
+                exponent: u32::from_le_bytes(chunk[20..24].try_into().expect("slice is 4 bytes")),
+            })
+            .collect();
+
+        Ok(RecoveryFileSliceChecksumPacket {
+            length,
+            md5: Md5Hash::new(md5),
+            set_id: RecoverySetId::new(set_id),
+            slice_checksums,
+        })
+    }
+}
+
+impl RecoveryFileSliceChecksumPacket {
+    /// Verifies the MD5 hash of the packet.
+    /// Computes the MD5 hash of the serialized fields and compares it to the stored MD5 value.
+    pub fn verify(&self) -> bool {
+        if self.length < 64 {
+            println!("Invalid packet length: {}", self.length);
+            return false;
+        }
+        let mut data = Vec::new();
+        data.extend_from_slice(self.set_id.as_bytes());
+        data.extend_from_slice(TYPE_OF_PACKET);
+        for checksum in &self.slice_checksums {
+            data.extend_from_slice(checksum.md5.as_bytes());
+            data.extend_from_slice(&checksum.crc32.to_le_bytes());
+            data.extend_from_slice(&checksum.exponent.to_le_bytes());
+        }
+        let computed_md5 = crate::checksum::compute_md5_bytes(&data);
+        if computed_md5 != *self.md5.as_bytes() {
+            println!(
+                "MD5 mismatch: computed {:?}, expected {:?}",
+                computed_md5, self.md5
+            );
+            return false;
+        }
+
+        // Check that BinWrite output matches the packet length
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        if self.write_le(&mut buffer).is_err() {
+            println!("Failed to serialize packet");
+            return false;
+        }
+
+        let serialized_length = buffer.get_ref().len() as u64;
+        if serialized_length != self.length {
+            println!(
+                "Serialized length mismatch: expected {}, got {}",
+                self.length, serialized_length
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+impl BinWrite for RecoveryFileSliceChecksumPacket {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        writer.write_all(b"PAR2\0PKT")?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        writer.write_all(self.md5.as_bytes())?;
+        writer.write_all(self.set_id.as_bytes())?;
+        writer.write_all(TYPE_OF_PACKET)?;
+        for checksum in &self.slice_checksums {
+            writer.write_all(checksum.md5.as_bytes())?;
+            writer.write_all(&checksum.crc32.to_le_bytes())?;
+            writer.write_all(&checksum.exponent.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use binrw::BinReaderExt;
+
+    #[test]
+    fn round_trips_and_verifies() {
+        let mut body = Vec::new();
+        body.extend_from_slice(&[3u8; 16]);
+        body.extend_from_slice(TYPE_OF_PACKET);
+        for exponent in [0u32, 7] {
+            body.extend_from_slice(&[exponent as u8 + 1; 16]);
+            body.extend_from_slice(&(0xDEAD_0000 + exponent).to_le_bytes());
+            body.extend_from_slice(&exponent.to_le_bytes());
+        }
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"PAR2\0PKT");
+        bytes.extend_from_slice(&(32 + body.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&crate::checksum::compute_md5_bytes(&body));
+        bytes.extend_from_slice(&body);
+
+        let packet: RecoveryFileSliceChecksumPacket =
+            std::io::Cursor::new(&bytes).read_le().unwrap();
+        assert!(packet.verify());
+        assert_eq!(packet.slice_checksums.len(), 2);
+        assert_eq!(packet.slice_checksums[1].exponent, 7);
+        assert_eq!(
+            packet.slice_checksums[1].crc32,
+            Crc32Value::new(0xDEAD_0007)
+        );
+
+        let mut written = std::io::Cursor::new(Vec::new());
+        packet.write_le(&mut written).unwrap();
+        assert_eq!(written.into_inner(), bytes);
+    }
+}
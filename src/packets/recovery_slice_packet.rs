@@ -74,6 +74,11 @@ impl RecoverySliceMetadata {
         self.loader.data_size()
     }
 
+    /// Whether the recovery data matches the checksums recorded for it
+    pub fn is_intact(&self) -> std::io::Result<bool> {
+        self.loader.is_intact()
+    }
+
     /// Parse recovery slice metadata from a reader without loading the data
     /// This is the memory-efficient alternative to parsing RecoverySlicePacket
     pub fn parse_from_reader<R: std::io::Read + std::io::Seek>(
@@ -104,8 +109,8 @@ impl RecoverySliceMetadata {
             .try_into()
             .expect("slice is exactly 16 bytes");
 
-        // Check type
-        if type_bytes != *TYPE_OF_PACKET {
+        // Check type - packed recovery slices share the same body layout
+        if !crate::packets::is_recovery_slice_type(&type_bytes) {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 "Not a recovery slice packet",
@@ -0,0 +1,152 @@
+use crate::domain::{Md5Hash, RecoverySetId};
+use binrw::{BinRead, BinWrite};
+
+pub const TYPE_OF_PACKET: &[u8] = b"PAR 2.0\0CommUni\0";
+
+/// Optional Unicode comment packet
+///
+/// Holds UTF-16LE comment text. When an ASCII comment packet is just the
+/// translation of this comment, `ascii_comment_md5` holds that packet's MD5 so
+/// readers can show the Unicode text in its place; otherwise it is all zeros.
+#[derive(Debug, Clone)]
+pub struct UnicodeCommentPacket {
+    pub length: u64,                // Length of the packet
+    pub md5: Md5Hash,               // MD5 hash of the packet
+    pub set_id: RecoverySetId,      // Unique identifier for the PAR2 set
+    pub ascii_comment_md5: Md5Hash, // MD5 of the matching ASCII comment packet, or zeros
+    pub comment: Vec<u16>,          // UTF-16 code units, including any trailing padding
+}
+
+impl UnicodeCommentPacket {
+    /// Decode the comment text, dropping trailing NUL padding
+    pub fn comment_text(&self) -> String {
+        let end = self
+            .comment
+            .iter()
+            .rposition(|&unit| unit != 0)
+            .map_or(0, |pos| pos + 1);
+        String::from_utf16_lossy(&self.comment[..end])
+    }
+
+    /// Verifies the MD5 hash of the packet.
+    /// Computes the MD5 hash of the serialized fields and compares it to the stored MD5 value.
+    pub fn verify(&self) -> bool {
+        if self.length < 64 + 16 {
+            println!("Invalid packet length: {}", self.length);
+            return false;
+        }
+        let mut data = Vec::new();
+        data.extend_from_slice(self.set_id.as_bytes());
+        data.extend_from_slice(TYPE_OF_PACKET);
+        data.extend_from_slice(self.ascii_comment_md5.as_bytes());
+        for unit in &self.comment {
+            data.extend_from_slice(&unit.to_le_bytes());
+        }
+        let computed_md5 = crate::checksum::compute_md5_bytes(&data);
+        if computed_md5 != *self.md5.as_bytes() {
+            println!(
+                "MD5 mismatch: expected {:?}, computed {:?}",
+                self.md5.as_bytes(),
+                computed_md5
+            );
+            return false;
+        }
+
+        // Check that BinWrite output matches the packet length
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        if self.write_le(&mut buffer).is_err() {
+            println!("Failed to serialize packet");
+            return false;
+        }
+
+        let serialized_length = buffer.get_ref().len() as u64;
+        if serialized_length != self.length {
+            println!(
+                "Serialized length mismatch: expected {}, got {}",
+                self.length, serialized_length
+            );
+            return false;
+        }
+
+        true
+    }
+}
+
+impl BinRead for UnicodeCommentPacket {
+    type Args<'a> = ();
+
+    fn read_options<R: std::io::Read + std::io::Seek>(
+        reader: &mut R,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<Self> {
+        let mut header = [0u8; 64];
+        reader.read_exact(&mut header).map_err(binrw::Error::Io)?;
+
+        if &header[0..8] != b"PAR2\0PKT" {
+            return Err(binrw::Error::AssertFail {
+                pos: 0,
+                message: "Invalid magic".to_string(),
+            });
+        }
+
+        let length =
+            u64::from_le_bytes(header[8..16].try_into().expect("slice is exactly 8 bytes"));
+        if length < 64 + 16 || length % 4 != 0 {
+            return Err(binrw::Error::AssertFail {
+                pos: 8,
+                message: format!("Invalid CommUni packet length: {}", length),
+            });
+        }
+        let mut md5 = [0u8; 16];
+        md5.copy_from_slice(&header[16..32]);
+        let mut set_id = [0u8; 16];
+        set_id.copy_from_slice(&header[32..48]);
+
+        let mut ascii_comment_md5 = [0u8; 16];
+        reader
+            .read_exact(&mut ascii_comment_md5)
+            .map_err(binrw::Error::Io)?;
+
+        let mut comment_bytes = vec![0u8; (length - 64 - 16) as usize];
+        reader
+            .read_exact(&mut comment_bytes)
+            .map_err(binrw::Error::Io)?;
+        let comment = comment_bytes
+            .as_chunks::<2>()
+            .0
+            .iter()
+            .map(|pair| u16::from_le_bytes(*pair))
+            .collect();
+
+        Ok(UnicodeCommentPacket {
+            length,
+            md5: Md5Hash::new(md5),
+            set_id: RecoverySetId::new(set_id),
+            ascii_comment_md5: Md5Hash::new(ascii_comment_md5),
+            comment,
+        })
+    }
+}
+
+impl BinWrite for UnicodeCommentPacket {
+    type Args<'a> = ();
+
+    fn write_options<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: &mut W,
+        _endian: binrw::Endian,
+        _args: Self::Args<'_>,
+    ) -> binrw::BinResult<()> {
+        writer.write_all(b"PAR2\0PKT")?;
+        writer.write_all(&self.length.to_le_bytes())?;
+        writer.write_all(self.md5.as_bytes())?;
+        writer.write_all(self.set_id.as_bytes())?;
+        writer.write_all(TYPE_OF_PACKET)?;
+        writer.write_all(self.ascii_comment_md5.as_bytes())?;
+        for unit in &self.comment {
+            writer.write_all(&unit.to_le_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+//! Unknown and application-specific packets
+//!
+//! Packets whose type is not recognised are kept as raw bytes instead of being
+//! dropped, so application-specific packets (spec appendix B) survive a round
+//! trip through the library. Their MD5 is still checked: a packet that fails
+//! the check cannot be told apart from garbage and is discarded.
+
+use super::Packet;
+use crate::domain::{Md5Hash, RecoverySetId};
+use md5::Digest;
+
+/// Compute the packet MD5 over set ID, packet type and body
+pub fn compute_packet_md5(set_id: &RecoverySetId, packet_type: &[u8; 16], body: &[u8]) -> Md5Hash {
+    let mut hasher = crate::checksum::new_md5_hasher();
+    hasher.update(set_id.as_bytes());
+    hasher.update(packet_type);
+    hasher.update(body);
+    crate::checksum::finalize_md5(hasher)
+}
+
+/// Build a `Packet::Unknown` from full packet bytes (header included)
+///
+/// Returns `None` if the data is shorter than a header or the MD5 does not match.
+pub fn parse(packet_data: &[u8]) -> Option<Packet> {
+    if packet_data.len() < 64 {
+        return None;
+    }
+    let md5 = Md5Hash::new(packet_data[16..32].try_into().ok()?);
+    let set_id = RecoverySetId::new(packet_data[32..48].try_into().ok()?);
+    let packet_type: [u8; 16] = packet_data[48..64].try_into().ok()?;
+    let body = packet_data[64..].to_vec();
+
+    (compute_packet_md5(&set_id, &packet_type, &body) == md5).then_some(Packet::Unknown {
+        packet_type,
+        set_id,
+        md5,
+        body,
+    })
+}
+
+/// Serialize a packet of any type from its parts, computing the MD5
+///
+/// The body is written as-is; PAR2 requires it to be a multiple of 4 bytes.
+pub fn write<W: std::io::Write>(
+    writer: &mut W,
+    packet_type: &[u8; 16],
+    set_id: &RecoverySetId,
+    body: &[u8],
+) -> std::io::Result<()> {
+    let md5 = compute_packet_md5(set_id, packet_type, body);
+    writer.write_all(super::MAGIC_BYTES)?;
+    writer.write_all(&(64 + body.len() as u64).to_le_bytes())?;
+    writer.write_all(md5.as_bytes())?;
+    writer.write_all(set_id.as_bytes())?;
+    writer.write_all(packet_type)?;
+    writer.write_all(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JOB_TYPE: &[u8; 16] = b"ACME\0\0\0\0IngestID";
+
+    #[test]
+    fn write_then_parse_round_trips() {
+        let set_id = RecoverySetId::new([4; 16]);
+        let mut bytes = Vec::new();
+        write(&mut bytes, JOB_TYPE, &set_id, b"job-1234").unwrap();
+        assert_eq!(bytes.len(), 72);
+
+        let packet = parse(&bytes).unwrap();
+        assert!(packet.verify());
+        match packet {
+            Packet::Unknown {
+                packet_type,
+                set_id: parsed_set_id,
+                body,
+                ..
+            } => {
+                assert_eq!(&packet_type, JOB_TYPE);
+                assert_eq!(parsed_set_id, set_id);
+                assert_eq!(body, b"job-1234");
+            }
+            other => panic!("expected unknown packet, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_rejects_md5_mismatch() {
+        let mut bytes = Vec::new();
+        write(
+            &mut bytes,
+            JOB_TYPE,
+            &RecoverySetId::new([4; 16]),
+            b"job-1234",
+        )
+        .unwrap();
+        bytes[70] ^= 0x01;
+        assert!(parse(&bytes).is_none());
+    }
+}
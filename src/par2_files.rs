@@ -4,10 +4,10 @@
 //! It includes utilities for finding PAR2 files in a directory and parsing their
 //! packet structures from disk with minimal memory overhead.
 
-use crate::domain::Md5Hash;
+use crate::domain::{Md5Hash, RecoverySetId};
 use crate::Packet;
 use rayon::prelude::*;
-use rustc_hash::FxHashSet as HashSet;
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
 use std::fs;
 use std::io::{BufReader, Read, Seek};
 use std::path::{Path, PathBuf};
@@ -27,6 +27,10 @@ pub struct PacketSet {
     pub recovery_block_count: usize,
     /// Base directory for resolving relative file paths in the PAR2 set
     pub base_dir: PathBuf,
+    /// Validated recovery blocks per recovery set, when known
+    ///
+    /// Filled by [`load_par2_packets`]; empty for sets built from packets directly.
+    pub recovery_blocks_by_set: HashMap<RecoverySetId, usize>,
 }
 
 impl PacketSet {
@@ -36,37 +40,113 @@ impl PacketSet {
             packets,
             recovery_block_count,
             base_dir,
+            recovery_blocks_by_set: HashMap::default(),
         }
     }
 
     /// Create a packet set by counting recovery blocks from the packets
     /// Uses current directory as base_dir
     pub fn from_packets(packets: Vec<Packet>) -> Self {
-        let recovery_block_count = packets
-            .iter()
-            .filter(|p| matches!(p, Packet::RecoverySlice(_)))
-            .count();
+        let recovery_block_count = packets.iter().filter(|p| p.is_recovery_slice()).count();
         Self {
             packets,
             recovery_block_count,
             base_dir: PathBuf::from("."),
+            recovery_blocks_by_set: HashMap::default(),
         }
     }
 
     /// Create a packet set with a specific base directory
     pub fn from_packets_with_base_dir(packets: Vec<Packet>, base_dir: PathBuf) -> Self {
-        let recovery_block_count = packets
-            .iter()
-            .filter(|p| matches!(p, Packet::RecoverySlice(_)))
-            .count();
+        let recovery_block_count = packets.iter().filter(|p| p.is_recovery_slice()).count();
         Self {
             packets,
             recovery_block_count,
             base_dir,
+            recovery_blocks_by_set: HashMap::default(),
         }
     }
+
+    /// Comments attached to the recovery set, deduplicated
+    ///
+    /// Unicode comments are preferred over the ASCII comments they translate.
+    pub fn comments(&self) -> Vec<String> {
+        crate::packets::processing::extract_comments(&self.packets)
+    }
+
+    /// Split into one packet set per recovery set ID, in order of first appearance
+    ///
+    /// Each set keeps the base directory and its own recovery block count. When
+    /// per-set counts were not recorded, recovery slices in the packet list are
+    /// counted instead.
+    pub fn group_by_set_id(self) -> Vec<(RecoverySetId, PacketSet)> {
+        let mut groups: Vec<(RecoverySetId, Vec<Packet>)> = Vec::new();
+        let mut group_index: HashMap<RecoverySetId, usize> = HashMap::default();
+        for packet in self.packets {
+            let set_id = packet.set_id();
+            let index = *group_index.entry(set_id).or_insert_with(|| {
+                groups.push((set_id, Vec::new()));
+                groups.len() - 1
+            });
+            groups[index].1.push(packet);
+        }
+
+        groups
+            .into_iter()
+            .map(|(set_id, packets)| {
+                let recovery_block_count = if self.recovery_blocks_by_set.is_empty() {
+                    packets.iter().filter(|p| p.is_recovery_slice()).count()
+                } else {
+                    self.recovery_blocks_by_set
+                        .get(&set_id)
+                        .copied()
+                        .unwrap_or(0)
+                };
+                let mut set = PacketSet::new(packets, recovery_block_count, self.base_dir.clone());
+                set.recovery_blocks_by_set
+                    .insert(set_id, recovery_block_count);
+                (set_id, set)
+            })
+            .collect()
+    }
+
+    /// Keep only the packets of one recovery set
+    ///
+    /// Fails with the IDs of the sets that were found if no packet belongs to `set_id`.
+    pub fn select_set(self, set_id: &RecoverySetId) -> Result<PacketSet, SetNotFound> {
+        let mut sets = self.group_by_set_id();
+        match sets.iter().position(|(id, _)| id == set_id) {
+            Some(index) => Ok(sets.swap_remove(index).1),
+            None => Err(SetNotFound {
+                set_id: *set_id,
+                found: sets.into_iter().map(|(id, _)| id).collect(),
+            }),
+        }
+    }
+}
+
+/// A requested recovery set has no packets among the loaded PAR2 files
+#[derive(Debug)]
+pub struct SetNotFound {
+    /// The requested recovery set
+    pub set_id: RecoverySetId,
+    /// Recovery sets that are present
+    pub found: Vec<RecoverySetId>,
+}
+
+impl std::fmt::Display for SetNotFound {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "recovery set {} not found", self.set_id)?;
+        if self.found.is_empty() {
+            return write!(f, "; no recovery sets were found");
+        }
+        let found: Vec<String> = self.found.iter().map(ToString::to_string).collect();
+        write!(f, "; found {}", found.join(", "))
+    }
 }
 
+impl std::error::Error for SetNotFound {}
+
 /// Type alias for I/O results in this module
 type IoResult<T> = std::io::Result<T>;
 
@@ -106,7 +186,7 @@ pub enum RecoveryFormat {
 /// - `test.par2`         → `test`
 /// - `test.vol0+1.par2`  → `test`
 /// - `test.vol000+02.par2` → `test`
-fn par2_base_stem(path: &Path) -> String {
+pub fn par2_base_stem(path: &Path) -> String {
     let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
     let without_ext = if name
         .get(name.len().saturating_sub(5)..)
@@ -264,6 +344,194 @@ pub fn collect_par2_files(file_path: &Path) -> Vec<PathBuf> {
     par2_files
 }
 
+/// Collect the PAR2 files of a set, optionally discovering volumes by content
+///
+/// Without `by_content` this is [`collect_par2_files`]. With it, files in the
+/// directory of `file_path` that start with a packet header carrying the
+/// recovery set ID of `file_path` are added too, so volumes are found whatever
+/// they have been renamed to. Extra files are scanned whole for such packets.
+#[must_use]
+pub fn discover_par2_files(
+    file_path: &Path,
+    extra_files: &[PathBuf],
+    by_content: bool,
+) -> Vec<PathBuf> {
+    let mut par2_files = collect_par2_files(file_path);
+    if !by_content {
+        return par2_files;
+    }
+
+    let Some(set_id) = read_recovery_set_id(file_path) else {
+        return par2_files;
+    };
+
+    let discovered = find_files_with_packets(file_path, extra_files, &par2_files, |header| {
+        header[32..48] == *set_id.as_bytes()
+    });
+    par2_files.extend(discovered);
+    par2_files.sort();
+    par2_files
+}
+
+/// Collect every PAR2 file next to `file_path`, whatever set it belongs to
+///
+/// Unlike [`collect_par2_files`] the base stem is ignored, so directories
+/// holding several recovery sets are loaded whole. With `by_content`, files in
+/// the directory starting with a packet header and extra files holding packets
+/// of any set are added as well.
+#[must_use]
+pub fn collect_all_par2_files(
+    file_path: &Path,
+    extra_files: &[PathBuf],
+    by_content: bool,
+) -> Vec<PathBuf> {
+    let folder_path = file_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let mut par2_files = vec![file_path.to_path_buf()];
+    par2_files.extend(find_par2_files_in_directory(folder_path, file_path));
+    if by_content {
+        let discovered = find_files_with_packets(file_path, extra_files, &par2_files, |_| true);
+        par2_files.extend(discovered);
+    }
+
+    par2_files.sort();
+    par2_files
+}
+
+/// Files next to `file_path` or in `extra_files` holding a packet header accepted by `accept`
+///
+/// Files in the directory only count when they start with a packet header, and
+/// files already listed in `known` or named by their FileDesc packets are
+/// skipped, so protected data is never read. Extra files were named by the
+/// user and are scanned whole for packets appended to or embedded in them.
+fn find_files_with_packets(
+    file_path: &Path,
+    extra_files: &[PathBuf],
+    known: &[PathBuf],
+    accept: impl Fn(&[u8; PACKET_HEADER_SIZE]) -> bool + Sync,
+) -> Vec<PathBuf> {
+    let folder_path = file_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+
+    let candidates: Vec<PathBuf> = fs::read_dir(folder_path)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let protected = crate::packets::processing::extract_filenames(
+        &load_par2_packets(known, false, false).packets,
+    );
+    let mut seen: HashSet<PathBuf> = known
+        .iter()
+        .cloned()
+        .chain(protected.iter().map(|name| folder_path.join(name)))
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .collect();
+    let mut unseen = |path: &PathBuf| {
+        path.is_file() && fs::canonicalize(path).is_ok_and(|canonical| seen.insert(canonical))
+    };
+    let extra_files: Vec<PathBuf> = extra_files.iter().filter(|p| unseen(p)).cloned().collect();
+    let candidates: Vec<PathBuf> = candidates.into_iter().filter(|p| unseen(p)).collect();
+
+    let mut found: Vec<PathBuf> = candidates
+        .into_par_iter()
+        .filter(|path| {
+            read_leading_packet_header(path, &accept)
+                .ok()
+                .flatten()
+                .is_some()
+        })
+        .collect();
+    found.par_extend(
+        extra_files
+            .into_par_iter()
+            .filter(|path| find_packet_header(path, &accept).ok().flatten().is_some()),
+    );
+    found
+}
+
+/// The packet header at the start of a file, if it is plausible and accepted by `accept`
+fn read_leading_packet_header(
+    path: &Path,
+    accept: impl Fn(&[u8; PACKET_HEADER_SIZE]) -> bool,
+) -> IoResult<Option<[u8; PACKET_HEADER_SIZE]>> {
+    let mut header = [0u8; PACKET_HEADER_SIZE];
+    match fs::File::open(path)?.read_exact(&mut header) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+    Ok((is_plausible_header(&header) && accept(&header)).then_some(header))
+}
+
+/// Whether a header starts with the PAR2 magic and has a sane packet length
+fn is_plausible_header(header: &[u8; PACKET_HEADER_SIZE]) -> bool {
+    header.starts_with(PAR2_MAGIC)
+        && get_packet_length(header)
+            .is_some_and(|length| length >= PACKET_HEADER_SIZE as u64 && length.is_multiple_of(4))
+}
+
+/// Recovery set ID of the first packet found in a file
+fn read_recovery_set_id(path: &Path) -> Option<RecoverySetId> {
+    let header = find_packet_header(path, |_| true).ok()??;
+    Some(RecoverySetId::new(header[32..48].try_into().ok()?))
+}
+
+/// Find the first plausible packet header in a file accepted by `accept`
+///
+/// Headers are searched for at any offset, so packets appended to or embedded
+/// in other data are found as well. Only the header is checked here; packet
+/// MD5s are validated when the file is parsed.
+fn find_packet_header(
+    path: &Path,
+    accept: impl Fn(&[u8; PACKET_HEADER_SIZE]) -> bool,
+) -> IoResult<Option<[u8; PACKET_HEADER_SIZE]>> {
+    let mut file = fs::File::open(path)?;
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut filled = 0;
+
+    loop {
+        let read = file.read(&mut buffer[filled..])?;
+        filled += read;
+
+        let mut start = 0;
+        while let Some(pos) = buffer[start..filled]
+            .windows(PAR2_MAGIC.len())
+            .position(|window| window == PAR2_MAGIC)
+        {
+            let offset = start + pos;
+            if offset + PACKET_HEADER_SIZE > filled {
+                break;
+            }
+            let header: &[u8; PACKET_HEADER_SIZE] = buffer[offset..offset + PACKET_HEADER_SIZE]
+                .try_into()
+                .expect("slice is exactly one header");
+            if is_plausible_header(header) && accept(header) {
+                return Ok(Some(*header));
+            }
+            start = offset + 1;
+        }
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        // Keep a tail that may hold the start of a header split across reads
+        let keep_from = filled.saturating_sub(PACKET_HEADER_SIZE - 1);
+        buffer.copy_within(keep_from..filled, 0);
+        filled -= keep_from;
+    }
+}
+
 /// Collect all PAR1 files related to the input file (`.par` and `.pNN`).
 #[must_use]
 pub fn collect_par1_files(file_path: &Path) -> Vec<PathBuf> {
@@ -300,9 +568,16 @@ pub fn get_packet_hash(packet: &Packet) -> Md5Hash {
         Packet::Main(p) => p.md5,
         Packet::FileDescription(p) => p.md5,
         Packet::InputFileSliceChecksum(p) => p.md5,
+        Packet::InputFileSlice(p) => p.md5,
         Packet::RecoverySlice(p) => p.md5,
+        Packet::PackedRecoverySlice(p) => p.md5,
         Packet::Creator(p) => p.md5,
         Packet::PackedMain(p) => p.md5,
+        Packet::UnicodeFilename(p) => p.md5,
+        Packet::AsciiComment(p) => p.md5,
+        Packet::UnicodeComment(p) => p.md5,
+        Packet::RecoveryFileSliceChecksum(p) => p.md5,
+        Packet::Unknown { md5, .. } => *md5,
     }
 }
 
@@ -340,7 +615,13 @@ fn parse_par2_file_impl(
 #[derive(Debug)]
 struct ParseResult {
     packets: Vec<Packet>,
-    recovery_block_count: usize,
+    recovery_blocks_by_set: HashMap<RecoverySetId, usize>,
+}
+
+impl ParseResult {
+    fn recovery_block_count(&self) -> usize {
+        self.recovery_blocks_by_set.values().sum()
+    }
 }
 
 /// Parse a single PAR2 file with optional progress output
@@ -363,18 +644,18 @@ fn parse_single_file(
     // Parse without deduplication - that happens at the global level
     let file = fs::File::open(par2_file)?;
     let mut buffered = BufReader::with_capacity(BUFFER_SIZE, file);
-    let (packets, recovery_block_count) =
-        crate::packets::parse_packets_with_options(&mut buffered, include_recovery_slices);
+    let (packets, recovery_blocks_by_set) =
+        crate::packets::parse_packets_with_set_counts(&mut buffered, include_recovery_slices);
 
     let result = ParseResult {
         packets,
-        recovery_block_count,
+        recovery_blocks_by_set,
     };
 
     if show_progress {
         print_packet_load_result(
             result.packets.len(),
-            result.recovery_block_count,
+            result.recovery_block_count(),
             output_lock,
         );
     }
@@ -444,11 +725,9 @@ pub fn load_par2_packets(
     include_recovery_slices: bool,
     show_progress: bool,
 ) -> PacketSet {
-    use std::sync::atomic::{AtomicUsize, Ordering};
-
     // Parse files in parallel and collect results
     // Use mutex for thread-safe output (like par2cmdline-turbo's output_lock)
-    let total_recovery_blocks = AtomicUsize::new(0);
+    let recovery_blocks_by_set: Mutex<HashMap<RecoverySetId, usize>> = Mutex::default();
     let output_lock = Mutex::new(());
 
     let all_packets: Vec<Vec<Packet>> = par2_files
@@ -461,8 +740,11 @@ pub fn load_par2_packets(
                 &output_lock,
             )
             .map(|result| {
-                // Accumulate recovery block count atomically
-                total_recovery_blocks.fetch_add(result.recovery_block_count, Ordering::Relaxed);
+                // Accumulate recovery block counts per set
+                let mut counts = recovery_blocks_by_set.lock().unwrap();
+                for (set_id, count) in &result.recovery_blocks_by_set {
+                    *counts.entry(*set_id).or_default() += count;
+                }
                 result.packets
             })
             .map_err(|e| {
@@ -487,20 +769,12 @@ pub fn load_par2_packets(
         .flatten()
         .filter(|packet| {
             // Skip recovery slices if not including them (already counted above)
-            if !include_recovery_slices && matches!(packet, Packet::RecoverySlice(_)) {
+            if !include_recovery_slices && packet.is_recovery_slice() {
                 return false;
             }
 
             // Track recovery set IDs to detect mixed PAR2 files
-            let set_id = match packet {
-                Packet::Main(p) => p.set_id,
-                Packet::PackedMain(p) => p.set_id,
-                Packet::FileDescription(p) => p.set_id,
-                Packet::InputFileSliceChecksum(p) => p.set_id,
-                Packet::RecoverySlice(p) => p.set_id,
-                Packet::Creator(p) => p.set_id,
-            };
-            recovery_set_ids.insert(set_id);
+            recovery_set_ids.insert(packet.set_id());
 
             // Deduplicate based on packet hash
             let packet_hash = get_packet_hash(packet);
@@ -521,8 +795,9 @@ pub fn load_par2_packets(
         eprintln!("Please specify only PAR2 files that belong to the same recovery set.\n");
         eprintln!("Hint: Each PAR2 set has a unique base filename (e.g., 'myfile.par2', 'myfile.vol*.par2')");
         eprintln!(
-            "      Don't mix files like 'file1.par2' and 'file2.par2' in the same operation.\n"
+            "      Don't mix files like 'file1.par2' and 'file2.par2' in the same operation,"
         );
+        eprintln!("      or use --all-sets to process every set separately.\n");
     }
 
     // Determine base directory from the first PAR2 file
@@ -532,7 +807,15 @@ pub fn load_par2_packets(
         .map(ToOwned::to_owned)
         .unwrap_or_else(|| PathBuf::from("."));
 
-    PacketSet::new(packets, total_recovery_blocks.into_inner(), base_dir)
+    // Recovery slices kept in an external RFSC-described file count as well
+    let mut recovery_blocks_by_set = recovery_blocks_by_set.into_inner().unwrap();
+    for metadata in parse_external_recovery_metadata(par2_files, &packets) {
+        *recovery_blocks_by_set.entry(metadata.set_id).or_default() += 1;
+    }
+
+    let mut packet_set = PacketSet::new(packets, recovery_blocks_by_set.values().sum(), base_dir);
+    packet_set.recovery_blocks_by_set = recovery_blocks_by_set;
+    packet_set
 }
 
 /// Load all PAR2 packets INCLUDING recovery slices (in parallel)
@@ -637,9 +920,14 @@ fn parse_next_recovery_metadata<R: Read + Seek>(
         };
     }
 
-    // Check if this is a valid PAR2 packet
+    // Damaged data (such as a wiped copy of the critical packets) is skipped
+    // by scanning for the next packet
     if !is_valid_par2_header(&header) {
-        return Ok(None); // Not a valid packet, end of file
+        return if seek_to_next_packet(reader, start_pos + 1)? {
+            parse_next_recovery_metadata(reader, par2_file)
+        } else {
+            Ok(None)
+        };
     }
 
     // Get packet type and length
@@ -670,16 +958,167 @@ fn parse_next_recovery_metadata<R: Read + Seek>(
     }
 }
 
+/// Locate input file slice (FileSlic) packets in PAR2 files without keeping their data
+///
+/// Packets whose MD5 does not match are dropped. Returns one entry per distinct
+/// (set_id, file_id, offset), so copies repeated across volumes are used once.
+#[must_use]
+pub fn parse_input_file_slice_metadata(
+    par2_files: &[PathBuf],
+) -> Vec<crate::packets::InputFileSliceMetadata> {
+    let all_metadata: Vec<Vec<crate::packets::InputFileSliceMetadata>> = par2_files
+        .par_iter()
+        .filter_map(|par2_file| parse_input_file_slice_metadata_from_file(par2_file).ok())
+        .collect();
+
+    let mut seen_slices = HashSet::default();
+    all_metadata
+        .into_iter()
+        .flatten()
+        .filter(|metadata| seen_slices.insert((metadata.set_id, metadata.file_id, metadata.offset)))
+        .collect()
+}
+
+/// Locate the input file slice packets of a single PAR2 file
+fn parse_input_file_slice_metadata_from_file(
+    par2_file: &Path,
+) -> IoResult<Vec<crate::packets::InputFileSliceMetadata>> {
+    use std::io::SeekFrom;
+
+    let file = fs::File::open(par2_file)?;
+    let mut reader = BufReader::with_capacity(BUFFER_SIZE, file);
+    let mut metadata_list = Vec::new();
+
+    loop {
+        let start_pos = reader.stream_position()?;
+        let mut header = [0u8; PACKET_HEADER_SIZE];
+        if reader.read_exact(&mut header).is_err() {
+            break;
+        }
+        if !is_valid_par2_header(&header) {
+            if seek_to_next_packet(&mut reader, start_pos + 1)? {
+                continue;
+            }
+            break;
+        }
+        let Some(length) = get_packet_length(&header).filter(|&length| length >= 64) else {
+            break;
+        };
+
+        if get_packet_type(&header).as_ref().map(|t| &t[..])
+            == Some(crate::packets::input_file_slice_packet::TYPE_OF_PACKET)
+        {
+            reader.seek(SeekFrom::Start(start_pos))?;
+            if let Ok(metadata) = crate::packets::InputFileSliceMetadata::parse_from_reader(
+                &mut reader,
+                par2_file.to_path_buf(),
+            ) {
+                metadata_list.push(metadata);
+            }
+        }
+        reader.seek(SeekFrom::Start(start_pos + length))?;
+    }
+
+    Ok(metadata_list)
+}
+
+/// Position the reader at the next packet magic found at or after `from`
+///
+/// Returns false when no further packet exists.
+fn seek_to_next_packet<R: Read + Seek>(reader: &mut R, from: u64) -> IoResult<bool> {
+    use std::io::SeekFrom;
+
+    reader.seek(SeekFrom::Start(from))?;
+    if crate::packets::scan_for_next_magic(reader)?.is_none() {
+        return Ok(false);
+    }
+    reader.seek(SeekFrom::Current(-(PAR2_MAGIC.len() as i64)))?;
+    Ok(true)
+}
+
+/// Path of the headerless recovery data file belonging to a PAR2 set
+///
+/// The file sits next to the index file and shares its base name:
+/// `test.par2` and `test.vol0+1.par2` both map to `test.rfsc`.
+pub fn external_recovery_data_path(par2_file: &Path) -> PathBuf {
+    let base_stem = par2_base_stem(par2_file);
+    par2_file.with_file_name(format!("{base_stem}.rfsc"))
+}
+
+/// Locate recovery slices stored in external files described by RFSC packets
+///
+/// The external file holds raw recovery slices laid end to end, in the order of
+/// the RFSC entries. Every entry the file is long enough to hold is returned;
+/// its MD5 and CRC32 are only checked when repair asks whether it is intact.
+#[must_use]
+pub fn parse_external_recovery_metadata(
+    par2_files: &[PathBuf],
+    packets: &[Packet],
+) -> Vec<crate::RecoverySliceMetadata> {
+    use crate::repair::{ChecksummedLoader, FileSystemLoader};
+
+    let Some(layout) = crate::packets::processing::extract_slice_layout(packets) else {
+        return Vec::new();
+    };
+    let slice_size = layout.slice_size as usize;
+
+    let mut seen_exponents = HashSet::default();
+    let Some(checksums) = packets.iter().find_map(|packet| match packet {
+        Packet::RecoveryFileSliceChecksum(rfsc) if rfsc.set_id == layout.set_id => {
+            Some(&rfsc.slice_checksums)
+        }
+        _ => None,
+    }) else {
+        return Vec::new();
+    };
+
+    let mut blob_paths: Vec<PathBuf> = par2_files
+        .iter()
+        .map(|par2_file| external_recovery_data_path(par2_file))
+        .collect();
+    blob_paths.sort();
+    blob_paths.dedup();
+
+    blob_paths
+        .iter()
+        .filter_map(|path| Some((path, fs::metadata(path).ok().filter(|m| m.is_file())?.len())))
+        .flat_map(|(path, blob_len)| {
+            let held = (blob_len / slice_size.max(1) as u64) as usize;
+            checksums
+                .iter()
+                .take(held)
+                .enumerate()
+                .map(move |(index, checksum)| {
+                    let loader = ChecksummedLoader {
+                        loader: FileSystemLoader {
+                            file_path: path.clone(),
+                            data_offset: (index * slice_size) as u64,
+                            data_size: slice_size,
+                        },
+                        md5: checksum.md5,
+                        crc32: checksum.crc32,
+                    };
+                    crate::RecoverySliceMetadata::new(
+                        checksum.exponent,
+                        layout.set_id,
+                        std::sync::Arc::new(loader),
+                    )
+                })
+        })
+        .filter(|metadata| seen_exponents.insert(metadata.exponent))
+        .collect()
+}
+
 /// Helper function to check if a header is a valid PAR2 packet header
 #[inline]
 fn is_valid_par2_header(header: &[u8; PACKET_HEADER_SIZE]) -> bool {
     &header[MAGIC_OFFSET..MAGIC_END] == PAR2_MAGIC
 }
 
-/// Helper function to check if packet type is a recovery slice
+/// Helper function to check if packet type is a recovery slice (RecvSlic or PkdRecvS)
 #[inline]
 fn is_recovery_slice_packet(type_bytes: &[u8; 16]) -> bool {
-    type_bytes == crate::packets::recovery_slice_packet::TYPE_OF_PACKET
+    crate::packets::is_recovery_slice_type(type_bytes)
 }
 
 /// Helper function to extract packet type from header
@@ -3,6 +3,7 @@
 use super::context::RepairContext;
 use super::error::{RepairError, Result};
 use super::progress::{ConsoleReporter, ProgressReporter};
+use crate::packets::InputFileSliceMetadata;
 use crate::{Packet, RecoverySliceMetadata};
 use std::path::PathBuf;
 
@@ -27,6 +28,7 @@ use std::path::PathBuf;
 pub struct RepairContextBuilder {
     packets: Option<Vec<Packet>>,
     metadata: Option<Vec<RecoverySliceMetadata>>,
+    embedded_slices: Vec<InputFileSliceMetadata>,
     base_path: Option<PathBuf>,
     reporter: Option<Box<dyn ProgressReporter>>,
     memory_limit: Option<usize>,
@@ -38,6 +40,7 @@ impl RepairContextBuilder {
         Self {
             packets: None,
             metadata: None,
+            embedded_slices: Vec::new(),
             base_path: None,
             reporter: None,
             memory_limit: None,
@@ -56,6 +59,12 @@ impl RepairContextBuilder {
         self
     }
 
+    /// Set the locations of source data embedded in FileSlic packets
+    pub fn embedded_slices(mut self, embedded_slices: Vec<InputFileSliceMetadata>) -> Self {
+        self.embedded_slices = embedded_slices;
+        self
+    }
+
     /// Set the base path for file resolution
     pub fn base_path(mut self, path: PathBuf) -> Self {
         self.base_path = Some(path);
@@ -111,6 +120,7 @@ impl RepairContextBuilder {
             RepairContext::new_with_reporter(packets, base_path, reporter)
         }?;
         context.set_memory_limit(self.memory_limit);
+        context.set_embedded_slices(self.embedded_slices);
         Ok(context)
     }
 }
@@ -5,7 +5,9 @@ use super::error_helpers::delete_file;
 use super::progress::{ConsoleReporter, ProgressReporter};
 use super::types::{FileInfo, RecoverySetInfo};
 use crate::domain::{BlockCount, BlockSize, FileId, FileSize, GlobalSliceIndex};
-use crate::packets::{FileDescriptionPacket, Packet, RecoverySliceMetadata};
+use crate::packets::{
+    FileDescriptionPacket, InputFileSliceMetadata, Packet, RecoverySliceMetadata,
+};
 use log::{debug, warn};
 use rustc_hash::FxHashMap as HashMap;
 use std::path::PathBuf;
@@ -78,6 +80,15 @@ impl RepairContext {
         self.memory_limit = memory_limit;
     }
 
+    /// Keep the embedded FileSlic data that belongs to this recovery set
+    pub(super) fn set_embedded_slices(&mut self, embedded_slices: Vec<InputFileSliceMetadata>) {
+        let set_id = self.recovery_set.set_id;
+        self.recovery_set.embedded_slices = embedded_slices
+            .into_iter()
+            .filter(|slice| slice.set_id == set_id)
+            .collect();
+    }
+
     /// Get a reference to the progress reporter
     pub(super) fn reporter(&self) -> &dyn ProgressReporter {
         self.reporter.as_ref()
@@ -96,12 +107,21 @@ impl RepairContext {
 
     /// Extract recovery set information from packets
     fn extract_recovery_set_info(packets: Vec<Packet>) -> Result<RecoverySetInfo> {
+        // Unicode names and comments must be collected before the packets are consumed
+        let unicode_names = crate::packets::processing::extract_unicode_filenames(&packets);
+        let comments = crate::packets::processing::extract_comments(&packets);
+        let non_recovery_file_count =
+            crate::packets::processing::extract_non_recovery_file_ids(&packets).len();
+
+        // Packed sets carry a PackedMain packet instead of a Main packet
+        let layout = crate::packets::processing::extract_slice_layout(&packets)
+            .ok_or(RepairError::NoMainPacket)?;
+        let block_size = layout.block_size();
+
         // Use functional packet processing for clean separation
-        let (main_packet, file_descriptions, input_file_slice_checksums, _recovery_count) =
+        let (_main_packet, file_descriptions, input_file_slice_checksums, _recovery_count) =
             crate::packets::processing::separate_packets(packets);
 
-        let main = main_packet.ok_or(RepairError::NoMainPacket)?;
-
         if file_descriptions.is_empty() {
             return Err(RepairError::NoFileDescriptions);
         }
@@ -114,32 +134,31 @@ impl RepairContext {
 
         // Build file information in the order specified by main.file_ids
         // This is critical for correct global slice indexing!
+        // For packed sets the indices count subslices, matching the packed stream order.
         let mut files = Vec::new();
         let mut global_slice_offset = 0;
 
         debug!(
             "Building file list from main packet's file_ids array ({} files)",
-            main.file_ids.len()
+            layout.file_ids.len()
         );
 
-        for (idx, file_id) in main.file_ids.iter().enumerate() {
+        for (idx, file_id) in layout.file_ids.iter().enumerate() {
             let fd = fd_map
                 .get(file_id)
                 .ok_or_else(|| RepairError::MissingFileDescription(format!("{:?}", file_id)))?;
 
-            let file_name = String::from_utf8_lossy(&fd.file_name)
-                .trim_end_matches('\0')
-                .to_string();
+            let file_name = crate::verify::resolve_file_name(fd, &unicode_names);
 
-            let slice_count = fd.file_length.div_ceil(main.slice_size) as usize;
+            let slice_count = fd.file_length.div_ceil(block_size) as usize;
 
-            if idx < 3 || idx >= main.file_ids.len() - 3 {
+            if idx < 3 || idx >= layout.file_ids.len() - 3 {
                 debug!(
                     "  File {}: {} (slices: {}, global offset: {})",
                     idx, file_name, slice_count, global_slice_offset
                 );
             } else if idx == 3 {
-                debug!("  ... ({} files omitted) ...", main.file_ids.len() - 6);
+                debug!("  ... ({} files omitted) ...", layout.file_ids.len() - 6);
             }
 
             files.push(FileInfo {
@@ -174,11 +193,15 @@ impl RepairContext {
         }
 
         Ok(RecoverySetInfo {
-            set_id: main.set_id,
-            slice_size: BlockSize::new(main.slice_size),
+            set_id: layout.set_id,
+            slice_size: BlockSize::new(layout.slice_size),
+            subslice_size: layout.subslice_size.map(BlockSize::new),
             files,
             recovery_slices_metadata: Vec::new(), // Populated later for memory-efficient loading
             file_slice_checksums,
+            comments,
+            embedded_slices: Vec::new(), // Populated later from the PAR2 files
+            non_recovery_file_count,
         })
     }
 
@@ -138,6 +138,10 @@ pub enum RepairError {
     #[error("Invalid path: {0}")]
     InvalidPath(PathBuf),
 
+    /// The recovery set selected with `--set-id` is not present
+    #[error(transparent)]
+    SetNotFound(#[from] crate::par2_files::SetNotFound),
+
     /// I/O error occurred (catch-all for other I/O errors)
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -28,7 +28,7 @@ pub use context::RepairContext;
 pub use error::{RepairError, Result};
 pub use md5_writer::Md5Writer;
 pub use progress::{ConsoleReporter, ProgressReporter, SilentReporter};
-pub use recovery_loader::{FileSystemLoader, RecoveryDataLoader};
+pub use recovery_loader::{ChecksummedLoader, FileSystemLoader, RecoveryDataLoader};
 pub use slice_provider::{
     ActualDataSize, ChunkedSliceProvider, LogicalSliceSize, RecoverySliceProvider,
     Result as SliceProviderResult, SliceLocation, SliceProvider, SliceProviderError,
@@ -43,11 +43,12 @@ pub use types::{
 pub use validate::validate_blocks_md5_crc32;
 
 use crate::domain::{FileId, LocalSliceIndex, Md5Hash};
-use crate::RecoverySlicePacket;
+use crate::{RecoverySliceMetadata, RecoverySlicePacket};
 use error_helpers::*;
 use log::debug;
 use rayon::prelude::*;
 use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
@@ -254,22 +255,37 @@ impl RepairContext {
             );
         }
 
-        // Recalculate total damaged blocks
-        let mut total_damaged_blocks = 0;
+        // Damaged blocks with a good copy embedded in a FileSlic packet are
+        // restored by copying that data instead of by Reed-Solomon
+        let embedded_locations = self.locate_embedded_blocks(&validation_cache);
+        let embedded_block_count: usize = embedded_locations.values().map(HashMap::len).sum();
+
+        // Recalculate total damaged blocks. In packed sets this counts recovery
+        // slices, since every damaged subslice in a slice is restored together.
+        let per_slice = self.recovery_set.subslices_per_slice();
+        let mut damaged_slices = HashSet::default();
         for file_info in &self.recovery_set.files {
-            let valid_count = validation_cache.valid_count(&file_info.file_id);
-            let damaged_count = file_info.slice_count - valid_count;
-            total_damaged_blocks += damaged_count;
+            let embedded = embedded_locations.get(&file_info.file_id);
+            for local_idx in 0..file_info.slice_count.as_usize() {
+                if !validation_cache.is_valid(&file_info.file_id, local_idx)
+                    && !embedded.is_some_and(|blocks| blocks.contains_key(&local_idx))
+                {
+                    let global_idx = file_info.local_to_global(LocalSliceIndex::new(local_idx));
+                    damaged_slices.insert(global_idx.as_usize() / per_slice);
+                }
+            }
         }
+        let total_damaged_blocks = damaged_slices.len();
 
         debug!(
-            "  total_damaged_blocks: {}, recovery_blocks: {}",
+            "  total_damaged_blocks: {}, embedded_blocks: {}, recovery_blocks: {}",
             total_damaged_blocks,
+            embedded_block_count,
             self.recovery_set.recovery_slices_metadata.len()
         );
 
         // Check if repair is needed
-        if total_damaged_blocks == 0 {
+        if total_damaged_blocks == 0 && embedded_block_count == 0 {
             let verified_files: Vec<String> = file_status.keys().cloned().collect();
             let files_verified = verified_files.len();
             return Ok(RepairResult::NoRepairNeeded {
@@ -299,7 +315,85 @@ impl RepairContext {
         }
 
         // Perform the actual repair with validation cache from comprehensive verification
-        self.perform_reed_solomon_repair(&file_status, &validation_cache, &block_positions_map)
+        self.perform_reed_solomon_repair(
+            &file_status,
+            &validation_cache,
+            &block_positions_map,
+            &embedded_locations,
+        )
+    }
+
+    /// Locate damaged blocks that can be copied from embedded FileSlic data
+    ///
+    /// Each candidate is read back from its PAR2 file and checked against the IFSC
+    /// checksums, so a damaged copy is ignored and the block falls back to
+    /// Reed-Solomon. Returns, per file, local block index -> location of the data.
+    fn locate_embedded_blocks(
+        &self,
+        validation_cache: &ValidationCache,
+    ) -> HashMap<FileId, HashMap<usize, SliceLocation>> {
+        let block_size = self.recovery_set.block_size().as_u64();
+        let mut locations: HashMap<FileId, HashMap<usize, SliceLocation>> = HashMap::default();
+
+        for embedded in &self.recovery_set.embedded_slices {
+            let Some(file_info) = self
+                .recovery_set
+                .files
+                .iter()
+                .find(|file| file.file_id == embedded.file_id)
+            else {
+                continue;
+            };
+            let Some((block, block_len)) = crate::packets::processing::embedded_block(
+                embedded.offset,
+                embedded.data_size as u64,
+                file_info.file_length.as_u64(),
+                block_size,
+            ) else {
+                continue;
+            };
+            let already_available = validation_cache.is_valid(&file_info.file_id, block)
+                || locations
+                    .get(&file_info.file_id)
+                    .is_some_and(|blocks| blocks.contains_key(&block));
+            if already_available {
+                continue;
+            }
+            let Some(&(expected_md5, expected_crc)) = self
+                .recovery_set
+                .file_slice_checksums
+                .get(&file_info.file_id)
+                .and_then(|checksums| checksums.slice_checksums.get(block))
+            else {
+                continue;
+            };
+
+            let matches = read_embedded_data(&embedded.file_path, embedded.data_offset, block_len)
+                .is_some_and(|data| {
+                    crate::checksum::compute_block_checksums_padded(&data, block_size as usize)
+                        == (expected_md5, expected_crc)
+                });
+            if !matches {
+                debug!(
+                    "  Embedded data for {} block {} in {:?} does not match, ignoring",
+                    file_info.file_name, block, embedded.file_path
+                );
+                continue;
+            }
+
+            locations.entry(file_info.file_id).or_default().insert(
+                block,
+                SliceLocation {
+                    file_path: embedded.file_path.clone(),
+                    offset: embedded.data_offset,
+                    actual_size: ActualDataSize::new(block_len),
+                    logical_size: LogicalSliceSize::new(block_size as usize),
+                    expected_crc: Some(expected_crc),
+                },
+            );
+        }
+
+        locations
     }
 
     /// Perform Reed-Solomon repair
@@ -326,6 +420,7 @@ impl RepairContext {
         file_status: &HashMap<String, FileStatus>,
         validation_cache: &ValidationCache,
         block_positions_map: &HashMap<FileId, HashMap<u32, usize>>,
+        embedded_locations: &HashMap<FileId, HashMap<usize, SliceLocation>>,
     ) -> Result<RepairResult> {
         debug!(
             "perform_reed_solomon_repair: processing {} files",
@@ -406,6 +501,7 @@ impl RepairContext {
             &files_to_repair,
             validation_cache,
             block_positions_map,
+            embedded_locations,
         )?;
 
         // STEP 3: Write reconstructed data to each file
@@ -434,6 +530,11 @@ impl RepairContext {
                 .cloned()
                 .unwrap_or_default();
 
+            let no_embedded = HashMap::default();
+            let embedded_blocks = embedded_locations
+                .get(&file_info.file_id)
+                .unwrap_or(&no_embedded);
+
             let file_path = self.base_path.join(&file_info.file_name);
             match self.write_repaired_file(
                 &file_path,
@@ -441,6 +542,7 @@ impl RepairContext {
                 valid_slice_indices,
                 &file_reconstructed,
                 &block_positions,
+                embedded_blocks,
             ) {
                 Ok(()) => {
                     self.reporter()
@@ -528,48 +630,103 @@ impl RepairContext {
         })
     }
 
+    /// The first `needed` recovery slices whose data is intact
+    ///
+    /// Headerless (RFSC) slices are checked against their checksums here rather
+    /// than when the set is loaded, so only the slices repair uses are hashed;
+    /// damaged ones are passed over for the next slice.
+    fn intact_recovery_slices(&self, needed: usize) -> Result<Vec<RecoverySliceMetadata>> {
+        let mut intact = Vec::with_capacity(needed);
+        for metadata in &self.recovery_set.recovery_slices_metadata {
+            if intact.len() == needed {
+                break;
+            }
+            match metadata.is_intact() {
+                Ok(true) => intact.push(metadata.clone()),
+                Ok(false) => debug!("Recovery slice {} is damaged, skipping", metadata.exponent),
+                Err(e) => debug!("Recovery slice {} is unreadable: {}", metadata.exponent, e),
+            }
+        }
+        if intact.len() < needed {
+            return Err(RepairError::InsufficientRecovery {
+                missing: needed,
+                available: intact.len(),
+            });
+        }
+        Ok(intact)
+    }
+
     /// Reconstruct ALL missing slices across ALL files in a single Reed-Solomon operation
     ///
     /// This is the core fix for the multifile repair bug. We collect ALL missing/damaged
     /// slices from ALL files and reconstruct them together, ensuring the Reed-Solomon
     /// matrix uses only valid slices as input.
     ///
+    /// For packed sets the per-file indices are subslices. Every recovery slice that
+    /// contains a damaged subslice is reconstructed as a whole, and the intact slices
+    /// are fed to Reed-Solomon assembled from their subslices.
+    ///
+    /// Blocks with embedded FileSlic data are not reconstructed; their embedded
+    /// copies are fed to Reed-Solomon as input instead.
+    ///
     /// Returns a HashMap mapping global slice index -> reconstructed data
     fn reconstruct_all_missing_slices(
         &self,
         files_to_repair: &[(&FileInfo, Vec<usize>)],
         validation_cache: &ValidationCache,
         block_positions_map: &HashMap<FileId, HashMap<u32, usize>>,
+        embedded_locations: &HashMap<FileId, HashMap<usize, SliceLocation>>,
     ) -> Result<HashMap<usize, Vec<u8>>> {
-        use self::slice_provider::{ChunkedSliceProvider, RecoverySliceProvider, SliceLocation};
+        use self::slice_provider::{ChunkedSliceProvider, RecoverySliceProvider};
         use std::io::Cursor;
 
+        let slice_size = self.recovery_set.slice_size.as_usize();
+        let block_size = self.recovery_set.block_size().as_usize();
+        let per_slice = self.recovery_set.subslices_per_slice();
+
         // Collect all global missing indices
         let mut all_missing_global: Vec<usize> = Vec::new();
         for (file_info, missing_local) in files_to_repair {
+            let embedded = embedded_locations.get(&file_info.file_id);
             for &local_idx in missing_local {
+                if embedded.is_some_and(|blocks| blocks.contains_key(&local_idx)) {
+                    continue;
+                }
                 let global_idx = file_info.local_to_global(LocalSliceIndex::new(local_idx));
                 all_missing_global.push(global_idx.as_usize());
             }
         }
         all_missing_global.sort();
 
+        if all_missing_global.is_empty() {
+            debug!("All damaged blocks are embedded, skipping Reed-Solomon");
+            return Ok(HashMap::default());
+        }
+
+        // Recovery slices to reconstruct (identical to the above for unpacked sets)
+        let mut missing_recovery_slices: Vec<usize> = all_missing_global
+            .iter()
+            .map(|&global_idx| global_idx / per_slice)
+            .collect();
+        missing_recovery_slices.dedup();
+
         debug!(
             "Reconstructing {} total missing slices across {} files",
-            all_missing_global.len(),
+            missing_recovery_slices.len(),
             files_to_repair.len()
         );
 
         // Check if we have enough recovery blocks
-        if all_missing_global.len() > self.recovery_set.recovery_slices_metadata.len() {
+        if missing_recovery_slices.len() > self.recovery_set.recovery_slices_metadata.len() {
             return Err(RepairError::InsufficientRecovery {
-                missing: all_missing_global.len(),
+                missing: missing_recovery_slices.len(),
                 available: self.recovery_set.recovery_slices_metadata.len(),
             });
         }
+        let recovery_slices = self.intact_recovery_slices(missing_recovery_slices.len())?;
 
-        // Build slice provider with ONLY valid slices (excludes ALL missing/damaged)
-        let mut input_provider = ChunkedSliceProvider::new(self.recovery_set.slice_size.as_usize());
+        // Locate every valid block (slice, or subslice when packed) on disk
+        let mut block_locations: BTreeMap<usize, SliceLocation> = BTreeMap::new();
 
         debug!("Building input provider (excluding ALL missing/damaged slices):");
         for file_info in &self.recovery_set.files {
@@ -579,6 +736,14 @@ impl RepairContext {
                 .get(&file_info.file_id)
                 .ok_or_else(|| RepairError::NoValidationCache(file_info.file_name.clone()))?;
 
+            // Embedded copies of damaged blocks are valid input as well
+            if let Some(embedded) = embedded_locations.get(&file_info.file_id) {
+                for (&slice_index, location) in embedded {
+                    let global_index = file_info.local_to_global(LocalSliceIndex::new(slice_index));
+                    block_locations.insert(global_index.as_usize(), location.clone());
+                }
+            }
+
             if valid_slices.is_empty() {
                 debug!(
                     "  File {} - no valid slices (skipping)",
@@ -621,20 +786,18 @@ impl RepairContext {
                 let offset = file_block_positions
                     .get(&(slice_index as u32))
                     .map(|&pos| pos as u64)
-                    .unwrap_or_else(|| {
-                        (slice_index * self.recovery_set.slice_size.as_usize()) as u64
-                    });
+                    .unwrap_or_else(|| (slice_index * block_size) as u64);
 
                 // Calculate expected slice size based on PAR2 metadata
                 let expected_slice_size = if slice_index == file_info.slice_count.as_usize() - 1 {
-                    let remaining = file_info.file_length % self.recovery_set.slice_size;
+                    let remaining = file_info.file_length % self.recovery_set.block_size();
                     if remaining == 0 {
-                        self.recovery_set.slice_size.as_usize()
+                        block_size
                     } else {
                         remaining as usize
                     }
                 } else {
-                    self.recovery_set.slice_size.as_usize()
+                    block_size
                 };
 
                 // CRITICAL FIX: Calculate ACTUAL available bytes in the file for this slice
@@ -658,33 +821,58 @@ impl RepairContext {
                     .and_then(|checksums| checksums.slice_checksums.get(slice_index))
                     .map(|(_, crc)| *crc);
 
-                input_provider.add_slice(
+                block_locations.insert(
                     global_index.as_usize(),
                     SliceLocation {
                         file_path: file_path.clone(),
                         offset,
                         actual_size: ActualDataSize::new(actual_size),
-                        logical_size: LogicalSliceSize::new(
-                            self.recovery_set.slice_size.as_usize(),
-                        ),
+                        logical_size: LogicalSliceSize::new(block_size),
                         expected_crc,
                     },
                 );
             }
         }
 
+        // Build slice provider with ONLY valid slices (excludes ALL missing/damaged)
+        let total_blocks = self.recovery_set.total_blocks();
+        let total_input_slices = total_blocks.div_ceil(per_slice);
+        let mut input_provider = ChunkedSliceProvider::new(slice_size);
+
+        if self.recovery_set.subslice_size.is_none() {
+            for (global_index, location) in block_locations {
+                input_provider.add_slice(global_index, location);
+            }
+        } else {
+            // A packed slice is usable only if all of its subslices were found
+            for slice_index in 0..total_input_slices {
+                if missing_recovery_slices.binary_search(&slice_index).is_ok() {
+                    continue;
+                }
+                let first = slice_index * per_slice;
+                let last = (first + per_slice).min(total_blocks);
+                let subslices: Option<Vec<SliceLocation>> = (first..last)
+                    .map(|global_index| block_locations.remove(&global_index))
+                    .collect();
+                match subslices {
+                    Some(subslices) => input_provider.add_packed_slice(slice_index, subslices),
+                    None => debug!(
+                        "  Packed slice {} has unreadable subslices, skipping",
+                        slice_index
+                    ),
+                }
+            }
+        }
+
         // Build recovery slice provider
-        let mut recovery_provider =
-            RecoverySliceProvider::new(self.recovery_set.slice_size.as_usize());
+        let mut recovery_provider = RecoverySliceProvider::new(slice_size);
 
-        for metadata in &self.recovery_set.recovery_slices_metadata {
+        for metadata in &recovery_slices {
             recovery_provider.add_recovery_metadata(metadata.exponent as usize, metadata.clone());
         }
 
         // Create reconstruction engine
-        let dummy_recovery_slices: Vec<RecoverySlicePacket> = self
-            .recovery_set
-            .recovery_slices_metadata
+        let dummy_recovery_slices: Vec<RecoverySlicePacket> = recovery_slices
             .iter()
             .map(|metadata| RecoverySlicePacket {
                 length: 68,
@@ -696,22 +884,16 @@ impl RepairContext {
             })
             .collect();
 
-        let total_input_slices: usize = self
-            .recovery_set
-            .files
-            .iter()
-            .map(|f| f.slice_count.as_usize())
-            .sum();
         let reconstruction_engine = crate::reed_solomon::ReconstructionEngine::new(
-            self.recovery_set.slice_size.as_usize(),
+            slice_size,
             total_input_slices,
             dummy_recovery_slices,
         );
 
         // Create output buffers for all missing slices
         let mut output_buffers: HashMap<usize, Cursor<Vec<u8>>> = HashMap::default();
-        for &global_idx in &all_missing_global {
-            output_buffers.insert(global_idx, Cursor::new(Vec::new()));
+        for &slice_idx in &missing_recovery_slices {
+            output_buffers.insert(slice_idx, Cursor::new(Vec::new()));
         }
 
         // Perform reconstruction
@@ -724,14 +906,11 @@ impl RepairContext {
         //   = 12,500 × 2MB = 25GB total (each slice read exactly once)
         //
         // This reduces I/O from ~126GB to ~50GB (2x file size, not 5x)
-        let optimal_chunk_size = calculate_repair_chunk_size(
-            self.recovery_set.slice_size.as_usize(),
-            self.memory_limit,
-        )?;
+        let optimal_chunk_size = calculate_repair_chunk_size(slice_size, self.memory_limit)?;
         let result = reconstruction_engine.reconstruct_missing_slices_chunked(
             &mut input_provider,
             &recovery_provider,
-            &all_missing_global,
+            &missing_recovery_slices,
             &mut output_buffers,
             optimal_chunk_size,
         );
@@ -751,7 +930,20 @@ impl RepairContext {
             .collect();
 
         debug!("Successfully reconstructed {} slices", reconstructed.len());
-        Ok(reconstructed)
+
+        if self.recovery_set.subslice_size.is_none() {
+            return Ok(reconstructed);
+        }
+
+        // Split reconstructed packed slices back into the missing subslices
+        Ok(all_missing_global
+            .into_iter()
+            .filter_map(|global_idx| {
+                let slice = reconstructed.get(&(global_idx / per_slice))?;
+                let start = (global_idx % per_slice) * block_size;
+                Some((global_idx, slice[start..start + block_size].to_vec()))
+            })
+            .collect())
     }
 
     /// Validate slices from an existing file
@@ -792,7 +984,7 @@ impl RepairContext {
         let valid_slices = crate::verify::validation::validate_slices_crc32(
             &file_path,
             &crc_checksums,
-            self.recovery_set.slice_size.as_usize(),
+            self.recovery_set.block_size().as_usize(),
             file_info.file_length.as_u64(),
         )?;
 
@@ -813,6 +1005,7 @@ impl RepairContext {
         valid_slice_indices: &HashSet<usize>,
         reconstructed_slices: &ReconstructedSlices,
         block_positions: &HashMap<u32, usize>,
+        embedded_blocks: &HashMap<usize, SliceLocation>,
     ) -> Result<()> {
         debug!("Writing repaired file with streaming I/O: {:?}", file_path);
 
@@ -852,14 +1045,15 @@ impl RepairContext {
         let buffered = std::io::BufWriter::with_capacity(1024 * 1024, file);
         let mut writer = md5_writer::Md5Writer::new(buffered);
 
-        let slice_size = self.recovery_set.slice_size.as_usize();
+        // Files are written block by block: whole slices, or subslices in packed sets
+        let slice_size = self.recovery_set.block_size().as_usize();
         let mut slice_buffer = vec![0u8; slice_size];
         let mut bytes_written = 0u64;
         let mut next_expected_offset: Option<u64> = Some(0);
 
         for slice_index in 0..file_info.slice_count.as_usize() {
             let actual_size = if slice_index == file_info.slice_count - 1 {
-                let remaining = file_info.file_length % self.recovery_set.slice_size;
+                let remaining = file_info.file_length % self.recovery_set.block_size();
                 if remaining == 0 {
                     slice_size
                 } else {
@@ -887,6 +1081,32 @@ impl RepairContext {
                 bytes_written += actual_size as u64;
                 // Mark that we've broken the sequential read pattern
                 next_expected_offset = None;
+            } else if let Some(location) = embedded_blocks.get(&slice_index) {
+                // Copy the block straight out of the PAR2 file that embeds it
+                debug!(
+                    "Copying slice {} from embedded data in {:?}",
+                    slice_index, location.file_path
+                );
+                let mut embedded_file = open_for_reading(&location.file_path)?;
+                seek_file(
+                    &mut embedded_file,
+                    SeekFrom::Start(location.offset),
+                    &location.file_path,
+                )?;
+                read_slice_exact(
+                    &mut embedded_file,
+                    &mut slice_buffer[..actual_size],
+                    &location.file_path,
+                    slice_index,
+                )?;
+                write_slice_all(
+                    &mut writer,
+                    &slice_buffer[..actual_size],
+                    &temp_path,
+                    slice_index,
+                )?;
+                bytes_written += actual_size as u64;
+                next_expected_offset = None;
             } else if valid_slice_indices.contains(&slice_index) {
                 // Read from source file
                 if let Some(ref mut file) = source_file {
@@ -1030,20 +1250,49 @@ pub fn repair_files_with_base_path_and_extra_files(
         return Err(RepairError::FileNotFound(par2_file.to_string()));
     }
 
-    // Collect all PAR2 files in the set
-    let par2_files = crate::par2_files::collect_par2_files(par2_path);
+    // Collect all PAR2 files in the set; a selected set may live in files of any name
+    let par2_files = match verify_config.set_id {
+        Some(_) => crate::par2_files::collect_all_par2_files(
+            par2_path,
+            extra_files,
+            verify_config.discover_volumes,
+        ),
+        None => crate::par2_files::discover_par2_files(
+            par2_path,
+            extra_files,
+            verify_config.discover_volumes,
+        ),
+    };
+    let in_selected_set = |set_id: &crate::domain::RecoverySetId| {
+        verify_config
+            .set_id
+            .is_none_or(|selected| selected == *set_id)
+    };
 
     // Load metadata for memory-efficient recovery slice loading
-    let metadata = crate::par2_files::parse_recovery_slice_metadata(&par2_files, false);
+    let mut metadata = crate::par2_files::parse_recovery_slice_metadata(&par2_files, false);
+    metadata.retain(|m| in_selected_set(&m.set_id));
 
     // Load packets WITHOUT recovery slices (use metadata for lazy loading instead)
     // This saves ~1.5GB of memory for large PAR2 sets since recovery data is
     // loaded on-demand during reconstruction via RecoverySliceProvider
-    let initial_packet_set = crate::par2_files::load_par2_packets(&par2_files, false, false);
+    let initial_packet_set = load_repair_packets(&par2_files, verify_config)?;
     if initial_packet_set.packets.is_empty() {
         return Err(RepairError::NoValidPackets);
     }
 
+    // Add recovery slices held in an external RFSC-described file, unless a
+    // packet already carries the same exponent
+    let packet_exponents: HashSet<u32> = metadata.iter().map(|m| m.exponent).collect();
+    metadata.extend(
+        crate::par2_files::parse_external_recovery_metadata(
+            &par2_files,
+            &initial_packet_set.packets,
+        )
+        .into_iter()
+        .filter(|m| in_selected_set(&m.set_id) && !packet_exponents.contains(&m.exponent)),
+    );
+
     // Get the base directory for file resolution
     let base_path = base_path_override
         .map(Path::to_path_buf)
@@ -1069,20 +1318,24 @@ pub fn repair_files_with_base_path_and_extra_files(
     repair_verify_config.data_skipping = verify_config.data_skipping;
     repair_verify_config.skip_leeway = verify_config.skip_leeway;
     repair_verify_config.rename_only = verify_config.rename_only;
+    repair_verify_config.set_id = verify_config.set_id;
     if !extra_files.is_empty() || verify_config.rename_only {
         repair_verify_config.skip_full_file_md5 = false;
     }
     let mut verification_results =
-        run_repair_verification(&par2_files, &repair_verify_config, &base_path, extra_files);
+        run_repair_verification(&par2_files, &repair_verify_config, &base_path, extra_files)?;
 
     // Re-load packets for repair context (verification consumed them)
     // This is acceptable since packet parsing is fast (no recovery slice data)
-    let packet_set = crate::par2_files::load_par2_packets(&par2_files, false, false);
+    let packet_set = load_repair_packets(&par2_files, verify_config)?;
 
     // Create repair context using builder
     let mut repair_builder = RepairContextBuilder::new()
         .packets(packet_set.packets)
         .metadata(metadata)
+        .embedded_slices(crate::par2_files::parse_input_file_slice_metadata(
+            &par2_files,
+        ))
         .base_path(base_path.clone())
         .reporter(reporter);
     if let Some(memory_limit) = verify_config.memory_limit {
@@ -1098,39 +1351,117 @@ pub fn repair_files_with_base_path_and_extra_files(
     let renamed_files = repair_context.restore_renamed_files(&verification_results)?;
     if !renamed_files.is_empty() {
         verification_results =
-            run_repair_verification(&par2_files, &repair_verify_config, &base_path, extra_files);
-
-        if repair_verification_is_complete(&verification_results) {
-            return Ok((
-                repair_context,
-                RepairResult::Success {
-                    files_repaired: renamed_files.len(),
-                    files_verified: verification_results.present_file_count,
-                    repaired_files: renamed_files.clone(),
-                    verified_files: verification_results
-                        .files
-                        .iter()
-                        .map(|file| file.file_name.clone())
-                        .collect(),
-                    message: format!(
-                        "Successfully restored {} renamed file(s)",
-                        renamed_files.len()
-                    ),
-                },
-            ));
-        }
+            run_repair_verification(&par2_files, &repair_verify_config, &base_path, extra_files)?;
     }
 
-    if verify_config.rename_only {
-        return Ok((
-            repair_context,
-            rename_only_repair_result(&verification_results, renamed_files),
-        ));
+    // Non-recovery files cannot be repaired, so their damage outlives any repair
+    let damaged_non_recovery: Vec<String> = verification_results
+        .damaged_non_recovery_files()
+        .map(|file| file.file_name.clone())
+        .collect();
+
+    let result =
+        if !renamed_files.is_empty() && repair_verification_is_complete(&verification_results) {
+            RepairResult::Success {
+                files_repaired: renamed_files.len(),
+                files_verified: verification_results.present_file_count,
+                repaired_files: renamed_files.clone(),
+                verified_files: verification_results
+                    .files
+                    .iter()
+                    .map(|file| file.file_name.clone())
+                    .collect(),
+                message: format!(
+                    "Successfully restored {} renamed file(s)",
+                    renamed_files.len()
+                ),
+            }
+        } else if verify_config.rename_only {
+            rename_only_repair_result(&verification_results, renamed_files)
+        } else {
+            repair_context.repair(verification_results)?
+        };
+
+    Ok((
+        repair_context,
+        with_non_recovery_damage(result, damaged_non_recovery),
+    ))
+}
+
+/// Fail a repair result when non-recovery files are damaged or missing
+///
+/// Repair never touches these files, so an otherwise successful repair still
+/// leaves the set incomplete and must say which files are affected.
+fn with_non_recovery_damage(result: RepairResult, damaged: Vec<String>) -> RepairResult {
+    if damaged.is_empty() {
+        return result;
     }
+    let damage = format!(
+        "{} non-recovery file(s) are damaged or missing and cannot be repaired: {}",
+        damaged.len(),
+        damaged.join(", ")
+    );
 
-    let result = repair_context.repair(verification_results)?;
+    match result {
+        RepairResult::Success {
+            files_repaired,
+            files_verified,
+            verified_files,
+            ..
+        } => RepairResult::Failed {
+            files_failed: damaged,
+            files_verified,
+            verified_files,
+            message: format!("Repaired {files_repaired} file(s), but {damage}"),
+        },
+        RepairResult::NoRepairNeeded {
+            files_verified,
+            verified_files,
+            ..
+        } => RepairResult::Failed {
+            files_failed: damaged,
+            files_verified,
+            verified_files,
+            message: damage,
+        },
+        RepairResult::Failed {
+            mut files_failed,
+            files_verified,
+            verified_files,
+            message,
+        } => {
+            files_failed.extend(damaged);
+            RepairResult::Failed {
+                files_failed,
+                files_verified,
+                verified_files,
+                message: format!("{message}; {damage}"),
+            }
+        }
+    }
+}
+
+/// Read `len` bytes of embedded FileSlic data from a PAR2 file
+fn read_embedded_data(path: &Path, offset: u64, len: usize) -> Option<Vec<u8>> {
+    use std::io::{Read, Seek};
 
-    Ok((repair_context, result))
+    let mut file = fs::File::open(path).ok()?;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut data = vec![0u8; len];
+    file.read_exact(&mut data).ok()?;
+    Some(data)
+}
+
+/// Load packets without recovery slices, keeping only the selected recovery set
+fn load_repair_packets(
+    par2_files: &[PathBuf],
+    verify_config: &crate::verify::VerificationConfig,
+) -> Result<crate::par2_files::PacketSet> {
+    let packet_set = crate::par2_files::load_par2_packets(par2_files, false, false);
+    Ok(match &verify_config.set_id {
+        Some(set_id) => packet_set.select_set(set_id)?,
+        None => packet_set,
+    })
 }
 
 fn run_repair_verification(
@@ -1138,11 +1469,11 @@ fn run_repair_verification(
     repair_verify_config: &crate::verify::VerificationConfig,
     base_path: &Path,
     extra_files: &[PathBuf],
-) -> crate::verify::VerificationResults {
-    let packet_set = crate::par2_files::load_par2_packets(par2_files, false, false);
+) -> Result<crate::verify::VerificationResults> {
+    let packet_set = load_repair_packets(par2_files, repair_verify_config)?;
     let silent_reporter = crate::reporters::SilentVerificationReporter;
 
-    if extra_files.is_empty() {
+    Ok(if extra_files.is_empty() {
         crate::verify::comprehensive_verify_files(
             packet_set,
             repair_verify_config,
@@ -1157,7 +1488,7 @@ fn run_repair_verification(
             base_path,
             extra_files,
         )
-    }
+    })
 }
 
 fn repair_verification_is_complete(results: &crate::verify::VerificationResults) -> bool {
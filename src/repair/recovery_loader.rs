@@ -3,6 +3,7 @@
 //! This module provides a trait-based approach to loading recovery slice data,
 //! allowing for different strategies like filesystem reads, memory mapping, etc.
 
+use crate::domain::{Crc32Value, Md5Hash};
 use std::io;
 use std::path::PathBuf;
 
@@ -28,6 +29,14 @@ pub trait RecoveryDataLoader: Send + Sync {
 
     /// Get the size of the recovery data
     fn data_size(&self) -> usize;
+
+    /// Whether the data matches the checksums recorded for it
+    ///
+    /// Recovery packets are checked against their MD5 when parsed, so by
+    /// default the data is taken as intact.
+    fn is_intact(&self) -> io::Result<bool> {
+        Ok(true)
+    }
 }
 
 /// Standard filesystem-based loader
@@ -39,6 +48,22 @@ pub struct FileSystemLoader {
     pub data_size: usize, // Length of recovery_data
 }
 
+impl FileSystemLoader {
+    /// Check the recovery data against the checksums recorded for it
+    ///
+    /// Used for headerless recovery data (RFSC), where there is no packet MD5 to
+    /// rely on. Returns `Ok(false)` if the data is short or the checksums differ.
+    pub fn matches_checksums(&self, md5: &Md5Hash, crc32: Crc32Value) -> io::Result<bool> {
+        let data = match self.load_data() {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let (actual_md5, actual_crc32) = crate::checksum::compute_block_checksums(&data);
+        Ok(actual_md5 == *md5 && actual_crc32 == crc32)
+    }
+}
+
 impl RecoveryDataLoader for FileSystemLoader {
     fn load_data(&self) -> io::Result<Vec<u8>> {
         use std::fs::File;
@@ -82,6 +107,35 @@ impl RecoveryDataLoader for FileSystemLoader {
     }
 }
 
+/// Loader for headerless recovery data (RFSC) with its recorded checksums
+///
+/// Nothing is hashed until `is_intact` is asked, so loading a set stays cheap
+/// and only the slices repair actually uses are read twice.
+#[derive(Debug, Clone)]
+pub struct ChecksummedLoader {
+    pub loader: FileSystemLoader,
+    pub md5: Md5Hash,
+    pub crc32: Crc32Value,
+}
+
+impl RecoveryDataLoader for ChecksummedLoader {
+    fn load_data(&self) -> io::Result<Vec<u8>> {
+        self.loader.load_data()
+    }
+
+    fn load_chunk(&self, chunk_offset: usize, chunk_size: usize) -> io::Result<Vec<u8>> {
+        self.loader.load_chunk(chunk_offset, chunk_size)
+    }
+
+    fn data_size(&self) -> usize {
+        self.loader.data_size()
+    }
+
+    fn is_intact(&self) -> io::Result<bool> {
+        self.loader.matches_checksums(&self.md5, self.crc32)
+    }
+}
+
 // Future: MmapLoader implementation
 // #[derive(Debug)]
 // pub struct MmapLoader {
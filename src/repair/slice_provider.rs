@@ -149,6 +149,8 @@ pub trait SliceProvider {
 pub struct ChunkedSliceProvider {
     /// Map of slice index to location info - BTreeMap maintains sorted order!
     slice_locations: BTreeMap<usize, SliceLocation>,
+    /// Slices of a packed set, each made of consecutive subslice locations
+    packed_slices: BTreeMap<usize, Vec<SliceLocation>>,
     /// Open file handles (cached for performance)
     file_handles: HashMap<PathBuf, BufReader<File>>,
     /// Logical slice size for Reed-Solomon (all slices appear this size, zero-padded)
@@ -177,6 +179,7 @@ impl ChunkedSliceProvider {
 
         Self {
             slice_locations: BTreeMap::new(),
+            packed_slices: BTreeMap::new(),
             file_handles: HashMap::default(),
             logical_slice_size: LogicalSliceSize::new(slice_size),
             verified_slices: HashMap::default(),
@@ -192,6 +195,15 @@ impl ChunkedSliceProvider {
         self.slice_locations.insert(slice_index, location);
     }
 
+    /// Add a slice of a packed set, assembled from consecutive subslices
+    ///
+    /// Each subslice location uses the subslice size as its logical size. The
+    /// subslices are laid end to end; anything past the last one (the tail of the
+    /// final slice in the set) reads as zeros.
+    pub fn add_packed_slice(&mut self, slice_index: usize, subslices: Vec<SliceLocation>) {
+        self.packed_slices.insert(slice_index, subslices);
+    }
+
     /// Get or create a reader for the given file path
     fn get_or_create_reader(&mut self, path: &Path) -> Result<&mut BufReader<File>> {
         let reader = match self.file_handles.entry(path.to_path_buf()) {
@@ -315,6 +327,62 @@ impl ChunkedSliceProvider {
         Ok(buffer)
     }
 
+    /// Read a chunk of a packed slice by stitching together its subslices
+    fn read_packed_chunk(
+        &mut self,
+        slice_index: usize,
+        chunk_offset: usize,
+        chunk_size: usize,
+    ) -> Result<ChunkData> {
+        let subslices = self
+            .packed_slices
+            .get(&slice_index)
+            .ok_or(SliceProviderError::SliceNotFound { index: slice_index })?
+            .clone();
+
+        let slice_size = self.logical_slice_size.as_usize();
+        if chunk_offset >= slice_size {
+            return Err(SliceProviderError::InvalidChunkOffset {
+                offset: chunk_offset,
+                slice_size,
+            });
+        }
+
+        let chunk_end = (chunk_offset + chunk_size).min(slice_size);
+        let mut buffer = crate::reed_solomon::alloc_aligned_vec(chunk_end - chunk_offset);
+
+        let mut subslice_start = 0;
+        for location in &subslices {
+            let subslice_end = subslice_start + location.logical_size.as_usize();
+            let overlap_start = chunk_offset.max(subslice_start);
+            let overlap_end = chunk_end.min(subslice_end);
+
+            // Only the part of the overlap backed by file data needs reading;
+            // the rest stays zero, matching the subslice padding
+            let data_end = overlap_end.min(subslice_start + location.actual_size.as_usize());
+            if overlap_start < data_end {
+                let reader = self.get_or_create_reader(&location.file_path)?;
+                slice_provider_seek(
+                    reader,
+                    SeekFrom::Start(location.offset + (overlap_start - subslice_start) as u64),
+                    &location.file_path,
+                )?;
+                slice_provider_read_exact(
+                    reader,
+                    &mut buffer[overlap_start - chunk_offset..data_end - chunk_offset],
+                    &location.file_path,
+                )?;
+            }
+
+            if subslice_end >= chunk_end {
+                break;
+            }
+            subslice_start = subslice_end;
+        }
+
+        Ok(ChunkData::new(buffer))
+    }
+
     /// Update cache with LRU eviction
     fn update_cache(&mut self, cache_key: (usize, usize), buffer: Vec<u8>) {
         self.cache_access_counter += 1;
@@ -332,6 +400,10 @@ impl SliceProvider for ChunkedSliceProvider {
         chunk_offset: usize,
         chunk_size: usize,
     ) -> Result<ChunkData> {
+        if self.packed_slices.contains_key(&slice_index) {
+            return self.read_packed_chunk(slice_index, chunk_offset, chunk_size);
+        }
+
         let cache_key = (slice_index, chunk_offset);
 
         // Check cache first - early return with cached data
@@ -384,6 +456,10 @@ impl SliceProvider for ChunkedSliceProvider {
     }
 
     fn get_slice_size(&self, slice_index: usize) -> Option<usize> {
+        if self.packed_slices.contains_key(&slice_index) {
+            return Some(self.logical_slice_size.as_usize());
+        }
+
         // Return LOGICAL size (what Reed-Solomon sees), not actual size
         self.slice_locations
             .get(&slice_index)
@@ -392,13 +468,31 @@ impl SliceProvider for ChunkedSliceProvider {
 
     fn is_slice_available(&self, slice_index: usize) -> bool {
         self.slice_locations.contains_key(&slice_index)
+            || self.packed_slices.contains_key(&slice_index)
     }
 
     fn available_slices(&self) -> Vec<usize> {
-        self.slice_locations.keys().copied().collect()
+        if self.packed_slices.is_empty() {
+            return self.slice_locations.keys().copied().collect();
+        }
+
+        let mut slices: Vec<usize> = self
+            .slice_locations
+            .keys()
+            .chain(self.packed_slices.keys())
+            .copied()
+            .collect();
+        slices.sort_unstable();
+        slices.dedup();
+        slices
     }
 
     fn verify_slice(&mut self, slice_index: usize) -> Result<Option<bool>> {
+        // Packed slices have no slice-level checksum; IFSC entries cover subslices
+        if self.packed_slices.contains_key(&slice_index) {
+            return Ok(None);
+        }
+
         // Check cache first
         if let Some(&verified) = self.verified_slices.get(&slice_index) {
             return Ok(Some(verified));
@@ -536,6 +630,51 @@ mod tests {
         assert_eq!(chunk.data.len(), 50);
     }
 
+    #[test]
+    fn test_packed_slice_stitches_subslices() {
+        let mut first = NamedTempFile::new().unwrap();
+        first.write_all(&[0x11u8; 100]).unwrap();
+        first.flush().unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        second.write_all(&[0x22u8; 40]).unwrap();
+        second.flush().unwrap();
+
+        let subslice = |file: &NamedTempFile, offset: u64, actual: usize| SliceLocation {
+            file_path: file.path().to_path_buf(),
+            offset,
+            actual_size: ActualDataSize::new(actual),
+            logical_size: LogicalSliceSize::new(64),
+            expected_crc: None,
+        };
+
+        // Slice of four 64-byte subslices: two from the first file (the second one
+        // short), one from the second file, and a missing tail that reads as zeros
+        let mut provider = ChunkedSliceProvider::new(256);
+        provider.add_packed_slice(
+            0,
+            vec![
+                subslice(&first, 0, 64),
+                subslice(&first, 64, 36),
+                subslice(&second, 0, 40),
+            ],
+        );
+
+        assert!(provider.is_slice_available(0));
+        assert_eq!(provider.get_slice_size(0), Some(256));
+        assert_eq!(provider.available_slices(), vec![0]);
+        assert_eq!(provider.verify_slice(0).unwrap(), None);
+
+        let chunk = provider.read_chunk(0, 0, 256).unwrap();
+        let mut expected = vec![0u8; 256];
+        expected[..100].fill(0x11);
+        expected[128..168].fill(0x22);
+        assert_eq!(chunk.data, expected);
+
+        // A chunk straddling the subslice boundary
+        let chunk = provider.read_chunk(0, 96, 64).unwrap();
+        assert_eq!(chunk.data, expected[96..160]);
+    }
+
     #[test]
     fn test_recovery_slice_provider() {
         use std::io::Write;
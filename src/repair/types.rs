@@ -4,6 +4,7 @@ use crate::domain::{
     BlockCount, BlockSize, FileId, FileSize, GlobalSliceIndex, LocalSliceIndex, Md5Hash,
     RecoverySetId,
 };
+use crate::packets::InputFileSliceMetadata;
 use crate::{InputFileSliceChecksumPacket, RecoverySliceMetadata};
 use rustc_hash::FxHashMap as HashMap;
 
@@ -43,13 +44,35 @@ impl FileInfo {
 pub struct RecoverySetInfo {
     pub set_id: RecoverySetId,
     pub slice_size: BlockSize,
+    /// Subslice size for packed sets; file slice counts and offsets are then in subslices
+    pub subslice_size: Option<BlockSize>,
     pub files: Vec<FileInfo>,
     /// Memory-efficient metadata for recovery slices (lazy loading)
     pub recovery_slices_metadata: Vec<RecoverySliceMetadata>,
     pub file_slice_checksums: HashMap<FileId, InputFileSliceChecksumPacket>,
+    /// Deduplicated comments from CommASCI/CommUni packets
+    pub comments: Vec<String>,
+    /// Locations of source data embedded in FileSlic packets
+    pub embedded_slices: Vec<InputFileSliceMetadata>,
+    /// Files in the non-recovery set, which `files` leaves out
+    pub non_recovery_file_count: usize,
 }
 
 impl RecoverySetInfo {
+    /// Size of the per-file blocks described by IFSC checksums
+    ///
+    /// This is the slice size for ordinary sets and the subslice size for packed sets.
+    pub fn block_size(&self) -> BlockSize {
+        self.subslice_size.unwrap_or(self.slice_size)
+    }
+
+    /// Number of subslices per recovery slice (1 for ordinary sets)
+    pub fn subslices_per_slice(&self) -> usize {
+        self.subslice_size.map_or(1, |subslice_size| {
+            (self.slice_size.as_u64() / subslice_size.as_u64()) as usize
+        })
+    }
+
     /// Calculate the total number of data blocks across all files
     pub fn total_blocks(&self) -> usize {
         self.files.iter().map(|f| f.slice_count.as_usize()).sum()
@@ -60,20 +83,22 @@ impl RecoverySetInfo {
         self.files.iter().map(|f| f.file_length.as_u64()).sum()
     }
 
+    /// Statistics of the set, with non-recovery files counted as other files
+    pub fn stats(&self) -> crate::analysis::Par2Stats {
+        crate::analysis::Par2Stats {
+            file_count: self.files.len(),
+            other_file_count: self.non_recovery_file_count,
+            block_size: self.slice_size.as_u64() as u32,
+            total_blocks: self.total_blocks(),
+            total_size: self.total_size(),
+            recovery_blocks: self.recovery_slices_metadata.len(),
+        }
+    }
+
     /// Print statistics in par2cmdline format
     pub fn print_statistics(&self) {
-        println!();
-        println!(
-            "There are {} recoverable files and {} other files.",
-            self.files.len(),
-            0
-        );
-        println!("The block size used was {} bytes.", self.slice_size);
-        println!("There are a total of {} data blocks.", self.total_blocks());
-        println!(
-            "The total size of the data files is {} bytes.",
-            self.total_size()
-        );
+        crate::analysis::print_summary_stats(&self.stats());
+        crate::analysis::print_comments(&self.comments);
         println!();
         println!("Verifying source files:");
         println!();
@@ -21,6 +21,12 @@ pub struct VerificationConfig {
     pub skip_leeway: usize,
     /// Turbo-compatible rename-only mode for verify/repair.
     pub rename_only: bool,
+    /// Find PAR2 volumes by scanning file contents instead of relying on names.
+    pub discover_volumes: bool,
+    /// Restrict verify/repair to one recovery set among all PAR2 files in the directory.
+    pub set_id: Option<crate::domain::RecoverySetId>,
+    /// Hash cache file; files unchanged since they were cached skip the full-file MD5
+    pub hash_cache: Option<std::path::PathBuf>,
 }
 
 impl Default for VerificationConfig {
@@ -34,6 +40,9 @@ impl Default for VerificationConfig {
             data_skipping: false,
             skip_leeway: 0,
             rename_only: false,
+            discover_volumes: false,
+            set_id: None,
+            hash_cache: None,
         }
     }
 }
@@ -49,6 +58,9 @@ impl VerificationConfig {
             data_skipping: false,
             skip_leeway: 0,
             rename_only: false,
+            discover_volumes: false,
+            set_id: None,
+            hash_cache: None,
         }
     }
 
@@ -63,6 +75,9 @@ impl VerificationConfig {
             data_skipping: false,
             skip_leeway: 0,
             rename_only: false,
+            discover_volumes: false,
+            set_id: None,
+            hash_cache: None,
         }
     }
 
@@ -143,6 +158,30 @@ impl VerificationConfig {
                 .flatten()
                 .copied()
                 .unwrap_or(false),
+            discover_volumes: matches
+                .try_get_one::<bool>("discover_volumes")
+                .ok()
+                .flatten()
+                .copied()
+                .unwrap_or(false),
+            set_id: matches
+                .try_get_one::<String>("set_id")
+                .ok()
+                .flatten()
+                .map(|s| s.parse())
+                .transpose()?,
+            hash_cache: matches
+                .try_get_one::<String>("hash_cache")
+                .ok()
+                .flatten()
+                .map(|value| {
+                    let par2_file = matches
+                        .try_get_one::<String>("par2_file")
+                        .ok()
+                        .flatten()
+                        .map_or("", String::as_str);
+                    crate::hash_cache::path_from_option(value, std::path::Path::new(par2_file))
+                }),
         })
     }
 
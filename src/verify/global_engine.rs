@@ -10,9 +10,10 @@ use super::types::{
     BlockCount, BlockNumber, BlockVerificationResult, FileScanMetadata, FileSize, FileStatus,
     FileVerificationResult, VerificationResults,
 };
-use super::utils::extract_file_name;
+use super::utils::resolve_file_name;
 
 use crate::domain::{Crc32Value, FileId, Md5Hash};
+use crate::hash_cache::HashCache;
 use crate::packets::FileDescriptionPacket;
 use crate::reporters::VerificationReporter;
 use rayon::prelude::*;
@@ -20,7 +21,7 @@ use rustc_hash::FxHashMap as HashMap;
 use rustc_hash::FxHashSet as HashSet;
 use smallvec::SmallVec;
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 /// Map of block checksums to file locations where they were found
 type AvailableBlocksMap = HashMap<(Md5Hash, Crc32Value), Vec<(FileId, u32)>>;
@@ -84,8 +85,14 @@ pub struct GlobalVerificationEngine {
     block_table: GlobalBlockTable,
     /// File descriptions for all files in the recovery set
     file_descriptions: HashMap<FileId, FileDescriptionPacket>,
+    /// Unicode names from UniFileN packets, overriding FileDescription names
+    unicode_names: HashMap<FileId, String>,
     /// Deterministic protected-file order from the main packet.
     file_order: Vec<FileId>,
+    /// Subslices per recovery slice when the set is packed (blocks are subslices)
+    subslices_per_slice: Option<usize>,
+    /// Blocks that FileSlic packets can restore without recovery data
+    embedded_blocks: HashMap<FileId, HashSet<u32>>,
     /// Base directory for file operations
     base_dir: std::path::PathBuf,
     /// Number of recovery blocks available
@@ -98,6 +105,11 @@ pub struct GlobalVerificationEngine {
     skip_leeway: usize,
     /// Only scan extra files that can be exact renamed matches.
     rename_only: bool,
+    /// Separate engine for the non-recovery set, which never shares blocks
+    /// with the recovery set
+    non_recovery_engine: Option<Box<GlobalVerificationEngine>>,
+    /// File hashes recorded by create, used instead of hashing unchanged files
+    hash_cache: Option<Arc<HashCache>>,
 }
 
 /// Result of verifying a single file using global block table
@@ -131,53 +143,96 @@ impl GlobalVerificationEngine {
         config: &super::VerificationConfig,
     ) -> Result<Self, String> {
         // Extract packet information
-        let main_packet = crate::packets::processing::extract_main_packet(packets)
+        let layout = crate::packets::processing::extract_slice_layout(packets)
             .ok_or("No main packet found")?;
-        let block_size = main_packet.slice_size;
-        let file_order = main_packet.file_ids.clone();
-
-        let file_descriptions = crate::packets::processing::extract_file_descriptions(packets);
+        let block_size = layout.block_size();
+        let subslices_per_slice = layout.subslices_per_slice();
+        let file_order = layout.file_ids;
+
+        // Non-recovery files are verified by their own engine so their blocks
+        // never count towards (or stand in for) recovery set blocks
+        let non_recovery_ids: HashSet<FileId> =
+            crate::packets::processing::extract_non_recovery_file_ids(packets)
+                .into_iter()
+                .collect();
+        let (file_descriptions, non_recovery_descriptions): (Vec<_>, Vec<_>) =
+            crate::packets::processing::extract_file_descriptions(packets)
+                .into_iter()
+                .partition(|desc| !non_recovery_ids.contains(&desc.file_id));
         let slice_checksums = crate::packets::processing::extract_slice_checksums(packets);
+        let unicode_names = crate::packets::processing::extract_unicode_filenames(packets);
+        let embedded_blocks =
+            crate::packets::processing::extract_embedded_blocks(packets, block_size);
 
         // Count recovery blocks available
-        let recovery_block_count = packets
-            .iter()
-            .filter_map(|p| match p {
-                crate::Packet::RecoverySlice(_) => Some(1),
-                _ => None,
-            })
-            .sum();
+        let recovery_block_count = packets.iter().filter(|p| p.is_recovery_slice()).count();
 
         // Build global block table
-        let mut builder = GlobalBlockTableBuilder::new(block_size);
-
-        for file_description in &file_descriptions {
-            if let Some(checksums) = slice_checksums.get(&file_description.file_id) {
-                builder.add_file_blocks(file_description.file_id, checksums);
+        let build_block_table = |descriptions: &[&FileDescriptionPacket]| {
+            let mut builder = GlobalBlockTableBuilder::new(block_size);
+            for file_description in descriptions {
+                if let Some(checksums) = slice_checksums.get(&file_description.file_id) {
+                    builder.add_file_blocks(file_description.file_id, checksums);
+                }
             }
-        }
-
-        let block_table = builder.build();
+            builder.build()
+        };
 
         // Create file description lookup
-        let file_lookup = file_descriptions
-            .into_iter()
-            .map(|desc| (desc.file_id, desc.clone()))
-            .collect();
+        let file_lookup = |descriptions: Vec<&FileDescriptionPacket>| {
+            descriptions
+                .into_iter()
+                .map(|desc| (desc.file_id, desc.clone()))
+                .collect()
+        };
+
+        let hash_cache = config
+            .hash_cache
+            .as_deref()
+            .map(|path| Arc::new(HashCache::load(path)));
+
+        let non_recovery_engine = (!non_recovery_descriptions.is_empty()).then(|| {
+            Box::new(Self {
+                block_table: build_block_table(&non_recovery_descriptions),
+                file_descriptions: file_lookup(non_recovery_descriptions),
+                unicode_names: unicode_names.clone(),
+                file_order: crate::packets::processing::extract_non_recovery_file_ids(packets),
+                subslices_per_slice: None,
+                embedded_blocks: HashMap::default(),
+                base_dir: base_dir.as_ref().to_path_buf(),
+                recovery_block_count: 0,
+                skip_full_md5: config.skip_full_file_md5,
+                data_skipping: config.data_skipping,
+                skip_leeway: config.skip_leeway,
+                rename_only: config.rename_only,
+                non_recovery_engine: None,
+                hash_cache: hash_cache.clone(),
+            })
+        });
 
         Ok(Self {
-            block_table,
-            file_descriptions: file_lookup,
+            block_table: build_block_table(&file_descriptions),
+            file_descriptions: file_lookup(file_descriptions),
+            unicode_names,
             file_order,
+            subslices_per_slice,
+            embedded_blocks,
             base_dir: base_dir.as_ref().to_path_buf(),
             recovery_block_count,
             skip_full_md5: config.skip_full_file_md5,
             data_skipping: config.data_skipping,
             skip_leeway: config.skip_leeway,
             rename_only: config.rename_only,
+            non_recovery_engine,
+            hash_cache,
         })
     }
 
+    /// Resolve the on-disk name of a protected file
+    fn file_name(&self, file_description: &FileDescriptionPacket) -> String {
+        resolve_file_name(file_description, &self.unicode_names)
+    }
+
     /// Get the global block table
     pub fn block_table(&self) -> &GlobalBlockTable {
         &self.block_table
@@ -227,11 +282,59 @@ impl GlobalVerificationEngine {
         // Note: We count ALL recovery packets that were loaded, not just those needed
         let recovery_blocks_available = self.recovery_block_count;
 
-        VerificationResults::from_file_results(
+        let mut results = VerificationResults::from_file_results(
             file_results,
             block_results,
             recovery_blocks_available,
-        )
+        );
+
+        // In packed sets one recovery block restores a whole slice of subslices,
+        // so damaged subslices sharing a slice only cost one recovery block.
+        // Blocks embedded in FileSlic packets are copied and cost nothing.
+        if self.subslices_per_slice.is_some() || !self.embedded_blocks.is_empty() {
+            results.blocks_needed_for_repair = self.slices_needed_for_repair(&results.files);
+            results.repair_possible = recovery_blocks_available >= results.blocks_needed_for_repair;
+        }
+
+        // Non-recovery files are reported on their own and never need recovery blocks
+        if let Some(engine) = &self.non_recovery_engine {
+            results.non_recovery_files = engine
+                .verify_recovery_set_with_extra_files(reporter, parallel, &[])
+                .files;
+        }
+
+        results
+    }
+
+    /// Count the distinct recovery slices touched by damaged, non-embedded blocks
+    fn slices_needed_for_repair(&self, files: &[FileVerificationResult]) -> usize {
+        let per_slice = self.subslices_per_slice.unwrap_or(1);
+        let damaged: HashMap<FileId, &[u32]> = files
+            .iter()
+            .map(|file| (file.file_id, file.damaged_blocks.as_slice()))
+            .collect();
+        let no_embedded = HashSet::default();
+
+        let mut slices = HashSet::default();
+        let mut global_offset = 0usize;
+        for file_id in &self.file_order {
+            let Some(desc) = self.file_descriptions.get(file_id) else {
+                continue;
+            };
+            if let Some(blocks) = damaged.get(file_id) {
+                let embedded = self.embedded_blocks.get(file_id).unwrap_or(&no_embedded);
+                slices.extend(
+                    blocks
+                        .iter()
+                        .filter(|block| !embedded.contains(block))
+                        .map(|&block| (global_offset + block as usize) / per_slice),
+                );
+            }
+            global_offset += self
+                .calculate_total_blocks(FileSize::new(desc.file_length))
+                .as_usize();
+        }
+        slices.len()
     }
 
     /// Scan all available files and build a global map of which blocks exist where
@@ -256,7 +359,7 @@ impl GlobalVerificationEngine {
             .file_descriptions
             .values()
             .filter(|desc| {
-                let file_name = extract_file_name(desc);
+                let file_name = self.file_name(desc);
                 let file_path = self.base_dir.join(&file_name);
                 file_path.exists()
             })
@@ -381,7 +484,7 @@ impl GlobalVerificationEngine {
             self.scan_single_file_with_progress(file_path, file_size, reporter_lock);
 
         if self.skip_full_md5 && scan_metadata.actual_file_hash.is_none() {
-            Self::record_file_hashes(
+            self.record_file_hashes(
                 file_path,
                 super::types::BlockSize::new(self.block_table.block_size() as usize),
                 false,
@@ -407,7 +510,7 @@ impl GlobalVerificationEngine {
     ) {
         use crate::verify::types::FileSize;
 
-        let file_name = extract_file_name(file_description);
+        let file_name = self.file_name(file_description);
         let file_path = self.base_dir.join(&file_name);
         let file_size = FileSize::new(file_description.file_length);
 
@@ -523,7 +626,7 @@ impl GlobalVerificationEngine {
             // Short file is now complete - mark as 100% scanned and compute file hash
             Self::report_progress(reporter_lock, &state, file_size);
 
-            Self::record_file_hashes(
+            self.record_file_hashes(
                 file_path,
                 block_size,
                 self.skip_full_md5,
@@ -650,7 +753,7 @@ impl GlobalVerificationEngine {
 
         // Compute file hashes and store them in metadata using a streaming hasher
         // (avoid reading entire file into memory for large files).
-        Self::record_file_hashes(
+        self.record_file_hashes(
             file_path,
             block_size,
             self.skip_full_md5,
@@ -661,6 +764,7 @@ impl GlobalVerificationEngine {
     }
 
     fn record_file_hashes(
+        &self,
         file_path: &Path,
         block_size: super::types::BlockSize,
         skip_full_md5: bool,
@@ -670,6 +774,17 @@ impl GlobalVerificationEngine {
             return;
         }
 
+        if let Some((file_size, hash, hash_16k)) = self
+            .hash_cache
+            .as_ref()
+            .and_then(|cache| cache.file_hashes(file_path))
+        {
+            metadata.actual_file_hash = Some(hash);
+            metadata.actual_file_hash_16k = Some(hash_16k);
+            metadata.actual_file_size = Some(file_size);
+            return;
+        }
+
         let file_path_string = file_path.to_string_lossy().into_owned();
         if let Ok(checksummer) =
             crate::checksum::FileCheckSummer::new(file_path_string, block_size.as_usize())
@@ -709,7 +824,7 @@ impl GlobalVerificationEngine {
             .filter(|(file_id, _)| !seen.contains(file_id))
             .map(|(_, description)| description)
             .collect();
-        leftovers.sort_by_key(|description| extract_file_name(description));
+        leftovers.sort_by_key(|description| self.file_name(description));
         ordered.extend(leftovers);
         ordered
     }
@@ -719,7 +834,7 @@ impl GlobalVerificationEngine {
         file_description: &FileDescriptionPacket,
         file_statuses: &FileStatusMap,
     ) -> bool {
-        let file_name = extract_file_name(file_description);
+        let file_name = self.file_name(file_description);
         let file_path = self.base_dir.join(&file_name);
 
         match file_statuses.get(&file_description.file_id) {
@@ -1206,7 +1321,7 @@ impl GlobalVerificationEngine {
         let mut file_results = Vec::new();
 
         for file_description in self.ordered_file_descriptions() {
-            let file_name = extract_file_name(file_description);
+            let file_name = self.file_name(file_description);
             let file_size = FileSize::new(file_description.file_length);
             let total_blocks = self.calculate_total_blocks(file_size);
 
@@ -1424,9 +1539,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut local_map = HashMap::default();
@@ -1504,9 +1624,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut local_map = HashMap::default();
@@ -1561,9 +1686,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut local_map = HashMap::default();
@@ -1625,9 +1755,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         // Test 1: Direct insertion
@@ -1999,9 +2134,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         // Case 1: All blocks available
@@ -2148,9 +2288,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         // Create a buffer with the matching block
@@ -2214,9 +2359,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let block_size = BlockSize::new(1024);
@@ -2276,9 +2426,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let block_size = BlockSize::new(1024);
@@ -2328,9 +2483,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let block_size = BlockSize::new(1024);
@@ -2382,9 +2542,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let block_size = BlockSize::new(1024);
@@ -2437,9 +2602,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         // Create a buffer with 2MB worth of data
@@ -2559,9 +2729,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let block_size = BlockSize::new(1024);
@@ -2623,9 +2798,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut state = ScannerState::new(3072);
@@ -2702,9 +2882,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut local_map = HashMap::default();
@@ -2772,9 +2957,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let block_size = BlockSize::new(1024);
@@ -2822,9 +3012,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         // Simulate finding only 2 of 3 blocks
@@ -2895,9 +3090,14 @@ mod tests {
             skip_leeway: 0,
             block_table,
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut local_map = HashMap::default();
@@ -3074,9 +3274,14 @@ mod tests {
             skip_leeway: 2,
             block_table: builder.build(),
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut state = ScannerState::new(64);
@@ -3129,9 +3334,14 @@ mod tests {
             skip_leeway: 2,
             block_table: builder.build(),
             file_descriptions: HashMap::default(),
+            unicode_names: HashMap::default(),
             base_dir: std::path::PathBuf::from("."),
             file_order: Vec::new(),
+            subslices_per_slice: None,
+            embedded_blocks: HashMap::default(),
             rename_only: false,
+            non_recovery_engine: None,
+            hash_cache: None,
         };
 
         let mut state = ScannerState::new(64);
@@ -30,7 +30,7 @@ pub use types::{
     BlockVerificationResult, FileScanMetadata, FileStatus, FileVerificationResult,
     VerificationResults,
 };
-pub use utils::extract_file_name;
+pub use utils::{extract_file_name, resolve_file_name};
 pub use validation::{validate_slices_crc32, validate_slices_crc32_with_progress};
 pub use verifier::FileVerifier;
 
@@ -103,6 +103,7 @@ pub fn comprehensive_verify_files_with_extra_files<R: VerificationReporter>(
                 recovery_blocks_available: 0,
                 repair_possible: true, // 0 missing blocks is mathematically repairable
                 blocks_needed_for_repair: 0,
+                non_recovery_files: Vec::new(),
             };
         }
     };
@@ -134,7 +135,7 @@ pub fn comprehensive_verify_files_with_extra_files<R: VerificationReporter>(
 
     // Use the recovery block count from the packet set
     results.recovery_blocks_available = packet_set.recovery_block_count;
-    results.repair_possible = packet_set.recovery_block_count >= results.missing_block_count;
+    results.repair_possible = packet_set.recovery_block_count >= results.blocks_needed_for_repair;
 
     results
 }
@@ -279,6 +279,8 @@ pub struct VerificationResults {
     pub recovery_blocks_available: usize,
     pub repair_possible: bool,
     pub blocks_needed_for_repair: usize,
+    /// Results for files the set checksums but cannot repair
+    pub non_recovery_files: Vec<FileVerificationResult>,
 }
 
 impl VerificationResults {
@@ -323,8 +325,16 @@ impl VerificationResults {
             recovery_blocks_available,
             repair_possible: recovery_blocks_available >= missing_blocks,
             blocks_needed_for_repair: missing_blocks,
+            non_recovery_files: Vec::new(),
         }
     }
+
+    /// Non-recovery files that are damaged or missing
+    pub fn damaged_non_recovery_files(&self) -> impl Iterator<Item = &FileVerificationResult> {
+        self.non_recovery_files
+            .iter()
+            .filter(|file| matches!(file.status, FileStatus::Corrupted | FileStatus::Missing))
+    }
 }
 
 impl fmt::Display for VerificationResults {
@@ -350,6 +360,15 @@ impl fmt::Display for VerificationResults {
         .filter(|(count, _)| *count > 0)
         .try_for_each(|(count, message)| writeln!(f, "{} {}", count, message))?;
 
+        let damaged_non_recovery = self.damaged_non_recovery_files().count();
+        if damaged_non_recovery > 0 {
+            writeln!(
+                f,
+                "{} non-recovery file(s) are damaged or missing and cannot be repaired.",
+                damaged_non_recovery
+            )?;
+        }
+
         writeln!(
             f,
             "You have {} out of {} data blocks available.",
@@ -367,8 +386,14 @@ impl fmt::Display for VerificationResults {
 
         // Repair status using functional pattern matching
         match (self.missing_block_count, self.repair_possible) {
+            (0, _) if damaged_non_recovery > 0 => writeln!(
+                f,
+                "Protected files are correct, but {} non-recovery file(s) are damaged or missing.",
+                damaged_non_recovery
+            )?,
             (0, _) => writeln!(f, "All files are correct, repair is not required.")?,
-            (missing, true) => {
+            (_, true) => {
+                let missing = self.blocks_needed_for_repair;
                 writeln!(f, "Repair is possible.")?;
                 if self.recovery_blocks_available > missing {
                     writeln!(
@@ -379,7 +404,8 @@ impl fmt::Display for VerificationResults {
                 }
                 writeln!(f, "{} recovery blocks will be used to repair.", missing)?;
             }
-            (missing, false) => {
+            (_, false) => {
+                let missing = self.blocks_needed_for_repair;
                 writeln!(f, "Repair is not possible.")?;
                 writeln!(
                     f,
@@ -388,6 +414,13 @@ impl fmt::Display for VerificationResults {
                 )?;
             }
         }
+        if damaged_non_recovery > 0 && self.missing_block_count > 0 {
+            writeln!(
+                f,
+                "{} non-recovery file(s) will remain damaged or missing after repair.",
+                damaged_non_recovery
+            )?;
+        }
 
         Ok(())
     }
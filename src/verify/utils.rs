@@ -1,6 +1,8 @@
 //! Utility functions for verification operations
 
+use crate::domain::FileId;
 use crate::packets::FileDescriptionPacket;
+use rustc_hash::FxHashMap as HashMap;
 
 /// Extract clean file name from FileDescription packet
 ///
@@ -10,3 +12,16 @@ pub fn extract_file_name(file_desc: &FileDescriptionPacket) -> String {
         .trim_end_matches('\0')
         .to_string()
 }
+
+/// Resolve the on-disk name for a file, preferring its Unicode Filename packet
+///
+/// Falls back to the FileDescription name when no UniFileN packet was found
+pub fn resolve_file_name(
+    file_desc: &FileDescriptionPacket,
+    unicode_names: &HashMap<FileId, String>,
+) -> String {
+    unicode_names
+        .get(&file_desc.file_id)
+        .cloned()
+        .unwrap_or_else(|| extract_file_name(file_desc))
+}
@@ -1,3 +1,9 @@
+//! Fixtures and helpers shared by the integration tests
+//!
+//! Each test crate declares this module `pub`, so the helpers it does not
+//! call are not reported as dead code.
+
+use par2rs::create::CreateContextBuilder;
 use std::path::{Path, PathBuf};
 
 pub const PAR1_FLATDATA_FILES: [(&str, &[(usize, u8)]); 10] = [
@@ -166,3 +172,82 @@ pub fn prepare_par1_flatdata_fixture(dir: &Path) -> PathBuf {
     write_par1_flatdata_files(dir);
     dir.join("testdata.par")
 }
+
+/// Deterministic pseudo-random test data
+pub fn lcg_data(seed: u64, len: usize) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 56) as u8
+        })
+        .collect()
+}
+
+/// Write `lcg_data(seed, len)` for each `(name, seed, len)` into `dir`
+pub fn write_lcg_files(dir: &Path, files: &[(&str, u64, usize)]) -> Vec<(PathBuf, Vec<u8>)> {
+    files
+        .iter()
+        .map(|&(name, seed, len)| {
+            let path = dir.join(name);
+            let data = lcg_data(seed, len);
+            std::fs::write(&path, &data)
+                .unwrap_or_else(|err| panic!("failed to write {name}: {err}"));
+            (path, data)
+        })
+        .collect()
+}
+
+/// Write `a.bin` and `b.bin` of the given lengths into `dir`
+pub fn write_sources(dir: &Path, a_len: usize, b_len: usize) -> Vec<PathBuf> {
+    write_lcg_files(dir, &[("a.bin", 1, a_len), ("b.bin", 2, b_len)])
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Quiet create of `sources` into `par2_file`
+pub fn create_builder(par2_file: &Path, sources: &[PathBuf]) -> CreateContextBuilder {
+    CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(sources.to_vec())
+        .quiet(true)
+}
+
+/// File names and contents of `files`, sorted by name
+pub fn read_outputs(files: &[String]) -> Vec<(String, Vec<u8>)> {
+    let mut outputs: Vec<(String, Vec<u8>)> = files
+        .iter()
+        .map(|path| {
+            let name = Path::new(path).file_name().unwrap().to_string_lossy();
+            (name.to_string(), std::fs::read(path).unwrap())
+        })
+        .collect();
+    outputs.sort();
+    outputs
+}
+
+/// Verify the set of `par2_file` with the default configuration
+pub fn verify_set(par2_file: &Path) -> par2rs::verify::VerificationResults {
+    let par2_files = par2rs::par2_files::collect_par2_files(par2_file);
+    let packet_set = par2rs::par2_files::load_par2_packets(&par2_files, false, false);
+    par2rs::verify::comprehensive_verify_files(
+        packet_set,
+        &par2rs::verify::VerificationConfig::default(),
+        &par2rs::reporters::SilentVerificationReporter,
+        par2_file.parent().unwrap(),
+    )
+}
+
+/// Repair the set of `par2_file` with the default configuration
+pub fn repair_set(par2_file: &Path) -> par2rs::repair::RepairResult {
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    result
+}
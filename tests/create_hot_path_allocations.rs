@@ -3,35 +3,56 @@
 use par2rs::create::{CreateContextBuilder, SilentCreateReporter};
 use std::alloc::{GlobalAlloc, Layout, System};
 use std::path::Path;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+use std::sync::Mutex;
 
 struct CountingAllocator;
 
 static ALLOCATIONS_ENABLED: AtomicBool = AtomicBool::new(false);
 static ALLOCATION_COUNT: AtomicUsize = AtomicUsize::new(0);
+// Bytes allocated while enabled minus bytes freed; frees of earlier allocations
+// can drive it negative, which only makes the peak more conservative
+static LIVE_BYTES: AtomicIsize = AtomicIsize::new(0);
+static PEAK_BYTES: AtomicIsize = AtomicIsize::new(0);
+// The counters are global, so tests measuring them must not overlap
+static MEASURE_LOCK: Mutex<()> = Mutex::new(());
+
+fn record_alloc(size: usize) {
+    ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+    let live = LIVE_BYTES.fetch_add(size as isize, Ordering::Relaxed) + size as isize;
+    PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+}
+
+fn record_dealloc(size: usize) {
+    LIVE_BYTES.fetch_sub(size as isize, Ordering::Relaxed);
+}
 
 unsafe impl GlobalAlloc for CountingAllocator {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         if ALLOCATIONS_ENABLED.load(Ordering::Relaxed) {
-            ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+            record_alloc(layout.size());
         }
         unsafe { System.alloc(layout) }
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        if ALLOCATIONS_ENABLED.load(Ordering::Relaxed) {
+            record_dealloc(layout.size());
+        }
         unsafe { System.dealloc(ptr, layout) }
     }
 
     unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
         if ALLOCATIONS_ENABLED.load(Ordering::Relaxed) {
-            ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+            record_alloc(layout.size());
         }
         unsafe { System.alloc_zeroed(layout) }
     }
 
     unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
         if ALLOCATIONS_ENABLED.load(Ordering::Relaxed) {
-            ALLOCATION_COUNT.fetch_add(1, Ordering::Relaxed);
+            record_dealloc(layout.size());
+            record_alloc(new_size);
         }
         unsafe { System.realloc(ptr, layout, new_size) }
     }
@@ -40,6 +61,22 @@ unsafe impl GlobalAlloc for CountingAllocator {
 #[global_allocator]
 static GLOBAL: CountingAllocator = CountingAllocator;
 
+/// Run `create` with allocation tracking on, returning `(allocations, peak_bytes)`
+fn measure_create(context: &mut par2rs::create::CreateContext) -> (usize, isize) {
+    let _guard = MEASURE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    ALLOCATION_COUNT.store(0, Ordering::Relaxed);
+    LIVE_BYTES.store(0, Ordering::Relaxed);
+    PEAK_BYTES.store(0, Ordering::Relaxed);
+    ALLOCATIONS_ENABLED.store(true, Ordering::SeqCst);
+    context.create().unwrap();
+    ALLOCATIONS_ENABLED.store(false, Ordering::SeqCst);
+
+    (
+        ALLOCATION_COUNT.load(Ordering::Relaxed),
+        PEAK_BYTES.load(Ordering::Relaxed),
+    )
+}
+
 fn count_allocations_during_create(
     source_path: &Path,
     output_path: &Path,
@@ -58,13 +95,7 @@ fn count_allocations_during_create(
     }
 
     let mut context = builder.build().unwrap();
-
-    ALLOCATION_COUNT.store(0, Ordering::Relaxed);
-    ALLOCATIONS_ENABLED.store(true, Ordering::SeqCst);
-    context.create().unwrap();
-    ALLOCATIONS_ENABLED.store(false, Ordering::SeqCst);
-
-    ALLOCATION_COUNT.load(Ordering::Relaxed)
+    measure_create(&mut context).0
 }
 
 #[test]
@@ -85,3 +116,41 @@ fn create_hot_path_allocations_do_not_scale_with_chunk_count() {
         "multi-chunk create allocations scaled with chunk count: one={one_chunk}, multi={multi_chunk}, allowed_growth={allowed_growth}"
     );
 }
+
+/// Peak heap use of a create with 16 source and 64 recovery blocks of `block_size`
+fn peak_bytes_for_block_size(dir: &Path, block_size: usize) -> isize {
+    let source_path = dir.join(format!("source{block_size}.bin"));
+    let data: Vec<u8> = (0..16 * block_size).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&source_path, data).unwrap();
+
+    let mut context = CreateContextBuilder::new()
+        .output_name(
+            dir.join(format!("out{block_size}.par2"))
+                .to_string_lossy()
+                .to_string(),
+        )
+        .source_files(vec![source_path])
+        .block_size(block_size as u64)
+        .recovery_block_count(64)
+        .recovery_file_count(4)
+        .memory_limit(256 * 1024)
+        .thread_count(1)
+        .reporter(Box::new(SilentCreateReporter))
+        .build()
+        .unwrap();
+    measure_create(&mut context).1
+}
+
+#[test]
+fn create_peak_memory_does_not_scale_with_recovery_size() {
+    let tmp = tempfile::tempdir().unwrap();
+    let small = peak_bytes_for_block_size(tmp.path(), 16 * 1024);
+    let large = peak_bytes_for_block_size(tmp.path(), 128 * 1024);
+
+    // 8 MiB of recovery data in the large run against 1 MiB in the small one
+    let recovery_growth = 64 * (128 - 16) * 1024;
+    assert!(
+        large - small < recovery_growth / 4,
+        "create peak memory grew with recovery size: small={small}, large={large}"
+    );
+}
@@ -347,6 +347,7 @@ fn test_calculate_par2_stats_complex() {
 fn test_par2_stats_clone() {
     let stats = analysis::Par2Stats {
         file_count: 5,
+        other_file_count: 0,
         block_size: 4096,
         total_blocks: 20,
         total_size: 81920,
@@ -360,6 +361,7 @@ fn test_par2_stats_clone() {
 fn test_par2_stats_debug_format() {
     let stats = analysis::Par2Stats {
         file_count: 3,
+        other_file_count: 0,
         block_size: 2048,
         total_blocks: 15,
         total_size: 30720,
@@ -374,6 +376,7 @@ fn test_par2_stats_print_summary() {
     // Just ensure it doesn't panic
     let stats = analysis::Par2Stats {
         file_count: 3,
+        other_file_count: 0,
         block_size: 4096,
         total_blocks: 10,
         total_size: 40960,
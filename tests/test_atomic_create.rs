@@ -0,0 +1,107 @@
+//! Crash-safe create
+//!
+//! Outputs appear under their final names only once the whole set is written;
+//! a failed run leaves nothing behind, and temporaries from a crashed run are
+//! removed by the next one.
+
+use par2rs::create::CreateContextBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn builder(par2_file: &Path, sources: &[PathBuf]) -> CreateContextBuilder {
+    common::create_builder(par2_file, sources)
+        .block_size(1024)
+        .recovery_block_count(8)
+        .recovery_file_count(3)
+}
+
+fn file_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn failed_create_leaves_no_outputs_and_can_be_rerun() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 7_000);
+    let par2_file = temp.path().join("data.par2");
+
+    // Shrink a source after the scan so reading it fails midway through create
+    let mut context = builder(&par2_file, &sources).build().unwrap();
+    fs::write(&sources[1], common::lcg_data(2, 100)).unwrap();
+    assert!(context.create().is_err());
+    assert_eq!(file_names(temp.path()), vec!["a.bin", "b.bin"]);
+
+    fs::write(&sources[1], common::lcg_data(2, 7_000)).unwrap();
+    let mut context = builder(&par2_file, &sources).build().unwrap();
+    context.create().unwrap();
+    assert_eq!(context.output_files().len(), 4);
+    assert!(context
+        .output_files()
+        .iter()
+        .all(|path| Path::new(path).exists()));
+    assert!(!file_names(temp.path())
+        .iter()
+        .any(|name| name.ends_with(par2rs::create::staged_output::TEMP_SUFFIX)));
+}
+
+#[test]
+fn stale_temporaries_of_the_set_are_removed() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 7_000);
+    let par2_file = temp.path().join("data.par2");
+
+    let stale = temp.path().join("data.vol07+15.par2.par2rs-tmp");
+    let unrelated = temp.path().join("other.par2.par2rs-tmp");
+    fs::write(&stale, b"left by a crash").unwrap();
+    fs::write(&unrelated, b"another set").unwrap();
+
+    builder(&par2_file, &sources)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+    assert!(!stale.exists());
+    assert!(unrelated.exists());
+}
+
+#[test]
+fn failed_overwrite_restores_the_previous_set() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 7_000);
+    let par2_file = temp.path().join("data.par2");
+
+    let mut context = builder(&par2_file, &sources).build().unwrap();
+    context.create().unwrap();
+    let outputs = context.output_files().to_vec();
+    let previous: Vec<Vec<u8>> = outputs.iter().map(|path| fs::read(path).unwrap()).collect();
+
+    // A directory under the last output's name makes its rename fail after
+    // the earlier outputs have already been replaced
+    let blocked = Path::new(outputs.last().unwrap());
+    fs::remove_file(blocked).unwrap();
+    fs::create_dir(blocked).unwrap();
+    fs::write(blocked.join("keep"), b"in the way").unwrap();
+
+    fs::write(&sources[0], common::lcg_data(7, 20_000)).unwrap();
+    let mut context = builder(&par2_file, &sources)
+        .overwrite_existing(true)
+        .build()
+        .unwrap();
+    assert!(context.create().is_err());
+
+    for (path, old) in outputs.iter().zip(&previous).take(outputs.len() - 1) {
+        assert_eq!(&fs::read(path).unwrap(), old, "{path} was not restored");
+    }
+    assert!(!file_names(temp.path()).iter().any(|name| {
+        name.ends_with(par2rs::create::staged_output::TEMP_SUFFIX)
+            || name.ends_with(par2rs::create::staged_output::BACKUP_SUFFIX)
+    }));
+}
@@ -14,7 +14,7 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 use tempfile::TempDir;
 
-mod common;
+pub mod common;
 
 /// Helper to get the path to a compiled binary
 fn get_binary_path(name: &str) -> PathBuf {
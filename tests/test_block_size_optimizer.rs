@@ -0,0 +1,110 @@
+//! Block size search for sets of many small files
+//!
+//! The optimized block size must beat the default 2000-block choice on padding
+//! plus recovery data, stay within the block limit, and still repair.
+
+use par2rs::create::CreateContextBuilder;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Many small files of uneven sizes, plus one larger file
+fn write_sources(dir: &Path) -> Vec<PathBuf> {
+    let mut sizes: Vec<usize> = (0..300).map(|i| 200 + (i * 7919) % 3000).collect();
+    sizes.push(150_000);
+    sizes
+        .into_iter()
+        .enumerate()
+        .map(|(i, size)| {
+            let path = dir.join(format!("file{:03}.bin", i));
+            fs::write(&path, common::lcg_data(i as u64, size)).unwrap();
+            path
+        })
+        .collect()
+}
+
+fn builder(dir: &Path, name: &str, sources: &[PathBuf]) -> CreateContextBuilder {
+    common::create_builder(&dir.join(name), sources).redundancy_percentage(10)
+}
+
+fn set_size(context: &par2rs::create::CreateContext) -> u64 {
+    context
+        .output_files()
+        .iter()
+        .map(|path| fs::metadata(path).unwrap().len())
+        .sum()
+}
+
+#[test]
+fn optimized_block_size_beats_the_default() {
+    let temp = tempdir().unwrap();
+    let sources = write_sources(temp.path());
+
+    let mut default = builder(temp.path(), "default.par2", &sources)
+        .build()
+        .unwrap();
+    assert!(default.block_size_choice().is_none());
+    default.create().unwrap();
+
+    let mut optimized = builder(temp.path(), "optimized.par2", &sources)
+        .optimize_block_size(true)
+        .build()
+        .unwrap();
+    let choice = optimized.block_size_choice().unwrap().clone();
+    assert_eq!(choice.chosen.block_size, optimized.block_size());
+    assert_eq!(
+        choice.chosen.source_block_count,
+        optimized.source_block_count()
+    );
+    assert!(choice.chosen.block_size % 4 == 0);
+    assert!(optimized.source_block_count() <= 32768);
+
+    let baseline = choice.default.unwrap();
+    assert_eq!(baseline.block_size, default.block_size());
+    assert!(choice.chosen.total() < baseline.total());
+    assert!(choice.describe().iter().any(|line| line.contains("saving")));
+
+    optimized.create().unwrap();
+    assert!(set_size(&optimized) < set_size(&default));
+}
+
+#[test]
+fn optimized_set_repairs() {
+    let temp = tempdir().unwrap();
+    let sources = write_sources(temp.path());
+    let par2_file = temp.path().join("data.par2");
+
+    let mut context = builder(temp.path(), "data.par2", &sources)
+        .optimize_block_size(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+
+    let original = fs::read(&sources[7]).unwrap();
+    fs::remove_file(&sources[7]).unwrap();
+    let mut damaged = fs::read(&sources[300]).unwrap();
+    damaged[1000] = !damaged[1000];
+    fs::write(&sources[300], damaged).unwrap();
+
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(&sources[7]).unwrap(), original);
+}
+
+#[test]
+fn optimization_rejects_an_explicit_block_size() {
+    let temp = tempdir().unwrap();
+    let sources = write_sources(temp.path());
+    let result = builder(temp.path(), "data.par2", &sources)
+        .optimize_block_size(true)
+        .block_size(4096)
+        .build();
+    assert!(result.is_err());
+}
@@ -0,0 +1,127 @@
+//! Comment packet (CommASCI / CommUni) tests
+//!
+//! Covers writing comments during create and reading them back through the
+//! library API with Unicode comments taking precedence over ASCII translations.
+
+use par2rs::create::CreateContextBuilder;
+use par2rs::Packet;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+#[test]
+fn create_writes_comments_into_every_output_file() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("dump.sql");
+    fs::write(&source, vec![0x42; 5000]).unwrap();
+    let par2_file = temp.path().join("dump.par2");
+
+    let mut context = CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source])
+        .block_size(1024)
+        .recovery_block_count(3)
+        .comment("ingest batch 2026-10")
+        .comment("source tape № 14")
+        .quiet(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+
+    let outputs: Vec<PathBuf> = context.output_files().iter().map(PathBuf::from).collect();
+    assert!(outputs.len() > 1);
+
+    for output in &outputs {
+        let packet_set =
+            par2rs::par2_files::load_par2_packets(std::slice::from_ref(output), false, false);
+        let ascii_count = packet_set
+            .packets
+            .iter()
+            .filter(|packet| matches!(packet, Packet::AsciiComment(_)))
+            .count();
+        let unicode_count = packet_set
+            .packets
+            .iter()
+            .filter(|packet| matches!(packet, Packet::UnicodeComment(_)))
+            .count();
+        assert_eq!(ascii_count, 2, "{}", output.display());
+        assert_eq!(unicode_count, 2, "{}", output.display());
+        assert_eq!(
+            packet_set.comments(),
+            vec!["ingest batch 2026-10", "source tape № 14"]
+        );
+    }
+}
+
+#[test]
+fn comments_are_deduplicated_across_volumes() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("data.bin");
+    fs::write(&source, vec![0x17; 4096]).unwrap();
+    let par2_file = temp.path().join("data.par2");
+
+    let mut context = CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source])
+        .block_size(1024)
+        .recovery_block_count(4)
+        .comment("provenance note")
+        .quiet(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+
+    let outputs: Vec<PathBuf> = context.output_files().iter().map(PathBuf::from).collect();
+    let packet_set = par2rs::par2_files::load_par2_packets(&outputs, false, false);
+
+    assert_eq!(packet_set.comments(), vec!["provenance note"]);
+}
+
+#[test]
+fn sets_without_comments_report_none() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("data.bin");
+    fs::write(&source, vec![0x17; 4096]).unwrap();
+    let par2_file = temp.path().join("data.par2");
+
+    let mut context = CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source])
+        .block_size(1024)
+        .recovery_block_count(1)
+        .quiet(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[par2_file], false, false);
+    assert!(packet_set.comments().is_empty());
+}
+
+#[test]
+fn repair_context_exposes_comments() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("data.bin");
+    fs::write(&source, vec![0x17; 4096]).unwrap();
+    let par2_file = temp.path().join("data.par2");
+
+    let mut context = CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source])
+        .block_size(1024)
+        .recovery_block_count(1)
+        .comment("repair side note")
+        .quiet(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+
+    let (context, _result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+
+    assert_eq!(context.recovery_set.comments, vec!["repair side note"]);
+}
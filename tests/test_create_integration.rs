@@ -239,6 +239,98 @@ fn forced_chunked_create_verifies_with_par2cmdline() {
     );
 }
 
+/// Create a set from three fixed source files in `dir` and return its files' bytes
+fn create_outputs_with_memory_limit(
+    dir: &Path,
+    memory_limit: Option<usize>,
+    reader_threads: usize,
+    recovery_block_count: u32,
+) -> Vec<Vec<u8>> {
+    let sources: Vec<PathBuf> = [("a.dat", 5000), ("b.dat", 1024), ("c.dat", 2900)]
+        .iter()
+        .map(|(name, size)| {
+            let path = dir.join(name);
+            create_varied_test_file(&path, *size).unwrap();
+            path
+        })
+        .collect();
+
+    let mut builder = par2rs::create::CreateContextBuilder::new()
+        .output_name(dir.join("set.par2").to_str().unwrap())
+        .source_files(sources)
+        .block_size(1024)
+        .recovery_block_count(recovery_block_count)
+        .file_thread_count(reader_threads)
+        .quiet(true);
+    if let Some(limit) = memory_limit {
+        builder = builder.memory_limit(limit);
+    }
+    let mut context = builder.build().unwrap();
+    context.create().unwrap();
+
+    let mut outputs: Vec<&String> = context.output_files().iter().collect();
+    outputs.sort();
+    outputs.iter().map(|path| fs::read(path).unwrap()).collect()
+}
+
+#[test]
+fn chunked_create_output_matches_single_pass_create() {
+    let temp = tempdir().unwrap();
+    let run = |name: &str, memory_limit, reader_threads, recovery_block_count| {
+        let dir = temp.path().join(name);
+        fs::create_dir(&dir).unwrap();
+        create_outputs_with_memory_limit(&dir, memory_limit, reader_threads, recovery_block_count)
+    };
+
+    // Every source block is read once, in order, whatever the memory limit
+    let single_pass = run("single", None, 1, 5);
+    assert_eq!(run("grouped", Some(4096), 1, 5), single_pass);
+    assert_eq!(run("tiny_chunks", Some(64), 1, 5), single_pass);
+
+    // With one recovery block, 4 KiB buffers three source blocks per pass
+    let single_pass_one = run("single_one", None, 1, 1);
+    assert_eq!(run("grouped_one", Some(4096), 1, 1), single_pass_one);
+
+    // Several read-ahead threads must hand data over in the same order
+    assert_eq!(run("parallel", None, 3, 5), single_pass);
+    assert_eq!(run("parallel_grouped", Some(4096), 4, 5), single_pass);
+    assert_eq!(run("parallel_tiny_chunks", Some(64), 2, 5), single_pass);
+    assert_eq!(
+        run("parallel_grouped_one", Some(4096), 4, 1),
+        single_pass_one
+    );
+}
+
+#[test]
+fn create_reports_source_read_errors_from_reader_threads() {
+    let temp = tempdir().unwrap();
+    let sources: Vec<PathBuf> = (0..4)
+        .map(|i| {
+            let path = temp.path().join(format!("part{i}.dat"));
+            create_varied_test_file(&path, 3000).unwrap();
+            path
+        })
+        .collect();
+
+    let mut context = par2rs::create::CreateContextBuilder::new()
+        .output_name(temp.path().join("set.par2").to_str().unwrap())
+        .source_files(sources.clone())
+        .block_size(1024)
+        .recovery_block_count(2)
+        .file_thread_count(3)
+        .quiet(true)
+        .build()
+        .unwrap();
+    fs::remove_file(&sources[2]).unwrap();
+
+    match context.create() {
+        Err(par2rs::create::CreateError::FileReadError { file, .. }) => {
+            assert!(file.ends_with("part2.dat"), "{file}")
+        }
+        other => panic!("expected a read error for the removed file, got {other:?}"),
+    }
+}
+
 #[test]
 fn test_create_with_explicit_block_size() {
     if !par2_available() {
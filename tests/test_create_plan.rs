@@ -0,0 +1,112 @@
+//! Dry-run create planning
+//!
+//! The plan is worked out without reading source data, yet must name exactly
+//! the files a real create writes, with their exact sizes.
+
+use par2rs::create::{CreateContextBuilder, CreatePlan};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn write_sources(dir: &Path) -> Vec<PathBuf> {
+    common::write_lcg_files(
+        dir,
+        &[
+            ("a.bin", 1, 20_001),
+            ("b.bin", 2, 5_500),
+            ("\u{e9}t\u{e9}.bin", 3, 1_234),
+        ],
+    )
+    .into_iter()
+    .map(|(path, _)| path)
+    .collect()
+}
+
+fn builder(dir: &Path, sources: &[PathBuf]) -> CreateContextBuilder {
+    common::create_builder(&dir.join("data.par2"), sources)
+        .block_size(1024)
+        .recovery_block_count(7)
+        .recovery_file_count(3)
+}
+
+/// Plan, then create, and check the plan described the created files exactly
+fn assert_plan_matches_create(configure: impl Fn(CreateContextBuilder) -> CreateContextBuilder) {
+    let temp = tempdir().unwrap();
+    let sources = write_sources(temp.path());
+    let mut context = configure(builder(temp.path(), &sources)).build().unwrap();
+
+    let plan = context.plan().unwrap();
+    assert!(plan.output_files.iter().all(|file| !file.path.exists()));
+
+    context.create().unwrap();
+    let mut planned: Vec<(PathBuf, u64)> = plan
+        .output_files
+        .iter()
+        .map(|file| (file.path.clone(), file.size))
+        .collect();
+    let mut created: Vec<(PathBuf, u64)> = context
+        .output_files()
+        .iter()
+        .map(|path| (PathBuf::from(path), fs::metadata(path).unwrap().len()))
+        .collect();
+    planned.sort();
+    created.sort();
+    assert_eq!(planned, created);
+    assert_eq!(plan.block_size, context.block_size());
+    assert_eq!(plan.recovery_block_count, context.recovery_block_count());
+}
+
+#[test]
+fn planned_sizes_match_created_files() {
+    assert_plan_matches_create(|builder| builder);
+}
+
+#[test]
+fn planned_sizes_include_optional_packets() {
+    assert_plan_matches_create(|builder| {
+        builder
+            .embed_source_data(true)
+            .unicode_filenames(true)
+            .comment("planned comment")
+            .custom_packet(*b"PlanTest\0\0\0\0Data", vec![1u8; 12])
+    });
+}
+
+#[test]
+fn planned_sizes_cover_external_and_packed_layouts() {
+    assert_plan_matches_create(|builder| builder.external_recovery_data(true));
+    assert_plan_matches_create(|builder| builder.subslice_size(256).embed_source_data(true));
+}
+
+#[test]
+fn planned_sizes_count_critical_packet_copies() {
+    assert_plan_matches_create(|builder| builder.critical_copies(3));
+}
+
+#[test]
+fn plan_reports_padding_and_exponent_ranges() {
+    let temp = tempdir().unwrap();
+    let sources = write_sources(temp.path());
+    let context = builder(temp.path(), &sources)
+        .first_recovery_block(2)
+        .build()
+        .unwrap();
+    let plan: CreatePlan = context.plan().unwrap();
+
+    assert_eq!(plan.source_block_count, 20 + 6 + 2);
+    let paddings: Vec<u64> = plan.source_files.iter().map(|file| file.padding).collect();
+    assert_eq!(paddings, vec![479, 644, 814]);
+    assert_eq!(plan.total_padding(), paddings.iter().sum::<u64>());
+
+    let exponents: Vec<_> = plan
+        .output_files
+        .iter()
+        .map(|file| file.exponents.clone())
+        .collect();
+    assert_eq!(exponents.first(), Some(&(2..2)));
+    assert_eq!(exponents.last().unwrap().end, 9);
+    assert_eq!(plan.chunk_size, 1024);
+    assert!(plan.estimated_memory >= (7 + 2) * 1024);
+}
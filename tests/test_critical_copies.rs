@@ -0,0 +1,96 @@
+//! Critical packet copies spread through recovery volumes
+//!
+//! A volume must stay usable when its head is damaged, and the repeated
+//! packets must load as a single set.
+
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn create(par2_file: &Path, sources: &[PathBuf], copies: Option<u32>) -> Vec<PathBuf> {
+    let mut builder = common::create_builder(par2_file, sources)
+        .block_size(1024)
+        .recovery_block_count(8)
+        .recovery_file_count(1);
+    if let Some(copies) = copies {
+        builder = builder.critical_copies(copies);
+    }
+    let mut context = builder.build().unwrap();
+    context.create().unwrap();
+    context.output_files().iter().map(PathBuf::from).collect()
+}
+
+fn count_occurrences(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack
+        .windows(needle.len())
+        .filter(|window| *window == needle)
+        .count()
+}
+
+#[test]
+fn volume_copies_scale_with_block_count_and_load_once() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 12_000, 3_000);
+    let par2_file = temp.path().join("data.par2");
+    let outputs = create(&par2_file, &sources, None);
+
+    let index = fs::read(&par2_file).unwrap();
+    let volume = fs::read(outputs.iter().find(|path| **path != par2_file).unwrap()).unwrap();
+    // Eight blocks take four bits, so four copies
+    assert_eq!(count_occurrences(&volume, &index), 4);
+    assert!(volume.starts_with(&index));
+    assert!(volume.ends_with(&index));
+
+    let packets = par2rs::parse_packets(&mut fs::File::open(&par2_file).unwrap());
+    let volume_packets = par2rs::parse_packets(&mut std::io::Cursor::new(&volume));
+    assert_eq!(volume_packets.len(), packets.len());
+    assert_eq!(
+        volume_packets
+            .iter()
+            .filter(|packet| matches!(packet, Packet::Main(_)))
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn explicit_copy_count_is_used_for_every_volume() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 12_000, 3_000);
+    let par2_file = temp.path().join("data.par2");
+    let outputs = create(&par2_file, &sources, Some(2));
+
+    let index = fs::read(&par2_file).unwrap();
+    for volume in outputs.iter().filter(|path| **path != par2_file) {
+        assert_eq!(count_occurrences(&fs::read(volume).unwrap(), &index), 2);
+    }
+}
+
+#[test]
+fn repair_survives_a_damaged_volume_head_without_the_index() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 12_000, 3_000);
+    let par2_file = temp.path().join("data.par2");
+    let outputs = create(&par2_file, &sources, None);
+
+    // Wipe the first copy of the critical packets and drop the index file
+    let critical_size = fs::metadata(&par2_file).unwrap().len() as usize;
+    let volume_path = outputs.iter().find(|path| **path != par2_file).unwrap();
+    let mut volume = fs::read(volume_path).unwrap();
+    volume[..critical_size].fill(0);
+    fs::write(volume_path, volume).unwrap();
+    fs::remove_file(&par2_file).unwrap();
+
+    fs::remove_file(&sources[1]).unwrap();
+    let (_context, result) = par2rs::repair::repair_files(
+        volume_path.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(&sources[1]).unwrap(), common::lcg_data(2, 3_000));
+}
@@ -0,0 +1,146 @@
+//! Input File Slice (FileSlic) packet tests
+//!
+//! Sets created with embedded source data must let verify and repair restore
+//! lost blocks by copying them out of the PAR2 files, without needing any
+//! recovery blocks for them.
+
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Write small source files into `dir` and create an embedded set protecting them
+fn create_embedded_set(
+    dir: &Path,
+    recovery_blocks: u32,
+    subslice_size: Option<u64>,
+) -> (PathBuf, Vec<(PathBuf, Vec<u8>)>) {
+    let files: Vec<(PathBuf, Vec<u8>)> = (0..6)
+        .map(|i| {
+            let data = common::lcg_data(i as u64 + 1, 100 + i * 450);
+            (dir.join(format!("note_{i}.txt")), data)
+        })
+        .collect();
+    for (path, data) in &files {
+        fs::write(path, data).unwrap();
+    }
+
+    let par2_file = dir.join("notes.par2");
+    let mut builder = par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(files.iter().map(|(path, _)| path.clone()).collect())
+        .block_size(1024)
+        .recovery_block_count(recovery_blocks)
+        .embed_source_data(true)
+        .quiet(true);
+    if let Some(size) = subslice_size {
+        builder = builder.subslice_size(size);
+    }
+    builder.build().unwrap().create().unwrap();
+
+    (par2_file, files)
+}
+
+fn par2_files(par2_file: &Path) -> Vec<PathBuf> {
+    par2rs::par2_files::collect_par2_files(par2_file)
+}
+
+#[test]
+fn create_embeds_every_file_once() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_embedded_set(temp.path(), 3, None);
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&par2_files(&par2_file), false, false);
+    let embedded_bytes: usize = packet_set
+        .packets
+        .iter()
+        .filter_map(|packet| match packet {
+            Packet::InputFileSlice(slice) => {
+                assert!(slice.verify());
+                Some(slice.slice_data.len())
+            }
+            _ => None,
+        })
+        .sum();
+    let expected: usize = files
+        .iter()
+        .map(|(_, data)| data.len().div_ceil(4) * 4)
+        .sum();
+    assert_eq!(embedded_bytes, expected);
+
+    let metadata = par2rs::par2_files::parse_input_file_slice_metadata(&par2_files(&par2_file));
+    let blocks: usize = files
+        .iter()
+        .map(|(_, data)| data.len().div_ceil(1024))
+        .sum();
+    assert_eq!(metadata.len(), blocks);
+}
+
+#[test]
+fn missing_files_are_copied_out_without_recovery_blocks() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_embedded_set(temp.path(), 0, None);
+
+    fs::remove_file(&files[1].0).unwrap();
+    let mut damaged = files[4].1.clone();
+    damaged[1500] ^= 0xFF;
+    fs::write(&files[4].0, damaged).unwrap();
+
+    let results = common::verify_set(&par2_file);
+    assert!(results.missing_block_count > 0);
+    assert_eq!(results.recovery_blocks_available, 0);
+    assert_eq!(results.blocks_needed_for_repair, 0);
+    assert!(results.repair_possible);
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "{result:?}");
+    for (path, data) in &files {
+        assert_eq!(&fs::read(path).unwrap(), data, "{}", path.display());
+    }
+}
+
+#[test]
+fn damaged_embedded_data_falls_back_to_reed_solomon() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_embedded_set(temp.path(), 2, None);
+
+    // Corrupt the embedded copies of note_0.txt wherever they were written
+    let needle = &files[0].1[..64];
+    for volume in par2_files(&par2_file) {
+        let mut bytes = fs::read(&volume).unwrap();
+        if let Some(pos) = bytes.windows(needle.len()).position(|w| w == needle) {
+            bytes[pos] ^= 0xFF;
+            fs::write(&volume, bytes).unwrap();
+        }
+    }
+    fs::remove_file(&files[0].0).unwrap();
+
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.blocks_needed_for_repair, 1);
+    assert!(results.repair_possible);
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "{result:?}");
+    assert_eq!(fs::read(&files[0].0).unwrap(), files[0].1);
+}
+
+#[test]
+fn packed_sets_copy_embedded_subslices() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_embedded_set(temp.path(), 0, Some(256));
+
+    fs::remove_file(&files[2].0).unwrap();
+    fs::remove_file(&files[5].0).unwrap();
+
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.blocks_needed_for_repair, 0);
+    assert!(results.repair_possible);
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "{result:?}");
+    for (path, data) in &files {
+        assert_eq!(&fs::read(path).unwrap(), data, "{}", path.display());
+    }
+}
@@ -0,0 +1,166 @@
+//! Extending an existing PAR2 set with more recovery volumes
+//!
+//! The new volumes hold recovery blocks with higher exponents and the same
+//! recovery set ID, so repair can combine them with the original volumes.
+
+use par2rs::create::{extend_recovery_set, ExtendConfig, SilentCreateReporter};
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+const CUSTOM_TYPE: [u8; 16] = *b"ExtTest\0Metadata";
+
+/// Create a set with 4 recovery blocks over two source files
+fn create_set(dir: &Path) -> (PathBuf, Vec<(PathBuf, Vec<u8>)>) {
+    let sources = vec![
+        (dir.join("a.bin"), common::lcg_data(1, 10_000)),
+        (dir.join("b.bin"), common::lcg_data(2, 5_500)),
+    ];
+    for (path, data) in &sources {
+        fs::write(path, data).unwrap();
+    }
+
+    let par2_file = dir.join("data.par2");
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(sources.iter().map(|(path, _)| path.clone()).collect())
+        .block_size(1024)
+        .recovery_block_count(4)
+        .custom_packet(CUSTOM_TYPE, vec![9u8; 8])
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+    (par2_file, sources)
+}
+
+fn extend(par2_file: &Path, recovery_block_count: u32, memory_limit: Option<usize>) -> Vec<String> {
+    let config = ExtendConfig {
+        recovery_block_count,
+        recovery_file_count: Some(2),
+        recovery_file_scheme: par2rs::create::RecoveryFileScheme::Uniform,
+        memory_limit,
+        ..Default::default()
+    };
+    extend_recovery_set(par2_file, &config, &SilentCreateReporter).unwrap()
+}
+
+fn damage_blocks(path: &Path, data: &[u8], blocks: usize) {
+    let mut damaged = data.to_vec();
+    for block in 0..blocks {
+        damaged[block * 1024 + 7] ^= 0xFF;
+    }
+    fs::write(path, damaged).unwrap();
+}
+
+#[test]
+fn extended_volumes_combine_with_existing_ones_during_repair() {
+    for memory_limit in [None, Some(2048)] {
+        let temp = tempdir().unwrap();
+        let (par2_file, sources) = create_set(temp.path());
+
+        let created = extend(&par2_file, 6, memory_limit);
+        assert_eq!(
+            created,
+            vec![
+                temp.path().join("data.vol04+3.par2").to_string_lossy(),
+                temp.path().join("data.vol07+3.par2").to_string_lossy(),
+            ]
+        );
+
+        // 10 damaged blocks need both the original 4 and the new 6 recovery blocks
+        damage_blocks(&sources[0].0, &sources[0].1, 8);
+        damage_blocks(&sources[1].0, &sources[1].1, 2);
+        assert!(
+            common::repair_set(&par2_file).is_success(),
+            "memory limit {memory_limit:?}"
+        );
+        for (path, data) in &sources {
+            assert_eq!(&fs::read(path).unwrap(), data);
+        }
+    }
+}
+
+#[test]
+fn extend_skips_the_exponents_of_a_volume_with_no_intact_packets() {
+    let temp = tempdir().unwrap();
+    let (par2_file, _sources) = create_set(temp.path());
+    let last_volume = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.to_string_lossy().contains(".vol"))
+        .max()
+        .unwrap();
+    assert!(last_volume.ends_with("data.vol3+1.par2"), "{last_volume:?}");
+    let len = fs::metadata(&last_volume).unwrap().len() as usize;
+    fs::write(&last_volume, vec![0u8; len]).unwrap();
+
+    let created = extend(&par2_file, 2, None);
+    assert_eq!(
+        created,
+        vec![
+            temp.path().join("data.vol4+1.par2").to_string_lossy(),
+            temp.path().join("data.vol5+1.par2").to_string_lossy(),
+        ]
+    );
+}
+
+#[test]
+fn extended_volumes_keep_set_id_and_critical_packets() {
+    let temp = tempdir().unwrap();
+    let (par2_file, _sources) = create_set(temp.path());
+    let original =
+        par2rs::par2_files::load_par2_packets(std::slice::from_ref(&par2_file), false, false);
+    let set_id = original.packets[0].set_id();
+
+    let created = extend(&par2_file, 2, None);
+    let extended =
+        par2rs::par2_files::load_par2_packets(&[PathBuf::from(&created[0])], true, false);
+
+    assert!(extended
+        .packets
+        .iter()
+        .all(|packet| packet.set_id() == set_id));
+    assert_eq!(extended.recovery_block_count, 1);
+    assert!(extended.packets.iter().any(|packet| matches!(
+        packet,
+        Packet::RecoverySlice(slice) if slice.exponent == 4
+    )));
+    assert!(extended.packets.iter().any(|packet| matches!(
+        packet,
+        Packet::Unknown { packet_type, body, .. } if *packet_type == CUSTOM_TYPE && body == &[9u8; 8]
+    )));
+    assert_eq!(
+        extended
+            .packets
+            .iter()
+            .filter(|packet| !packet.is_recovery_slice())
+            .count(),
+        original.packets.len()
+    );
+}
+
+#[test]
+fn extend_refuses_changed_source_files() {
+    let temp = tempdir().unwrap();
+    let (par2_file, sources) = create_set(temp.path());
+    damage_blocks(&sources[1].0, &sources[1].1, 1);
+
+    let config = ExtendConfig {
+        recovery_block_count: 3,
+        ..Default::default()
+    };
+    let error = extend_recovery_set(&par2_file, &config, &SilentCreateReporter).unwrap_err();
+    assert!(error.to_string().contains("b.bin"), "{error}");
+
+    let mut remaining = par2rs::par2_files::collect_par2_files(&par2_file);
+    remaining.sort();
+    assert_eq!(remaining.len(), 4, "{remaining:?}");
+    assert!(remaining
+        .iter()
+        .all(|path| !path.to_string_lossy().contains("vol04")));
+}
@@ -0,0 +1,112 @@
+//! Recovery File Slice Checksum (RFSC) packet tests
+//!
+//! Sets created with external recovery data keep only critical packets in the
+//! index file; the recovery slices live in a headerless `.rfsc` file that
+//! verify and repair must locate and validate through the RFSC checksums.
+
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Write source files into `dir` and create a set with external recovery data
+fn create_external_set(dir: &Path, recovery_blocks: u32) -> (PathBuf, Vec<(PathBuf, Vec<u8>)>) {
+    let files: Vec<(PathBuf, Vec<u8>)> = (0..3)
+        .map(|i| {
+            let data = common::lcg_data(i as u64 + 11, 3000 + i * 1700);
+            (dir.join(format!("part_{i}.bin")), data)
+        })
+        .collect();
+    for (path, data) in &files {
+        fs::write(path, data).unwrap();
+    }
+
+    let par2_file = dir.join("parts.par2");
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(files.iter().map(|(path, _)| path.clone()).collect())
+        .block_size(1024)
+        .recovery_block_count(recovery_blocks)
+        .external_recovery_data(true)
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+
+    (par2_file, files)
+}
+
+#[test]
+fn create_writes_index_and_flat_recovery_file() {
+    let temp = tempdir().unwrap();
+    let (par2_file, _files) = create_external_set(temp.path(), 4);
+
+    let rfsc_path = temp.path().join("parts.rfsc");
+    assert_eq!(fs::metadata(&rfsc_path).unwrap().len(), 4 * 1024);
+    assert_eq!(
+        par2rs::par2_files::external_recovery_data_path(&par2_file),
+        rfsc_path
+    );
+    assert_eq!(par2rs::par2_files::collect_par2_files(&par2_file).len(), 1);
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[par2_file], false, false);
+    assert_eq!(packet_set.recovery_block_count, 4);
+    let rfsc = packet_set
+        .packets
+        .iter()
+        .find_map(|packet| match packet {
+            Packet::RecoveryFileSliceChecksum(rfsc) => Some(rfsc),
+            _ => None,
+        })
+        .expect("index file should carry an RFSC packet");
+    assert!(rfsc.verify());
+    let exponents: Vec<u32> = rfsc.slice_checksums.iter().map(|c| c.exponent).collect();
+    assert_eq!(exponents, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn repair_reads_recovery_slices_from_external_file() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_external_set(temp.path(), 6);
+
+    fs::remove_file(&files[1].0).unwrap();
+
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.recovery_blocks_available, 6);
+    assert_eq!(results.blocks_needed_for_repair, 5);
+    assert!(results.repair_possible);
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "{result:?}");
+    for (path, data) in &files {
+        assert_eq!(&fs::read(path).unwrap(), data, "{}", path.display());
+    }
+}
+
+#[test]
+fn damaged_external_slices_are_not_used() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_external_set(temp.path(), 3);
+
+    let rfsc_path = temp.path().join("parts.rfsc");
+    let mut blob = fs::read(&rfsc_path).unwrap();
+    blob[17] ^= 0xFF;
+    fs::write(&rfsc_path, blob).unwrap();
+
+    let mut damaged = files[0].1.clone();
+    damaged[100] ^= 0xFF;
+    fs::write(&files[0].0, &damaged).unwrap();
+
+    // Slices are only counted on load; repair checks the ones it uses and
+    // passes over the damaged first slice
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.recovery_blocks_available, 3);
+    assert_eq!(results.blocks_needed_for_repair, 1);
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "{result:?}");
+    assert_eq!(fs::read(&files[0].0).unwrap(), files[0].1);
+}
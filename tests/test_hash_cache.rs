@@ -0,0 +1,141 @@
+//! Persistent hash cache shared by create and verify
+//!
+//! Cached hashes must produce the same PAR2 files as hashing the data, be
+//! used only while the file is unchanged, and stand in for the full-file MD5
+//! during verify.
+
+use par2rs::domain::Md5Hash;
+use par2rs::hash_cache::{default_path, FileStamp, HashCache};
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn create(par2_file: &Path, sources: &[PathBuf], hash_cache: Option<&Path>) {
+    let mut builder = common::create_builder(par2_file, sources)
+        .block_size(1024)
+        .recovery_block_count(4)
+        .recovery_file_count(2)
+        .overwrite_existing(true);
+    if let Some(path) = hash_cache {
+        builder = builder.hash_cache(path);
+    }
+    builder.build().unwrap().create().unwrap();
+}
+
+/// Contents of every file of the set, keyed by the part of the name after the base
+fn set_contents(par2_file: &Path) -> Vec<(String, Vec<u8>)> {
+    let base = par2_file.file_stem().unwrap().to_string_lossy().to_string();
+    let mut files: Vec<_> = par2rs::par2_files::collect_par2_files(par2_file)
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            (name[base.len()..].to_string(), fs::read(&path).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn described_md5(par2_file: &Path, name: &str) -> Md5Hash {
+    par2rs::par2_files::load_par2_packets(&[par2_file.to_path_buf()], false, false)
+        .packets
+        .iter()
+        .find_map(|packet| match packet {
+            Packet::FileDescription(desc) if desc.file_name.starts_with(name.as_bytes()) => {
+                Some(desc.md5_hash)
+            }
+            _ => None,
+        })
+        .unwrap()
+}
+
+/// Replace the cached full-file MD5 of `file`, as if the cache recorded other data
+fn poison_cache(cache_path: &Path, file: &Path) {
+    let mut cache = HashCache::load(cache_path);
+    let mut hashes = cache.get(file, 1024).unwrap().clone();
+    hashes.hash = Md5Hash::new([7; 16]);
+    cache.insert(file, FileStamp::of(file).unwrap(), hashes);
+    cache.save().unwrap();
+}
+
+fn verify(par2_file: &Path, hash_cache: Option<PathBuf>) -> par2rs::verify::VerificationResults {
+    let packet_set =
+        par2rs::par2_files::load_par2_packets(&[par2_file.to_path_buf()], false, false);
+    par2rs::verify::comprehensive_verify_files(
+        packet_set,
+        &par2rs::verify::VerificationConfig {
+            hash_cache,
+            ..Default::default()
+        },
+        &par2rs::reporters::SilentVerificationReporter,
+        par2_file.parent().unwrap(),
+    )
+}
+
+#[test]
+fn cached_hashes_produce_the_same_set_as_hashing() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 5_500);
+    let cache_path = default_path(temp.path());
+
+    let plain = temp.path().join("plain.par2");
+    create(&plain, &sources, None);
+    assert!(!cache_path.exists());
+
+    let cached = temp.path().join("cached.par2");
+    create(&cached, &sources, Some(&cache_path));
+    let cache = HashCache::load(&cache_path);
+    assert!(sources
+        .iter()
+        .all(|source| cache.get(source, 1024).is_some()));
+    assert_eq!(set_contents(&cached), set_contents(&plain));
+
+    // Second run takes every hash from the cache
+    create(&cached, &sources, Some(&cache_path));
+    assert_eq!(set_contents(&cached), set_contents(&plain));
+}
+
+#[test]
+fn create_uses_cached_hashes_only_while_the_file_is_unchanged() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 5_500);
+    let cache_path = default_path(temp.path());
+    let par2_file = temp.path().join("data.par2");
+    create(&par2_file, &sources, Some(&cache_path));
+
+    poison_cache(&cache_path, &sources[0]);
+    create(&par2_file, &sources, Some(&cache_path));
+    assert_eq!(described_md5(&par2_file, "a.bin"), Md5Hash::new([7; 16]));
+
+    fs::write(&sources[0], common::lcg_data(3, 21_000)).unwrap();
+    create(&par2_file, &sources, Some(&cache_path));
+    let fresh = temp.path().join("fresh.par2");
+    create(&fresh, &sources, None);
+    assert_eq!(
+        described_md5(&par2_file, "a.bin"),
+        described_md5(&fresh, "a.bin")
+    );
+    assert_eq!(set_contents(&par2_file), set_contents(&fresh));
+}
+
+#[test]
+fn verify_takes_full_file_hashes_from_the_cache() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 5_500);
+    let cache_path = default_path(temp.path());
+    let par2_file = temp.path().join("data.par2");
+    create(&par2_file, &sources, Some(&cache_path));
+
+    let results = verify(&par2_file, Some(cache_path.clone()));
+    assert_eq!(results.present_file_count, 2);
+    assert_eq!(results.corrupted_file_count, 0);
+
+    // A cached hash that disagrees with the set is trusted over the data
+    poison_cache(&cache_path, &sources[0]);
+    let results = verify(&par2_file, Some(cache_path));
+    assert_eq!(results.corrupted_file_count, 1);
+    assert_eq!(verify(&par2_file, None).corrupted_file_count, 0);
+}
@@ -0,0 +1,78 @@
+//! Recovery volumes capped at a maximum file size
+//!
+//! Every output file, critical packets included, must stay within the limit,
+//! and a limit too small for even one recovery block must fail before
+//! anything is written.
+
+use par2rs::create::file_naming::default_critical_copies;
+use par2rs::create::{CreateContextBuilder, RecoveryFileScheme};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+fn builder(par2_file: &Path, sources: &[PathBuf], max_file_size: u64) -> CreateContextBuilder {
+    common::create_builder(par2_file, sources)
+        .block_size(1024)
+        .recovery_block_count(30)
+        .recovery_file_scheme(RecoveryFileScheme::MaxFileSize(max_file_size))
+}
+
+#[test]
+fn every_volume_stays_within_the_limit() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 40_000, 9_000);
+    let par2_file = temp.path().join("data.par2");
+    let max_file_size = 8 * 1024;
+
+    let mut context = builder(&par2_file, &sources, max_file_size)
+        .build()
+        .unwrap();
+    let plan = context.plan().unwrap();
+    assert!(plan
+        .output_files
+        .iter()
+        .all(|file| file.size <= max_file_size));
+    context.create().unwrap();
+
+    let sizes: Vec<u64> = context
+        .output_files()
+        .iter()
+        .map(|path| fs::metadata(path).unwrap().len())
+        .collect();
+    assert!(sizes.len() > 2);
+    assert!(sizes.iter().all(|&size| size <= max_file_size));
+
+    // No more volumes than the limit requires, counting the critical packet
+    // copies that grow with each volume's block count
+    let critical_size = plan.output_files[0].size;
+    let blocks_per_volume = (1..=30u64)
+        .take_while(|&blocks| {
+            let copies = default_critical_copies(blocks as u32) as u64;
+            blocks * (1024 + 68) + copies * critical_size <= max_file_size
+        })
+        .last()
+        .unwrap();
+    assert_eq!(sizes.len() as u64 - 1, 30u64.div_ceil(blocks_per_volume));
+
+    fs::remove_file(&sources[1]).unwrap();
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(&sources[1]).unwrap(), common::lcg_data(2, 9_000));
+}
+
+#[test]
+fn limit_below_one_recovery_block_fails_before_writing() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 40_000, 9_000);
+    let par2_file = temp.path().join("data.par2");
+
+    assert!(builder(&par2_file, &sources, 2048).build().is_err());
+    assert!(!par2_file.exists());
+}
@@ -3,6 +3,8 @@ use std::path::PathBuf;
 use std::process::Command;
 use tempfile::tempdir;
 
+pub mod common;
+
 /// Test that verifying with mixed PAR2 sets from different files doesn't cause issues
 /// This replicates the bug where having testfile_par2rs.par2 and testfile_par2cmd.par2
 /// in the same directory causes confusion
@@ -79,3 +81,174 @@ fn test_mixed_recovery_sets_ignored() {
         stderr,
     );
 }
+
+/// Create two independent sets side by side; returns (par2 file, source, data) per set
+fn create_two_sets(dir: &std::path::Path) -> Vec<(PathBuf, PathBuf, Vec<u8>)> {
+    [("alpha", 3u32), ("beta", 5u32)]
+        .iter()
+        .enumerate()
+        .map(|(i, (name, recovery_blocks))| {
+            let source = dir.join(format!("{name}.dat"));
+            let data = common::lcg_data(i as u64 + 1, 7000);
+            fs::write(&source, &data).unwrap();
+            let par2_file = dir.join(format!("{name}.par2"));
+            par2rs::create::CreateContextBuilder::new()
+                .output_name(par2_file.to_str().unwrap())
+                .source_files(vec![source.clone()])
+                .block_size(1024)
+                .recovery_block_count(*recovery_blocks)
+                .quiet(true)
+                .build()
+                .unwrap()
+                .create()
+                .unwrap();
+            (par2_file, source, data)
+        })
+        .collect()
+}
+
+fn set_id_of(par2_file: &std::path::Path) -> par2rs::domain::RecoverySetId {
+    let packet_set =
+        par2rs::par2_files::load_par2_packets(&[par2_file.to_path_buf()], false, false);
+    par2rs::packets::processing::extract_slice_layout(&packet_set.packets)
+        .unwrap()
+        .set_id
+}
+
+#[test]
+fn group_by_set_id_splits_packets_and_recovery_counts() {
+    let temp_dir = tempdir().unwrap();
+    let sets = create_two_sets(temp_dir.path());
+
+    let par2_files = par2rs::par2_files::collect_all_par2_files(&sets[0].0, &[], false);
+    let on_disk = fs::read_dir(temp_dir.path())
+        .unwrap()
+        .filter(|entry| {
+            entry.as_ref().unwrap().path().extension() == Some(std::ffi::OsStr::new("par2"))
+        })
+        .count();
+    assert_eq!(par2_files.len(), on_disk);
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&par2_files, false, false);
+    assert_eq!(packet_set.recovery_block_count, 8);
+
+    let groups = packet_set.group_by_set_id();
+    assert_eq!(groups.len(), 2);
+    for ((set_id, group), (par2_file, _, _), expected_blocks) in groups
+        .iter()
+        .zip(&sets)
+        .zip([3, 5])
+        .map(|((group, set), blocks)| (group, set, blocks))
+    {
+        assert_eq!(*set_id, set_id_of(par2_file));
+        assert_eq!(group.recovery_block_count, expected_blocks);
+        assert!(group.packets.iter().all(|p| p.set_id() == *set_id));
+    }
+
+    let id = groups[1].0;
+    assert_eq!(
+        id.to_string().parse::<par2rs::domain::RecoverySetId>(),
+        Ok(id)
+    );
+    assert!("abc".parse::<par2rs::domain::RecoverySetId>().is_err());
+}
+
+#[test]
+fn verify_all_sets_reports_each_set() {
+    let temp_dir = tempdir().unwrap();
+    let sets = create_two_sets(temp_dir.path());
+    let mut damaged = sets[1].2.clone();
+    damaged[10] ^= 0xFF;
+    fs::write(&sets[1].1, damaged).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_par2"))
+        .args(["verify", "--all-sets", sets[0].0.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "{stdout}");
+    assert!(stdout.contains(&format!("{}: all files are correct", set_id_of(&sets[0].0))));
+    assert!(stdout.contains(&format!("{}: repair is required", set_id_of(&sets[1].0))));
+
+    // Selecting the damaged set by ID works from either index file
+    let output = Command::new(env!("CARGO_BIN_EXE_par2"))
+        .args([
+            "verify",
+            "--set-id",
+            &set_id_of(&sets[0].0).to_string(),
+            sets[1].0.to_str().unwrap(),
+        ])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn repair_all_sets_repairs_each_set_independently() {
+    let temp_dir = tempdir().unwrap();
+    let sets = create_two_sets(temp_dir.path());
+    for (_, source, data) in &sets {
+        let mut damaged = data.clone();
+        damaged[3000] ^= 0xFF;
+        fs::write(source, damaged).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_par2"))
+        .args(["repair", "--all-sets", sets[1].0.to_str().unwrap()])
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    for (_, source, data) in &sets {
+        assert_eq!(&fs::read(source).unwrap(), data);
+    }
+}
+
+#[test]
+fn unknown_set_id_is_an_error() {
+    let temp_dir = tempdir().unwrap();
+    let sets = create_two_sets(temp_dir.path());
+    let unknown: par2rs::domain::RecoverySetId =
+        "00112233445566778899aabbccddeeff".parse().unwrap();
+
+    let par2_files = par2rs::par2_files::collect_all_par2_files(&sets[0].0, &[], false);
+    let error = par2rs::par2_files::load_par2_packets(&par2_files, false, false)
+        .select_set(&unknown)
+        .unwrap_err();
+    assert_eq!(error.set_id, unknown);
+    assert_eq!(error.found.len(), 2);
+    let message = error.to_string();
+    assert!(message.starts_with(&format!("recovery set {unknown} not found")));
+    for (par2_file, _, _) in &sets {
+        assert!(
+            message.contains(&set_id_of(par2_file).to_string()),
+            "{message}"
+        );
+    }
+
+    let config = par2rs::verify::VerificationConfig {
+        set_id: Some(unknown),
+        ..Default::default()
+    };
+    let error = par2rs::repair::repair_files_with_base_path_and_extra_files(
+        sets[0].0.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &config,
+        None,
+        &[],
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(error, par2rs::repair::RepairError::SetNotFound(_)));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_par2"))
+        .args(["verify", "--set-id", &unknown.to_string()])
+        .arg(&sets[0].0)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("not found"));
+}
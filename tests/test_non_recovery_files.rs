@@ -0,0 +1,170 @@
+//! Non-recovery set tests
+//!
+//! Files in the non-recovery set are described and checksummed by the PAR2
+//! set but contribute nothing to the recovery data. Verify reports their
+//! damage separately and repair leaves them alone.
+
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+struct TestSet {
+    par2_file: PathBuf,
+    source: (PathBuf, Vec<u8>),
+    checksum_only: (PathBuf, Vec<u8>),
+}
+
+fn create_set(dir: &Path) -> TestSet {
+    let source = (dir.join("data.bin"), common::lcg_data(3, 9000));
+    let checksum_only = (dir.join("huge.iso"), common::lcg_data(5, 30_000));
+    fs::write(&source.0, &source.1).unwrap();
+    fs::write(&checksum_only.0, &checksum_only.1).unwrap();
+
+    let par2_file = dir.join("data.par2");
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source.0.clone()])
+        .non_recovery_files(vec![checksum_only.0.clone()])
+        .block_size(1024)
+        .recovery_block_count(4)
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+
+    TestSet {
+        par2_file,
+        source,
+        checksum_only,
+    }
+}
+
+#[test]
+fn create_lists_non_recovery_files_in_main_packet() {
+    let temp = tempdir().unwrap();
+    let set = create_set(temp.path());
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[set.par2_file], false, false);
+    let main = packet_set
+        .packets
+        .iter()
+        .find_map(|packet| match packet {
+            Packet::Main(main) => Some(main),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(main.file_count, 1);
+    assert_eq!(main.file_ids.len(), 1);
+    assert_eq!(main.non_recovery_file_ids.len(), 1);
+
+    let non_recovery_id = main.non_recovery_file_ids[0];
+    let described = packet_set.packets.iter().any(
+        |packet| matches!(packet, Packet::FileDescription(desc) if desc.file_id == non_recovery_id),
+    );
+    assert!(described, "non-recovery file should have a FileDesc packet");
+    assert_eq!(
+        par2rs::packets::processing::extract_non_recovery_file_ids(&packet_set.packets),
+        vec![non_recovery_id]
+    );
+}
+
+#[test]
+fn verify_reports_non_recovery_damage_separately() {
+    let temp = tempdir().unwrap();
+    let set = create_set(temp.path());
+
+    let results = common::verify_set(&set.par2_file);
+    assert_eq!(results.files.len(), 1);
+    assert_eq!(results.total_block_count, 9);
+    assert_eq!(results.non_recovery_files.len(), 1);
+    assert_eq!(results.damaged_non_recovery_files().count(), 0);
+
+    let mut damaged = set.checksum_only.1.clone();
+    damaged[12_345] ^= 0xFF;
+    fs::write(&set.checksum_only.0, damaged).unwrap();
+
+    let results = common::verify_set(&set.par2_file);
+    assert_eq!(results.missing_block_count, 0);
+    assert_eq!(results.blocks_needed_for_repair, 0);
+    let damaged: Vec<_> = results.damaged_non_recovery_files().collect();
+    assert_eq!(damaged.len(), 1);
+    assert_eq!(damaged[0].file_name, "huge.iso");
+    let summary = results.to_string();
+    assert!(summary.contains("1 non-recovery file(s) are damaged or missing"));
+    assert!(!summary.contains("All files are correct"), "{summary}");
+}
+
+#[test]
+fn statistics_count_non_recovery_files_as_other_files() {
+    let temp = tempdir().unwrap();
+    let set = create_set(temp.path());
+    let expected = "There are 1 recoverable files and 1 other files.\n\
+                    The block size used was 1024 bytes.\n\
+                    There are a total of 9 data blocks.\n\
+                    The total size of the data files is 9000 bytes.";
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[set.par2_file.clone()], false, false);
+    let stats = par2rs::analysis::calculate_par2_stats(
+        &packet_set.packets,
+        packet_set.recovery_block_count,
+    );
+    assert_eq!(par2rs::analysis::format_summary_stats(&stats), expected);
+
+    let (context, _result) = par2rs::repair::repair_files(
+        set.par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(
+        par2rs::analysis::format_summary_stats(&context.recovery_set.stats()),
+        expected
+    );
+}
+
+#[test]
+fn repair_reports_non_recovery_damage() {
+    let temp = tempdir().unwrap();
+    let set = create_set(temp.path());
+
+    let mut damaged_source = set.source.1.clone();
+    damaged_source[2000] ^= 0xFF;
+    fs::write(&set.source.0, damaged_source).unwrap();
+    fs::remove_file(&set.checksum_only.0).unwrap();
+
+    let results = common::verify_set(&set.par2_file);
+    assert_eq!(results.blocks_needed_for_repair, 1);
+    assert!(results.repair_possible);
+    assert!(results
+        .to_string()
+        .contains("1 non-recovery file(s) will remain damaged or missing after repair."));
+
+    let result = common::repair_set(&set.par2_file);
+    assert!(!result.is_success(), "{result:?}");
+    assert_eq!(result.failed_files(), ["huge.iso"]);
+    assert_eq!(fs::read(&set.source.0).unwrap(), set.source.1);
+    assert!(!set.checksum_only.0.exists());
+}
+
+#[test]
+fn repair_fails_when_only_non_recovery_files_are_damaged() {
+    let temp = tempdir().unwrap();
+    let set = create_set(temp.path());
+
+    let mut damaged = set.checksum_only.1.clone();
+    damaged[100] ^= 0xFF;
+    fs::write(&set.checksum_only.0, &damaged).unwrap();
+
+    let result = common::repair_set(&set.par2_file);
+    match &result {
+        par2rs::repair::RepairResult::Failed { message, .. } => {
+            assert!(message.contains("huge.iso"), "{message}")
+        }
+        other => panic!("expected a failed repair, got {other:?}"),
+    }
+    assert_eq!(fs::read(&set.checksum_only.0).unwrap(), damaged);
+}
@@ -0,0 +1,295 @@
+//! Packed recovery set (PkdMain + PkdRecvS) tests
+//!
+//! Builds a small packed set by hand: each file is padded to whole subslices, the
+//! files are concatenated in PkdMain order, and recovery data is computed over
+//! slices of that stream. Verify and repair must both understand the layout.
+
+use par2rs::checksum::{compute_block_checksums_padded, compute_file_id, compute_md5_bytes};
+use par2rs::reed_solomon::RecoveryBlockEncoder;
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+const SUBSLICE_SIZE: usize = 256;
+const SLICE_SIZE: usize = 1024;
+
+fn packet(set_id: &[u8; 16], packet_type: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut hashed = Vec::new();
+    hashed.extend_from_slice(set_id);
+    hashed.extend_from_slice(packet_type);
+    hashed.extend_from_slice(body);
+
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(b"PAR2\0PKT");
+    bytes.extend_from_slice(&(64 + body.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&compute_md5_bytes(&hashed));
+    bytes.extend_from_slice(&hashed);
+    bytes
+}
+
+fn padded(data: &[u8], multiple: usize) -> Vec<u8> {
+    let mut out = data.to_vec();
+    out.resize(data.len().div_ceil(multiple) * multiple, 0);
+    out
+}
+
+/// Write `files` into `dir` and a packed PAR2 set protecting them with
+/// `recovery_count` PkdRecvS packets. Returns the path of the PAR2 file.
+fn create_packed_set(dir: &Path, files: &[(&str, Vec<u8>)], recovery_count: u16) -> PathBuf {
+    let mut file_ids = Vec::new();
+    let mut file_packets = Vec::new();
+    let mut stream = Vec::new();
+
+    for (name, data) in files {
+        fs::write(dir.join(name), data).unwrap();
+
+        let md5_16k = par2rs::checksum::compute_md5(&data[..data.len().min(16 * 1024)]);
+        let file_id = compute_file_id(&md5_16k, data.len() as u64, name.as_bytes());
+        file_ids.push(file_id);
+
+        let mut desc = Vec::new();
+        desc.extend_from_slice(file_id.as_bytes());
+        desc.extend_from_slice(&compute_md5_bytes(data));
+        desc.extend_from_slice(md5_16k.as_bytes());
+        desc.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        desc.extend_from_slice(&padded(name.as_bytes(), 4));
+
+        let mut ifsc = Vec::new();
+        ifsc.extend_from_slice(file_id.as_bytes());
+        for subslice in data.chunks(SUBSLICE_SIZE) {
+            let (md5, crc) = compute_block_checksums_padded(subslice, SUBSLICE_SIZE);
+            ifsc.extend_from_slice(md5.as_bytes());
+            ifsc.extend_from_slice(&crc.as_u32().to_le_bytes());
+        }
+
+        file_packets.push((desc, ifsc));
+        stream.extend_from_slice(&padded(data, SUBSLICE_SIZE));
+    }
+
+    let mut main = Vec::new();
+    main.extend_from_slice(&(SUBSLICE_SIZE as u64).to_le_bytes());
+    main.extend_from_slice(&(SLICE_SIZE as u64).to_le_bytes());
+    main.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    for file_id in &file_ids {
+        main.extend_from_slice(file_id.as_bytes());
+    }
+    let set_id = compute_md5_bytes(&main);
+
+    let mut par2 = packet(&set_id, b"PAR 2.0\0PkdMain\0", &main);
+    for (desc, ifsc) in &file_packets {
+        par2.extend(packet(&set_id, b"PAR 2.0\0FileDesc", desc));
+        par2.extend(packet(&set_id, b"PAR 2.0\0IFSC\0\0\0\0", ifsc));
+    }
+
+    let stream = padded(&stream, SLICE_SIZE);
+    let slices: Vec<&[u8]> = stream.chunks(SLICE_SIZE).collect();
+    let encoder = RecoveryBlockEncoder::new(SLICE_SIZE, slices.len());
+    for exponent in 0..recovery_count {
+        let recovery = encoder.encode_recovery_block(exponent, &slices).unwrap();
+        let mut body = (exponent as u32).to_le_bytes().to_vec();
+        body.extend_from_slice(&recovery);
+        par2.extend(packet(&set_id, b"PAR 2.0\0PkdRecvS", &body));
+    }
+
+    let par2_file = dir.join("packed.par2");
+    fs::write(&par2_file, par2).unwrap();
+    par2_file
+}
+
+fn test_files() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        (
+            "alpha.dat",
+            (0..1000u32).map(|i| (i * 7 % 251) as u8).collect(),
+        ),
+        (
+            "beta.dat",
+            (0..700u32).map(|i| (i * 13 % 241) as u8).collect(),
+        ),
+    ]
+}
+
+#[test]
+fn packed_recovery_slices_are_parsed_and_counted() {
+    let temp = tempdir().unwrap();
+    let par2_file = create_packed_set(temp.path(), &test_files(), 2);
+
+    let packet_set =
+        par2rs::par2_files::load_par2_packets(std::slice::from_ref(&par2_file), true, false);
+    assert_eq!(packet_set.recovery_block_count, 2);
+    let packed: Vec<_> = packet_set
+        .packets
+        .iter()
+        .filter_map(|packet| match packet {
+            Packet::PackedRecoverySlice(recovery) => Some(recovery),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(packed.len(), 2);
+    assert!(packed.iter().all(|recovery| recovery.verify()));
+
+    let metadata = par2rs::par2_files::parse_recovery_slice_metadata(&[par2_file], false);
+    assert_eq!(metadata.len(), 2);
+    assert!(metadata.iter().all(|m| m.data_size() == SLICE_SIZE));
+}
+
+#[test]
+fn verify_intact_packed_set() {
+    let temp = tempdir().unwrap();
+    let par2_file = create_packed_set(temp.path(), &test_files(), 1);
+
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.present_file_count, 2);
+    assert_eq!(results.total_block_count, 7);
+    assert_eq!(results.missing_block_count, 0);
+}
+
+#[test]
+fn verify_counts_recovery_slices_not_subslices() {
+    let temp = tempdir().unwrap();
+    let par2_file = create_packed_set(temp.path(), &test_files(), 1);
+    fs::remove_file(temp.path().join("beta.dat")).unwrap();
+
+    // beta.dat's three subslices all live in the second slice of the packed stream
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.missing_file_count, 1);
+    assert_eq!(results.missing_block_count, 3);
+    assert_eq!(results.blocks_needed_for_repair, 1);
+    assert!(results.repair_possible);
+}
+
+#[test]
+fn repair_missing_file_in_packed_set() {
+    let temp = tempdir().unwrap();
+    let files = test_files();
+    let par2_file = create_packed_set(temp.path(), &files, 1);
+    fs::remove_file(temp.path().join("beta.dat")).unwrap();
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(temp.path().join("beta.dat")).unwrap(), files[1].1);
+    assert_eq!(fs::read(temp.path().join("alpha.dat")).unwrap(), files[0].1);
+}
+
+#[test]
+fn repair_damage_spanning_both_packed_slices() {
+    let temp = tempdir().unwrap();
+    let files = test_files();
+    let par2_file = create_packed_set(temp.path(), &files, 2);
+
+    // Damage alpha.dat's second subslice (slice 0) and its padded final one (slice 0),
+    // and drop beta.dat entirely (slice 1)
+    let mut alpha = files[0].1.clone();
+    alpha[300..310].fill(0xFF);
+    alpha[990] ^= 0x55;
+    fs::write(temp.path().join("alpha.dat"), alpha).unwrap();
+    fs::remove_file(temp.path().join("beta.dat")).unwrap();
+
+    assert_eq!(common::verify_set(&par2_file).blocks_needed_for_repair, 2);
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(temp.path().join("alpha.dat")).unwrap(), files[0].1);
+    assert_eq!(fs::read(temp.path().join("beta.dat")).unwrap(), files[1].1);
+}
+
+#[test]
+fn repair_fails_without_enough_packed_recovery_slices() {
+    let temp = tempdir().unwrap();
+    let files = test_files();
+    let par2_file = create_packed_set(temp.path(), &files, 1);
+
+    let mut alpha = files[0].1.clone();
+    alpha[0] ^= 0x01;
+    fs::write(temp.path().join("alpha.dat"), alpha).unwrap();
+    fs::remove_file(temp.path().join("beta.dat")).unwrap();
+
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.blocks_needed_for_repair, 2);
+    assert!(!results.repair_possible);
+    assert!(!common::repair_set(&par2_file).is_success());
+}
+
+/// Create a packed set with the library create path over `count` small files
+fn create_with_builder(dir: &Path, count: usize) -> (PathBuf, Vec<(PathBuf, Vec<u8>)>) {
+    let files: Vec<(PathBuf, Vec<u8>)> = (0..count)
+        .map(|i| {
+            let data = common::lcg_data(i as u64 + 1, 37 + (i * 53) % 400);
+            (dir.join(format!("photo_{i:03}.jpg")), data)
+        })
+        .collect();
+    for (path, data) in &files {
+        fs::write(path, data).unwrap();
+    }
+
+    let par2_file = dir.join("photos.par2");
+    let mut context = par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(files.iter().map(|(path, _)| path.clone()).collect())
+        .subslice_size(64)
+        .block_size(512)
+        .recovery_block_count(4)
+        .quiet(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+
+    (par2_file, files)
+}
+
+#[test]
+fn create_writes_packed_main_and_recovery_packets() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_with_builder(temp.path(), 40);
+
+    let volumes: Vec<PathBuf> = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "par2"))
+        .collect();
+    let packet_set = par2rs::par2_files::load_par2_packets(&volumes, true, false);
+
+    assert!(packet_set
+        .packets
+        .iter()
+        .all(|packet| !matches!(packet, Packet::Main(_) | Packet::RecoverySlice(_))));
+    let main = packet_set
+        .packets
+        .iter()
+        .find_map(|packet| match packet {
+            Packet::PackedMain(main) => Some(main),
+            _ => None,
+        })
+        .expect("packed main packet should be written");
+    assert!(main.verify());
+    assert_eq!(main.subslice_size, 64);
+    assert_eq!(main.slice_size, 512);
+    assert_eq!(main.file_count as usize, files.len());
+    assert_eq!(packet_set.recovery_block_count, 4);
+
+    // Far fewer blocks than files: small files share blocks instead of padding to one each
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.present_file_count, files.len());
+    assert_eq!(results.missing_block_count, 0);
+}
+
+#[test]
+fn create_packed_set_can_repair_missing_files() {
+    let temp = tempdir().unwrap();
+    let (par2_file, files) = create_with_builder(temp.path(), 40);
+
+    fs::remove_file(&files[3].0).unwrap();
+    fs::remove_file(&files[4].0).unwrap();
+    let mut damaged = files[20].1.clone();
+    damaged[10] ^= 0xFF;
+    fs::write(&files[20].0, damaged).unwrap();
+
+    let result = common::repair_set(&par2_file);
+    assert!(result.is_success(), "repair failed: {result:?}");
+    for (path, data) in &files {
+        assert_eq!(&fs::read(path).unwrap(), data);
+    }
+}
@@ -161,9 +161,13 @@ mod recovery_set_info {
         let info = RecoverySetInfo {
             set_id: RecoverySetId::new([0u8; 16]),
             slice_size: BlockSize::new(1024),
+            subslice_size: None,
             files: Vec::new(),
             recovery_slices_metadata: Vec::new(),
             file_slice_checksums: Default::default(),
+            comments: Vec::new(),
+            embedded_slices: Vec::new(),
+            non_recovery_file_count: 0,
         };
         assert_eq!(info.total_blocks(), 0);
     }
@@ -173,9 +177,13 @@ mod recovery_set_info {
         let info = RecoverySetInfo {
             set_id: RecoverySetId::new([0u8; 16]),
             slice_size: BlockSize::new(1024),
+            subslice_size: None,
             files: Vec::new(),
             recovery_slices_metadata: Vec::new(),
             file_slice_checksums: Default::default(),
+            comments: Vec::new(),
+            embedded_slices: Vec::new(),
+            non_recovery_file_count: 0,
         };
         assert_eq!(info.total_size(), 0);
     }
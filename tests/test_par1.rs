@@ -4,7 +4,7 @@ use par2rs::par1::verify::{verify_par1_file, verify_par1_file_with_options, Par1
 use par2rs::verify::FileStatus;
 use std::path::Path;
 
-mod common;
+pub mod common;
 
 fn copy_real_par1_fixture(temp: &tempfile::TempDir) {
     common::prepare_par1_flatdata_fixture(temp.path());
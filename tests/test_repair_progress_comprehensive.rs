@@ -11,9 +11,13 @@ fn create_test_recovery_set() -> RecoverySetInfo {
     RecoverySetInfo {
         set_id: RecoverySetId::new([0; 16]),
         slice_size: BlockSize::new(16384),
+        subslice_size: None,
         files: vec![],
         recovery_slices_metadata: vec![],
         file_slice_checksums: FxHashMap::default(),
+        comments: Vec::new(),
+        embedded_slices: Vec::new(),
+        non_recovery_file_count: 0,
     }
 }
 
@@ -71,6 +71,7 @@ fn test_recovery_set_info_total_blocks() {
     let set_info = RecoverySetInfo {
         set_id: RecoverySetId::new([0u8; 16]),
         slice_size: BlockSize::new(1024),
+        subslice_size: None,
         files: vec![
             FileInfo {
                 file_id: FileId::new([1u8; 16]),
@@ -93,6 +94,9 @@ fn test_recovery_set_info_total_blocks() {
         ],
         recovery_slices_metadata: vec![],
         file_slice_checksums: Default::default(),
+        comments: Vec::new(),
+        embedded_slices: Vec::new(),
+        non_recovery_file_count: 0,
     };
 
     assert_eq!(set_info.total_blocks(), 8);
@@ -103,6 +107,7 @@ fn test_recovery_set_info_total_size() {
     let set_info = RecoverySetInfo {
         set_id: RecoverySetId::new([0u8; 16]),
         slice_size: BlockSize::new(1024),
+        subslice_size: None,
         files: vec![
             FileInfo {
                 file_id: FileId::new([1u8; 16]),
@@ -125,6 +130,9 @@ fn test_recovery_set_info_total_size() {
         ],
         recovery_slices_metadata: vec![],
         file_slice_checksums: Default::default(),
+        comments: Vec::new(),
+        embedded_slices: Vec::new(),
+        non_recovery_file_count: 0,
     };
 
     assert_eq!(set_info.total_size(), 3072 + 5120);
@@ -135,6 +143,7 @@ fn test_recovery_set_info_print_statistics() {
     let set_info = RecoverySetInfo {
         set_id: RecoverySetId::new([0u8; 16]),
         slice_size: BlockSize::new(1024),
+        subslice_size: None,
         files: vec![FileInfo {
             file_id: FileId::new([1u8; 16]),
             file_name: "test.dat".to_string(),
@@ -146,6 +155,9 @@ fn test_recovery_set_info_print_statistics() {
         }],
         recovery_slices_metadata: vec![],
         file_slice_checksums: Default::default(),
+        comments: Vec::new(),
+        embedded_slices: Vec::new(),
+        non_recovery_file_count: 0,
     };
 
     // Just verify it doesn't panic
@@ -401,9 +413,13 @@ fn test_empty_recovery_set() {
     let set_info = RecoverySetInfo {
         set_id: RecoverySetId::new([0u8; 16]),
         slice_size: BlockSize::new(1024),
+        subslice_size: None,
         files: vec![],
         recovery_slices_metadata: vec![],
         file_slice_checksums: Default::default(),
+        comments: Vec::new(),
+        embedded_slices: Vec::new(),
+        non_recovery_file_count: 0,
     };
 
     assert_eq!(set_info.total_blocks(), 0);
@@ -0,0 +1,135 @@
+//! Checkpointed, resumable create
+//!
+//! A run interrupted part way through keeps its temporaries and checkpoint;
+//! resuming it must produce exactly the files an uninterrupted run writes,
+//! whether or not the group being encoded had reached the disk.
+
+use par2rs::create::{BlockSizeChoice, CreateContextBuilder, CreateReporter};
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tempfile::tempdir;
+
+pub mod common;
+
+/// 27 source blocks encoded in three groups, each as nine chunks
+fn builder(par2_file: &Path, sources: &[PathBuf]) -> CreateContextBuilder {
+    common::create_builder(par2_file, sources)
+        .block_size(1024)
+        .recovery_block_count(8)
+        .recovery_file_count(3)
+        .memory_limit(16 * 1024)
+}
+
+/// Reporter that crashes the run after a number of group chunks
+struct CrashAfter {
+    chunks_left: AtomicU32,
+}
+
+impl CreateReporter for CrashAfter {
+    fn report_scanning_files(&self, _current: usize, _total: usize, _filename: &str) {}
+    fn report_block_size_choice(&self, _choice: &BlockSizeChoice) {}
+    fn report_file_hashing(&self, _filename: &str, _bytes_processed: u64, _total_bytes: u64) {}
+    fn report_block_checksums(&self, _blocks_processed: u32, _total_blocks: u32) {}
+    fn report_recovery_generation(&self, _blocks_generated: u32, _total_blocks: u32) {
+        if self.chunks_left.fetch_sub(1, Ordering::SeqCst) == 1 {
+            panic!("simulated crash");
+        }
+    }
+    fn report_read_throughput(&self, _bytes_read: u64, _total_bytes: u64, _elapsed: Duration) {}
+    fn report_writing_file(&self, _filename: &str) {}
+    fn report_complete(&self, _output_files: &[String]) {}
+    fn report_error(&self, _error: &str) {}
+}
+
+fn uninterrupted_outputs() -> Vec<(String, Vec<u8>)> {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 7_000);
+    let mut context = builder(&temp.path().join("data.par2"), &sources)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+    common::read_outputs(context.output_files())
+}
+
+/// Crash a checkpointed run after `crash_after` group chunks, then resume it
+fn assert_resume_matches_uninterrupted(crash_after: u32) {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 7_000);
+    let par2_file = temp.path().join("data.par2");
+    let checkpoint = temp.path().join("data.ckpt");
+
+    let mut context = builder(&par2_file, &sources)
+        .checkpoint(&checkpoint)
+        .reporter(Box::new(CrashAfter {
+            chunks_left: AtomicU32::new(crash_after),
+        }))
+        .build()
+        .unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| context.create())).is_err());
+    drop(context);
+    assert!(checkpoint.exists());
+    assert!(!par2_file.exists());
+
+    let mut context = builder(&par2_file, &sources)
+        .checkpoint(&checkpoint)
+        .resume(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+    assert!(!checkpoint.exists());
+    assert_eq!(
+        common::read_outputs(context.output_files()),
+        uninterrupted_outputs()
+    );
+
+    fs::remove_file(&sources[1]).unwrap();
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(&sources[1]).unwrap(), common::lcg_data(2, 7_000));
+}
+
+#[test]
+fn resume_after_a_crash_part_way_through_a_group() {
+    assert_resume_matches_uninterrupted(9 + 4);
+}
+
+#[test]
+fn resume_after_a_crash_before_a_written_group_was_recorded() {
+    assert_resume_matches_uninterrupted(9 * 2);
+}
+
+#[test]
+fn resume_refuses_a_changed_source() {
+    let temp = tempdir().unwrap();
+    let sources = common::write_sources(temp.path(), 20_000, 7_000);
+    let par2_file = temp.path().join("data.par2");
+    let checkpoint = temp.path().join("data.ckpt");
+
+    let mut context = builder(&par2_file, &sources)
+        .checkpoint(&checkpoint)
+        .reporter(Box::new(CrashAfter {
+            chunks_left: AtomicU32::new(9 + 1),
+        }))
+        .build()
+        .unwrap();
+    assert!(catch_unwind(AssertUnwindSafe(|| context.create())).is_err());
+    drop(context);
+
+    fs::write(&sources[1], common::lcg_data(3, 7_001)).unwrap();
+    let mut context = builder(&par2_file, &sources)
+        .checkpoint(&checkpoint)
+        .resume(true)
+        .build()
+        .unwrap();
+    let error = context.create().unwrap_err().to_string();
+    assert!(error.contains("does not match"), "{error}");
+    assert!(checkpoint.exists());
+}
@@ -27,6 +27,7 @@ fn create_test_results(
         recovery_blocks_available: 10,
         repair_possible: true,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     }
 }
 
@@ -0,0 +1,152 @@
+//! Source selection for create
+//!
+//! Include/exclude patterns, hidden-file skipping and file lists decide which
+//! files end up protected; the set must only describe the selected files.
+
+use par2rs::create::{CreateContextBuilder, SourceFilter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// A small tree with version control, scratch and hidden files in it
+fn write_tree(root: &Path) -> Vec<PathBuf> {
+    let files = [
+        "data/a.sql",
+        "data/b.sql",
+        "data/notes.txt",
+        "data/scratch.tmp",
+        "data/.cache/c.sql",
+        ".git/objects/d.sql",
+    ];
+    files
+        .iter()
+        .enumerate()
+        .map(|(seed, name)| {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, common::lcg_data(seed as u64 + 1, 3_000)).unwrap();
+            path
+        })
+        .collect()
+}
+
+fn protected_names(
+    root: &Path,
+    configure: impl Fn(CreateContextBuilder) -> CreateContextBuilder,
+) -> Vec<String> {
+    let sources = write_tree(root);
+    let mut context = configure(
+        common::create_builder(&root.join("set.par2"), &sources)
+            .block_size(1024)
+            .recovery_block_count(2),
+    )
+    .build()
+    .unwrap();
+    context.create().unwrap();
+
+    let (context, result) = par2rs::repair::repair_files(
+        root.join("set.par2").to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "verify failed: {result:?}");
+    let mut names: Vec<String> = context
+        .recovery_set
+        .files
+        .iter()
+        .map(|file| file.file_name.clone())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn builder_filters_sources_by_their_par2_name() {
+    let temp = tempdir().unwrap();
+    let names = protected_names(temp.path(), |builder| {
+        builder.exclude(".git").exclude("*.tmp").skip_hidden(true)
+    });
+    assert_eq!(names, ["data/a.sql", "data/b.sql", "data/notes.txt"]);
+}
+
+#[test]
+fn include_patterns_keep_only_matching_files() {
+    let temp = tempdir().unwrap();
+    let names = protected_names(temp.path(), |builder| builder.include("data/*.sql"));
+    assert_eq!(names, ["data/a.sql", "data/b.sql"]);
+}
+
+#[test]
+fn file_lists_add_sources() {
+    let temp = tempdir().unwrap();
+    let list = temp.path().join("list");
+    let extra = temp.path().join("extra file.bin");
+    fs::write(&extra, common::lcg_data(9, 2_000)).unwrap();
+    fs::write(&list, format!("{}\0", extra.display())).unwrap();
+
+    let names = protected_names(temp.path(), |builder| {
+        builder.files_from(&list).include("*.bin")
+    });
+    assert_eq!(names, ["extra file.bin"]);
+}
+
+#[test]
+fn directory_walks_prune_excluded_directories() {
+    let temp = tempdir().unwrap();
+    write_tree(temp.path());
+    let filter = SourceFilter::default().exclude(".git").exclude("*.tmp");
+    let (_, files) = par2rs::create::cli::resolve_create_inputs(
+        temp.path().join("set.par2").to_str().unwrap(),
+        None,
+        vec![temp.path().to_path_buf()],
+        true,
+        &filter,
+    )
+    .unwrap();
+    let mut names: Vec<String> = files
+        .iter()
+        .map(|path| {
+            path.strip_prefix(temp.path())
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "data/.cache/c.sql",
+            "data/a.sql",
+            "data/b.sql",
+            "data/notes.txt"
+        ]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn special_files_are_refused() {
+    let temp = tempdir().unwrap();
+    let file = temp.path().join("a.bin");
+    let socket = temp.path().join("service.sock");
+    fs::write(&file, common::lcg_data(1, 2_000)).unwrap();
+    let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+
+    let error = par2rs::create::cli::expand_source_files(
+        vec![temp.path().to_path_buf()],
+        true,
+        &SourceFilter::default(),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("(socket)"), "{error}");
+
+    let error = common::create_builder(&temp.path().join("set.par2"), &[file, socket])
+        .build()
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("Not a regular file"), "{error}");
+}
@@ -0,0 +1,151 @@
+//! Create from a non-seekable stream
+//!
+//! A stream is read once, front to back, yet must produce exactly the set a
+//! create from the same data in a file does, whether it fits in the memory
+//! limit or not.
+
+use par2rs::create::CreateContextBuilder;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Reader that hands out a few hundred bytes per call, like a pipe
+struct Pipe {
+    data: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for Pipe {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(300).min(self.data.len() - self.pos);
+        buf[..len].copy_from_slice(&self.data[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+fn pipe(data: &[u8]) -> Pipe {
+    Pipe {
+        data: data.to_vec(),
+        pos: 0,
+    }
+}
+
+fn builder(par2_file: &Path) -> CreateContextBuilder {
+    common::create_builder(par2_file, &[]).recovery_file_count(3)
+}
+
+/// Outputs of a regular create of `data` stored as `dump.sql`
+fn file_outputs(
+    data: &[u8],
+    configure: impl Fn(CreateContextBuilder) -> CreateContextBuilder,
+) -> Vec<(String, Vec<u8>)> {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("dump.sql");
+    fs::write(&source, data).unwrap();
+    let mut context = configure(builder(&temp.path().join("data.par2")))
+        .source_files(vec![source])
+        .build()
+        .unwrap();
+    context.create().unwrap();
+    common::read_outputs(context.output_files())
+}
+
+#[test]
+fn stream_within_the_memory_limit_matches_a_file_create() {
+    let data = common::lcg_data(1, 50_000);
+    let temp = tempdir().unwrap();
+    let mut context = builder(&temp.path().join("data.par2"))
+        .source_block_count(100)
+        .redundancy_percentage(10)
+        .stream_source("dump.sql", pipe(&data))
+        .build()
+        .unwrap();
+    let plan = context.plan().unwrap();
+    context.create().unwrap();
+
+    let outputs = common::read_outputs(context.output_files());
+    assert_eq!(
+        outputs,
+        file_outputs(&data, |builder| builder
+            .source_block_count(100)
+            .redundancy_percentage(10))
+    );
+    assert_eq!(plan.output_files.len(), outputs.len());
+}
+
+#[test]
+fn stream_past_the_memory_limit_is_encoded_in_one_pass() {
+    let data = common::lcg_data(2, 100_003);
+    let explicit = |builder: CreateContextBuilder| {
+        builder
+            .block_size(1024)
+            .recovery_block_count(12)
+            .memory_limit(16 * 1024)
+    };
+    let temp = tempdir().unwrap();
+    let par2_file = temp.path().join("data.par2");
+    let tee = temp.path().join("dump.sql");
+    let mut context = explicit(builder(&par2_file))
+        .stream_source("dump.sql", pipe(&data))
+        .stream_tee(&tee)
+        .build()
+        .unwrap();
+    assert!(context.plan().is_err());
+    context.create().unwrap();
+
+    // The recovery data's scratch file is gone once the set is written
+    let mut names: Vec<String> = fs::read_dir(temp.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    let mut expected: Vec<String> = context
+        .output_files()
+        .iter()
+        .map(|path| {
+            Path::new(path)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .chain(["dump.sql".to_string()])
+        .collect();
+    expected.sort();
+    assert_eq!(names, expected);
+
+    assert_eq!(fs::read(&tee).unwrap(), data);
+    assert_eq!(
+        common::read_outputs(context.output_files()),
+        file_outputs(&data, explicit)
+    );
+
+    // The tee copy is the protected file
+    let mut damaged = data.clone();
+    damaged[5_000..9_000].fill(0);
+    fs::write(&tee, damaged).unwrap();
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(&tee).unwrap(), data);
+}
+
+#[test]
+fn long_stream_needs_an_explicit_layout() {
+    let temp = tempdir().unwrap();
+    let error = builder(&temp.path().join("data.par2"))
+        .memory_limit(16 * 1024)
+        .stream_source("dump.sql", pipe(&common::lcg_data(3, 20_000)))
+        .build()
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("explicit block size"), "{error}");
+}
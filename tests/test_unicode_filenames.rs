@@ -0,0 +1,167 @@
+//! Unicode Filename (UniFileN) packet tests
+//!
+//! Covers emitting UniFileN packets during create and using them to resolve
+//! target names when the FileDescription name is a lossy 8-bit rendering.
+
+use par2rs::create::CreateContextBuilder;
+use par2rs::Packet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn create_set(source: &Path, par2_file: &Path, unicode_filenames: bool) {
+    let mut context = CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source.to_path_buf()])
+        .block_size(1024)
+        .recovery_block_count(4)
+        .unicode_filenames(unicode_filenames)
+        .quiet(true)
+        .build()
+        .unwrap();
+    context.create().unwrap();
+}
+
+fn par2_files_in(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "par2"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Replace the name inside every FileDescription packet and fix up its MD5,
+/// simulating a client that stored a lossy 8-bit name next to the UniFileN packet.
+fn rewrite_file_description_names(par2_file: &Path, from: &[u8], to: &[u8]) {
+    assert_eq!(from.len(), to.len());
+    let mut data = fs::read(par2_file).unwrap();
+    let mut offset = 0;
+    while offset + 64 <= data.len() {
+        assert_eq!(&data[offset..offset + 8], par2rs::packets::MAGIC_BYTES);
+        let length = u64::from_le_bytes(data[offset + 8..offset + 16].try_into().unwrap()) as usize;
+        let packet = &mut data[offset..offset + length];
+        if &packet[48..64] == par2rs::packets::file_description_packet::TYPE_OF_PACKET {
+            let name = &mut packet[120..];
+            if let Some(pos) = name.windows(from.len()).position(|w| w == from) {
+                name[pos..pos + to.len()].copy_from_slice(to);
+                let md5 = par2rs::checksum::compute_md5_bytes(&packet[32..]);
+                packet[16..32].copy_from_slice(&md5);
+            }
+        }
+        offset += length;
+    }
+    fs::write(par2_file, data).unwrap();
+}
+
+#[test]
+fn create_emits_unicode_filename_for_non_ascii_names() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("写真.dat");
+    fs::write(&source, vec![0x5A; 3000]).unwrap();
+    let par2_file = temp.path().join("photos.par2");
+
+    create_set(&source, &par2_file, true);
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[par2_file], false, false);
+    let names: Vec<String> = packet_set
+        .packets
+        .iter()
+        .filter_map(|packet| match packet {
+            Packet::UnicodeFilename(uni) => {
+                assert!(uni.verify());
+                Some(uni.file_name())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(names, vec!["写真.dat".to_string()]);
+}
+
+#[test]
+fn create_skips_unicode_filename_for_ascii_names() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("plain.dat");
+    fs::write(&source, vec![0x5A; 3000]).unwrap();
+    let par2_file = temp.path().join("plain.par2");
+
+    create_set(&source, &par2_file, true);
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[par2_file], false, false);
+    assert!(!packet_set
+        .packets
+        .iter()
+        .any(|packet| matches!(packet, Packet::UnicodeFilename(_))));
+}
+
+#[test]
+fn create_does_not_emit_unicode_filename_by_default() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("café.dat");
+    fs::write(&source, vec![0x5A; 3000]).unwrap();
+    let par2_file = temp.path().join("cafe.par2");
+
+    create_set(&source, &par2_file, false);
+
+    let packet_set = par2rs::par2_files::load_par2_packets(&[par2_file], false, false);
+    assert!(!packet_set
+        .packets
+        .iter()
+        .any(|packet| matches!(packet, Packet::UnicodeFilename(_))));
+}
+
+#[test]
+fn repair_restores_file_under_unicode_name() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("café.dat");
+    let original: Vec<u8> = (0..3000u32).map(|i| (i * 7 % 251) as u8).collect();
+    fs::write(&source, &original).unwrap();
+    let par2_file = temp.path().join("cafe.par2");
+
+    create_set(&source, &par2_file, true);
+
+    // "é" is two bytes in UTF-8; make the 8-bit name unusable on disk
+    for file in par2_files_in(temp.path()) {
+        rewrite_file_description_names(&file, "café".as_bytes(), b"caf??");
+    }
+    fs::remove_file(&source).unwrap();
+
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+
+    assert!(result.is_success(), "repair failed: {result:?}");
+    assert_eq!(fs::read(&source).unwrap(), original);
+    assert!(!temp.path().join("caf??.dat").exists());
+}
+
+#[test]
+fn verify_resolves_unicode_name_over_file_description_name() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("café.dat");
+    let data: Vec<u8> = (0..3000u32).map(|i| (i * 13 % 253) as u8).collect();
+    fs::write(&source, data).unwrap();
+    let par2_file = temp.path().join("cafe.par2");
+
+    create_set(&source, &par2_file, true);
+    for file in par2_files_in(temp.path()) {
+        rewrite_file_description_names(&file, "café".as_bytes(), b"caf??");
+    }
+
+    let packet_set =
+        par2rs::par2_files::load_par2_packets(&par2_files_in(temp.path()), false, false);
+    let results = par2rs::verify::comprehensive_verify_files_with_extra_files(
+        packet_set,
+        &par2rs::verify::VerificationConfig::default(),
+        &par2rs::reporters::SilentVerificationReporter,
+        temp.path(),
+        &[],
+    );
+
+    assert_eq!(results.present_file_count, 1);
+    assert_eq!(results.files[0].file_name, "café.dat");
+}
@@ -0,0 +1,96 @@
+//! Unknown and application-specific packet tests
+//!
+//! Packets of a type the library does not interpret must be kept when their
+//! MD5 verifies, and must not get in the way of verify and repair.
+
+use par2rs::domain::RecoverySetId;
+use par2rs::packets::unknown_packet;
+use par2rs::{parse_packets, Packet};
+use std::fs;
+use std::io::Cursor;
+use tempfile::tempdir;
+
+const JOB_TYPE: [u8; 16] = *b"ACME\0\0\0\0IngestID";
+
+fn unknown_packets(packets: &[Packet]) -> Vec<(&[u8; 16], &RecoverySetId, &Vec<u8>)> {
+    packets
+        .iter()
+        .filter_map(|packet| match packet {
+            Packet::Unknown {
+                packet_type,
+                set_id,
+                body,
+                ..
+            } => Some((packet_type, set_id, body)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn parse_keeps_verified_unknown_packets_only() {
+    let set_id = RecoverySetId::new([7; 16]);
+    let mut data = Vec::new();
+    unknown_packet::write(&mut data, &JOB_TYPE, &set_id, b"job-0001").unwrap();
+    let mut corrupt = Vec::new();
+    unknown_packet::write(&mut corrupt, b"ACME\0\0\0\0Other\0\0\0", &set_id, b"abcd").unwrap();
+    corrupt[64] ^= 0xFF;
+    data.extend_from_slice(&corrupt);
+
+    let packets = parse_packets(&mut Cursor::new(&data));
+    assert_eq!(packets.len(), 1);
+    assert!(packets[0].verify());
+    let unknown = unknown_packets(&packets);
+    assert_eq!(unknown, vec![(&JOB_TYPE, &set_id, &b"job-0001".to_vec())]);
+}
+
+#[test]
+fn create_writes_custom_packets_that_survive_verify_and_repair() {
+    let temp = tempdir().unwrap();
+    let source = temp.path().join("data.bin");
+    let data: Vec<u8> = (0..20_000u32).map(|i| (i * 31 % 251) as u8).collect();
+    fs::write(&source, &data).unwrap();
+
+    let par2_file = temp.path().join("data.par2");
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source.clone()])
+        .block_size(1024)
+        .recovery_block_count(4)
+        .custom_packet(JOB_TYPE, b"job-7781".to_vec())
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+
+    let par2_files = par2rs::par2_files::collect_par2_files(&par2_file);
+    for file in &par2_files {
+        let packets = parse_packets(&mut fs::File::open(file).unwrap());
+        let unknown = unknown_packets(&packets);
+        assert_eq!(unknown.len(), 1, "{}", file.display());
+        assert_eq!(unknown[0].2, b"job-7781");
+    }
+
+    // Copies in every volume collapse to one packet, tagged with the set ID
+    let packet_set = par2rs::par2_files::load_par2_packets(&par2_files, false, false);
+    let main_set_id = par2rs::packets::processing::extract_slice_layout(&packet_set.packets)
+        .unwrap()
+        .set_id;
+    let unknown = unknown_packets(&packet_set.packets);
+    assert_eq!(unknown.len(), 1);
+    assert_eq!(*unknown[0].1, main_set_id);
+
+    let mut damaged = data.clone();
+    damaged[5000] ^= 0xFF;
+    fs::write(&source, damaged).unwrap();
+
+    let (_context, result) = par2rs::repair::repair_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &par2rs::verify::VerificationConfig::default(),
+    )
+    .unwrap();
+    assert!(result.is_success(), "{result:?}");
+    assert_eq!(fs::read(&source).unwrap(), data);
+}
@@ -0,0 +1,272 @@
+//! Updating an existing PAR2 set after its source files changed in place
+//!
+//! The updated set must match a set created from scratch over the new data,
+//! whichever way the old block contents were found.
+
+use par2rs::create::{update_recovery_set, SilentCreateReporter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Write the source files into `dir` and create a set with 4 recovery blocks
+fn create_set(dir: &Path, sources: &[(&str, Vec<u8>)], embed: bool) -> PathBuf {
+    for (name, data) in sources {
+        fs::write(dir.join(name), data).unwrap();
+    }
+    let par2_file = dir.join("data.par2");
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(sources.iter().map(|(name, _)| dir.join(name)).collect())
+        .block_size(1024)
+        .recovery_block_count(4)
+        .recovery_file_count(2)
+        .embed_source_data(embed)
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+    par2_file
+}
+
+fn original_sources() -> Vec<(&'static str, Vec<u8>)> {
+    vec![
+        ("a.bin", common::lcg_data(1, 20_000)),
+        ("b.bin", common::lcg_data(2, 5_500)),
+    ]
+}
+
+/// Overwrite a few bytes in each listed block of the source
+fn change_blocks(data: &mut [u8], blocks: &[usize]) {
+    for &block in blocks {
+        let offset = (block * 1024 + 100).min(data.len() - 1);
+        data[offset] ^= 0x5A;
+    }
+}
+
+fn par2_file_contents(dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "par2"))
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            (name, fs::read(&path).unwrap())
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+/// Change blocks in place, update, and compare against a freshly created set
+fn assert_update_matches_fresh_set(embed: bool, changes: &[(usize, &[usize])]) -> usize {
+    let temp = tempdir().unwrap();
+    let mut sources = original_sources();
+    let par2_file = create_set(temp.path(), &sources, embed);
+
+    for &(file, blocks) in changes {
+        change_blocks(&mut sources[file].1, blocks);
+        fs::write(temp.path().join(sources[file].0), &sources[file].1).unwrap();
+    }
+    let summary = update_recovery_set(&par2_file, &SilentCreateReporter).unwrap();
+    let changed: usize = changes.iter().map(|(_, blocks)| blocks.len()).sum();
+    assert_eq!(summary.changed_blocks, changed);
+    assert_eq!(summary.updated_files.len(), 3);
+    assert!(!fs::read_dir(temp.path()).unwrap().any(|entry| {
+        let name = entry.unwrap().file_name().to_string_lossy().to_string();
+        name.ends_with(par2rs::create::staged_output::TEMP_SUFFIX)
+            || name.ends_with(par2rs::create::staged_output::BACKUP_SUFFIX)
+    }));
+
+    let fresh = tempdir().unwrap();
+    create_set(fresh.path(), &sources, embed);
+    assert_eq!(
+        par2_file_contents(temp.path()),
+        par2_file_contents(fresh.path())
+    );
+
+    // The updated set repairs the new contents
+    let damaged_path = temp.path().join(sources[0].0);
+    let mut damaged = sources[0].1.clone();
+    change_blocks(&mut damaged, &[2, 5, 9, 17]);
+    fs::write(&damaged_path, damaged).unwrap();
+    assert!(common::repair_set(&par2_file).is_success());
+    assert_eq!(fs::read(&damaged_path).unwrap(), sources[0].1);
+
+    summary.recovered_blocks
+}
+
+#[test]
+fn update_rebuilds_old_blocks_from_recovery_data() {
+    // Block 0 changes the 16k hash, and with it the file ID and set ID
+    let recovered = assert_update_matches_fresh_set(false, &[(0, &[0, 7, 19]), (1, &[5])]);
+    assert_eq!(recovered, 4);
+}
+
+#[test]
+fn update_leaves_file_ids_alone_when_the_first_16k_is_unchanged() {
+    let recovered = assert_update_matches_fresh_set(false, &[(0, &[18])]);
+    assert_eq!(recovered, 1);
+}
+
+#[test]
+fn update_uses_embedded_source_data_for_any_number_of_changes() {
+    let recovered = assert_update_matches_fresh_set(true, &[(0, &[0, 1, 2, 3, 4, 10]), (1, &[0])]);
+    assert_eq!(recovered, 0);
+}
+
+#[test]
+fn update_uses_unchanged_duplicate_blocks_for_old_contents() {
+    let temp = tempdir().unwrap();
+    let mut sources = vec![
+        ("a.bin", vec![0u8; 8192]),
+        ("b.bin", common::lcg_data(3, 4096)),
+    ];
+    let par2_file = create_set(temp.path(), &sources, false);
+
+    // Six zero blocks change; the two left untouched still hold their old contents
+    change_blocks(&mut sources[0].1, &[0, 1, 2, 3, 4, 5]);
+    fs::write(temp.path().join("a.bin"), &sources[0].1).unwrap();
+    let summary = update_recovery_set(&par2_file, &SilentCreateReporter).unwrap();
+    assert_eq!(summary.changed_blocks, 6);
+    assert_eq!(summary.recovered_blocks, 0);
+
+    let fresh = tempdir().unwrap();
+    create_set(fresh.path(), &sources, false);
+    assert_eq!(
+        par2_file_contents(temp.path()),
+        par2_file_contents(fresh.path())
+    );
+}
+
+#[test]
+fn update_without_changes_leaves_the_set_alone() {
+    let temp = tempdir().unwrap();
+    let par2_file = create_set(temp.path(), &original_sources(), false);
+    let before = par2_file_contents(temp.path());
+
+    let summary = update_recovery_set(&par2_file, &SilentCreateReporter).unwrap();
+    assert_eq!(summary.changed_blocks, 0);
+    assert!(summary.updated_files.is_empty());
+    assert_eq!(par2_file_contents(temp.path()), before);
+}
+
+#[test]
+fn update_refuses_more_unknown_blocks_than_recovery_blocks() {
+    let temp = tempdir().unwrap();
+    let mut sources = original_sources();
+    let par2_file = create_set(temp.path(), &sources, false);
+    let before = par2_file_contents(temp.path());
+
+    change_blocks(&mut sources[0].1, &[1, 3, 5, 7, 9]);
+    fs::write(temp.path().join("a.bin"), &sources[0].1).unwrap();
+    let error = update_recovery_set(&par2_file, &SilentCreateReporter).unwrap_err();
+    assert!(error.to_string().contains("5 changed blocks"), "{error}");
+    assert_eq!(par2_file_contents(temp.path()), before);
+}
+
+#[test]
+fn failed_update_leaves_the_set_untouched() {
+    let temp = tempdir().unwrap();
+    let mut sources = original_sources();
+    let par2_file = create_set(temp.path(), &sources, false);
+    let before = par2_file_contents(temp.path());
+
+    // The last volume cannot be staged, after the others already have been
+    let last = temp.path().join(&before.last().unwrap().0);
+    let blocked = par2rs::create::staged_output::temp_path(&last);
+    fs::create_dir(&blocked).unwrap();
+    fs::write(blocked.join("keep"), b"in the way").unwrap();
+
+    change_blocks(&mut sources[0].1, &[3]);
+    fs::write(temp.path().join(sources[0].0), &sources[0].1).unwrap();
+    assert!(update_recovery_set(&par2_file, &SilentCreateReporter).is_err());
+    assert_eq!(par2_file_contents(temp.path()), before);
+}
+
+#[test]
+fn update_rehashes_changed_non_recovery_files() {
+    let temp = tempdir().unwrap();
+    let sources = original_sources();
+    for (name, data) in &sources {
+        fs::write(temp.path().join(name), data).unwrap();
+    }
+    let checksum_only = temp.path().join("huge.iso");
+    let mut checksum_data = common::lcg_data(7, 12_000);
+    fs::write(&checksum_only, &checksum_data).unwrap();
+    let create = |dir: &Path| {
+        let par2_file = dir.join("data.par2");
+        par2rs::create::CreateContextBuilder::new()
+            .output_name(par2_file.to_str().unwrap())
+            .source_files(sources.iter().map(|(name, _)| dir.join(name)).collect())
+            .non_recovery_files(vec![dir.join("huge.iso")])
+            .block_size(1024)
+            .recovery_block_count(4)
+            .recovery_file_count(2)
+            .quiet(true)
+            .build()
+            .unwrap()
+            .create()
+            .unwrap();
+        par2_file
+    };
+    let par2_file = create(temp.path());
+
+    change_blocks(&mut checksum_data, &[3, 11]);
+    fs::write(&checksum_only, &checksum_data).unwrap();
+    let summary = update_recovery_set(&par2_file, &SilentCreateReporter).unwrap();
+    assert_eq!(summary.changed_blocks, 0);
+    assert_eq!(summary.changed_non_recovery_files, 1);
+    assert_eq!(summary.updated_files.len(), 3);
+
+    let results = common::verify_set(&par2_file);
+    assert_eq!(results.damaged_non_recovery_files().count(), 0);
+    assert!(results.to_string().contains("All files are correct"));
+
+    let fresh = tempdir().unwrap();
+    for (name, data) in &sources {
+        fs::write(fresh.path().join(name), data).unwrap();
+    }
+    fs::write(fresh.path().join("huge.iso"), &checksum_data).unwrap();
+    create(fresh.path());
+    assert_eq!(
+        par2_file_contents(temp.path()),
+        par2_file_contents(fresh.path())
+    );
+}
+
+#[test]
+fn update_refuses_a_resized_non_recovery_file() {
+    let temp = tempdir().unwrap();
+    let sources = original_sources();
+    for (name, data) in &sources {
+        fs::write(temp.path().join(name), data).unwrap();
+    }
+    let checksum_only = temp.path().join("huge.iso");
+    fs::write(&checksum_only, common::lcg_data(7, 12_000)).unwrap();
+    let par2_file = temp.path().join("data.par2");
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(
+            sources
+                .iter()
+                .map(|(name, _)| temp.path().join(name))
+                .collect(),
+        )
+        .non_recovery_files(vec![checksum_only.clone()])
+        .block_size(1024)
+        .recovery_block_count(4)
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+    let before = par2_file_contents(temp.path());
+
+    fs::write(&checksum_only, common::lcg_data(7, 13_000)).unwrap();
+    let error = update_recovery_set(&par2_file, &SilentCreateReporter).unwrap_err();
+    assert!(error.to_string().contains("huge.iso"), "{error}");
+    assert_eq!(par2_file_contents(temp.path()), before);
+}
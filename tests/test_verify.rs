@@ -438,6 +438,7 @@ mod comprehensive_verify_tests {
             recovery_blocks_available: 50,
             repair_possible: true,
             blocks_needed_for_repair: 0,
+            non_recovery_files: Vec::new(),
         };
 
         let cloned = results.clone();
@@ -670,6 +671,7 @@ mod print_verification_results_tests {
             recovery_blocks_available: 20,
             repair_possible: true,
             blocks_needed_for_repair: 0,
+            non_recovery_files: Vec::new(),
         };
 
         // This test just ensures the function doesn't panic
@@ -695,6 +697,7 @@ mod print_verification_results_tests {
             recovery_blocks_available: 60,
             repair_possible: true,
             blocks_needed_for_repair: 50,
+            non_recovery_files: Vec::new(),
         };
 
         {
@@ -719,6 +722,7 @@ mod print_verification_results_tests {
             recovery_blocks_available: 50,
             repair_possible: false,
             blocks_needed_for_repair: 100,
+            non_recovery_files: Vec::new(),
         };
 
         {
@@ -743,6 +747,7 @@ mod print_verification_results_tests {
             recovery_blocks_available: 70,
             repair_possible: true,
             blocks_needed_for_repair: 50,
+            non_recovery_files: Vec::new(),
         };
 
         {
@@ -784,6 +789,7 @@ mod print_verification_results_tests {
             recovery_blocks_available: 50,
             repair_possible: true,
             blocks_needed_for_repair: 25,
+            non_recovery_files: Vec::new(),
         };
 
         {
@@ -808,6 +814,7 @@ mod print_verification_results_tests {
             recovery_blocks_available: 0,
             repair_possible: false,
             blocks_needed_for_repair: 0,
+            non_recovery_files: Vec::new(),
         };
 
         {
@@ -854,6 +861,7 @@ mod verification_result_calculations {
             recovery_blocks_available: 100,
             repair_possible: true,
             blocks_needed_for_repair: 20,
+            non_recovery_files: Vec::new(),
         };
 
         assert_eq!(results.files.len(), 3);
@@ -880,6 +888,7 @@ mod verification_result_calculations {
             recovery_blocks_available: 50,
             repair_possible: false,
             blocks_needed_for_repair: 100,
+            non_recovery_files: Vec::new(),
         };
 
         assert!(!results.repair_possible, "Should not be repairable");
@@ -934,6 +943,7 @@ mod verification_result_calculations {
             recovery_blocks_available: 50,
             repair_possible: true,
             blocks_needed_for_repair: 15,
+            non_recovery_files: Vec::new(),
         };
 
         assert_eq!(results.present_file_count, 1);
@@ -1131,6 +1141,7 @@ mod recovery_status_tests {
             recovery_blocks_available: 50,
             repair_possible: false,
             blocks_needed_for_repair: 100,
+            non_recovery_files: Vec::new(),
         };
 
         assert!(!results.repair_possible);
@@ -1152,6 +1163,7 @@ mod recovery_status_tests {
             recovery_blocks_available: 50,
             repair_possible: true,
             blocks_needed_for_repair: 50,
+            non_recovery_files: Vec::new(),
         };
 
         assert!(results.repair_possible);
@@ -1176,6 +1188,7 @@ mod recovery_status_tests {
             recovery_blocks_available: 100,
             repair_possible: true,
             blocks_needed_for_repair: 30,
+            non_recovery_files: Vec::new(),
         };
 
         assert!(results.repair_possible);
@@ -1197,6 +1210,7 @@ mod recovery_status_tests {
             recovery_blocks_available: 50,
             repair_possible: true,
             blocks_needed_for_repair: 0,
+            non_recovery_files: Vec::new(),
         };
 
         assert_eq!(results.missing_block_count, 0);
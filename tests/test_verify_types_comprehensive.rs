@@ -189,6 +189,7 @@ fn test_verification_results_all_ok() {
         recovery_blocks_available: 20,
         repair_possible: false,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     };
 
     let display = results.to_string();
@@ -211,6 +212,7 @@ fn test_verification_results_repair_needed_possible() {
         recovery_blocks_available: 30,
         repair_possible: true,
         blocks_needed_for_repair: 20,
+        non_recovery_files: Vec::new(),
     };
 
     let display = results.to_string();
@@ -239,6 +241,7 @@ fn test_verification_results_repair_not_possible() {
         recovery_blocks_available: 10,
         repair_possible: false,
         blocks_needed_for_repair: 30,
+        non_recovery_files: Vec::new(),
     };
 
     let display = results.to_string();
@@ -261,6 +264,7 @@ fn test_verification_results_renamed_files() {
         recovery_blocks_available: 0,
         repair_possible: false,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     };
 
     let display = results.to_string();
@@ -283,6 +287,7 @@ fn test_verification_results_exact_recovery_blocks() {
         recovery_blocks_available: 10,
         repair_possible: true,
         blocks_needed_for_repair: 10,
+        non_recovery_files: Vec::new(),
     };
 
     let display = results.to_string();
@@ -307,6 +312,7 @@ fn test_verification_results_no_recovery_blocks() {
         recovery_blocks_available: 0,
         repair_possible: false,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     };
 
     let display = results.to_string();
@@ -329,6 +335,7 @@ fn test_verification_results_clone() {
         recovery_blocks_available: 5,
         repair_possible: false,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     };
 
     let results2 = results1.clone();
@@ -351,6 +358,7 @@ fn test_verification_results_debug() {
         recovery_blocks_available: 5,
         repair_possible: false,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     };
 
     let debug_str = format!("{:?}", results);
@@ -392,6 +400,7 @@ fn test_verification_results_with_file_data() {
         recovery_blocks_available: 2,
         repair_possible: false,
         blocks_needed_for_repair: 0,
+        non_recovery_files: Vec::new(),
     };
 
     assert_eq!(results.files.len(), 1);
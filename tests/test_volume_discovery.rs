@@ -0,0 +1,162 @@
+//! Content-based PAR2 volume discovery tests
+//!
+//! Renamed volumes no longer match the `.par2` naming scheme, so they are
+//! found by scanning files for packets that carry the set's recovery set ID.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+pub mod common;
+
+/// Create a set in `dir` and return the index file, source file and volumes
+fn create_set(dir: &Path, name: &str, seed: u64) -> (PathBuf, (PathBuf, Vec<u8>), Vec<PathBuf>) {
+    let source = (
+        dir.join(format!("{name}.bin")),
+        common::lcg_data(seed, 12_000),
+    );
+    fs::write(&source.0, &source.1).unwrap();
+
+    let par2_file = dir.join(format!("{name}.par2"));
+    par2rs::create::CreateContextBuilder::new()
+        .output_name(par2_file.to_str().unwrap())
+        .source_files(vec![source.0.clone()])
+        .block_size(1024)
+        .recovery_block_count(4)
+        .recovery_file_count(2)
+        .quiet(true)
+        .build()
+        .unwrap()
+        .create()
+        .unwrap();
+
+    let mut volumes: Vec<PathBuf> = par2rs::par2_files::collect_par2_files(&par2_file)
+        .into_iter()
+        .filter(|path| path != &par2_file)
+        .collect();
+    volumes.sort();
+    (par2_file, source, volumes)
+}
+
+fn repair(par2_file: &Path, extra_files: &[PathBuf], discover_volumes: bool) -> bool {
+    let config = par2rs::verify::VerificationConfig {
+        discover_volumes,
+        ..Default::default()
+    };
+    par2rs::repair::repair_files_with_base_path_and_extra_files(
+        par2_file.to_str().unwrap(),
+        Box::new(par2rs::repair::SilentReporter),
+        &config,
+        None,
+        extra_files,
+    )
+    .map(|(_context, result)| result.is_success())
+    .unwrap_or(false)
+}
+
+#[test]
+fn renamed_volumes_are_found_by_content() {
+    let temp = tempdir().unwrap();
+    let (par2_file, _source, volumes) = create_set(temp.path(), "data", 1);
+    let (_other_par2, _other_source, other_volumes) = create_set(temp.path(), "other", 2);
+
+    let renamed: Vec<PathBuf> = volumes
+        .iter()
+        .enumerate()
+        .map(|(i, volume)| {
+            let target = temp.path().join(format!("abc123.{i}"));
+            fs::rename(volume, &target).unwrap();
+            target
+        })
+        .collect();
+    fs::rename(&other_volumes[0], temp.path().join("part7.bin")).unwrap();
+
+    assert_eq!(
+        par2rs::par2_files::collect_par2_files(&par2_file),
+        vec![par2_file.clone()]
+    );
+
+    let mut expected = renamed.clone();
+    expected.push(par2_file.clone());
+    expected.sort();
+    assert_eq!(
+        par2rs::par2_files::discover_par2_files(&par2_file, &[], true),
+        expected
+    );
+}
+
+#[test]
+fn repair_uses_renamed_volumes_only_when_discovery_is_enabled() {
+    let temp = tempdir().unwrap();
+    let (par2_file, source, volumes) = create_set(temp.path(), "data", 3);
+    for (i, volume) in volumes.iter().enumerate() {
+        fs::rename(volume, temp.path().join(format!("download_{i}.tmp"))).unwrap();
+    }
+
+    let mut damaged = source.1.clone();
+    damaged[100] ^= 0xFF;
+    damaged[5000] ^= 0xFF;
+    fs::write(&source.0, &damaged).unwrap();
+
+    assert!(!repair(&par2_file, &[], false));
+    assert!(repair(&par2_file, &[], true));
+    assert_eq!(fs::read(&source.0).unwrap(), source.1);
+}
+
+#[test]
+fn extra_files_are_scanned_for_embedded_volumes() {
+    let temp = tempdir().unwrap();
+    let elsewhere = tempdir().unwrap();
+    let (par2_file, source, volumes) = create_set(temp.path(), "data", 5);
+
+    // Bury every volume behind a prefix so its first header straddles the
+    // scanner's read buffer boundary
+    let extra_files: Vec<PathBuf> = volumes
+        .iter()
+        .enumerate()
+        .map(|(i, volume)| {
+            let mut contents = common::lcg_data(9, 1024 * 1024 - 30);
+            contents.extend_from_slice(&fs::read(volume).unwrap());
+            fs::remove_file(volume).unwrap();
+            let target = elsewhere.path().join(format!("blob{i}"));
+            fs::write(&target, contents).unwrap();
+            target
+        })
+        .collect();
+
+    let discovered = par2rs::par2_files::discover_par2_files(&par2_file, &extra_files, true);
+    assert_eq!(discovered.len(), 1 + extra_files.len());
+
+    fs::remove_file(&source.0).unwrap();
+    let results = {
+        let packet_set = par2rs::par2_files::load_par2_packets(&discovered, false, false);
+        par2rs::verify::comprehensive_verify_files(
+            packet_set,
+            &par2rs::verify::VerificationConfig::default(),
+            &par2rs::reporters::SilentVerificationReporter,
+            temp.path(),
+        )
+    };
+    assert_eq!(results.recovery_blocks_available, 4);
+    assert!(!results.repair_possible);
+}
+
+#[test]
+fn directory_files_only_count_when_they_start_with_a_packet() {
+    let temp = tempdir().unwrap();
+    let (par2_file, _source, volumes) = create_set(temp.path(), "data", 7);
+
+    let buried = temp.path().join("buried.bin");
+    let mut contents = common::lcg_data(11, 5_000);
+    contents.extend_from_slice(&fs::read(&volumes[0]).unwrap());
+    fs::write(&buried, contents).unwrap();
+    fs::remove_file(&volumes[0]).unwrap();
+
+    let discovered = par2rs::par2_files::discover_par2_files(&par2_file, &[], true);
+    assert!(!discovered.contains(&buried));
+    assert_eq!(discovered.len(), volumes.len());
+
+    let discovered =
+        par2rs::par2_files::discover_par2_files(&par2_file, std::slice::from_ref(&buried), true);
+    assert!(discovered.contains(&buried));
+}
@@ -214,6 +214,7 @@ mod par2_stats_struct {
     fn supports_clone_and_debug() {
         let stats = Par2Stats {
             file_count: 5,
+            other_file_count: 0,
             block_size: 1024,
             total_blocks: 100,
             total_size: 102400,
@@ -232,6 +233,7 @@ mod par2_stats_struct {
     fn print_summary_does_not_panic() {
         let stats = Par2Stats {
             file_count: 1,
+            other_file_count: 0,
             block_size: 528,
             total_blocks: 1986,
             total_size: 1048576,